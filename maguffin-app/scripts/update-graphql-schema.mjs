@@ -0,0 +1,504 @@
+#!/usr/bin/env node
+// Regenerate src-tauri/graphql/schema.graphql from GitHub's public schema.
+//
+// The vendored schema only holds what the operations in src-tauri/graphql/
+// use: the fields they select (with all of their arguments), the types of
+// their variables and arguments, every input type those reach, the possible
+// types of the unions and interfaces selected, and the enums and scalars in
+// between. Descriptions and directives are dropped.
+//
+// Usage:
+//   node scripts/update-graphql-schema.mjs [upstream.graphql]
+//
+// Without an argument the upstream schema is downloaded from UPSTREAM_URL.
+// Run it after adding or changing an operation, then commit the result.
+
+import { readFile, readdir, writeFile } from "node:fs/promises";
+import { dirname, join } from "node:path";
+import { fileURLToPath } from "node:url";
+
+const UPSTREAM_URL = "https://docs.github.com/public/fpt/schema.docs.graphql";
+const GRAPHQL_DIR = join(dirname(fileURLToPath(import.meta.url)), "..", "src-tauri", "graphql");
+const SCHEMA_FILE = "schema.graphql";
+const BUILT_IN_SCALARS = new Set(["Boolean", "Float", "ID", "Int", "String"]);
+
+const HEADER = `# GitHub GraphQL API schema (vendored subset).
+#
+# Generated by maguffin-app/scripts/update-graphql-schema.mjs from GitHub's
+# public schema (${UPSTREAM_URL}),
+# keeping only the types and fields reachable from the operations in this
+# directory. Field names, arguments and nullability match the upstream schema;
+# descriptions and directives are omitted.
+#
+# Operations are checked against this file at compile time by
+# \`#[derive(GraphQLQuery)]\` in \`src/github/queries/mod.rs\`. Don't edit it by
+# hand: after adding or changing an operation, run
+# \`npm run update-graphql-schema\` in maguffin-app and commit the result.
+`;
+
+// ---------------------------------------------------------------------------
+// Lexing
+
+function tokenize(source) {
+  const tokens = [];
+  let i = 0;
+  while (i < source.length) {
+    const c = source[i];
+    if (/[\s,﻿]/.test(c)) {
+      i++;
+    } else if (c === "#") {
+      while (i < source.length && source[i] !== "\n") i++;
+    } else if (source.startsWith('"""', i)) {
+      const start = i;
+      i += 3;
+      while (i < source.length && !source.startsWith('"""', i)) {
+        i += source.startsWith('\\"""', i) ? 4 : 1;
+      }
+      i += 3;
+      tokens.push({ kind: "string", value: source.slice(start, i), start, end: i });
+    } else if (c === '"') {
+      const start = i++;
+      while (i < source.length && source[i] !== '"') {
+        i += source[i] === "\\" ? 2 : 1;
+      }
+      i++;
+      tokens.push({ kind: "string", value: source.slice(start, i), start, end: i });
+    } else if (source.startsWith("...", i)) {
+      tokens.push({ kind: "punct", value: "...", start: i, end: i + 3 });
+      i += 3;
+    } else if (/[A-Za-z_]/.test(c)) {
+      const start = i;
+      while (i < source.length && /\w/.test(source[i])) i++;
+      tokens.push({ kind: "name", value: source.slice(start, i), start, end: i });
+    } else if (/[-\d]/.test(c)) {
+      const start = i++;
+      while (i < source.length && /[\w.+-]/.test(source[i])) i++;
+      tokens.push({ kind: "number", value: source.slice(start, i), start, end: i });
+    } else {
+      tokens.push({ kind: "punct", value: c, start: i, end: i + 1 });
+      i++;
+    }
+  }
+  return tokens;
+}
+
+class Parser {
+  constructor(source) {
+    this.source = source;
+    this.tokens = tokenize(source);
+    this.pos = 0;
+  }
+
+  peek(value) {
+    const token = this.tokens[this.pos];
+    return token !== undefined && (value === undefined || token.value === value);
+  }
+
+  next() {
+    const token = this.tokens[this.pos++];
+    if (token === undefined) throw new Error("Unexpected end of document");
+    return token;
+  }
+
+  expect(value) {
+    const token = this.next();
+    if (token.value !== value) {
+      throw new Error(`Expected '${value}' but found '${token.value}' at offset ${token.start}`);
+    }
+    return token;
+  }
+
+  skipDescription() {
+    if (this.peek() && this.tokens[this.pos].kind === "string") this.pos++;
+  }
+
+  // Skip a balanced group opened by the current token
+  skipGroup() {
+    const open = this.next().value;
+    const close = { "(": ")", "{": "}", "[": "]" }[open];
+    let depth = 1;
+    while (depth > 0) {
+      const { value } = this.next();
+      if (value === open) depth++;
+      else if (value === close) depth--;
+    }
+  }
+
+  skipDirectives() {
+    while (this.peek("@")) {
+      this.pos++;
+      this.next();
+      if (this.peek("(")) this.skipGroup();
+    }
+  }
+
+  // Source text from token `from` up to the current position, on one line
+  textFrom(from) {
+    const start = this.tokens[from].start;
+    const end = this.tokens[this.pos - 1].end;
+    return this.source.slice(start, end).replace(/\s+/g, " ");
+  }
+
+  // Parse a type reference, returning its text and named type
+  typeRef() {
+    const from = this.pos;
+    let depth = 0;
+    while (this.peek("[")) {
+      this.pos++;
+      depth++;
+    }
+    const named = this.next().value;
+    if (this.peek("!")) this.pos++;
+    while (depth-- > 0) {
+      this.expect("]");
+      if (this.peek("!")) this.pos++;
+    }
+    return { text: this.textFrom(from).replace(/\s/g, ""), named };
+  }
+
+  // Skip a value literal such as a default value
+  value() {
+    const from = this.pos;
+    if (this.peek("{") || this.peek("[")) this.skipGroup();
+    else if (this.peek("$")) this.pos += 2;
+    else this.pos++;
+    return this.textFrom(from);
+  }
+
+  // Parse `name: Type = default` definitions, or `$name: ...` variable
+  // definitions, up to the closing delimiter
+  inputValues(close) {
+    const values = [];
+    while (!this.peek(close)) {
+      this.skipDescription();
+      if (this.peek("$")) this.pos++;
+      const name = this.next().value;
+      this.expect(":");
+      const type = this.typeRef();
+      let text = `${name}: ${type.text}`;
+      if (this.peek("=")) {
+        this.pos++;
+        text += ` = ${this.value()}`;
+      }
+      this.skipDirectives();
+      values.push({ name, text, named: type.named });
+    }
+    this.expect(close);
+    return values;
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Schema
+
+function parseSchema(source) {
+  const parser = new Parser(source);
+  const types = new Map();
+  const roots = { query: "Query", mutation: "Mutation" };
+
+  while (parser.peek()) {
+    parser.skipDescription();
+    const keyword = parser.next().value;
+
+    if (keyword === "schema") {
+      parser.skipDirectives();
+      parser.expect("{");
+      while (!parser.peek("}")) {
+        const operation = parser.next().value;
+        parser.expect(":");
+        roots[operation] = parser.next().value;
+      }
+      parser.expect("}");
+      continue;
+    }
+    if (keyword === "directive") {
+      parser.expect("@");
+      parser.next();
+      if (parser.peek("(")) parser.skipGroup();
+      if (parser.peek("repeatable")) parser.pos++;
+      parser.expect("on");
+      if (parser.peek("|")) parser.pos++;
+      parser.next();
+      while (parser.peek("|")) {
+        parser.pos++;
+        parser.next();
+      }
+      continue;
+    }
+
+    const name = parser.next().value;
+    const type = { kind: keyword, name, interfaces: [], fields: [], values: [], members: [] };
+
+    if (keyword === "type" || keyword === "interface") {
+      if (parser.peek("implements")) {
+        parser.pos++;
+        if (parser.peek("&")) parser.pos++;
+        type.interfaces.push(parser.next().value);
+        while (parser.peek("&")) {
+          parser.pos++;
+          type.interfaces.push(parser.next().value);
+        }
+      }
+      parser.skipDirectives();
+      parser.expect("{");
+      while (!parser.peek("}")) {
+        parser.skipDescription();
+        const fieldName = parser.next().value;
+        const args = parser.peek("(") ? (parser.pos++, parser.inputValues(")")) : [];
+        parser.expect(":");
+        const fieldType = parser.typeRef();
+        parser.skipDirectives();
+        type.fields.push({ name: fieldName, args, type: fieldType });
+      }
+      parser.expect("}");
+    } else if (keyword === "input") {
+      parser.skipDirectives();
+      parser.expect("{");
+      type.fields = parser.inputValues("}");
+    } else if (keyword === "enum") {
+      parser.skipDirectives();
+      parser.expect("{");
+      while (!parser.peek("}")) {
+        parser.skipDescription();
+        type.values.push(parser.next().value);
+        parser.skipDirectives();
+      }
+      parser.expect("}");
+    } else if (keyword === "union") {
+      parser.skipDirectives();
+      parser.expect("=");
+      if (parser.peek("|")) parser.pos++;
+      type.members.push(parser.next().value);
+      while (parser.peek("|")) {
+        parser.pos++;
+        type.members.push(parser.next().value);
+      }
+    } else if (keyword === "scalar") {
+      parser.skipDirectives();
+    } else {
+      throw new Error(`Unsupported definition '${keyword} ${name}'`);
+    }
+
+    types.set(name, type);
+  }
+
+  return { types, roots };
+}
+
+// ---------------------------------------------------------------------------
+// Reachability
+
+class Usage {
+  constructor(schema) {
+    this.schema = schema;
+    this.types = new Set();
+    this.fields = new Map();
+  }
+
+  lookup(name) {
+    const type = this.schema.types.get(name);
+    if (type === undefined && !BUILT_IN_SCALARS.has(name)) {
+      throw new Error(`Unknown type '${name}'`);
+    }
+    return type;
+  }
+
+  // Keep a type. Input types bring every type their fields reach. Unions
+  // and interfaces bring all of their possible types, so responses naming
+  // any of them in `__typename` still deserialize.
+  useType(name) {
+    const type = this.lookup(name);
+    if (type === undefined || this.types.has(name)) return;
+    this.types.add(name);
+    if (type.kind === "input") {
+      for (const field of type.fields) this.useType(field.named);
+    } else if (type.kind === "union") {
+      for (const member of type.members) this.useType(member);
+    } else if (type.kind === "interface") {
+      for (const other of this.schema.types.values()) {
+        if (other.interfaces.includes(name)) this.useType(other.name);
+      }
+    }
+  }
+
+  useField(typeName, fieldName) {
+    const type = this.lookup(typeName);
+    const field = type?.fields.find((f) => f.name === fieldName);
+    if (field === undefined) {
+      throw new Error(`Type '${typeName}' has no field '${fieldName}'`);
+    }
+    if (!this.fields.has(typeName)) this.fields.set(typeName, new Set());
+    this.fields.get(typeName).add(fieldName);
+    for (const arg of field.args) this.useType(arg.named);
+    this.useType(field.type.named);
+    return field;
+  }
+
+  // Walk a selection set on `typeName` at the parser's `{`. `fragments`
+  // maps fragment names to their type and the position of their selection.
+  selectionSet(parser, typeName, fragments) {
+    parser.expect("{");
+    while (!parser.peek("}")) {
+      if (parser.peek("...")) {
+        parser.pos++;
+        if (parser.peek("on")) {
+          parser.pos++;
+          const fragmentType = parser.next().value;
+          this.useType(fragmentType);
+          parser.skipDirectives();
+          this.selectionSet(parser, fragmentType, fragments);
+        } else {
+          const name = parser.next().value;
+          const fragment = fragments.get(name);
+          if (fragment === undefined) throw new Error(`Unknown fragment '${name}'`);
+          parser.skipDirectives();
+          const saved = parser.pos;
+          parser.pos = fragment.pos;
+          this.useType(fragment.type);
+          this.selectionSet(parser, fragment.type, fragments);
+          parser.pos = saved;
+        }
+        continue;
+      }
+
+      let fieldName = parser.next().value;
+      if (parser.peek(":")) {
+        parser.pos++;
+        fieldName = parser.next().value;
+      }
+      if (parser.peek("(")) parser.skipGroup();
+      parser.skipDirectives();
+
+      if (fieldName === "__typename") continue;
+      const field = this.useField(typeName, fieldName);
+      if (parser.peek("{")) this.selectionSet(parser, field.type.named, fragments);
+    }
+    parser.expect("}");
+  }
+
+  // Give types nothing selects fields of their first field, since a type
+  // can't be empty
+  fillEmptyTypes() {
+    for (const name of this.types) {
+      const type = this.schema.types.get(name);
+      if ((type.kind === "type" || type.kind === "interface") && !this.fields.has(name)) {
+        this.useField(name, type.fields[0].name);
+      }
+    }
+  }
+
+  // Walk the operations of a document, and the fragments they spread
+  document(source) {
+    const parser = new Parser(source);
+    const fragments = new Map();
+    const operations = [];
+
+    while (parser.peek()) {
+      const keyword = parser.next().value;
+      if (keyword === "fragment") {
+        const name = parser.next().value;
+        parser.expect("on");
+        const type = parser.next().value;
+        parser.skipDirectives();
+        fragments.set(name, { type, pos: parser.pos });
+        parser.skipGroup();
+        continue;
+      }
+
+      const root = this.schema.roots[keyword];
+      if (root === undefined) throw new Error(`Unsupported definition '${keyword}'`);
+      this.useType(root);
+      if (!parser.peek("(") && !parser.peek("{")) parser.next();
+      if (parser.peek("(")) {
+        parser.pos++;
+        for (const variable of parser.inputValues(")")) this.useType(variable.named);
+      }
+      parser.skipDirectives();
+      operations.push({ root, pos: parser.pos });
+      parser.skipGroup();
+    }
+
+    for (const { root, pos } of operations) {
+      parser.pos = pos;
+      this.selectionSet(parser, root, fragments);
+    }
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Output
+
+function print(schema, usage) {
+  const kept = [...schema.types.values()].filter((t) => usage.types.has(t.name));
+  const rootNames = Object.values(schema.roots);
+  const order = (t) => {
+    const root = rootNames.indexOf(t.name);
+    if (t.kind === "scalar") return [0, 0];
+    return root >= 0 ? [1, root] : [2, 0];
+  };
+  kept.sort((a, b) => {
+    const [x, y] = [order(a), order(b)];
+    return x[0] - y[0] || x[1] - y[1] || a.name.localeCompare(b.name);
+  });
+
+  const blocks = [HEADER.trimEnd()];
+  const roots = Object.entries(schema.roots)
+    .filter(([, name]) => usage.types.has(name))
+    .map(([operation, name]) => `  ${operation}: ${name}`);
+  blocks.push(`schema {\n${roots.join("\n")}\n}`);
+
+  for (const type of kept) {
+    if (type.kind === "scalar") {
+      blocks.push(`scalar ${type.name}`);
+    } else if (type.kind === "enum") {
+      blocks.push(`enum ${type.name} {\n${type.values.map((v) => `  ${v}`).join("\n")}\n}`);
+    } else if (type.kind === "union") {
+      blocks.push(`union ${type.name} = ${type.members.join(" | ")}`);
+    } else if (type.kind === "input") {
+      blocks.push(`input ${type.name} {\n${type.fields.map((f) => `  ${f.text}`).join("\n")}\n}`);
+    } else {
+      const used = usage.fields.get(type.name);
+      const fields = type.fields.filter((f) => used.has(f.name));
+      const interfaces = type.interfaces.filter((i) => usage.types.has(i));
+      const implementsClause = interfaces.length ? ` implements ${interfaces.join(" & ")}` : "";
+      const lines = fields.map((f) => {
+        const args = f.args.length ? `(${f.args.map((a) => a.text).join(", ")})` : "";
+        return `  ${f.name}${args}: ${f.type.text}`;
+      });
+      blocks.push(`${type.kind} ${type.name}${implementsClause} {\n${lines.join("\n")}\n}`);
+    }
+  }
+
+  return `${blocks.join("\n\n")}\n`;
+}
+
+async function main() {
+  const upstream = process.argv[2]
+    ? await readFile(process.argv[2], "utf8")
+    : await fetch(UPSTREAM_URL).then((response) => {
+        if (!response.ok) throw new Error(`Fetching ${UPSTREAM_URL}: ${response.status}`);
+        return response.text();
+      });
+
+  const schema = parseSchema(upstream);
+  const usage = new Usage(schema);
+  const operations = (await readdir(GRAPHQL_DIR))
+    .filter((file) => file.endsWith(".graphql") && file !== SCHEMA_FILE)
+    .sort();
+  for (const file of operations) {
+    try {
+      usage.document(await readFile(join(GRAPHQL_DIR, file), "utf8"));
+    } catch (error) {
+      throw new Error(`${file}: ${error.message}`);
+    }
+  }
+  usage.fillEmptyTypes();
+
+  await writeFile(join(GRAPHQL_DIR, SCHEMA_FILE), print(schema, usage));
+  console.log(`Wrote ${usage.types.size} types to ${join(GRAPHQL_DIR, SCHEMA_FILE)}`);
+}
+
+main().catch((error) => {
+  console.error(error.message);
+  process.exit(1);
+});
@@ -2,8 +2,15 @@
 //!
 //! Run with: cargo bench
 
+use chrono::{Duration, Utc};
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use maguffin_app_lib::cache::Cache;
+use maguffin_app_lib::domain::pr::{
+    Author, CheckState, Label, Mergeable, PrQuery, PrSortOrder, PrState, PullRequest,
+    ReviewDecision,
+};
 use std::collections::HashMap;
+use std::sync::Arc;
 use url::Url;
 
 /// Benchmark JSON serialization of PR-like structures
@@ -72,7 +79,7 @@ fn bench_stack_operations(c: &mut Criterion) {
 
     c.bench_function("stack_iteration", |b| {
         b.iter(|| {
-            let count: usize = black_box(stack_data.iter().map(|(_, commits)| commits.len()).sum());
+            let count: usize = black_box(stack_data.values().map(|commits| commits.len()).sum());
             count
         })
     });
@@ -126,11 +133,120 @@ fn bench_cache_operations(c: &mut Criterion) {
     });
 }
 
+/// Number of cached PRs the dashboard benchmarks load from.
+const DASHBOARD_PR_COUNT: i64 = 5_000;
+
+/// A PR with varied filterable fields.
+fn cached_pr(number: i64) -> PullRequest {
+    let now = Utc::now();
+    PullRequest {
+        id: format!("PR_{}", number),
+        number,
+        title: format!("Change {} to the sync engine", number),
+        body: Some(format!(
+            "Refactors module {} and updates its tests. Part of the dashboard work.",
+            number % 97
+        )),
+        state: PrState::Open,
+        is_draft: number % 7 == 0,
+        author: Author {
+            login: format!("user{}", number % 40),
+            avatar_url: String::new(),
+        },
+        head_ref: format!("feature/{}", number),
+        base_ref: if number % 10 == 0 { "release" } else { "main" }.to_string(),
+        labels: vec![Label {
+            name: ["bug", "enhancement", "docs"][(number % 3) as usize].to_string(),
+            color: "d73a4a".to_string(),
+        }],
+        review_decision: Some(
+            [
+                ReviewDecision::Approved,
+                ReviewDecision::ChangesRequested,
+                ReviewDecision::ReviewRequired,
+            ][(number % 3) as usize],
+        ),
+        mergeable: Mergeable::Mergeable,
+        created_at: now - Duration::minutes(number),
+        updated_at: now - Duration::seconds(number * 7 % 3_600),
+        commit_count: 3,
+        additions: 120,
+        deletions: 40,
+        changed_files: 6,
+        check_state: Some(CheckState::Success),
+        auto_merge: None,
+        merge_queue: None,
+        assignees: Vec::new(),
+        milestone: None,
+        requested_reviewers: Vec::new(),
+    }
+}
+
+/// Benchmark loading the PR dashboard from a file cache holding thousands of
+/// PRs: the default list, a filtered saved view, and a search, alone and
+/// with several loads in flight at once.
+fn bench_dashboard_load(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let dir = tempfile::TempDir::new().unwrap();
+    let cache = Arc::new(Cache::open(&dir.path().join("cache.db")).unwrap());
+
+    let prs: Vec<PullRequest> = (1..=DASHBOARD_PR_COUNT).map(cached_pr).collect();
+    rt.block_on(cache.replace_pull_requests("octocat", "hello-world", &prs))
+        .unwrap();
+
+    let view = PrQuery {
+        labels: vec!["bug".to_string()],
+        review_decisions: vec![ReviewDecision::ReviewRequired],
+        draft: Some(false),
+        sort: PrSortOrder::ReviewStatus,
+        ..Default::default()
+    };
+
+    let load = |cache: Arc<Cache>, view: PrQuery| async move {
+        let all = cache
+            .query_pull_requests("octocat", "hello-world", &PrQuery::default())
+            .await
+            .unwrap();
+        let filtered = cache
+            .query_pull_requests("octocat", "hello-world", &view)
+            .await
+            .unwrap();
+        (all.len(), filtered.len())
+    };
+
+    c.bench_function("dashboard_load", |b| {
+        b.iter(|| black_box(rt.block_on(load(cache.clone(), view.clone()))))
+    });
+
+    c.bench_function("dashboard_load_concurrent_8", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let loads: Vec<_> = (0..8)
+                    .map(|_| tokio::spawn(load(cache.clone(), view.clone())))
+                    .collect();
+                for handle in loads {
+                    black_box(handle.await.unwrap());
+                }
+            })
+        })
+    });
+
+    c.bench_function("dashboard_search", |b| {
+        b.iter(|| {
+            black_box(
+                rt.block_on(cache.search_pull_requests("sync refac", 50))
+                    .unwrap(),
+            )
+        })
+    });
+}
+
 criterion_group!(
     benches,
     bench_json_serialization,
     bench_stack_operations,
     bench_url_parsing,
     bench_cache_operations,
+    bench_dashboard_load,
 );
 criterion_main!(benches);
@@ -0,0 +1,44 @@
+-- Cache written by the first release, before schema versioning.
+CREATE TABLE repositories (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE,
+    owner TEXT NOT NULL,
+    name TEXT NOT NULL,
+    last_opened TEXT NOT NULL
+);
+
+CREATE TABLE pull_requests (
+    id INTEGER PRIMARY KEY,
+    repo_id INTEGER NOT NULL,
+    number INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (repo_id) REFERENCES repositories(id),
+    UNIQUE(repo_id, number)
+);
+
+CREATE TABLE settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE pr_templates (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    body TEXT NOT NULL,
+    is_default INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+INSERT INTO repositories (path, owner, name, last_opened)
+VALUES ('/home/octocat/src/hello-world', 'octocat', 'hello-world', '2025-01-02T03:04:05+00:00');
+
+INSERT INTO pull_requests (repo_id, number, data, updated_at)
+VALUES (1, 42, '{}', '2025-01-02T03:04:05+00:00');
+
+INSERT INTO settings (key, value) VALUES ('theme', 'dark');
+
+INSERT INTO pr_templates (id, name, body, is_default, created_at, updated_at)
+VALUES ('6f1c1b5e-3b7a-4a8e-9a59-2f0f5d8e7c11', 'Feature', '## Summary', 1,
+        '2025-01-02T03:04:05+00:00', '2025-01-02T03:04:05+00:00');
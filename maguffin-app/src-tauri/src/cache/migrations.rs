@@ -0,0 +1,472 @@
+//! Cache schema migrations.
+//!
+//! The schema version is kept in SQLite's `user_version` header field and
+//! every migration moves the database up by one version inside a
+//! transaction. Databases written before versioning existed report version
+//! 0 whichever tables they already have, so the migrations up to the first
+//! versioned release only create what is missing.
+
+use crate::error::{Result, StorageError};
+use rusqlite::Connection;
+
+/// One step of the schema history.
+pub struct Migration {
+    /// Version the database is at once the migration has run
+    pub version: u32,
+
+    /// What the migration changes
+    pub description: &'static str,
+
+    /// Statements to run
+    pub sql: &'static str,
+}
+
+/// Every migration, in order. Never edit a released migration; add a new
+/// one instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Repositories, settings and PR templates",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS repositories (
+                id INTEGER PRIMARY KEY,
+                path TEXT NOT NULL UNIQUE,
+                owner TEXT NOT NULL,
+                name TEXT NOT NULL,
+                last_opened TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS pull_requests (
+                id INTEGER PRIMARY KEY,
+                repo_id INTEGER NOT NULL,
+                number INTEGER NOT NULL,
+                data TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (repo_id) REFERENCES repositories(id),
+                UNIQUE(repo_id, number)
+            );
+
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS pr_templates (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                body TEXT NOT NULL,
+                is_default INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+        "#,
+    },
+    Migration {
+        version: 2,
+        description: "ETag cache for conditional requests",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS http_cache (
+                key TEXT PRIMARY KEY,
+                etag TEXT NOT NULL,
+                payload TEXT,
+                updated_at TEXT NOT NULL
+            );
+        "#,
+    },
+    Migration {
+        version: 3,
+        description: "Filterable PR cache",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS cached_pull_requests (
+                owner TEXT NOT NULL,
+                repo TEXT NOT NULL,
+                number INTEGER NOT NULL,
+                data TEXT NOT NULL,
+                title TEXT NOT NULL,
+                body TEXT NOT NULL,
+                author TEXT NOT NULL,
+                head_ref TEXT NOT NULL,
+                base_ref TEXT NOT NULL,
+                is_draft INTEGER NOT NULL,
+                review_decision TEXT,
+                check_state TEXT,
+                mergeable TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (owner, repo, number)
+            );
+        "#,
+    },
+    Migration {
+        // The PR cache gains a rowid for the search index to point at. Its
+        // rows are refetched on the next refresh, so it is recreated empty.
+        version: 4,
+        description: "Full-text search over cached PRs",
+        sql: r#"
+            DROP TRIGGER IF EXISTS cached_pull_requests_ai;
+            DROP TRIGGER IF EXISTS cached_pull_requests_au;
+            DROP TRIGGER IF EXISTS cached_pull_requests_ad;
+            DROP TRIGGER IF EXISTS cached_pr_comments_ai;
+            DROP TABLE IF EXISTS pull_request_search;
+            DROP TABLE IF EXISTS cached_pr_comments;
+            DROP TABLE IF EXISTS cached_pull_requests;
+
+            CREATE TABLE cached_pull_requests (
+                id INTEGER PRIMARY KEY,
+                owner TEXT NOT NULL,
+                repo TEXT NOT NULL,
+                number INTEGER NOT NULL,
+                data TEXT NOT NULL,
+                title TEXT NOT NULL,
+                body TEXT NOT NULL,
+                author TEXT NOT NULL,
+                head_ref TEXT NOT NULL,
+                base_ref TEXT NOT NULL,
+                is_draft INTEGER NOT NULL,
+                review_decision TEXT,
+                check_state TEXT,
+                mergeable TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                UNIQUE (owner, repo, number)
+            );
+
+            CREATE TABLE cached_pr_comments (
+                owner TEXT NOT NULL,
+                repo TEXT NOT NULL,
+                number INTEGER NOT NULL,
+                text TEXT NOT NULL,
+                PRIMARY KEY (owner, repo, number)
+            );
+
+            -- Full-text index over cached PRs, rowid = cached_pull_requests.id.
+            -- Kept in sync by the triggers below.
+            CREATE VIRTUAL TABLE pull_request_search USING fts5(
+                title,
+                body,
+                branches,
+                author,
+                comments,
+                tokenize = 'unicode61 remove_diacritics 2',
+                prefix = '2 3'
+            );
+
+            CREATE TRIGGER cached_pull_requests_ai
+            AFTER INSERT ON cached_pull_requests BEGIN
+                INSERT INTO pull_request_search (rowid, title, body, branches, author, comments)
+                VALUES (
+                    new.id, new.title, new.body, new.head_ref || ' ' || new.base_ref, new.author,
+                    COALESCE((SELECT text FROM cached_pr_comments
+                              WHERE owner = new.owner AND repo = new.repo AND number = new.number), '')
+                );
+            END;
+
+            CREATE TRIGGER cached_pull_requests_au
+            AFTER UPDATE ON cached_pull_requests BEGIN
+                DELETE FROM pull_request_search WHERE rowid = old.id;
+                INSERT INTO pull_request_search (rowid, title, body, branches, author, comments)
+                VALUES (
+                    new.id, new.title, new.body, new.head_ref || ' ' || new.base_ref, new.author,
+                    COALESCE((SELECT text FROM cached_pr_comments
+                              WHERE owner = new.owner AND repo = new.repo AND number = new.number), '')
+                );
+            END;
+
+            CREATE TRIGGER cached_pull_requests_ad
+            AFTER DELETE ON cached_pull_requests BEGIN
+                DELETE FROM pull_request_search WHERE rowid = old.id;
+            END;
+
+            CREATE TRIGGER cached_pr_comments_ai
+            AFTER INSERT ON cached_pr_comments BEGIN
+                UPDATE pull_request_search SET comments = new.text
+                WHERE rowid = (SELECT id FROM cached_pull_requests
+                               WHERE owner = new.owner AND repo = new.repo AND number = new.number);
+            END;
+        "#,
+    },
+    Migration {
+        // Superseded by cached_pull_requests and never read
+        version: 5,
+        description: "Drop the legacy PR table",
+        sql: r#"
+            DROP TABLE IF EXISTS pull_requests;
+        "#,
+    },
+];
+
+/// Schema version this build reads and writes.
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// Schema version recorded in the database.
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| StorageError::Database(e.to_string()).into())
+}
+
+/// Whether the database has any tables yet.
+pub fn is_empty(conn: &Connection) -> Result<bool> {
+    let tables: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| StorageError::Database(e.to_string()))?;
+    Ok(tables == 0)
+}
+
+/// Bring the database up to [`latest_version`].
+///
+/// Each migration commits together with its version bump, so a failure
+/// leaves the database at the last version that fully applied. Databases
+/// from a newer build are refused rather than guessed at.
+pub fn migrate(conn: &mut Connection) -> Result<()> {
+    let current = schema_version(conn)?;
+    let latest = latest_version();
+    if current > latest {
+        return Err(StorageError::Database(format!(
+            "Cache schema version {} is newer than this version of the app supports ({})",
+            current, latest
+        ))
+        .into());
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn
+            .transaction()
+            .map_err(|e| StorageError::Database(e.to_string()))?;
+        tx.execute_batch(migration.sql)
+            .and_then(|_| tx.pragma_update(None, "user_version", migration.version))
+            .map_err(|e| {
+                StorageError::Database(format!(
+                    "Migration {} ({}) failed: {}",
+                    migration.version, migration.description, e
+                ))
+            })?;
+        tx.commit()
+            .map_err(|e| StorageError::Database(e.to_string()))?;
+
+        tracing::info!(
+            "Migrated cache to version {}: {}",
+            migration.version,
+            migration.description
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::Cache;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    /// Caches as written by each schema before versioning existed.
+    const FIXTURES: &[(&str, &str)] = &[
+        (
+            "schema_1_baseline",
+            include_str!("../../fixtures/cache/schema_1_baseline.sql"),
+        ),
+        (
+            "schema_2_http_cache",
+            include_str!("../../fixtures/cache/schema_2_http_cache.sql"),
+        ),
+        (
+            "schema_3_pr_filters",
+            include_str!("../../fixtures/cache/schema_3_pr_filters.sql"),
+        ),
+        (
+            "schema_4_search",
+            include_str!("../../fixtures/cache/schema_4_search.sql"),
+        ),
+    ];
+
+    fn write_fixture(dir: &TempDir, sql: &str) -> PathBuf {
+        let path = dir.path().join("cache.db");
+        Connection::open(&path).unwrap().execute_batch(sql).unwrap();
+        path
+    }
+
+    fn table_exists(conn: &Connection, name: &str) -> bool {
+        conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE name = ?1",
+            [name],
+            |row| row.get::<_, i64>(0),
+        )
+        .unwrap()
+            > 0
+    }
+
+    fn backup_path(path: &Path) -> PathBuf {
+        PathBuf::from(format!("{}.bak", path.display()))
+    }
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, index + 1);
+        }
+    }
+
+    #[test]
+    fn test_migrate_fresh_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert!(is_empty(&conn).unwrap());
+
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+
+        // Running again is a no-op
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn test_migrate_refuses_newer_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+
+        let err = migrate(&mut conn).unwrap_err();
+        assert!(err.to_string().contains("newer"));
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_fixtures() {
+        for (name, sql) in FIXTURES {
+            let dir = TempDir::new().unwrap();
+            let path = write_fixture(&dir, sql);
+
+            let cache = Cache::open(&path).unwrap_or_else(|e| panic!("{}: {}", name, e));
+            let (version, legacy) = cache
+                .pool
+                .read(|conn| Ok((schema_version(conn)?, table_exists(conn, "pull_requests"))))
+                .await
+                .unwrap();
+            assert_eq!(version, latest_version(), "{}", name);
+            assert!(!legacy, "{}", name);
+
+            // User data survives the upgrade
+            assert_eq!(
+                cache.get_setting("theme").await.unwrap().as_deref(),
+                Some("dark")
+            );
+            assert_eq!(cache.get_recent_repositories(10).await.unwrap().len(), 1);
+            let templates = cache.get_templates().await.unwrap();
+            assert_eq!(templates.len(), 1, "{}", name);
+            assert!(templates[0].is_default);
+            if *name != "schema_1_baseline" {
+                assert!(cache
+                    .get_cached_response("pulls:octocat/hello-world")
+                    .await
+                    .unwrap()
+                    .is_some());
+            }
+            if name.starts_with("schema_3") || name.starts_with("schema_4") {
+                assert_eq!(cache.get_saved_views().await.unwrap()[0].name, "Mine");
+            }
+
+            // The rebuilt PR cache and search index work
+            let pr: crate::domain::pr::PullRequest = serde_json::from_value(serde_json::json!({
+                "id": "PR_1",
+                "number": 1,
+                "title": "Add dark mode",
+                "body": null,
+                "state": "OPEN",
+                "is_draft": false,
+                "author": { "login": "octocat", "avatar_url": "" },
+                "head_ref": "dark-mode",
+                "base_ref": "main",
+                "labels": [],
+                "review_decision": null,
+                "mergeable": "MERGEABLE",
+                "created_at": "2025-01-01T00:00:00Z",
+                "updated_at": "2025-01-02T00:00:00Z",
+                "commit_count": 1,
+                "additions": 0,
+                "deletions": 0,
+                "changed_files": 0,
+                "check_state": null,
+                "auto_merge": null,
+                "merge_queue": null,
+                "assignees": [],
+                "milestone": null,
+                "requested_reviewers": []
+            }))
+            .unwrap();
+            cache
+                .replace_pull_requests("octocat", "hello-world", &[pr])
+                .await
+                .unwrap();
+            assert_eq!(
+                cache.search_pull_requests("dark", 10).await.unwrap().len(),
+                1
+            );
+
+            // The backup holds the database as it was
+            let backup = Connection::open(backup_path(&path)).unwrap();
+            assert_eq!(schema_version(&backup).unwrap(), 0);
+            assert!(table_exists(&backup, "pull_requests"), "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_open_current_schema_skips_backup() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cache.db");
+
+        Cache::open(&path)
+            .unwrap()
+            .set_setting("theme", "light")
+            .await
+            .unwrap();
+        assert!(!backup_path(&path).exists());
+
+        let cache = Cache::open(&path).unwrap();
+        assert_eq!(
+            cache.get_setting("theme").await.unwrap().as_deref(),
+            Some("light")
+        );
+        assert!(!backup_path(&path).exists());
+    }
+
+    #[tokio::test]
+    async fn test_open_rebuilds_corrupt_cache() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("cache.db");
+        std::fs::write(
+            &path,
+            b"definitely not an SQLite database, just some junk bytes",
+        )
+        .unwrap();
+
+        let cache = Cache::open(&path).unwrap();
+        cache.set_setting("theme", "dark").await.unwrap();
+        assert!(dir.path().join("cache.db.corrupt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_open_refuses_newer_cache() {
+        let dir = TempDir::new().unwrap();
+        let path = write_fixture(
+            &dir,
+            &format!("PRAGMA user_version = {};", latest_version() + 1),
+        );
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE future (id INTEGER PRIMARY KEY);")
+            .unwrap();
+
+        assert!(Cache::open(&path).is_err());
+
+        // Left untouched for the newer build
+        let conn = Connection::open(&path).unwrap();
+        assert!(table_exists(&conn, "future"));
+        assert!(!backup_path(&path).exists());
+    }
+}
@@ -3,13 +3,19 @@
 //! This module provides persistent local storage for PR data, stack metadata,
 //! PR templates, and other cached information.
 
+pub mod migrations;
+pub mod pool;
+
+use crate::domain::pr::{PrQuery, PrSortOrder, PullRequest, SavedView};
+use crate::domain::search::{self, SearchHit};
 use crate::domain::template::PrTemplate;
 use crate::error::{Result, StorageError};
-use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection};
+use chrono::{DateTime, SecondsFormat, Utc};
+use pool::ConnectionPool;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, ErrorCode};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 /// Recent repository entry.
@@ -21,160 +27,203 @@ pub struct RecentRepository {
     pub last_opened: DateTime<Utc>,
 }
 
+/// Cached response for a conditional (ETag) request.
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    /// ETag returned by the server
+    pub etag: String,
+
+    /// Data derived from the response, if any was stored
+    pub payload: Option<String>,
+
+    /// When the entry was last refreshed
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Settings key under which saved PR list views are stored.
+const SAVED_VIEWS_KEY: &str = "saved_views";
+
 /// Local SQLite cache for the application.
 pub struct Cache {
-    conn: Mutex<Connection>,
+    pool: ConnectionPool,
 }
 
 impl Cache {
     /// Open or create a cache at the given path.
+    ///
+    /// An older database is backed up next to the cache and migrated to the
+    /// current schema. A corrupt database is moved aside and the cache is
+    /// rebuilt empty; everything in it can be refetched.
     pub fn open(path: &Path) -> Result<Self> {
-        let conn = Connection::open(path).map_err(|e| StorageError::Database(e.to_string()))?;
-
-        let cache = Self {
-            conn: Mutex::new(conn),
+        let mut conn = match Self::connect(path)? {
+            Some(conn) => conn,
+            None => {
+                let corrupt = Self::sibling_path(path, "corrupt");
+                tracing::warn!(
+                    "Cache database {} is corrupt; moving it to {} and rebuilding",
+                    path.display(),
+                    corrupt.display()
+                );
+                std::fs::rename(path, &corrupt).map_err(StorageError::Io)?;
+                for suffix in ["-journal", "-wal", "-shm"] {
+                    let _ = std::fs::remove_file(Self::sibling_path(path, suffix));
+                }
+                Connection::open(path).map_err(|e| StorageError::Database(e.to_string()))?
+            }
         };
 
-        cache.initialize()?;
-        Ok(cache)
+        let version = migrations::schema_version(&conn)?;
+        if version < migrations::latest_version() && !migrations::is_empty(&conn)? {
+            Self::backup(&conn, &Self::sibling_path(path, "bak"))?;
+        }
+
+        migrations::migrate(&mut conn)?;
+
+        Ok(Self {
+            pool: ConnectionPool::open(conn, path)?,
+        })
     }
 
     /// Open an in-memory cache (useful for testing).
     pub fn in_memory() -> Result<Self> {
-        let conn =
+        let mut conn =
             Connection::open_in_memory().map_err(|e| StorageError::Database(e.to_string()))?;
 
-        let cache = Self {
-            conn: Mutex::new(conn),
-        };
-
-        cache.initialize()?;
-        Ok(cache)
-    }
-
-    /// Initialize the database schema.
-    fn initialize(&self) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| StorageError::Database(format!("Lock error: {}", e)))?;
-
-        conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS repositories (
-                id INTEGER PRIMARY KEY,
-                path TEXT NOT NULL UNIQUE,
-                owner TEXT NOT NULL,
-                name TEXT NOT NULL,
-                last_opened TEXT NOT NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS pull_requests (
-                id INTEGER PRIMARY KEY,
-                repo_id INTEGER NOT NULL,
-                number INTEGER NOT NULL,
-                data TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                FOREIGN KEY (repo_id) REFERENCES repositories(id),
-                UNIQUE(repo_id, number)
-            );
-
-            CREATE TABLE IF NOT EXISTS settings (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL
-            );
-
-            CREATE TABLE IF NOT EXISTS pr_templates (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                body TEXT NOT NULL,
-                is_default INTEGER NOT NULL DEFAULT 0,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            );
-            "#,
-        )
-        .map_err(|e| StorageError::Database(e.to_string()))?;
+        migrations::migrate(&mut conn)?;
 
-        Ok(())
+        Ok(Self {
+            pool: ConnectionPool::single(conn)?,
+        })
     }
 
-    /// Save a recent repository.
-    pub fn save_recent_repository(&self, path: &str, owner: &str, name: &str) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| StorageError::Database(format!("Lock error: {}", e)))?;
+    /// Open the database at `path`, or `None` if it is corrupt.
+    fn connect(path: &Path) -> Result<Option<Connection>> {
+        let conn = Connection::open(path).map_err(|e| StorageError::Database(e.to_string()))?;
 
-        let now = Utc::now().to_rfc3339();
+        // SQLite only reads the file once it's queried
+        let check = conn.query_row("PRAGMA quick_check", [], |row| row.get::<_, String>(0));
+        match check {
+            Ok(result) if result == "ok" => Ok(Some(conn)),
+            Ok(_) => Ok(None),
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if matches!(e.code, ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(StorageError::Database(e.to_string()).into()),
+        }
+    }
 
+    /// Copy the database to `backup`, replacing any earlier backup.
+    fn backup(conn: &Connection, backup: &Path) -> Result<()> {
+        if backup.exists() {
+            std::fs::remove_file(backup).map_err(StorageError::Io)?;
+        }
         conn.execute(
-            "INSERT OR REPLACE INTO repositories (path, owner, name, last_opened) VALUES (?1, ?2, ?3, ?4)",
-            params![path, owner, name, now],
+            "VACUUM INTO ?1",
+            params![backup.to_string_lossy().into_owned()],
         )
-        .map_err(|e| StorageError::Database(e.to_string()))?;
+        .map_err(|e| StorageError::Database(format!("Failed to back up cache: {}", e)))?;
 
+        tracing::info!("Backed up cache to {}", backup.display());
         Ok(())
     }
 
-    /// Get recent repositories, ordered by last opened (most recent first).
-    pub fn get_recent_repositories(&self, limit: usize) -> Result<Vec<RecentRepository>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| StorageError::Database(format!("Lock error: {}", e)))?;
-
-        let mut stmt = conn
-            .prepare(
-                "SELECT path, owner, name, last_opened FROM repositories ORDER BY last_opened DESC LIMIT ?1",
-            )
-            .map_err(|e| StorageError::Database(e.to_string()))?;
-
-        let repos = stmt
-            .query_map(params![limit as i64], |row| {
-                let path: String = row.get(0)?;
-                let owner: String = row.get(1)?;
-                let name: String = row.get(2)?;
-                let last_opened_str: String = row.get(3)?;
-
-                let last_opened = DateTime::parse_from_rfc3339(&last_opened_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now());
-
-                Ok(RecentRepository {
-                    path,
-                    owner,
-                    name,
-                    last_opened,
-                })
+    /// `path` with `suffix` appended to its file name.
+    fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        if !suffix.starts_with('-') {
+            name.push(".");
+        }
+        name.push(suffix);
+        PathBuf::from(name)
+    }
+
+    /// Save a recent repository.
+    pub async fn save_recent_repository(&self, path: &str, owner: &str, name: &str) -> Result<()> {
+        let (path, owner, name) = (path.to_string(), owner.to_string(), name.to_string());
+        self.pool
+            .write(move |conn| {
+                let now = Utc::now().to_rfc3339();
+
+                conn.execute(
+                    "INSERT OR REPLACE INTO repositories (path, owner, name, last_opened) VALUES (?1, ?2, ?3, ?4)",
+                    params![path, owner, name, now],
+                )
+                .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                Ok(())
             })
-            .map_err(|e| StorageError::Database(e.to_string()))?
-            .collect::<std::result::Result<Vec<_>, _>>()
-            .map_err(|e| StorageError::Database(e.to_string()))?;
+            .await
+    }
 
-        Ok(repos)
+    /// Get recent repositories, ordered by last opened (most recent first).
+    pub async fn get_recent_repositories(&self, limit: usize) -> Result<Vec<RecentRepository>> {
+        self.pool
+            .read(move |conn| {
+                let mut stmt = conn
+                    .prepare(
+                        "SELECT path, owner, name, last_opened FROM repositories ORDER BY last_opened DESC LIMIT ?1",
+                    )
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                let repos = stmt
+                    .query_map(params![limit as i64], |row| {
+                        let path: String = row.get(0)?;
+                        let owner: String = row.get(1)?;
+                        let name: String = row.get(2)?;
+                        let last_opened_str: String = row.get(3)?;
+
+                        let last_opened = DateTime::parse_from_rfc3339(&last_opened_str)
+                            .map(|dt| dt.with_timezone(&Utc))
+                            .unwrap_or_else(|_| Utc::now());
+
+                        Ok(RecentRepository {
+                            path,
+                            owner,
+                            name,
+                            last_opened,
+                        })
+                    })
+                    .map_err(|e| StorageError::Database(e.to_string()))?
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                Ok(repos)
+            })
+            .await
     }
 
     /// Remove a repository from the recent list.
-    pub fn remove_recent_repository(&self, path: &str) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| StorageError::Database(format!("Lock error: {}", e)))?;
-
-        conn.execute("DELETE FROM repositories WHERE path = ?1", params![path])
-            .map_err(|e| StorageError::Database(e.to_string()))?;
-
-        Ok(())
+    pub async fn remove_recent_repository(&self, path: &str) -> Result<()> {
+        let path = path.to_string();
+        self.pool
+            .write(move |conn| {
+                conn.execute("DELETE FROM repositories WHERE path = ?1", params![path])
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                Ok(())
+            })
+            .await
     }
 
     /// Save a setting.
-    pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| StorageError::Database(format!("Lock error: {}", e)))?;
+    pub async fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        let (key, value) = (key.to_string(), value.to_string());
+        self.pool
+            .write(move |conn| Self::write_setting(conn, &key, &value))
+            .await
+    }
+
+    /// Get a setting.
+    pub async fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let key = key.to_string();
+        self.pool
+            .read(move |conn| Self::read_setting(conn, &key))
+            .await
+    }
 
+    fn write_setting(conn: &Connection, key: &str, value: &str) -> Result<()> {
         conn.execute(
             "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
             params![key, value],
@@ -184,13 +233,7 @@ impl Cache {
         Ok(())
     }
 
-    /// Get a setting.
-    pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| StorageError::Database(format!("Lock error: {}", e)))?;
-
+    fn read_setting(conn: &Connection, key: &str) -> Result<Option<String>> {
         let result = conn.query_row(
             "SELECT value FROM settings WHERE key = ?1",
             params![key],
@@ -205,326 +248,1186 @@ impl Cache {
     }
 
     /// Clear all cached data.
-    pub fn clear(&self) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| StorageError::Database(format!("Lock error: {}", e)))?;
-
-        conn.execute_batch(
-            r#"
-            DELETE FROM pull_requests;
-            DELETE FROM repositories;
-            "#,
-        )
-        .map_err(|e| StorageError::Database(e.to_string()))?;
+    pub async fn clear(&self) -> Result<()> {
+        self.pool
+            .write(|conn| {
+                conn.execute_batch(
+                    r#"
+                    DELETE FROM repositories;
+                    DELETE FROM http_cache;
+                    DELETE FROM cached_pull_requests;
+                    DELETE FROM cached_pr_comments;
+                    "#,
+                )
+                .map_err(|e| StorageError::Database(e.to_string()))?;
 
-        Ok(())
+                Ok(())
+            })
+            .await
     }
 
     // ========================================================================
-    // PR Template methods
+    // Conditional request methods
     // ========================================================================
 
-    /// Save a PR template.
-    pub fn save_template(&self, template: &PrTemplate) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| StorageError::Database(format!("Lock error: {}", e)))?;
-
-        // If this template is being set as default, unset any existing default
-        if template.is_default {
-            conn.execute("UPDATE pr_templates SET is_default = 0", [])
+    /// Save the ETag (and optional derived payload) for a request key.
+    pub async fn save_cached_response(
+        &self,
+        key: &str,
+        etag: &str,
+        payload: Option<&str>,
+    ) -> Result<()> {
+        let (key, etag, payload) = (
+            key.to_string(),
+            etag.to_string(),
+            payload.map(str::to_string),
+        );
+        self.pool
+            .write(move |conn| {
+                conn.execute(
+                    "INSERT OR REPLACE INTO http_cache (key, etag, payload, updated_at) VALUES (?1, ?2, ?3, ?4)",
+                    params![key, etag, payload, Utc::now().to_rfc3339()],
+                )
                 .map_err(|e| StorageError::Database(e.to_string()))?;
-        }
 
-        conn.execute(
-            "INSERT OR REPLACE INTO pr_templates (id, name, body, is_default, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
-                template.id.to_string(),
-                template.name,
-                template.body,
-                if template.is_default { 1 } else { 0 },
-                template.created_at.to_rfc3339(),
-                template.updated_at.to_rfc3339(),
-            ],
-        )
-        .map_err(|e| StorageError::Database(e.to_string()))?;
+                Ok(())
+            })
+            .await
+    }
 
-        Ok(())
+    /// Replace the payload cached for a request key, keeping its ETag and
+    /// fetch time.
+    ///
+    /// Does nothing if nothing is cached for the key.
+    pub async fn update_cached_payload(&self, key: &str, payload: &str) -> Result<()> {
+        let (key, payload) = (key.to_string(), payload.to_string());
+        self.pool
+            .write(move |conn| {
+                conn.execute(
+                    "UPDATE http_cache SET payload = ?2 WHERE key = ?1",
+                    params![key, payload],
+                )
+                .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                Ok(())
+            })
+            .await
     }
 
-    /// Get all PR templates.
-    pub fn get_templates(&self) -> Result<Vec<PrTemplate>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| StorageError::Database(format!("Lock error: {}", e)))?;
-
-        let mut stmt = conn
-            .prepare("SELECT id, name, body, is_default, created_at, updated_at FROM pr_templates ORDER BY name")
-            .map_err(|e| StorageError::Database(e.to_string()))?;
-
-        let templates = stmt
-            .query_map([], |row| {
-                let id_str: String = row.get(0)?;
-                let name: String = row.get(1)?;
-                let body: String = row.get(2)?;
-                let is_default: i32 = row.get(3)?;
-                let created_at_str: String = row.get(4)?;
-                let updated_at_str: String = row.get(5)?;
-
-                let id = Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::new_v4());
-                let created_at = DateTime::parse_from_rfc3339(&created_at_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now());
-                let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now());
-
-                Ok(PrTemplate {
-                    id,
-                    name,
-                    body,
-                    is_default: is_default != 0,
-                    created_at,
-                    updated_at,
-                })
+    /// Get the cached response for a request key.
+    pub async fn get_cached_response(&self, key: &str) -> Result<Option<CachedResponse>> {
+        let key = key.to_string();
+        self.pool
+            .read(move |conn| {
+                let result = conn.query_row(
+                    "SELECT etag, payload, updated_at FROM http_cache WHERE key = ?1",
+                    params![key],
+                    |row| {
+                        let etag: String = row.get(0)?;
+                        let payload: Option<String> = row.get(1)?;
+                        let updated_at_str: String = row.get(2)?;
+
+                        let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
+                            .map(|dt| dt.with_timezone(&Utc))
+                            .unwrap_or_else(|_| Utc::now());
+
+                        Ok(CachedResponse {
+                            etag,
+                            payload,
+                            updated_at,
+                        })
+                    },
+                );
+
+                match result {
+                    Ok(cached) => Ok(Some(cached)),
+                    Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                    Err(e) => Err(StorageError::Database(e.to_string()).into()),
+                }
             })
-            .map_err(|e| StorageError::Database(e.to_string()))?
-            .collect::<std::result::Result<Vec<_>, _>>()
-            .map_err(|e| StorageError::Database(e.to_string()))?;
+            .await
+    }
 
-        Ok(templates)
+    /// Remove the cached response for a request key.
+    pub async fn remove_cached_response(&self, key: &str) -> Result<()> {
+        let key = key.to_string();
+        self.pool
+            .write(move |conn| {
+                conn.execute("DELETE FROM http_cache WHERE key = ?1", params![key])
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                Ok(())
+            })
+            .await
     }
 
-    /// Get a specific template by ID.
-    pub fn get_template(&self, id: &Uuid) -> Result<Option<PrTemplate>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| StorageError::Database(format!("Lock error: {}", e)))?;
+    // ========================================================================
+    // Pull request list methods
+    // ========================================================================
 
-        let result = conn.query_row(
-            "SELECT id, name, body, is_default, created_at, updated_at FROM pr_templates WHERE id = ?1",
-            params![id.to_string()],
-            |row| {
-                let id_str: String = row.get(0)?;
-                let name: String = row.get(1)?;
-                let body: String = row.get(2)?;
-                let is_default: i32 = row.get(3)?;
-                let created_at_str: String = row.get(4)?;
-                let updated_at_str: String = row.get(5)?;
-
-                let id = Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::new_v4());
-                let created_at = DateTime::parse_from_rfc3339(&created_at_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now());
-                let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now());
-
-                Ok(PrTemplate {
-                    id,
-                    name,
-                    body,
-                    is_default: is_default != 0,
-                    created_at,
-                    updated_at,
-                })
-            },
+    /// Replace the cached open PRs of a repository.
+    ///
+    /// The filterable fields are stored in their own columns next to the
+    /// serialized PR so [`Cache::query_pull_requests`] can filter and sort in
+    /// SQL. PRs are upserted rather than rewritten, so only PRs that changed
+    /// are re-indexed for search.
+    pub async fn replace_pull_requests(
+        &self,
+        owner: &str,
+        repo: &str,
+        prs: &[PullRequest],
+    ) -> Result<()> {
+        let (owner, repo, prs) = (owner.to_string(), repo.to_string(), prs.to_vec());
+        self.pool
+            .write(move |conn| {
+                let tx = conn
+                    .transaction()
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                let numbers: Vec<i64> = prs.iter().map(|pr| pr.number).collect();
+                let numbers = serde_json::to_string(&numbers)
+                    .map_err(|e| StorageError::Serialization(e.to_string()))?;
+                tx.execute(
+                    "DELETE FROM cached_pull_requests WHERE owner = ?1 AND repo = ?2 AND number NOT IN (SELECT value FROM json_each(?3))",
+                    params![owner, repo, numbers],
+                )
+                .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                {
+                    let mut stmt = tx
+                        .prepare(
+                            "INSERT INTO cached_pull_requests (owner, repo, number, data, title, body, author, head_ref, base_ref, is_draft, review_decision, check_state, mergeable, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+                             ON CONFLICT (owner, repo, number) DO UPDATE SET data = excluded.data, title = excluded.title, body = excluded.body, author = excluded.author, head_ref = excluded.head_ref, base_ref = excluded.base_ref, is_draft = excluded.is_draft, review_decision = excluded.review_decision, check_state = excluded.check_state, mergeable = excluded.mergeable, created_at = excluded.created_at, updated_at = excluded.updated_at
+                             WHERE data != excluded.data",
+                        )
+                        .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                    for pr in &prs {
+                        let data = serde_json::to_string(pr)
+                            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+
+                        stmt.execute(params![
+                            owner,
+                            repo,
+                            pr.number,
+                            data,
+                            pr.title,
+                            pr.body.as_deref().unwrap_or_default(),
+                            pr.author.login,
+                            pr.head_ref,
+                            pr.base_ref,
+                            pr.is_draft,
+                            pr.review_decision.as_ref().map(Self::sql_text),
+                            pr.check_state.as_ref().map(Self::sql_text),
+                            Self::sql_text(&pr.mergeable),
+                            Self::sql_timestamp(&pr.created_at),
+                            Self::sql_timestamp(&pr.updated_at),
+                        ])
+                        .map_err(|e| StorageError::Database(e.to_string()))?;
+                    }
+                }
+
+                tx.commit()
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// Get the cached open PRs of a repository that match a query, in the
+    /// query's sort order.
+    pub async fn query_pull_requests(
+        &self,
+        owner: &str,
+        repo: &str,
+        query: &PrQuery,
+    ) -> Result<Vec<PullRequest>> {
+        let mut sql =
+            String::from("SELECT data FROM cached_pull_requests WHERE owner = ?1 AND repo = ?2");
+        let mut values: Vec<Value> = vec![owner.to_string().into(), repo.to_string().into()];
+
+        // Add a bound value and return its placeholder
+        let mut bind = |value: Value| {
+            values.push(value);
+            format!("?{}", values.len())
+        };
+
+        let mut any_of = |column: &str, items: Vec<String>| {
+            if items.is_empty() {
+                return String::new();
+            }
+            let placeholders: Vec<String> = items.into_iter().map(|v| bind(v.into())).collect();
+            format!(" AND {} IN ({})", column, placeholders.join(", "))
+        };
+        let authors = any_of("author", query.authors.clone());
+        let reviews = any_of(
+            "review_decision",
+            query.review_decisions.iter().map(Self::sql_text).collect(),
         );
+        let checks = any_of(
+            "check_state",
+            query.check_states.iter().map(Self::sql_text).collect(),
+        );
+        let mergeable = any_of(
+            "mergeable",
+            query.mergeable.iter().map(Self::sql_text).collect(),
+        );
+        sql.push_str(&authors);
+        sql.push_str(&reviews);
+        sql.push_str(&checks);
+        sql.push_str(&mergeable);
+
+        for label in &query.labels {
+            sql.push_str(&format!(
+                " AND EXISTS (SELECT 1 FROM json_each(data, '$.labels') WHERE json_extract(value, '$.name') = {})",
+                bind(label.clone().into())
+            ));
+        }
+        if let Some(draft) = query.draft {
+            sql.push_str(&format!(
+                " AND is_draft = {}",
+                bind(i64::from(draft).into())
+            ));
+        }
+        if let Some(base) = &query.base {
+            sql.push_str(&format!(" AND base_ref GLOB {}", bind(base.clone().into())));
+        }
+        if let Some(base) = &query.base_branch {
+            sql.push_str(&format!(" AND base_ref = {}", bind(base.clone().into())));
+        }
+        if let Some(head) = &query.head {
+            sql.push_str(&format!(" AND head_ref GLOB {}", bind(head.clone().into())));
+        }
+        for term in query.terms() {
+            if let Some(number) = term.strip_prefix('#').and_then(|n| n.parse::<i64>().ok()) {
+                sql.push_str(&format!(" AND number = {}", bind(number.into())));
+                continue;
+            }
+            let escaped = term
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            let pattern = bind(format!("%{}%", escaped).into());
+            sql.push_str(&format!(
+                " AND (title LIKE {0} ESCAPE '\\' OR body LIKE {0} ESCAPE '\\' OR head_ref LIKE {0} ESCAPE '\\' OR author LIKE {0} ESCAPE '\\')",
+                pattern
+            ));
+        }
 
-        match result {
-            Ok(template) => Ok(Some(template)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(StorageError::Database(e.to_string()).into()),
+        sql.push_str(match query.sort {
+            PrSortOrder::CreatedAt => " ORDER BY created_at DESC, number DESC",
+            PrSortOrder::UpdatedAt => " ORDER BY updated_at DESC, number DESC",
+            PrSortOrder::ReviewStatus => {
+                " ORDER BY CASE review_decision WHEN 'REVIEW_REQUIRED' THEN 0 WHEN 'CHANGES_REQUESTED' THEN 1 WHEN 'APPROVED' THEN 2 ELSE 3 END, updated_at DESC, number DESC"
+            }
+        });
+
+        self.pool
+            .read(move |conn| {
+                let mut stmt = conn
+                    .prepare(&sql)
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                let rows = stmt
+                    .query_map(params_from_iter(values), |row| row.get::<_, String>(0))
+                    .map_err(|e| StorageError::Database(e.to_string()))?
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                rows.iter()
+                    .map(|data| {
+                        serde_json::from_str(data)
+                            .map_err(|e| StorageError::Serialization(e.to_string()).into())
+                    })
+                    .collect()
+            })
+            .await
+    }
+
+    /// Index the text of a PR's comments for search, replacing what was
+    /// indexed before.
+    pub async fn index_pull_request_comments(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: i64,
+        comments: &[String],
+    ) -> Result<()> {
+        let (owner, repo, text) = (owner.to_string(), repo.to_string(), comments.join("\n\n"));
+        self.pool
+            .write(move |conn| {
+                conn.execute(
+                    "INSERT OR REPLACE INTO cached_pr_comments (owner, repo, number, text) VALUES (?1, ?2, ?3, ?4)",
+                    params![owner, repo, number, text],
+                )
+                .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// Search the cached PRs of every repository, best matches first.
+    ///
+    /// Every word of `text` must match the title, body, branch names, author
+    /// or comments of a PR, as a prefix. Title matches rank highest.
+    pub async fn search_pull_requests(&self, text: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let Some(query) = search::fts_query(text) else {
+            return Ok(Vec::new());
+        };
+
+        self.pool
+            .read(move |conn| {
+                // bm25() is lower for better matches; weights follow the column order
+                let mut stmt = conn
+                    .prepare(
+                        "SELECT p.owner, p.repo, p.data,
+                                bm25(pull_request_search, 10.0, 2.0, 5.0, 5.0, 1.0) AS rank,
+                                highlight(pull_request_search, 0, ?3, ?4),
+                                snippet(pull_request_search, -1, ?3, ?4, '…', 16)
+                         FROM pull_request_search
+                         JOIN cached_pull_requests p ON p.id = pull_request_search.rowid
+                         WHERE pull_request_search MATCH ?1
+                         ORDER BY rank
+                         LIMIT ?2",
+                    )
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                let rows = stmt
+                    .query_map(
+                        params![
+                            query,
+                            limit as i64,
+                            search::MATCH_START.to_string(),
+                            search::MATCH_END.to_string()
+                        ],
+                        |row| {
+                            Ok((
+                                row.get::<_, String>(0)?,
+                                row.get::<_, String>(1)?,
+                                row.get::<_, String>(2)?,
+                                row.get::<_, f64>(3)?,
+                                row.get::<_, String>(4)?,
+                                row.get::<_, String>(5)?,
+                            ))
+                        },
+                    )
+                    .map_err(|e| StorageError::Database(e.to_string()))?
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                rows.into_iter()
+                    .map(|(owner, repo, data, rank, title, snippet)| {
+                        let pull_request = serde_json::from_str(&data)
+                            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+                        Ok(SearchHit {
+                            owner,
+                            repo,
+                            pull_request,
+                            score: -rank,
+                            title: search::parse_highlight(&title),
+                            snippet: search::parse_highlight(&snippet),
+                        })
+                    })
+                    .collect()
+            })
+            .await
+    }
+
+    /// Text stored for an enum column: its serialized name.
+    fn sql_text<T: Serialize>(value: &T) -> String {
+        match serde_json::to_value(value) {
+            Ok(serde_json::Value::String(text)) => text,
+            _ => String::new(),
         }
     }
 
-    /// Get the default template, if one exists.
-    pub fn get_default_template(&self) -> Result<Option<PrTemplate>> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| StorageError::Database(format!("Lock error: {}", e)))?;
+    /// Fixed-width timestamp, so that text order is chronological order.
+    fn sql_timestamp(value: &DateTime<Utc>) -> String {
+        value.to_rfc3339_opts(SecondsFormat::Micros, true)
+    }
 
-        let result = conn.query_row(
-            "SELECT id, name, body, is_default, created_at, updated_at FROM pr_templates WHERE is_default = 1 LIMIT 1",
-            [],
-            |row| {
-                let id_str: String = row.get(0)?;
-                let name: String = row.get(1)?;
-                let body: String = row.get(2)?;
-                let is_default: i32 = row.get(3)?;
-                let created_at_str: String = row.get(4)?;
-                let updated_at_str: String = row.get(5)?;
-
-                let id = Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::new_v4());
-                let created_at = DateTime::parse_from_rfc3339(&created_at_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now());
-                let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(|_| Utc::now());
-
-                Ok(PrTemplate {
-                    id,
-                    name,
-                    body,
-                    is_default: is_default != 0,
-                    created_at,
-                    updated_at,
-                })
-            },
-        );
+    // ========================================================================
+    // Saved view methods
+    // ========================================================================
 
-        match result {
-            Ok(template) => Ok(Some(template)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(StorageError::Database(e.to_string()).into()),
+    /// Get the saved PR list views, in the order they were created.
+    pub async fn get_saved_views(&self) -> Result<Vec<SavedView>> {
+        self.pool.read(Self::read_saved_views).await
+    }
+
+    /// Save a PR list view, replacing any view with the same name.
+    pub async fn save_view(&self, view: &SavedView) -> Result<()> {
+        let view = view.clone();
+        self.pool
+            .write(move |conn| {
+                let mut views = Self::read_saved_views(conn)?;
+                match views.iter_mut().find(|v| v.name == view.name) {
+                    Some(existing) => *existing = view,
+                    None => views.push(view),
+                }
+                Self::write_saved_views(conn, &views)
+            })
+            .await
+    }
+
+    /// Delete a saved view by name.
+    pub async fn delete_view(&self, name: &str) -> Result<bool> {
+        let name = name.to_string();
+        self.pool
+            .write(move |conn| {
+                let mut views = Self::read_saved_views(conn)?;
+                let count = views.len();
+                views.retain(|v| v.name != name);
+                if views.len() == count {
+                    return Ok(false);
+                }
+                Self::write_saved_views(conn, &views)?;
+                Ok(true)
+            })
+            .await
+    }
+
+    fn read_saved_views(conn: &Connection) -> Result<Vec<SavedView>> {
+        match Self::read_setting(conn, SAVED_VIEWS_KEY)? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| StorageError::Serialization(e.to_string()).into()),
+            None => Ok(Vec::new()),
         }
     }
 
+    fn write_saved_views(conn: &Connection, views: &[SavedView]) -> Result<()> {
+        let json =
+            serde_json::to_string(views).map_err(|e| StorageError::Serialization(e.to_string()))?;
+        Self::write_setting(conn, SAVED_VIEWS_KEY, &json)
+    }
+
+    // ========================================================================
+    // PR Template methods
+    // ========================================================================
+
+    /// Save a PR template.
+    pub async fn save_template(&self, template: &PrTemplate) -> Result<()> {
+        let template = template.clone();
+        self.pool
+            .write(move |conn| {
+                let tx = conn
+                    .transaction()
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                // If this template is being set as default, unset any existing default
+                if template.is_default {
+                    tx.execute("UPDATE pr_templates SET is_default = 0", [])
+                        .map_err(|e| StorageError::Database(e.to_string()))?;
+                }
+
+                tx.execute(
+                    "INSERT OR REPLACE INTO pr_templates (id, name, body, is_default, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        template.id.to_string(),
+                        template.name,
+                        template.body,
+                        if template.is_default { 1 } else { 0 },
+                        template.created_at.to_rfc3339(),
+                        template.updated_at.to_rfc3339(),
+                    ],
+                )
+                .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                tx.commit()
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// Get all PR templates.
+    pub async fn get_templates(&self) -> Result<Vec<PrTemplate>> {
+        self.pool
+            .read(|conn| {
+                let mut stmt = conn
+                    .prepare("SELECT id, name, body, is_default, created_at, updated_at FROM pr_templates ORDER BY name")
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                let templates = stmt
+                    .query_map([], Self::template_from_row)
+                    .map_err(|e| StorageError::Database(e.to_string()))?
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                Ok(templates)
+            })
+            .await
+    }
+
+    /// Get a specific template by ID.
+    pub async fn get_template(&self, id: &Uuid) -> Result<Option<PrTemplate>> {
+        let id = id.to_string();
+        self.pool
+            .read(move |conn| {
+                let result = conn.query_row(
+                    "SELECT id, name, body, is_default, created_at, updated_at FROM pr_templates WHERE id = ?1",
+                    params![id],
+                    Self::template_from_row,
+                );
+
+                match result {
+                    Ok(template) => Ok(Some(template)),
+                    Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                    Err(e) => Err(StorageError::Database(e.to_string()).into()),
+                }
+            })
+            .await
+    }
+
+    /// Get the default template, if one exists.
+    pub async fn get_default_template(&self) -> Result<Option<PrTemplate>> {
+        self.pool
+            .read(|conn| {
+                let result = conn.query_row(
+                    "SELECT id, name, body, is_default, created_at, updated_at FROM pr_templates WHERE is_default = 1 LIMIT 1",
+                    [],
+                    Self::template_from_row,
+                );
+
+                match result {
+                    Ok(template) => Ok(Some(template)),
+                    Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                    Err(e) => Err(StorageError::Database(e.to_string()).into()),
+                }
+            })
+            .await
+    }
+
     /// Delete a template by ID.
-    pub fn delete_template(&self, id: &Uuid) -> Result<bool> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| StorageError::Database(format!("Lock error: {}", e)))?;
-
-        let rows_affected = conn
-            .execute(
-                "DELETE FROM pr_templates WHERE id = ?1",
-                params![id.to_string()],
-            )
-            .map_err(|e| StorageError::Database(e.to_string()))?;
+    pub async fn delete_template(&self, id: &Uuid) -> Result<bool> {
+        let id = id.to_string();
+        self.pool
+            .write(move |conn| {
+                let rows_affected = conn
+                    .execute("DELETE FROM pr_templates WHERE id = ?1", params![id])
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+
+                Ok(rows_affected > 0)
+            })
+            .await
+    }
 
-        Ok(rows_affected > 0)
+    fn template_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<PrTemplate> {
+        let id_str: String = row.get(0)?;
+        let name: String = row.get(1)?;
+        let body: String = row.get(2)?;
+        let is_default: i32 = row.get(3)?;
+        let created_at_str: String = row.get(4)?;
+        let updated_at_str: String = row.get(5)?;
+
+        let id = Uuid::parse_str(&id_str).unwrap_or_else(|_| Uuid::new_v4());
+        let created_at = DateTime::parse_from_rfc3339(&created_at_str)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+        let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+
+        Ok(PrTemplate {
+            id,
+            name,
+            body,
+            is_default: is_default != 0,
+            created_at,
+            updated_at,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::pr::{Author, CheckState, Label, Mergeable, PrState, ReviewDecision};
+    use crate::domain::search::TextSpan;
+    use chrono::Duration;
+
+    fn make_pr(number: i64, title: &str, author: &str, head_ref: &str) -> PullRequest {
+        let now = Utc::now();
+        PullRequest {
+            id: format!("PR_{}", number),
+            number,
+            title: title.to_string(),
+            body: None,
+            state: PrState::Open,
+            is_draft: false,
+            author: Author {
+                login: author.to_string(),
+                avatar_url: String::new(),
+            },
+            head_ref: head_ref.to_string(),
+            base_ref: "main".to_string(),
+            labels: Vec::new(),
+            review_decision: None,
+            mergeable: Mergeable::Mergeable,
+            created_at: now - Duration::days(number),
+            updated_at: now - Duration::hours(10 - number),
+            commit_count: 1,
+            additions: 0,
+            deletions: 0,
+            changed_files: 0,
+            check_state: None,
+            auto_merge: None,
+            merge_queue: None,
+            assignees: Vec::new(),
+            milestone: None,
+            requested_reviewers: Vec::new(),
+        }
+    }
+
+    fn sample_prs() -> Vec<PullRequest> {
+        let mut fix = make_pr(1, "Fix 100% CPU on startup", "alice", "fix/cpu");
+        fix.review_decision = Some(ReviewDecision::Approved);
+        fix.check_state = Some(CheckState::Success);
+        fix.labels = vec![
+            Label {
+                name: "bug".to_string(),
+                color: "d73a4a".to_string(),
+            },
+            Label {
+                name: "urgent".to_string(),
+                color: "b60205".to_string(),
+            },
+        ];
+
+        let mut feature = make_pr(2, "Add dark mode", "bob", "feature/dark-mode");
+        feature.review_decision = Some(ReviewDecision::ReviewRequired);
+        feature.check_state = Some(CheckState::Failure);
+        feature.is_draft = true;
+        feature.labels = vec![Label {
+            name: "bug".to_string(),
+            color: "d73a4a".to_string(),
+        }];
+
+        let mut release = make_pr(3, "Release 1.2", "alice", "release/1.2");
+        release.review_decision = Some(ReviewDecision::ChangesRequested);
+        release.mergeable = Mergeable::Conflicting;
+        release.body = Some("Bumps the version".to_string());
+
+        vec![fix, feature, release]
+    }
+
+    fn numbers(prs: Vec<PullRequest>) -> Vec<i64> {
+        prs.into_iter().map(|pr| pr.number).collect()
+    }
+
+    async fn query_numbers(cache: &Cache, query: PrQuery) -> Vec<i64> {
+        numbers(cache.query_pull_requests("o", "r", &query).await.unwrap())
+    }
+
+    async fn sorted_numbers(cache: &Cache, sort: PrSortOrder) -> Vec<i64> {
+        let query = PrQuery {
+            sort,
+            ..Default::default()
+        };
+        query_numbers(cache, query).await
+    }
+
+    async fn search_numbers(cache: &Cache, text: &str) -> Vec<i64> {
+        cache
+            .search_pull_requests(text, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|hit| hit.pull_request.number)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_query_pull_requests_filters() {
+        let cache = Cache::in_memory().unwrap();
+        cache
+            .replace_pull_requests("o", "r", &sample_prs())
+            .await
+            .unwrap();
+
+        assert_eq!(query_numbers(&cache, PrQuery::default()).await, [3, 2, 1]);
+        assert_eq!(
+            query_numbers(
+                &cache,
+                PrQuery {
+                    authors: vec!["alice".to_string()],
+                    ..Default::default()
+                }
+            )
+            .await,
+            [3, 1]
+        );
+        assert_eq!(
+            query_numbers(
+                &cache,
+                PrQuery {
+                    labels: vec!["bug".to_string(), "urgent".to_string()],
+                    ..Default::default()
+                }
+            )
+            .await,
+            [1]
+        );
+        assert_eq!(
+            query_numbers(
+                &cache,
+                PrQuery {
+                    draft: Some(false),
+                    review_decisions: vec![
+                        ReviewDecision::Approved,
+                        ReviewDecision::ChangesRequested
+                    ],
+                    ..Default::default()
+                }
+            )
+            .await,
+            [3, 1]
+        );
+        assert_eq!(
+            query_numbers(
+                &cache,
+                PrQuery {
+                    check_states: vec![CheckState::Failure],
+                    ..Default::default()
+                }
+            )
+            .await,
+            [2]
+        );
+        assert_eq!(
+            query_numbers(
+                &cache,
+                PrQuery {
+                    mergeable: vec![Mergeable::Conflicting],
+                    ..Default::default()
+                }
+            )
+            .await,
+            [3]
+        );
+        assert_eq!(
+            query_numbers(
+                &cache,
+                PrQuery {
+                    head: Some("feature/*".to_string()),
+                    base: Some("main".to_string()),
+                    ..Default::default()
+                }
+            )
+            .await,
+            [2]
+        );
+        assert_eq!(
+            query_numbers(
+                &cache,
+                PrQuery {
+                    text: Some("100%".to_string()),
+                    ..Default::default()
+                }
+            )
+            .await,
+            [1]
+        );
+        assert_eq!(
+            query_numbers(
+                &cache,
+                PrQuery {
+                    text: Some("VERSION alice".to_string()),
+                    ..Default::default()
+                }
+            )
+            .await,
+            [3]
+        );
+        assert_eq!(
+            query_numbers(
+                &cache,
+                PrQuery {
+                    text: Some("#2".to_string()),
+                    ..Default::default()
+                }
+            )
+            .await,
+            [2]
+        );
+
+        // Other repositories are unaffected by replacing one
+        cache
+            .replace_pull_requests("o", "other", &[])
+            .await
+            .unwrap();
+        cache
+            .replace_pull_requests("o", "r", &sample_prs()[..1])
+            .await
+            .unwrap();
+        assert_eq!(query_numbers(&cache, PrQuery::default()).await, [1]);
+    }
+
+    #[tokio::test]
+    async fn test_query_pull_requests_exact_base() {
+        let cache = Cache::in_memory().unwrap();
+        let mut prs = sample_prs();
+        prs[0].base_ref = "release/[1.x]".to_string();
+        cache.replace_pull_requests("o", "r", &prs).await.unwrap();
+
+        // Glob metacharacters in the branch name are matched literally
+        let base = |branch: &str| PrQuery {
+            base_branch: Some(branch.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(query_numbers(&cache, base("release/[1.x]")).await, [1]);
+        assert_eq!(query_numbers(&cache, base("main")).await, [3, 2]);
+        assert!(query_numbers(&cache, base("mai?")).await.is_empty());
+    }
 
-    #[test]
-    fn test_cache_in_memory() {
+    #[tokio::test]
+    async fn test_query_pull_requests_sorting() {
         let cache = Cache::in_memory().unwrap();
-        assert!(cache.get_setting("test").unwrap().is_none());
+        cache
+            .replace_pull_requests("o", "r", &sample_prs())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            sorted_numbers(&cache, PrSortOrder::CreatedAt).await,
+            [1, 2, 3]
+        );
+        assert_eq!(
+            sorted_numbers(&cache, PrSortOrder::UpdatedAt).await,
+            [3, 2, 1]
+        );
+        assert_eq!(
+            sorted_numbers(&cache, PrSortOrder::ReviewStatus).await,
+            [2, 3, 1]
+        );
     }
 
-    #[test]
-    fn test_cache_set_get_setting() {
+    #[tokio::test]
+    async fn test_search_pull_requests() {
         let cache = Cache::in_memory().unwrap();
-        cache.set_setting("test_key", "test_value").unwrap();
+        cache
+            .replace_pull_requests("o", "r", &sample_prs())
+            .await
+            .unwrap();
+
+        assert_eq!(search_numbers(&cache, "dark").await, [2]);
+        assert_eq!(search_numbers(&cache, "mod").await, [2]);
+        let mut by_alice = search_numbers(&cache, "alice").await;
+        by_alice.sort();
+        assert_eq!(by_alice, [1, 3]);
+        assert_eq!(search_numbers(&cache, "versión").await, [3]);
+        assert_eq!(search_numbers(&cache, "release").await, [3]);
+        assert!(search_numbers(&cache, "dark alice").await.is_empty());
+        assert!(search_numbers(&cache, "  ").await.is_empty());
+
+        // Title matches outrank body matches
+        let mut body_only = make_pr(4, "Refactor settings", "carol", "refactor");
+        body_only.body = Some("Prepares for dark mode".to_string());
+        let mut prs = sample_prs();
+        prs.push(body_only);
+        cache.replace_pull_requests("o", "r", &prs).await.unwrap();
+        assert_eq!(search_numbers(&cache, "dark").await, [2, 4]);
+
+        let hit = cache
+            .search_pull_requests("dark", 1)
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!((hit.owner.as_str(), hit.repo.as_str()), ("o", "r"));
+        assert!(hit.score > 0.0);
+        assert_eq!(
+            hit.title,
+            [
+                TextSpan {
+                    text: "Add ".to_string(),
+                    matched: false,
+                },
+                TextSpan {
+                    text: "dark".to_string(),
+                    matched: true,
+                },
+                TextSpan {
+                    text: " mode".to_string(),
+                    matched: false,
+                },
+            ]
+        );
+    }
 
-        let value = cache.get_setting("test_key").unwrap();
+    #[tokio::test]
+    async fn test_search_index_follows_cache() {
+        let cache = Cache::in_memory().unwrap();
+        cache
+            .replace_pull_requests("o", "r", &sample_prs())
+            .await
+            .unwrap();
+        cache
+            .replace_pull_requests(
+                "o",
+                "other",
+                &[make_pr(7, "Add dark theme", "dave", "theme")],
+            )
+            .await
+            .unwrap();
+
+        // Searches span repositories
+        assert_eq!(search_numbers(&cache, "dark").await.len(), 2);
+
+        // Comments are indexed, before or after the PR is cached
+        cache
+            .index_pull_request_comments("o", "r", 1, &["Profiled with flamegraph".to_string()])
+            .await
+            .unwrap();
+        cache
+            .index_pull_request_comments("o", "r", 9, &["Needs a changelog entry".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(search_numbers(&cache, "flamegraph").await, [1]);
+        let hit = cache
+            .search_pull_requests("flamegraph", 1)
+            .await
+            .unwrap()
+            .remove(0);
+        assert!(hit
+            .snippet
+            .iter()
+            .any(|span| span.matched && span.text == "flamegraph"));
+
+        cache
+            .index_pull_request_comments("o", "r", 1, &["Looks good".to_string()])
+            .await
+            .unwrap();
+        assert!(search_numbers(&cache, "flamegraph").await.is_empty());
+
+        let mut prs = sample_prs();
+        prs.push(make_pr(9, "Update docs", "erin", "docs"));
+        prs[1].title = "Add light mode".to_string();
+        cache.replace_pull_requests("o", "r", &prs).await.unwrap();
+        assert_eq!(search_numbers(&cache, "changelog").await, [9]);
+        assert_eq!(search_numbers(&cache, "light").await, [2]);
+        // #2 is still found by its branch name, below the title match
+        assert_eq!(search_numbers(&cache, "dark").await, [7, 2]);
+        // Unchanged PRs keep their comments
+        assert_eq!(search_numbers(&cache, "looks good").await, [1]);
+
+        // Closed PRs drop out of the index
+        cache
+            .replace_pull_requests("o", "r", &prs[..1])
+            .await
+            .unwrap();
+        assert!(search_numbers(&cache, "light").await.is_empty());
+        assert!(search_numbers(&cache, "changelog").await.is_empty());
+
+        cache.clear().await.unwrap();
+        assert!(search_numbers(&cache, "looks").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_saved_views() {
+        let cache = Cache::in_memory().unwrap();
+        assert!(cache.get_saved_views().await.unwrap().is_empty());
+
+        let mine = SavedView {
+            name: "Mine".to_string(),
+            query: PrQuery {
+                authors: vec!["alice".to_string()],
+                ..Default::default()
+            },
+        };
+        let drafts = SavedView {
+            name: "Drafts".to_string(),
+            query: PrQuery {
+                draft: Some(true),
+                ..Default::default()
+            },
+        };
+        cache.save_view(&mine).await.unwrap();
+        cache.save_view(&drafts).await.unwrap();
+
+        let updated = SavedView {
+            query: PrQuery {
+                sort: PrSortOrder::CreatedAt,
+                ..mine.query.clone()
+            },
+            ..mine
+        };
+        cache.save_view(&updated).await.unwrap();
+
+        let views = cache.get_saved_views().await.unwrap();
+        assert_eq!(views, [updated, drafts]);
+
+        assert!(cache.delete_view("Drafts").await.unwrap());
+        assert!(!cache.delete_view("Drafts").await.unwrap());
+        assert_eq!(cache.get_saved_views().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_in_memory() {
+        let cache = Cache::in_memory().unwrap();
+        assert!(cache.get_setting("test").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cache_set_get_setting() {
+        let cache = Cache::in_memory().unwrap();
+        cache.set_setting("test_key", "test_value").await.unwrap();
+
+        let value = cache.get_setting("test_key").await.unwrap();
         assert_eq!(value, Some("test_value".to_string()));
     }
 
-    #[test]
-    fn test_cache_overwrite_setting() {
+    #[tokio::test]
+    async fn test_cache_overwrite_setting() {
         let cache = Cache::in_memory().unwrap();
-        cache.set_setting("key", "value1").unwrap();
-        cache.set_setting("key", "value2").unwrap();
+        cache.set_setting("key", "value1").await.unwrap();
+        cache.set_setting("key", "value2").await.unwrap();
 
-        let value = cache.get_setting("key").unwrap();
+        let value = cache.get_setting("key").await.unwrap();
         assert_eq!(value, Some("value2".to_string()));
     }
 
-    #[test]
-    fn test_cache_clear() {
+    #[tokio::test]
+    async fn test_cache_clear() {
         let cache = Cache::in_memory().unwrap();
-        cache.set_setting("key", "value").unwrap();
-        cache.clear().unwrap();
+        cache.set_setting("key", "value").await.unwrap();
+        cache.clear().await.unwrap();
 
         // Settings are not cleared, only data tables
-        let value = cache.get_setting("key").unwrap();
+        let value = cache.get_setting("key").await.unwrap();
         assert_eq!(value, Some("value".to_string()));
     }
 
-    #[test]
-    fn test_recent_repositories() {
+    #[tokio::test]
+    async fn test_cached_response_roundtrip() {
+        let cache = Cache::in_memory().unwrap();
+        assert!(cache
+            .get_cached_response("repos/o/r/pulls")
+            .await
+            .unwrap()
+            .is_none());
+
+        cache
+            .save_cached_response("repos/o/r/pulls", "W/\"abc\"", Some("[]"))
+            .await
+            .unwrap();
+        let cached = cache
+            .get_cached_response("repos/o/r/pulls")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cached.etag, "W/\"abc\"");
+        assert_eq!(cached.payload.as_deref(), Some("[]"));
+
+        // Overwrite with a new ETag and no payload
+        cache
+            .save_cached_response("repos/o/r/pulls", "W/\"def\"", None)
+            .await
+            .unwrap();
+        let cached = cache
+            .get_cached_response("repos/o/r/pulls")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cached.etag, "W/\"def\"");
+        assert!(cached.payload.is_none());
+
+        cache.clear().await.unwrap();
+        assert!(cache
+            .get_cached_response("repos/o/r/pulls")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_recent_repositories() {
         let cache = Cache::in_memory().unwrap();
 
         cache
             .save_recent_repository("/path/to/repo1", "owner1", "repo1")
+            .await
             .unwrap();
         cache
             .save_recent_repository("/path/to/repo2", "owner2", "repo2")
+            .await
             .unwrap();
 
-        let repos = cache.get_recent_repositories(10).unwrap();
+        let repos = cache.get_recent_repositories(10).await.unwrap();
         assert_eq!(repos.len(), 2);
         // Most recent first
         assert_eq!(repos[0].name, "repo2");
         assert_eq!(repos[1].name, "repo1");
     }
 
-    #[test]
-    fn test_remove_recent_repository() {
+    #[tokio::test]
+    async fn test_remove_recent_repository() {
         let cache = Cache::in_memory().unwrap();
 
         cache
             .save_recent_repository("/path/to/repo1", "owner1", "repo1")
+            .await
+            .unwrap();
+        cache
+            .remove_recent_repository("/path/to/repo1")
+            .await
             .unwrap();
-        cache.remove_recent_repository("/path/to/repo1").unwrap();
 
-        let repos = cache.get_recent_repositories(10).unwrap();
+        let repos = cache.get_recent_repositories(10).await.unwrap();
         assert_eq!(repos.len(), 0);
     }
 
-    #[test]
-    fn test_save_and_get_template() {
+    #[tokio::test]
+    async fn test_save_and_get_template() {
         let cache = Cache::in_memory().unwrap();
         let template = PrTemplate::new("Test Template".to_string(), "Test body".to_string());
 
-        cache.save_template(&template).unwrap();
+        cache.save_template(&template).await.unwrap();
 
-        let retrieved = cache.get_template(&template.id).unwrap();
+        let retrieved = cache.get_template(&template.id).await.unwrap();
         assert!(retrieved.is_some());
         let retrieved = retrieved.unwrap();
         assert_eq!(retrieved.name, "Test Template");
         assert_eq!(retrieved.body, "Test body");
     }
 
-    #[test]
-    fn test_get_templates() {
+    #[tokio::test]
+    async fn test_get_templates() {
         let cache = Cache::in_memory().unwrap();
 
         let template1 = PrTemplate::new("Alpha".to_string(), "Body 1".to_string());
         let template2 = PrTemplate::new("Beta".to_string(), "Body 2".to_string());
 
-        cache.save_template(&template1).unwrap();
-        cache.save_template(&template2).unwrap();
+        cache.save_template(&template1).await.unwrap();
+        cache.save_template(&template2).await.unwrap();
 
-        let templates = cache.get_templates().unwrap();
+        let templates = cache.get_templates().await.unwrap();
         assert_eq!(templates.len(), 2);
         // Should be sorted by name
         assert_eq!(templates[0].name, "Alpha");
         assert_eq!(templates[1].name, "Beta");
     }
 
-    #[test]
-    fn test_default_template() {
+    #[tokio::test]
+    async fn test_default_template() {
         let cache = Cache::in_memory().unwrap();
 
         let template1 = PrTemplate::new("First".to_string(), "Body 1".to_string());
         let template2 =
             PrTemplate::new("Second".to_string(), "Body 2".to_string()).set_default(true);
 
-        cache.save_template(&template1).unwrap();
-        cache.save_template(&template2).unwrap();
+        cache.save_template(&template1).await.unwrap();
+        cache.save_template(&template2).await.unwrap();
 
-        let default = cache.get_default_template().unwrap();
+        let default = cache.get_default_template().await.unwrap();
         assert!(default.is_some());
         assert_eq!(default.unwrap().name, "Second");
     }
 
-    #[test]
-    fn test_only_one_default_template() {
+    #[tokio::test]
+    async fn test_only_one_default_template() {
         let cache = Cache::in_memory().unwrap();
 
         let template1 =
@@ -532,36 +1435,36 @@ mod tests {
         let template2 =
             PrTemplate::new("Second".to_string(), "Body 2".to_string()).set_default(true);
 
-        cache.save_template(&template1).unwrap();
-        cache.save_template(&template2).unwrap();
+        cache.save_template(&template1).await.unwrap();
+        cache.save_template(&template2).await.unwrap();
 
-        let templates = cache.get_templates().unwrap();
+        let templates = cache.get_templates().await.unwrap();
         let default_count = templates.iter().filter(|t| t.is_default).count();
         assert_eq!(default_count, 1);
 
-        let default = cache.get_default_template().unwrap();
+        let default = cache.get_default_template().await.unwrap();
         assert_eq!(default.unwrap().name, "Second");
     }
 
-    #[test]
-    fn test_delete_template() {
+    #[tokio::test]
+    async fn test_delete_template() {
         let cache = Cache::in_memory().unwrap();
         let template = PrTemplate::new("To Delete".to_string(), "Body".to_string());
 
-        cache.save_template(&template).unwrap();
-        assert!(cache.get_template(&template.id).unwrap().is_some());
+        cache.save_template(&template).await.unwrap();
+        assert!(cache.get_template(&template.id).await.unwrap().is_some());
 
-        let deleted = cache.delete_template(&template.id).unwrap();
+        let deleted = cache.delete_template(&template.id).await.unwrap();
         assert!(deleted);
-        assert!(cache.get_template(&template.id).unwrap().is_none());
+        assert!(cache.get_template(&template.id).await.unwrap().is_none());
     }
 
-    #[test]
-    fn test_delete_nonexistent_template() {
+    #[tokio::test]
+    async fn test_delete_nonexistent_template() {
         let cache = Cache::in_memory().unwrap();
         let fake_id = Uuid::new_v4();
 
-        let deleted = cache.delete_template(&fake_id).unwrap();
+        let deleted = cache.delete_template(&fake_id).await.unwrap();
         assert!(!deleted);
     }
 }
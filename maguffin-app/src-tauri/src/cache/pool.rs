@@ -0,0 +1,248 @@
+//! SQLite connection pool.
+//!
+//! Writes are serialised through one connection owned by a dedicated thread,
+//! and reads run on a small pool of read-only connections on Tokio's
+//! blocking threads. With the database in WAL mode, readers never wait for
+//! the writer or for each other, and async callers never block the runtime.
+
+use crate::error::{Result, StorageError};
+use rusqlite::{Connection, OpenFlags};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::{oneshot, Semaphore};
+
+/// Number of read-only connections kept open.
+pub const READER_COUNT: usize = 4;
+
+/// How long a connection waits on a lock before failing with `SQLITE_BUSY`.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Job = Box<dyn FnOnce(&mut Connection) + Send>;
+
+/// Read-only connections to a database file.
+struct Readers {
+    path: PathBuf,
+    idle: Mutex<Vec<Connection>>,
+    available: Arc<Semaphore>,
+}
+
+impl Readers {
+    fn open(path: &Path) -> Result<Connection> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX
+                | OpenFlags::SQLITE_OPEN_URI,
+        )
+        .map_err(|e| StorageError::Database(e.to_string()))?;
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .map_err(|e| StorageError::Database(e.to_string()))?;
+        Ok(conn)
+    }
+
+    /// Take an idle connection, reopening one lost to a panicked read.
+    fn checkout(&self) -> Result<Connection> {
+        let idle = self
+            .idle
+            .lock()
+            .map_err(|e| StorageError::Database(format!("Lock error: {}", e)))?
+            .pop();
+        match idle {
+            Some(conn) => Ok(conn),
+            None => Self::open(&self.path),
+        }
+    }
+
+    fn checkin(&self, conn: Connection) {
+        if let Ok(mut idle) = self.idle.lock() {
+            idle.push(conn);
+        }
+    }
+}
+
+/// A writer thread plus, for file databases, a pool of readers.
+pub struct ConnectionPool {
+    writer: mpsc::Sender<Job>,
+    readers: Option<Arc<Readers>>,
+}
+
+impl ConnectionPool {
+    /// Pool for the database file at `path`, writing through `conn`.
+    ///
+    /// Switches the database to WAL mode so readers can run alongside the
+    /// writer.
+    pub fn open(conn: Connection, path: &Path) -> Result<Self> {
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .and_then(|_| conn.pragma_update(None, "synchronous", "NORMAL"))
+            .and_then(|_| conn.busy_timeout(BUSY_TIMEOUT))
+            .map_err(|e| StorageError::Database(e.to_string()))?;
+
+        let idle = (0..READER_COUNT)
+            .map(|_| Readers::open(path))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            writer: Self::spawn_writer(conn)?,
+            readers: Some(Arc::new(Readers {
+                path: path.to_path_buf(),
+                idle: Mutex::new(idle),
+                available: Arc::new(Semaphore::new(READER_COUNT)),
+            })),
+        })
+    }
+
+    /// Pool where reads share the writer's connection.
+    ///
+    /// For in-memory databases, which other connections can't open.
+    pub fn single(conn: Connection) -> Result<Self> {
+        Ok(Self {
+            writer: Self::spawn_writer(conn)?,
+            readers: None,
+        })
+    }
+
+    fn spawn_writer(mut conn: Connection) -> Result<mpsc::Sender<Job>> {
+        let (tx, rx) = mpsc::channel::<Job>();
+        thread::Builder::new()
+            .name("cache-writer".to_string())
+            .spawn(move || {
+                // Runs until the pool, and with it the sender, is dropped
+                for job in rx {
+                    // A panicking job drops its reply; the writer carries on
+                    let _ = panic::catch_unwind(AssertUnwindSafe(|| job(&mut conn)));
+                }
+            })
+            .map_err(StorageError::Io)?;
+        Ok(tx)
+    }
+
+    /// Run `f` on the writer connection, after every write queued before it.
+    pub async fn write<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let job: Job = Box::new(move |conn| {
+            let _ = reply_tx.send(f(conn));
+        });
+
+        self.writer
+            .send(job)
+            .map_err(|_| StorageError::Database("Cache writer has stopped".to_string()))?;
+        reply_rx
+            .await
+            .map_err(|_| StorageError::Database("Cache write failed".to_string()))?
+    }
+
+    /// Run `f` on a reader connection.
+    ///
+    /// Sees every write that completed before the call.
+    pub async fn read<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let Some(readers) = self.readers.clone() else {
+            return self.write(move |conn| f(conn)).await;
+        };
+
+        let permit = readers
+            .available
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| StorageError::Database(e.to_string()))?;
+
+        tokio::task::spawn_blocking(move || {
+            let conn = readers.checkout()?;
+            let result = f(&conn);
+            readers.checkin(conn);
+            drop(permit);
+            result
+        })
+        .await
+        .map_err(|e| StorageError::Database(format!("Cache read failed: {}", e)))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn count(conn: &Connection) -> Result<i64> {
+        conn.query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0))
+            .map_err(|e| StorageError::Database(e.to_string()).into())
+    }
+
+    #[tokio::test]
+    async fn test_reads_see_completed_writes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("pool.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch("CREATE TABLE t (id INTEGER PRIMARY KEY);")
+            .unwrap();
+        let pool = Arc::new(ConnectionPool::open(conn, &path).unwrap());
+
+        let mode: String = pool
+            .read(|conn| {
+                conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))
+                    .map_err(|e| StorageError::Database(e.to_string()).into())
+            })
+            .await
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        for _ in 0..3 {
+            pool.write(|conn| {
+                conn.execute("INSERT INTO t DEFAULT VALUES", [])
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+        }
+
+        // More concurrent reads than readers
+        let reads: Vec<_> = (0..READER_COUNT * 4)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move { pool.read(count).await })
+            })
+            .collect();
+        for read in reads {
+            assert_eq!(read.await.unwrap().unwrap(), 3);
+        }
+
+        // Readers can't write
+        assert!(pool
+            .read(|conn| {
+                conn.execute("INSERT INTO t DEFAULT VALUES", [])
+                    .map_err(|e| StorageError::Database(e.to_string()))?;
+                Ok(())
+            })
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_writer_survives_panic() {
+        let pool = ConnectionPool::single(Connection::open_in_memory().unwrap()).unwrap();
+
+        let failed = pool.write(|_| -> Result<()> { panic!("boom") }).await;
+        assert!(failed.is_err());
+
+        let answer = pool
+            .read(|conn| {
+                conn.query_row("SELECT 42", [], |row| row.get::<_, i64>(0))
+                    .map_err(|e| StorageError::Database(e.to_string()).into())
+            })
+            .await
+            .unwrap();
+        assert_eq!(answer, 42);
+    }
+}
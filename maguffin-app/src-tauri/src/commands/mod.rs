@@ -4,15 +4,36 @@
 //! frontend UI to the Rust backend.
 
 use crate::cache::{Cache, RecentRepository};
-use crate::config::SyncConfig;
-use crate::domain::pr::PullRequestDetails;
+use crate::config::{GitHubConfig, SyncConfig, WebhookConfig};
+use crate::domain::checks::{CheckRunDetails, JobLogChunk};
+use crate::domain::pr::{
+    AutoMerge, BranchUpdate, BranchUpdateMethod, DetailsConnection, DetailsPage, MergeMethod,
+    MergeQueueEntry, Milestone, PrEdit, PrQuery, PullRequestDetails, RepoLabel, SavedView,
+};
 use crate::domain::repo::GitHubRemote;
-use crate::domain::stack::{ReconcileReport, RestackPreview, RestackResult, Stack};
-use crate::domain::sync::SyncStatus;
+use crate::domain::review::{
+    DraftReviewComment, FilePatch, ReviewEvent, ReviewThread, ReviewThreads,
+};
+use crate::domain::search::SearchHit;
+use crate::domain::stack::edit::{BranchCommit, StackEdit, StackEditPreview, StackEditResult};
+use crate::domain::stack::{
+    DiscoveredStack, ReconcileReport, RestackPreview, RestackResult, Stack, StackImport,
+};
+use crate::domain::sync::{OperationCost, SyncChange, SyncStatus};
+use crate::domain::timeline::{
+    self, Comment, CommentKind, ReactionContent, ReactionGroup, TimelineItem,
+};
 use crate::domain::{AuthState, PullRequest, Repository, SyncState};
 use crate::error::AppError;
+use crate::git::diff::{self, DiffOptions, DiffPage};
+use crate::git::stack_interop::StackTool;
+use crate::git::stack_store::StackStorage;
 use crate::git::{Git2Backend, GitOperations, RebaseState};
-use crate::github::{AuthService, GitHubClient, PrService, StackService, SyncService};
+use crate::github::webhook::{self, WebhookStatus};
+use crate::github::{
+    AuthService, ChangeProbe, ChecksService, GitHubClient, JobLogStore, PrService, ReviewService,
+    StackService, SyncService, TimelineService, WebhookReceiver,
+};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::State;
@@ -25,7 +46,10 @@ use tokio::sync::RwLock;
 /// - GitHubClient for API calls
 /// - Current repository context
 /// - Cache for persistent storage
+/// - ChangeProbe for ETag-gated PR queries
 /// - SyncService for background synchronization
+/// - WebhookReceiver for optional webhook ingestion
+/// - JobLogStore for downloaded CI job logs
 pub struct AppState {
     /// Authentication service
     auth_service: AuthService,
@@ -39,8 +63,22 @@ pub struct AppState {
     /// Local cache for recent repositories and settings
     cache: Arc<Cache>,
 
+    /// ETag change probe shared by the PR list and detail commands
+    change_probe: Arc<ChangeProbe>,
+
     /// Background sync service
     sync_service: Arc<SyncService>,
+
+    /// Running webhook receiver, if webhook mode is enabled
+    webhook_receiver: Arc<RwLock<Option<WebhookReceiver>>>,
+
+    /// Local cache of GitHub Actions job logs
+    job_logs: Arc<JobLogStore>,
+
+    /// Stack service of the current repository, with the path it was opened
+    /// for. Kept for as long as the repository is open, so every command
+    /// shares its metadata and its watcher picks up external changes.
+    stack_service: Arc<RwLock<Option<(PathBuf, Arc<StackService>)>>>,
 }
 
 /// Context for the currently opened repository.
@@ -70,11 +108,13 @@ impl AppState {
     /// This method handles service initialization failures gracefully by using
     /// fallback/default services where possible, rather than panicking.
     pub fn new() -> Self {
-        let github_client = GitHubClient::new("https://api.github.com/graphql".to_string())
+        let github_config = GitHubConfig::default();
+        let github_client = GitHubClient::new(github_config.api_endpoint)
             .unwrap_or_else(|e| {
                 tracing::warn!("Failed to create GitHub client, using default: {}", e);
                 GitHubClient::default()
-            });
+            })
+            .with_max_concurrent_requests(github_config.max_concurrent_requests);
         let github_client = Arc::new(github_client);
 
         let auth_service = AuthService::new().unwrap_or_else(|e| {
@@ -83,18 +123,64 @@ impl AppState {
         });
 
         // Create cache in the user's data directory
-        let cache = Self::create_cache();
+        let cache = Arc::new(Self::create_cache());
+
+        let change_probe = Arc::new(ChangeProbe::new(github_client.clone(), cache.clone()));
 
         // Create sync service with default config
-        let sync_service = SyncService::new(github_client.clone(), SyncConfig::default());
+        let sync_service = SyncService::new(github_client.clone(), SyncConfig::default())
+            .with_cache(cache.clone());
 
         Self {
             auth_service,
             github_client,
             current_repo: Arc::new(RwLock::new(None)),
-            cache: Arc::new(cache),
+            cache,
+            change_probe,
             sync_service: Arc::new(sync_service),
+            webhook_receiver: Arc::new(RwLock::new(None)),
+            job_logs: Arc::new(JobLogStore::new(Self::job_log_dir())),
+            stack_service: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// The current repository and its stack service, which is created and
+    /// starts watching the stack metadata on first use.
+    async fn stack_service(&self) -> Result<(RepoContext, Arc<StackService>), String> {
+        let repo = self
+            .current_repo
+            .read()
+            .await
+            .clone()
+            .ok_or("No repository opened")?;
+
+        let mut slot = self.stack_service.write().await;
+        if let Some((path, service)) = slot.as_ref() {
+            if *path == repo.path {
+                return Ok((repo, service.clone()));
+            }
         }
+
+        // PRs from the last sync, in case the metadata has to be rebuilt
+        let prs = self.sync_service.pull_requests().await;
+        let repo_path = repo.path.clone();
+        let pr_service = Arc::new(PrService::new(
+            self.github_client.clone(),
+            repo.owner.clone(),
+            repo.name.clone(),
+        ));
+        let service = tokio::task::spawn_blocking(move || {
+            let git = Git2Backend::open(&repo_path)?;
+            StackService::open(repo_path, git, &prs).map(|s| s.with_pr_service(pr_service))
+        })
+        .await
+        .map_err(|e| format!("Task failed: {:?}", e))?
+        .map_err(|e| e.to_string())?;
+
+        let service = Arc::new(service);
+        service.watch();
+        *slot = Some((repo.path.clone(), service.clone()));
+        Ok((repo, service))
     }
 
     /// Create cache, falling back to in-memory if file-based fails.
@@ -104,14 +190,23 @@ impl AppState {
             let cache_dir = data_dir.join("maguffin");
             if std::fs::create_dir_all(&cache_dir).is_ok() {
                 let cache_path = cache_dir.join("cache.db");
-                if let Ok(cache) = Cache::open(&cache_path) {
-                    return cache;
+                match Cache::open(&cache_path) {
+                    Ok(cache) => return cache,
+                    Err(e) => tracing::warn!("Failed to open cache, using in-memory: {}", e),
                 }
             }
         }
         // Fall back to in-memory cache
         Cache::in_memory().expect("Failed to create in-memory cache")
     }
+
+    /// Directory for cached job logs, next to the cache database.
+    fn job_log_dir() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("maguffin")
+            .join("logs")
+    }
 }
 
 /// Get the current authentication state.
@@ -212,6 +307,12 @@ pub async fn open_repository(
     };
     *state.current_repo.write().await = Some(context);
 
+    // Stop watching the previous repository's stacks
+    *state.stack_service.write().await = None;
+
+    // Drop queued and background requests made for the previous repository
+    state.github_client.cancel_pending();
+
     // Update sync service with new repository context
     state
         .sync_service
@@ -220,10 +321,10 @@ pub async fn open_repository(
 
     // Save to recent repositories
     let path_str = path.to_string_lossy().to_string();
-    let _ =
-        state
-            .cache
-            .save_recent_repository(&path_str, &github_remote.owner, &github_remote.name);
+    let _ = state
+        .cache
+        .save_recent_repository(&path_str, &github_remote.owner, &github_remote.name)
+        .await;
 
     Ok(Repository {
         path,
@@ -244,6 +345,7 @@ pub async fn get_recent_repositories(
     state
         .cache
         .get_recent_repositories(10)
+        .await
         .map_err(|e| e.to_string())
 }
 
@@ -256,14 +358,19 @@ pub async fn remove_recent_repository(
     state
         .cache
         .remove_recent_repository(&path)
+        .await
         .map_err(|e| e.to_string())
 }
 
 /// List pull requests for the current repository.
+///
+/// The list is refreshed into the local cache and filtered and sorted there
+/// by `query`; `base_branch` is shorthand for an exact base branch filter.
 #[tauri::command]
 pub async fn list_pull_requests(
     state: State<'_, AppState>,
     base_branch: Option<String>,
+    query: Option<PrQuery>,
 ) -> Result<Vec<PullRequest>, String> {
     let repo = state
         .current_repo
@@ -276,10 +383,77 @@ pub async fn list_pull_requests(
         state.github_client.clone(),
         repo.owner.clone(),
         repo.name.clone(),
-    );
+    )
+    .with_change_probe(state.change_probe.clone());
 
-    pr_service
-        .list_prs(base_branch)
+    let prs = pr_service.list_prs(None).await.map_err(|e| e.to_string())?;
+
+    let mut query = query.unwrap_or_default();
+    if base_branch.is_some() {
+        query.base_branch = base_branch;
+    }
+
+    state
+        .cache
+        .replace_pull_requests(&repo.owner, &repo.name, &prs)
+        .await
+        .map_err(|e| e.to_string())?;
+    state
+        .cache
+        .query_pull_requests(&repo.owner, &repo.name, &query)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get the saved PR list views.
+#[tauri::command]
+pub async fn get_saved_views(state: State<'_, AppState>) -> Result<Vec<SavedView>, String> {
+    state
+        .cache
+        .get_saved_views()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Save a PR list view, replacing any view with the same name.
+#[tauri::command]
+pub async fn save_view(state: State<'_, AppState>, view: SavedView) -> Result<(), String> {
+    if view.name.trim().is_empty() {
+        return Err("View name cannot be empty".to_string());
+    }
+    state
+        .cache
+        .save_view(&view)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Delete a saved PR list view.
+#[tauri::command]
+pub async fn delete_view(state: State<'_, AppState>, name: String) -> Result<bool, String> {
+    state
+        .cache
+        .delete_view(&name)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Number of search results returned when the caller doesn't say.
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// Search the cached pull requests of every opened repository.
+///
+/// Answers from the local index only, so it works offline; results are as
+/// fresh as the last PR list refresh.
+#[tauri::command]
+pub async fn search_pull_requests(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, String> {
+    state
+        .cache
+        .search_pull_requests(&query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
         .await
         .map_err(|e| e.to_string())
 }
@@ -301,7 +475,8 @@ pub async fn get_pull_request(
         state.github_client.clone(),
         repo.owner.clone(),
         repo.name.clone(),
-    );
+    )
+    .with_change_probe(state.change_probe.clone());
 
     let details = pr_service
         .get_pr_details(number)
@@ -328,7 +503,8 @@ pub async fn get_pull_request_details(
         state.github_client.clone(),
         repo.owner.clone(),
         repo.name.clone(),
-    );
+    )
+    .with_change_probe(state.change_probe.clone());
 
     pr_service
         .get_pr_details(number)
@@ -336,6 +512,36 @@ pub async fn get_pull_request_details(
         .map_err(|e| e.to_string())
 }
 
+/// Fetch the next page of a pull request details connection.
+///
+/// `cursor` is the `end_cursor` from the connection's pagination state.
+#[tauri::command]
+pub async fn get_pull_request_details_page(
+    state: State<'_, AppState>,
+    number: i64,
+    connection: DetailsConnection,
+    cursor: String,
+    commit_oid: Option<String>,
+) -> Result<DetailsPage, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let pr_service = PrService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    pr_service
+        .get_pr_details_page(number, connection, cursor, commit_oid)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// Checkout a PR branch locally.
 #[tauri::command]
 pub async fn checkout_pull_request(state: State<'_, AppState>, number: i64) -> Result<(), String> {
@@ -346,19 +552,18 @@ pub async fn checkout_pull_request(state: State<'_, AppState>, number: i64) -> R
         .clone()
         .ok_or("No repository opened")?;
 
-    // Get PR details to find the branch name
+    // Look up the PR to find the branch name
     let pr_service = PrService::new(
         state.github_client.clone(),
         repo.owner.clone(),
         repo.name.clone(),
     );
 
-    let details = pr_service
-        .get_pr_details(number)
+    let branch_name = pr_service
+        .get_pr_ref(number)
         .await
-        .map_err(|e| e.to_string())?;
-
-    let branch_name = details.pr.head_ref;
+        .map_err(|e| e.to_string())?
+        .head_ref;
 
     // Checkout the branch (wrap in spawn_blocking to avoid blocking async runtime)
     tokio::task::spawn_blocking({
@@ -427,10 +632,22 @@ pub async fn create_pull_request(
         repo.name.clone(),
     );
 
-    pr_service
+    let pr_number = pr_service
         .create_pr(title, body, head, base, draft)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    state.sync_service.notify_user_action();
+    Ok(pr_number)
+}
+
+fn parse_merge_method(merge_method: &str) -> Result<MergeMethod, String> {
+    match merge_method.to_uppercase().as_str() {
+        "MERGE" => Ok(MergeMethod::Merge),
+        "SQUASH" => Ok(MergeMethod::Squash),
+        "REBASE" => Ok(MergeMethod::Rebase),
+        _ => Err(format!("Invalid merge method: {}", merge_method)),
+    }
 }
 
 /// Merge a pull request.
@@ -447,12 +664,7 @@ pub async fn merge_pull_request(
         .clone()
         .ok_or("No repository opened")?;
 
-    let method = match merge_method.to_uppercase().as_str() {
-        "MERGE" => crate::domain::pr::MergeMethod::Merge,
-        "SQUASH" => crate::domain::pr::MergeMethod::Squash,
-        "REBASE" => crate::domain::pr::MergeMethod::Rebase,
-        _ => return Err(format!("Invalid merge method: {}", merge_method)),
-    };
+    let method = parse_merge_method(&merge_method)?;
 
     let pr_service = PrService::new(
         state.github_client.clone(),
@@ -460,15 +672,23 @@ pub async fn merge_pull_request(
         repo.name.clone(),
     );
 
-    pr_service
+    let merged = pr_service
         .merge_pr(pr_id, method)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    state.sync_service.notify_user_action();
+    Ok(merged)
 }
 
-/// Close a pull request without merging.
+/// Enable auto-merge on a pull request, merging it with the given method
+/// once its requirements are met.
 #[tauri::command]
-pub async fn close_pull_request(state: State<'_, AppState>, pr_id: String) -> Result<bool, String> {
+pub async fn enable_auto_merge(
+    state: State<'_, AppState>,
+    pr_id: String,
+    merge_method: String,
+) -> Result<AutoMerge, String> {
     let repo = state
         .current_repo
         .read()
@@ -476,22 +696,26 @@ pub async fn close_pull_request(state: State<'_, AppState>, pr_id: String) -> Re
         .clone()
         .ok_or("No repository opened")?;
 
+    let method = parse_merge_method(&merge_method)?;
+
     let pr_service = PrService::new(
         state.github_client.clone(),
         repo.owner.clone(),
         repo.name.clone(),
     );
 
-    pr_service.close_pr(pr_id).await.map_err(|e| e.to_string())
+    let auto_merge = pr_service
+        .enable_auto_merge(pr_id, method)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    state.sync_service.notify_user_action();
+    Ok(auto_merge)
 }
 
-/// Update a pull request's base branch.
+/// Disable auto-merge on a pull request.
 #[tauri::command]
-pub async fn update_pull_request_base(
-    state: State<'_, AppState>,
-    pr_id: String,
-    new_base: String,
-) -> Result<bool, String> {
+pub async fn disable_auto_merge(state: State<'_, AppState>, pr_id: String) -> Result<bool, String> {
     let repo = state
         .current_repo
         .read()
@@ -505,15 +729,24 @@ pub async fn update_pull_request_base(
         repo.name.clone(),
     );
 
-    pr_service
-        .update_pr_base(pr_id, new_base)
+    let disabled = pr_service
+        .disable_auto_merge(pr_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    state.sync_service.notify_user_action();
+    Ok(disabled)
 }
 
-/// List all stacks in the current repository.
+/// Add a pull request to the merge queue.
+///
+/// With `jump`, the PR is placed at the front of the queue.
 #[tauri::command]
-pub async fn list_stacks(state: State<'_, AppState>) -> Result<Vec<Stack>, String> {
+pub async fn enqueue_pull_request(
+    state: State<'_, AppState>,
+    pr_id: String,
+    jump: Option<bool>,
+) -> Result<MergeQueueEntry, String> {
     let repo = state
         .current_repo
         .read()
@@ -521,31 +754,27 @@ pub async fn list_stacks(state: State<'_, AppState>) -> Result<Vec<Stack>, Strin
         .clone()
         .ok_or("No repository opened")?;
 
-    let repo_path = repo.path;
+    let pr_service = PrService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
 
-    // Read stack metadata directly from file (no git operations needed)
-    tokio::task::spawn_blocking(move || {
-        let metadata_path = repo_path.join(".git").join("stack-metadata.json");
-        if metadata_path.exists() {
-            let content = std::fs::read_to_string(&metadata_path)
-                .map_err(|e| format!("Failed to read stack metadata: {}", e))?;
-            let metadata: crate::domain::stack::StackMetadata = serde_json::from_str(&content)
-                .map_err(|e| format!("Failed to parse stack metadata: {}", e))?;
-            Ok(metadata.stacks)
-        } else {
-            Ok(Vec::new())
-        }
-    })
-    .await
-    .map_err(|e| e.to_string())?
+    let entry = pr_service
+        .enqueue_pr(pr_id, jump.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    state.sync_service.notify_user_action();
+    Ok(entry)
 }
 
-/// Create a new stack rooted at the given branch.
+/// Remove a pull request from the merge queue.
 #[tauri::command]
-pub async fn create_stack(
+pub async fn dequeue_pull_request(
     state: State<'_, AppState>,
-    root_branch: String,
-) -> Result<Stack, String> {
+    pr_id: String,
+) -> Result<bool, String> {
     let repo = state
         .current_repo
         .read()
@@ -553,30 +782,34 @@ pub async fn create_stack(
         .clone()
         .ok_or("No repository opened")?;
 
-    let repo_path = repo.path.clone();
+    let pr_service = PrService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
 
-    // Run entire operation in spawn_blocking since StackService contains non-Send types
-    tokio::task::spawn_blocking(move || {
-        let git = Git2Backend::open(&repo_path).map_err(|e| e.to_string())?;
-        let stack_service = StackService::new(repo.path, git).map_err(|e| e.to_string())?;
+    let dequeued = pr_service
+        .dequeue_pr(pr_id)
+        .await
+        .map_err(|e| e.to_string())?;
 
-        // Use block_on for the async method since we're in a blocking context
-        let rt = tokio::runtime::Handle::current();
-        rt.block_on(async { stack_service.create_stack(root_branch).await })
-            .map_err(|e| e.to_string())
-    })
-    .await
-    .map_err(|e| format!("Task failed: {:?}", e))?
+    state.sync_service.notify_user_action();
+    Ok(dequeued)
 }
 
-/// Create a new branch on an existing stack.
+/// Bring a pull request branch up to date with its base branch.
+///
+/// `method` is `MERGE` or `REBASE`. The update is refused if the branch no
+/// longer points at `expected_head_oid` (default: the head when the PR is
+/// looked up). When GitHub can't rebase the branch, it is rebased in the
+/// local repository and force-pushed instead, unless it is in a fork.
 #[tauri::command]
-pub async fn create_stack_branch(
+pub async fn update_pull_request_branch(
     state: State<'_, AppState>,
-    stack_id: String,
-    branch_name: String,
-    parent_name: String,
-) -> Result<(), String> {
+    number: i64,
+    method: String,
+    expected_head_oid: Option<String>,
+) -> Result<BranchUpdate, String> {
     let repo = state
         .current_repo
         .read()
@@ -584,34 +817,97 @@ pub async fn create_stack_branch(
         .clone()
         .ok_or("No repository opened")?;
 
-    let repo_path = repo.path.clone();
+    let method = match method.to_uppercase().as_str() {
+        "MERGE" => BranchUpdateMethod::Merge,
+        "REBASE" => BranchUpdateMethod::Rebase,
+        _ => return Err(format!("Invalid update method: {}", method)),
+    };
 
-    // Run entire operation in spawn_blocking since StackService contains non-Send types
-    tokio::task::spawn_blocking(move || {
-        let git = Git2Backend::open(&repo_path).map_err(|e| e.to_string())?;
-        let stack_service = StackService::new(repo.path, git).map_err(|e| e.to_string())?;
-        let stack_uuid = uuid::Uuid::parse_str(&stack_id).map_err(|e| e.to_string())?;
+    let pr_service = PrService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
 
-        // Use block_on for the async method since we're in a blocking context
-        let rt = tokio::runtime::Handle::current();
-        rt.block_on(async {
-            stack_service
-                .create_stack_branch(stack_uuid, branch_name, parent_name)
-                .await
-        })
+    let pr = pr_service
+        .get_pr_ref(number)
+        .await
         .map_err(|e| e.to_string())?;
-        Ok::<_, String>(())
-    })
-    .await
-    .map_err(|e| format!("Task failed: {:?}", e))?
+    let expected_head_oid = expected_head_oid.unwrap_or(pr.head_oid);
+
+    let update = match pr_service
+        .update_branch(pr.id, method, Some(expected_head_oid.clone()))
+        .await
+    {
+        Ok(head_oid) => BranchUpdate {
+            head_oid,
+            method,
+            local: false,
+        },
+        Err(e)
+            if method == BranchUpdateMethod::Rebase
+                && PrService::is_update_method_unavailable(&e) =>
+        {
+            // The branch lives in the fork, which the local repository
+            // can't push to
+            if pr.is_cross_repository {
+                return Err(format!(
+                    "GitHub can't rebase PR #{} ({}), and its branch is in a fork, so it can't be rebased locally either",
+                    number, e
+                ));
+            }
+            tracing::info!(
+                "GitHub can't rebase PR #{}, rebasing locally: {}",
+                number,
+                e
+            );
+            let head_ref = pr.head_ref.clone();
+            let head_oid = tokio::task::spawn_blocking(move || {
+                let git = Git2Backend::open(&repo.path)?;
+                let remote = git.push_remote(&pr.head_ref);
+                git.rebase_and_push(&pr.head_ref, &pr.base_ref, &remote, &expected_head_oid)
+            })
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+
+            // A stack branch's new head is shared with the stacks. The push
+            // already succeeded, so failing to record it isn't an error.
+            let recorded = match state.stack_service().await {
+                Ok((_, stack_service)) => stack_service
+                    .branch_pushed(&head_ref)
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = recorded {
+                tracing::warn!("Failed to record push of {}: {}", head_ref, e);
+            }
+
+            BranchUpdate {
+                head_oid,
+                method,
+                local: true,
+            }
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+
+    state.sync_service.notify_user_action();
+    Ok(update)
 }
 
-/// Restack all branches in a stack.
+/// Edit a pull request.
+///
+/// The synced copy of the PR is updated right away and rolled back if the
+/// edit fails. Returns the edited copy, or `None` if the PR isn't synced
+/// (e.g. a closed PR being reopened).
 #[tauri::command]
-pub async fn restack(
+pub async fn edit_pull_request(
     state: State<'_, AppState>,
-    stack_id: String,
-) -> Result<RestackResult, String> {
+    number: i64,
+    edit: PrEdit,
+) -> Result<Option<PullRequest>, String> {
     let repo = state
         .current_repo
         .read()
@@ -619,30 +915,52 @@ pub async fn restack(
         .clone()
         .ok_or("No repository opened")?;
 
-    let repo_path = repo.path.clone();
+    let pr_service = PrService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    )
+    .with_change_probe(state.change_probe.clone());
 
-    // Run entire operation in spawn_blocking since StackService contains non-Send types
-    tokio::task::spawn_blocking(move || {
-        let git = Git2Backend::open(&repo_path).map_err(|e| e.to_string())?;
-        let stack_service = StackService::new(repo.path, git).map_err(|e| e.to_string())?;
-        let stack_uuid = uuid::Uuid::parse_str(&stack_id).map_err(|e| e.to_string())?;
+    let pr = pr_service
+        .get_pr_ref(number)
+        .await
+        .map_err(|e| e.to_string())?;
 
-        // Use block_on for the async method since we're in a blocking context
-        let rt = tokio::runtime::Handle::current();
-        rt.block_on(async { stack_service.restack(stack_uuid).await })
-            .map_err(|e| e.to_string())
-    })
-    .await
-    .map_err(|e| format!("Task failed: {:?}", e))?
+    let labels = match &edit {
+        PrEdit::AddLabels { .. } | PrEdit::RemoveLabels { .. } => {
+            pr_service.list_labels().await.map_err(|e| e.to_string())?
+        }
+        _ => Vec::new(),
+    };
+    let milestones = match &edit {
+        PrEdit::SetMilestone { milestone: Some(_) } => pr_service
+            .list_milestones()
+            .await
+            .map_err(|e| e.to_string())?,
+        _ => Vec::new(),
+    };
+
+    let apply = |pr: &mut PullRequest| edit.apply(pr, &repo.owner, &labels, &milestones);
+    let previous = state.sync_service.update_pull_request(number, apply).await;
+
+    if let Err(e) = pr_service.edit_pr(&pr, &edit, &labels, &milestones).await {
+        if let Some(previous) = previous {
+            state.sync_service.restore_pull_request(previous).await;
+        }
+        return Err(e.to_string());
+    }
+
+    state.sync_service.notify_user_action();
+    Ok(previous.map(|mut pr| {
+        apply(&mut pr);
+        pr
+    }))
 }
 
-/// Preview what a restack operation will do without making any changes.
-/// This is the dry-run mode for restack.
+/// List the labels defined in the current repository.
 #[tauri::command]
-pub async fn preview_restack(
-    state: State<'_, AppState>,
-    stack_id: String,
-) -> Result<RestackPreview, String> {
+pub async fn list_labels(state: State<'_, AppState>) -> Result<Vec<RepoLabel>, String> {
     let repo = state
         .current_repo
         .read()
@@ -650,12 +968,741 @@ pub async fn preview_restack(
         .clone()
         .ok_or("No repository opened")?;
 
-    let repo_path = repo.path.clone();
+    let pr_service = PrService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    )
+    .with_change_probe(state.change_probe.clone());
 
-    tokio::task::spawn_blocking(move || {
-        let git = Git2Backend::open(&repo_path).map_err(|e| e.to_string())?;
-        let stack_service = StackService::new(repo.path, git).map_err(|e| e.to_string())?;
-        let stack_uuid = uuid::Uuid::parse_str(&stack_id).map_err(|e| e.to_string())?;
+    pr_service.list_labels().await.map_err(|e| e.to_string())
+}
+
+/// List the open milestones of the current repository.
+#[tauri::command]
+pub async fn list_milestones(state: State<'_, AppState>) -> Result<Vec<Milestone>, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let pr_service = PrService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    pr_service
+        .list_milestones()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Close a pull request without merging.
+#[tauri::command]
+pub async fn close_pull_request(state: State<'_, AppState>, pr_id: String) -> Result<bool, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let pr_service = PrService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    let closed = pr_service
+        .close_pr(pr_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    state.sync_service.notify_user_action();
+    Ok(closed)
+}
+
+/// Update a pull request's base branch.
+#[tauri::command]
+pub async fn update_pull_request_base(
+    state: State<'_, AppState>,
+    pr_id: String,
+    new_base: String,
+) -> Result<bool, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let pr_service = PrService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    let updated = pr_service
+        .update_pr_base(pr_id, new_base)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    state.sync_service.notify_user_action();
+    Ok(updated)
+}
+
+// ============================================================================
+// Review Commands
+// ============================================================================
+
+/// Get the patch of every file changed by a pull request.
+#[tauri::command]
+pub async fn get_pull_request_patches(
+    state: State<'_, AppState>,
+    number: i64,
+) -> Result<Vec<FilePatch>, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let review_service = ReviewService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    review_service
+        .get_patches(number)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Get a pull request's review threads and the viewer's pending review.
+#[tauri::command]
+pub async fn get_review_threads(
+    state: State<'_, AppState>,
+    number: i64,
+) -> Result<ReviewThreads, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let review_service = ReviewService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    review_service
+        .get_threads(number)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Start a pending review, or return the one already in progress.
+#[tauri::command]
+pub async fn start_review(state: State<'_, AppState>, number: i64) -> Result<String, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let review_service = ReviewService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    review_service
+        .start_review(number)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Add a line or multi-line comment to the pending review.
+#[tauri::command]
+pub async fn add_review_comment(
+    state: State<'_, AppState>,
+    number: i64,
+    comment: DraftReviewComment,
+) -> Result<ReviewThread, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let review_service = ReviewService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    review_service
+        .add_comment(number, comment)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Submit the viewer's review with approve, comment or request-changes.
+#[tauri::command]
+pub async fn submit_review(
+    state: State<'_, AppState>,
+    number: i64,
+    event: ReviewEvent,
+    body: Option<String>,
+) -> Result<(), String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let review_service = ReviewService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    review_service
+        .submit_review(number, event, body)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    state.sync_service.notify_user_action();
+    Ok(())
+}
+
+/// Discard the viewer's pending review and its comments.
+#[tauri::command]
+pub async fn discard_review(state: State<'_, AppState>, number: i64) -> Result<bool, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let review_service = ReviewService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    review_service
+        .discard_review(number)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Resolve or unresolve a review thread.
+#[tauri::command]
+pub async fn set_review_thread_resolved(
+    state: State<'_, AppState>,
+    thread_id: String,
+    resolved: bool,
+) -> Result<bool, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let review_service = ReviewService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    review_service
+        .set_thread_resolved(thread_id, resolved)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Conversation Commands
+// ============================================================================
+
+/// Get a pull request's conversation timeline, oldest first.
+#[tauri::command]
+pub async fn get_pull_request_timeline(
+    state: State<'_, AppState>,
+    number: i64,
+) -> Result<Vec<TimelineItem>, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let timeline_service = TimelineService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    let items = timeline_service
+        .get_timeline(number)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Keep the search index's comment text fresh; failing to is not fatal
+    if let Err(e) = state
+        .cache
+        .index_pull_request_comments(
+            &repo.owner,
+            &repo.name,
+            number,
+            &timeline::comment_bodies(&items),
+        )
+        .await
+    {
+        tracing::warn!("Failed to index comments of PR #{}: {}", number, e);
+    }
+
+    Ok(items)
+}
+
+/// Post a comment on a pull request's conversation.
+#[tauri::command]
+pub async fn post_pull_request_comment(
+    state: State<'_, AppState>,
+    number: i64,
+    body: String,
+) -> Result<Comment, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let timeline_service = TimelineService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    timeline_service
+        .post_comment(number, body)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Reply to a review thread.
+#[tauri::command]
+pub async fn reply_to_review_thread(
+    state: State<'_, AppState>,
+    thread_id: String,
+    body: String,
+) -> Result<Comment, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let timeline_service = TimelineService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    timeline_service
+        .reply_to_thread(thread_id, body)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Edit a conversation or review comment.
+#[tauri::command]
+pub async fn edit_comment(
+    state: State<'_, AppState>,
+    kind: CommentKind,
+    comment_id: String,
+    body: String,
+) -> Result<(), String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let timeline_service = TimelineService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    timeline_service
+        .edit_comment(kind, comment_id, body)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Delete a conversation or review comment.
+#[tauri::command]
+pub async fn delete_comment(
+    state: State<'_, AppState>,
+    kind: CommentKind,
+    comment_id: String,
+) -> Result<(), String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let timeline_service = TimelineService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    timeline_service
+        .delete_comment(kind, comment_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Add or remove the viewer's reaction on a comment or review.
+///
+/// Returns the subject's reactions afterwards.
+#[tauri::command]
+pub async fn set_reaction(
+    state: State<'_, AppState>,
+    subject_id: String,
+    content: ReactionContent,
+    reacted: bool,
+) -> Result<Vec<ReactionGroup>, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let timeline_service = TimelineService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+
+    timeline_service
+        .set_reaction(subject_id, content, reacted)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Check Commands
+// ============================================================================
+
+/// Number of bytes of a job log returned per read.
+const JOB_LOG_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Get a check run's output summary and annotations.
+#[tauri::command]
+pub async fn get_check_run(
+    state: State<'_, AppState>,
+    check_run_id: i64,
+) -> Result<CheckRunDetails, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let checks_service = ChecksService::new(state.github_client.clone(), repo.owner, repo.name);
+    checks_service
+        .get_check_run(check_run_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Read a GitHub Actions job log from `offset`.
+///
+/// The first read starts downloading the log in the background; poll with
+/// the returned `next_offset` until a chunk is `complete`. Downloaded logs
+/// are cached locally.
+#[tauri::command]
+pub async fn get_job_log(
+    state: State<'_, AppState>,
+    job_id: i64,
+    offset: Option<u64>,
+) -> Result<JobLogChunk, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let store = state.job_logs.clone();
+    if let Some(error) = store.take_error(job_id) {
+        return Err(error);
+    }
+
+    if !store.is_cached(job_id).await && store.begin_download(job_id) {
+        let checks_service = ChecksService::new(state.github_client.clone(), repo.owner, repo.name);
+        let store = store.clone();
+        tokio::spawn(async move {
+            checks_service.download_job_log(job_id, &store).await;
+        });
+    }
+
+    store
+        .read(job_id, offset.unwrap_or(0), JOB_LOG_CHUNK_SIZE)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Re-run the failed jobs of the workflow run a GitHub Actions check run
+/// belongs to.
+#[tauri::command]
+pub async fn rerun_failed_jobs(
+    state: State<'_, AppState>,
+    check_run_id: i64,
+) -> Result<(), String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let checks_service = ChecksService::new(state.github_client.clone(), repo.owner, repo.name);
+    checks_service
+        .rerun_failed_jobs(check_run_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    state.sync_service.notify_user_action();
+    Ok(())
+}
+
+/// Re-request every check in a check suite.
+#[tauri::command]
+pub async fn rerequest_check_suite(
+    state: State<'_, AppState>,
+    check_suite_id: i64,
+) -> Result<(), String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let checks_service = ChecksService::new(state.github_client.clone(), repo.owner, repo.name);
+    checks_service
+        .rerequest_check_suite(check_suite_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    state.sync_service.notify_user_action();
+    Ok(())
+}
+
+// ============================================================================
+// Diff Commands
+// ============================================================================
+
+/// Get a page of the local diff between two revisions.
+///
+/// Used e.g. for a stack branch against its parent. Files are paged with
+/// `offset`/`limit`.
+#[tauri::command]
+pub async fn get_diff(
+    state: State<'_, AppState>,
+    from: String,
+    to: String,
+    options: Option<DiffOptions>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<DiffPage, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    tokio::task::spawn_blocking(move || {
+        let git = Git2Backend::open(&repo.path).map_err(|e| e.to_string())?;
+        git.diff(
+            &from,
+            &to,
+            &options.unwrap_or_default(),
+            offset.unwrap_or(0),
+            limit.unwrap_or(diff::DEFAULT_PAGE_SIZE),
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
+/// Get a page of a pull request's diff, computed locally from its branches.
+///
+/// Uses the branches as last fetched from `origin`, so it works offline but
+/// may lag behind GitHub until the next fetch.
+#[tauri::command]
+pub async fn get_pull_request_diff(
+    state: State<'_, AppState>,
+    number: i64,
+    options: Option<DiffOptions>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<DiffPage, String> {
+    let repo = state
+        .current_repo
+        .read()
+        .await
+        .clone()
+        .ok_or("No repository opened")?;
+
+    let pr_service = PrService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    );
+    let pr = pr_service
+        .get_pr_ref(number)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    tokio::task::spawn_blocking(move || {
+        let git = Git2Backend::open(&repo.path).map_err(|e| e.to_string())?;
+        let base = git.remote_branch_revision(&pr.base_ref);
+        let head = git.remote_branch_revision(&pr.head_ref);
+        git.diff(
+            &base,
+            &head,
+            &options.unwrap_or_default(),
+            offset.unwrap_or(0),
+            limit.unwrap_or(diff::DEFAULT_PAGE_SIZE),
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
+/// List all stacks in the current repository.
+///
+/// Each stack carries a CI rollup computed from the last synced PR list.
+#[tauri::command]
+pub async fn list_stacks(state: State<'_, AppState>) -> Result<Vec<Stack>, String> {
+    let (_, stack_service) = state.stack_service().await?;
+    let stacks = stack_service.list_stacks().await;
+
+    let prs = state.sync_service.pull_requests().await;
+    Ok(stacks
+        .into_iter()
+        .map(|stack| stack.with_ci(&prs))
+        .collect())
+}
+
+/// Create a new stack rooted at the given branch.
+#[tauri::command]
+pub async fn create_stack(
+    state: State<'_, AppState>,
+    root_branch: String,
+) -> Result<Stack, String> {
+    let (_, stack_service) = state.stack_service().await?;
+
+    // Run in spawn_blocking since StackService holds non-Send lock guards across awaits
+    tokio::task::spawn_blocking(move || {
+        // Use block_on for the async method since we're in a blocking context
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async { stack_service.create_stack(root_branch).await })
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
+/// Create a new branch on an existing stack.
+#[tauri::command]
+pub async fn create_stack_branch(
+    state: State<'_, AppState>,
+    stack_id: String,
+    branch_name: String,
+    parent_name: String,
+) -> Result<(), String> {
+    let (_, stack_service) = state.stack_service().await?;
+
+    // Run in spawn_blocking since StackService holds non-Send lock guards across awaits
+    tokio::task::spawn_blocking(move || {
+        let stack_uuid = uuid::Uuid::parse_str(&stack_id).map_err(|e| e.to_string())?;
+
+        // Use block_on for the async method since we're in a blocking context
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async {
+            stack_service
+                .create_stack_branch(stack_uuid, branch_name, parent_name)
+                .await
+        })
+        .map_err(|e| e.to_string())?;
+        Ok::<_, String>(())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
+/// Restack all branches in a stack.
+#[tauri::command]
+pub async fn restack(
+    state: State<'_, AppState>,
+    stack_id: String,
+) -> Result<RestackResult, String> {
+    let (_, stack_service) = state.stack_service().await?;
+
+    // Run in spawn_blocking since StackService holds non-Send lock guards across awaits
+    tokio::task::spawn_blocking(move || {
+        let stack_uuid = uuid::Uuid::parse_str(&stack_id).map_err(|e| e.to_string())?;
+
+        // Use block_on for the async method since we're in a blocking context
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async { stack_service.restack(stack_uuid).await })
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
+/// Preview what a restack operation will do without making any changes.
+/// This is the dry-run mode for restack.
+#[tauri::command]
+pub async fn preview_restack(
+    state: State<'_, AppState>,
+    stack_id: String,
+) -> Result<RestackPreview, String> {
+    let (_, stack_service) = state.stack_service().await?;
+
+    tokio::task::spawn_blocking(move || {
+        let stack_uuid = uuid::Uuid::parse_str(&stack_id).map_err(|e| e.to_string())?;
 
         let rt = tokio::runtime::Handle::current();
         rt.block_on(async { stack_service.preview_restack(stack_uuid).await })
@@ -671,18 +1718,9 @@ pub async fn continue_restack(
     state: State<'_, AppState>,
     stack_id: String,
 ) -> Result<RestackResult, String> {
-    let repo = state
-        .current_repo
-        .read()
-        .await
-        .clone()
-        .ok_or("No repository opened")?;
-
-    let repo_path = repo.path.clone();
+    let (_, stack_service) = state.stack_service().await?;
 
     tokio::task::spawn_blocking(move || {
-        let git = Git2Backend::open(&repo_path).map_err(|e| e.to_string())?;
-        let stack_service = StackService::new(repo.path, git).map_err(|e| e.to_string())?;
         let stack_uuid = uuid::Uuid::parse_str(&stack_id).map_err(|e| e.to_string())?;
 
         let rt = tokio::runtime::Handle::current();
@@ -693,6 +1731,107 @@ pub async fn continue_restack(
     .map_err(|e| format!("Task failed: {:?}", e))?
 }
 
+/// Preview what moving, inserting or reordering stack branches will do.
+#[tauri::command]
+pub async fn preview_stack_edit(
+    state: State<'_, AppState>,
+    edit: StackEdit,
+) -> Result<StackEditPreview, String> {
+    let (_, stack_service) = state.stack_service().await?;
+
+    tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async { stack_service.preview_edit(&edit).await })
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
+/// Move, insert or reorder stack branches, rebasing what has to move.
+#[tauri::command]
+pub async fn edit_stack(
+    state: State<'_, AppState>,
+    edit: StackEdit,
+) -> Result<StackEditResult, String> {
+    let (_, stack_service) = state.stack_service().await?;
+
+    tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async { stack_service.edit_stack(&edit).await })
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
+/// Undo the most recent stack edit.
+#[tauri::command]
+pub async fn undo_stack_edit(state: State<'_, AppState>) -> Result<StackEditResult, String> {
+    let (_, stack_service) = state.stack_service().await?;
+
+    tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async { stack_service.undo_edit().await })
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
+/// List the commits of a stack branch since its parent, oldest first.
+#[tauri::command]
+pub async fn list_branch_commits(
+    state: State<'_, AppState>,
+    branch: String,
+) -> Result<Vec<BranchCommit>, String> {
+    let (_, stack_service) = state.stack_service().await?;
+
+    tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async { stack_service.branch_commits(&branch).await })
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
+/// Split a stack branch after a commit, moving the rest to a new child branch.
+#[tauri::command]
+pub async fn split_branch(
+    state: State<'_, AppState>,
+    branch: String,
+    at: String,
+    new_branch: String,
+) -> Result<StackEditResult, String> {
+    let (_, stack_service) = state.stack_service().await?;
+
+    tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async { stack_service.split_branch(&branch, &at, &new_branch).await })
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
+/// Fold a stack branch into its parent.
+#[tauri::command]
+pub async fn fold_branch(
+    state: State<'_, AppState>,
+    branch: String,
+) -> Result<StackEditResult, String> {
+    let (_, stack_service) = state.stack_service().await?;
+
+    tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async { stack_service.fold_branch(&branch).await })
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
 /// Check if a rebase is currently in progress.
 #[tauri::command]
 pub async fn is_rebase_in_progress(state: State<'_, AppState>) -> Result<bool, String> {
@@ -743,27 +1882,19 @@ pub async fn create_stack_pr(
     body: Option<String>,
     draft: bool,
 ) -> Result<i64, String> {
-    let repo = state
-        .current_repo
-        .read()
-        .await
-        .clone()
-        .ok_or("No repository opened")?;
+    let (repo, stack_service) = state.stack_service().await?;
 
-    let repo_path = repo.path.clone();
     let owner = repo.owner.clone();
     let name = repo.name.clone();
 
     // First, get stack info and parent branch from the stack metadata
     let (parent_branch, stack_context) = tokio::task::spawn_blocking({
-        let repo_path = repo_path.clone();
+        let stack_service = stack_service.clone();
         let stack_id = stack_id.clone();
         let branch_name = branch_name.clone();
         let owner = owner.clone();
         let name = name.clone();
         move || {
-            let git = Git2Backend::open(&repo_path).map_err(|e| e.to_string())?;
-            let stack_service = StackService::new(repo_path, git).map_err(|e| e.to_string())?;
             let stack_uuid = uuid::Uuid::parse_str(&stack_id).map_err(|e| e.to_string())?;
 
             let rt = tokio::runtime::Handle::current();
@@ -836,20 +1967,15 @@ pub async fn create_stack_pr(
         .map_err(|e| e.to_string())?;
 
     // Update the stack metadata with the PR number
-    tokio::task::spawn_blocking({
-        let repo_path = repo.path;
-        move || {
-            let git = Git2Backend::open(&repo_path).map_err(|e| e.to_string())?;
-            let stack_service = StackService::new(repo_path, git).map_err(|e| e.to_string())?;
-
-            let rt = tokio::runtime::Handle::current();
-            rt.block_on(async { stack_service.set_branch_pr(&branch_name, pr_number).await })
-                .map_err(|e| e.to_string())
-        }
+    tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async { stack_service.set_branch_pr(&branch_name, pr_number).await })
+            .map_err(|e| e.to_string())
     })
     .await
     .map_err(|e| format!("Task failed: {:?}", e))??;
 
+    state.sync_service.notify_user_action();
     Ok(pr_number)
 }
 
@@ -857,29 +1983,152 @@ pub async fn create_stack_pr(
 /// This checks for branches that have been deleted, modified, or need rebase.
 #[tauri::command]
 pub async fn reconcile_stacks(state: State<'_, AppState>) -> Result<ReconcileReport, String> {
-    let repo = state
-        .current_repo
-        .read()
+    let (_, stack_service) = state.stack_service().await?;
+
+    // Run in spawn_blocking since StackService holds non-Send lock guards across awaits
+    tokio::task::spawn_blocking(move || {
+        // Use block_on for the async method since we're in a blocking context
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async { stack_service.reconcile().await })
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
+/// Find stacks among the repository's branches and open PRs that aren't
+/// tracked yet.
+///
+/// Branches whose parent is uncertain list every candidate parent with a
+/// confidence score; pass the stack, with the parents to use, to
+/// `adopt_stack` to start tracking it.
+#[tauri::command]
+pub async fn discover_stacks(state: State<'_, AppState>) -> Result<Vec<DiscoveredStack>, String> {
+    let (repo, stack_service) = state.stack_service().await?;
+
+    let pr_service = PrService::new(
+        state.github_client.clone(),
+        repo.owner.clone(),
+        repo.name.clone(),
+    )
+    .with_change_probe(state.change_probe.clone());
+    let prs = pr_service.list_prs(None).await.map_err(|e| e.to_string())?;
+
+    stack_service
+        .discover_stacks(prs, repo.default_branch)
         .await
-        .clone()
-        .ok_or("No repository opened")?;
+        .map_err(|e| e.to_string())
+}
 
-    let repo_path = repo.path.clone();
+/// Start tracking a stack returned by `discover_stacks`.
+#[tauri::command]
+pub async fn adopt_stack(
+    state: State<'_, AppState>,
+    stack: DiscoveredStack,
+) -> Result<Stack, String> {
+    let (_, stack_service) = state.stack_service().await?;
 
-    // Run entire operation in spawn_blocking since StackService contains non-Send types
     tokio::task::spawn_blocking(move || {
-        let git = Git2Backend::open(&repo_path).map_err(|e| e.to_string())?;
-        let stack_service = StackService::new(repo_path, git).map_err(|e| e.to_string())?;
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async { stack_service.adopt_stack(stack).await })
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
 
-        // Use block_on for the async method since we're in a blocking context
+/// Import the stacks another stacking tool has recorded for the current
+/// repository.
+///
+/// Branches that are already in a stack keep their place and are reported
+/// as skipped.
+#[tauri::command]
+pub async fn import_stacks(
+    state: State<'_, AppState>,
+    tool: StackTool,
+) -> Result<StackImport, String> {
+    let (_, stack_service) = state.stack_service().await?;
+
+    tokio::task::spawn_blocking(move || {
         let rt = tokio::runtime::Handle::current();
-        rt.block_on(async { stack_service.reconcile().await })
+        rt.block_on(async { stack_service.import_stacks(tool).await })
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
+/// Write the current repository's stacks to git-town's branch config.
+///
+/// Returns the number of branches written.
+#[tauri::command]
+pub async fn export_stacks_to_git_town(state: State<'_, AppState>) -> Result<usize, String> {
+    let (repo, stack_service) = state.stack_service().await?;
+
+    tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async { stack_service.export_git_town(&repo.default_branch).await })
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
+/// Get where the current repository's stack metadata is stored.
+#[tauri::command]
+pub async fn get_stack_storage(state: State<'_, AppState>) -> Result<StackStorage, String> {
+    let (_, stack_service) = state.stack_service().await?;
+    Ok(stack_service.storage())
+}
+
+/// Get the revision of the current repository's stack metadata.
+///
+/// It changes whenever stacks are saved, here or by another process, so
+/// polling it tells when to list stacks again.
+#[tauri::command]
+pub async fn get_stack_revision(state: State<'_, AppState>) -> Result<u64, String> {
+    let (_, stack_service) = state.stack_service().await?;
+    Ok(stack_service.revision().await)
+}
+
+/// Move the current repository's stack metadata to another storage.
+///
+/// With `ref` storage, stacks are kept in `refs/maguffin/stacks` and can be
+/// shared through the remote with `sync_stacks`.
+#[tauri::command]
+pub async fn set_stack_storage(
+    state: State<'_, AppState>,
+    storage: StackStorage,
+) -> Result<(), String> {
+    let (_, stack_service) = state.stack_service().await?;
+
+    tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async { stack_service.set_storage(storage).await })
             .map_err(|e| e.to_string())
     })
     .await
     .map_err(|e| format!("Task failed: {:?}", e))?
 }
 
+/// Fetch stacks from the default remote, merge them with the local ones and
+/// push the result.
+#[tauri::command]
+pub async fn sync_stacks(state: State<'_, AppState>) -> Result<(), String> {
+    let (_, stack_service) = state.stack_service().await?;
+
+    tokio::task::spawn_blocking(move || {
+        let rt = tokio::runtime::Handle::current();
+        rt.block_on(async {
+            let remote = stack_service.default_remote();
+            stack_service.sync_metadata(&remote).await
+        })
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Task failed: {:?}", e))?
+}
+
 // ============================================================================
 // Sync Commands
 // ============================================================================
@@ -890,6 +2139,12 @@ pub async fn get_sync_status(state: State<'_, AppState>) -> Result<SyncStatus, S
     Ok(state.sync_service.status().await)
 }
 
+/// Get the accumulated GraphQL query cost per operation.
+#[tauri::command]
+pub async fn get_query_metrics(state: State<'_, AppState>) -> Result<Vec<OperationCost>, String> {
+    Ok(state.github_client.cost_metrics())
+}
+
 /// Start background sync.
 #[tauri::command]
 pub async fn start_sync(state: State<'_, AppState>) -> Result<(), String> {
@@ -914,16 +2169,30 @@ pub async fn sync_now(state: State<'_, AppState>) -> Result<(), String> {
 }
 
 /// Update sync configuration.
+///
+/// Settings that aren't given keep their current values.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn update_sync_config(
     state: State<'_, AppState>,
     interval_secs: u64,
     enabled: bool,
+    unfocused_interval_secs: Option<u64>,
+    boost_interval_secs: Option<u64>,
+    boost_duration_secs: Option<u64>,
+    max_interval_secs: Option<u64>,
+    full_sync_interval_secs: Option<u64>,
 ) -> Result<(), String> {
+    let current = state.sync_service.config().await;
     let config = SyncConfig {
         interval_secs,
         enabled,
-        sync_on_startup: true,
+        unfocused_interval_secs: unfocused_interval_secs.unwrap_or(current.unfocused_interval_secs),
+        boost_interval_secs: boost_interval_secs.unwrap_or(current.boost_interval_secs),
+        boost_duration_secs: boost_duration_secs.unwrap_or(current.boost_duration_secs),
+        max_interval_secs: max_interval_secs.unwrap_or(current.max_interval_secs),
+        full_sync_interval_secs: full_sync_interval_secs.unwrap_or(current.full_sync_interval_secs),
+        ..current
     };
     state
         .sync_service
@@ -932,6 +2201,110 @@ pub async fn update_sync_config(
         .map_err(|e| e.to_string())
 }
 
+/// Report app window focus so background sync can slow down while unfocused.
+#[tauri::command]
+pub async fn set_window_focused(state: State<'_, AppState>, focused: bool) -> Result<(), String> {
+    state.sync_service.set_focused(focused);
+    Ok(())
+}
+
+// ============================================================================
+// Webhook Commands
+// ============================================================================
+
+/// Start the webhook receiver.
+///
+/// Deliveries are validated against `secret` and fed into the sync service.
+/// Restarts the receiver if it is already running.
+#[tauri::command]
+pub async fn start_webhook_receiver(
+    state: State<'_, AppState>,
+    secret: String,
+    port: Option<u16>,
+    record_dir: Option<String>,
+) -> Result<WebhookStatus, String> {
+    let defaults = WebhookConfig::default();
+    let addr = format!(
+        "{}:{}",
+        defaults.bind_address,
+        port.unwrap_or(defaults.port)
+    )
+    .parse()
+    .map_err(|e| format!("Invalid address: {}", e))?;
+
+    // Stop any running receiver first so the port is free
+    state.webhook_receiver.write().await.take();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+    let receiver = WebhookReceiver::start(
+        addr,
+        secret,
+        record_dir.map(PathBuf::from).or(defaults.record_dir),
+        tx,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // Forward deliveries until the receiver is stopped
+    let sync_service = state.sync_service.clone();
+    tokio::spawn(async move {
+        while let Some(delivery) = rx.recv().await {
+            if let Err(e) = sync_service.ingest_webhook(&delivery).await {
+                tracing::warn!("Failed to ingest webhook delivery: {}", e);
+            }
+        }
+    });
+
+    let status = receiver.status();
+    *state.webhook_receiver.write().await = Some(receiver);
+    Ok(status)
+}
+
+/// Stop the webhook receiver.
+#[tauri::command]
+pub async fn stop_webhook_receiver(state: State<'_, AppState>) -> Result<(), String> {
+    state.webhook_receiver.write().await.take();
+    Ok(())
+}
+
+/// Get the webhook receiver status, or `None` if it isn't running.
+#[tauri::command]
+pub async fn get_webhook_status(
+    state: State<'_, AppState>,
+) -> Result<Option<WebhookStatus>, String> {
+    Ok(state
+        .webhook_receiver
+        .read()
+        .await
+        .as_ref()
+        .map(|r| r.status()))
+}
+
+/// Replay recorded webhook deliveries from a file or directory.
+#[tauri::command]
+pub async fn replay_webhooks(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<Vec<SyncChange>, String> {
+    let deliveries =
+        tokio::task::spawn_blocking(move || webhook::load_recorded(&PathBuf::from(path)))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+
+    let mut changes = Vec::new();
+    for delivery in &deliveries {
+        changes.extend(
+            state
+                .sync_service
+                .ingest_webhook(delivery)
+                .await
+                .map_err(|e| e.to_string())?,
+        );
+    }
+    Ok(changes)
+}
+
 // ============================================================================
 // Template Commands
 // ============================================================================
@@ -941,7 +2314,7 @@ pub async fn update_sync_config(
 pub async fn get_templates(
     state: State<'_, AppState>,
 ) -> Result<Vec<crate::domain::template::PrTemplate>, String> {
-    state.cache.get_templates().map_err(|e| e.to_string())
+    state.cache.get_templates().await.map_err(|e| e.to_string())
 }
 
 /// Get a specific template by ID.
@@ -951,7 +2324,11 @@ pub async fn get_template(
     id: String,
 ) -> Result<Option<crate::domain::template::PrTemplate>, String> {
     let uuid = uuid::Uuid::parse_str(&id).map_err(|e| e.to_string())?;
-    state.cache.get_template(&uuid).map_err(|e| e.to_string())
+    state
+        .cache
+        .get_template(&uuid)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 /// Get the default template.
@@ -962,6 +2339,7 @@ pub async fn get_default_template(
     state
         .cache
         .get_default_template()
+        .await
         .map_err(|e| e.to_string())
 }
 
@@ -977,6 +2355,7 @@ pub async fn create_template(
     state
         .cache
         .save_template(&template)
+        .await
         .map_err(|e| e.to_string())?;
     Ok(template)
 }
@@ -996,6 +2375,7 @@ pub async fn update_template(
     let existing = state
         .cache
         .get_template(&uuid)
+        .await
         .map_err(|e| e.to_string())?
         .ok_or("Template not found")?;
 
@@ -1011,6 +2391,7 @@ pub async fn update_template(
     state
         .cache
         .save_template(&template)
+        .await
         .map_err(|e| e.to_string())?;
     Ok(template)
 }
@@ -1022,6 +2403,7 @@ pub async fn delete_template(state: State<'_, AppState>, id: String) -> Result<b
     state
         .cache
         .delete_template(&uuid)
+        .await
         .map_err(|e| e.to_string())
 }
 
@@ -1036,6 +2418,7 @@ pub async fn render_template(
     let template = state
         .cache
         .get_template(&uuid)
+        .await
         .map_err(|e| e.to_string())?
         .ok_or("Template not found")?;
 
@@ -1053,13 +2436,45 @@ pub fn generate_handlers() -> impl Fn(tauri::ipc::Invoke) -> bool + Send + Sync
         get_recent_repositories,
         remove_recent_repository,
         list_pull_requests,
+        get_saved_views,
+        search_pull_requests,
+        save_view,
+        delete_view,
         get_pull_request,
         get_pull_request_details,
+        get_pull_request_details_page,
         checkout_pull_request,
         create_pull_request,
         merge_pull_request,
+        enable_auto_merge,
+        disable_auto_merge,
+        enqueue_pull_request,
+        dequeue_pull_request,
+        update_pull_request_branch,
+        edit_pull_request,
+        list_labels,
+        list_milestones,
         close_pull_request,
         update_pull_request_base,
+        get_pull_request_patches,
+        get_review_threads,
+        start_review,
+        add_review_comment,
+        submit_review,
+        discard_review,
+        set_review_thread_resolved,
+        get_pull_request_timeline,
+        post_pull_request_comment,
+        reply_to_review_thread,
+        edit_comment,
+        delete_comment,
+        set_reaction,
+        get_check_run,
+        get_job_log,
+        rerun_failed_jobs,
+        rerequest_check_suite,
+        get_diff,
+        get_pull_request_diff,
         list_stacks,
         create_stack,
         create_stack_branch,
@@ -1067,14 +2482,34 @@ pub fn generate_handlers() -> impl Fn(tauri::ipc::Invoke) -> bool + Send + Sync
         restack,
         preview_restack,
         continue_restack,
+        preview_stack_edit,
+        edit_stack,
+        undo_stack_edit,
+        list_branch_commits,
+        split_branch,
+        fold_branch,
         is_rebase_in_progress,
         get_rebase_state,
         reconcile_stacks,
+        discover_stacks,
+        adopt_stack,
+        import_stacks,
+        export_stacks_to_git_town,
+        get_stack_storage,
+        get_stack_revision,
+        set_stack_storage,
+        sync_stacks,
         get_sync_status,
+        get_query_metrics,
         start_sync,
         stop_sync,
         sync_now,
         update_sync_config,
+        set_window_focused,
+        start_webhook_receiver,
+        stop_webhook_receiver,
+        get_webhook_status,
+        replay_webhooks,
         get_templates,
         get_template,
         get_default_template,
@@ -2,7 +2,9 @@
 //!
 //! Handles user preferences, sync intervals, and other configurable settings.
 
+pub use crate::domain::pr::PrSortOrder;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::time::Duration;
 
 /// Application configuration.
@@ -16,10 +18,19 @@ pub struct AppConfig {
 
     /// GitHub settings
     pub github: GitHubConfig,
+
+    /// Webhook receiver settings
+    #[serde(default)]
+    pub webhook: WebhookConfig,
 }
 
 /// Configuration for background synchronization.
+///
+/// The effective interval is adaptive: `interval_secs` is the baseline, which
+/// is stretched while the window is unfocused or the rate limit budget runs
+/// low, and shortened briefly after user actions.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SyncConfig {
     /// Interval between sync operations in seconds (default: 60)
     pub interval_secs: u64,
@@ -29,6 +40,22 @@ pub struct SyncConfig {
 
     /// Whether to sync on app startup
     pub sync_on_startup: bool,
+
+    /// Interval while the app window is unfocused in seconds (default: 300)
+    pub unfocused_interval_secs: u64,
+
+    /// Interval shortly after a user action in seconds (default: 15)
+    pub boost_interval_secs: u64,
+
+    /// How long the boosted interval lasts after a user action in seconds (default: 120)
+    pub boost_duration_secs: u64,
+
+    /// Upper bound for the adaptive interval in seconds (default: 900)
+    pub max_interval_secs: u64,
+
+    /// Maximum age of cached PR data in seconds before a full GraphQL refresh,
+    /// even if the ETag change probe reports no changes (default: 600)
+    pub full_sync_interval_secs: u64,
 }
 
 impl Default for SyncConfig {
@@ -37,6 +64,11 @@ impl Default for SyncConfig {
             interval_secs: 60,
             enabled: true,
             sync_on_startup: true,
+            unfocused_interval_secs: 300,
+            boost_interval_secs: 15,
+            boost_duration_secs: 120,
+            max_interval_secs: 900,
+            full_sync_interval_secs: 600,
         }
     }
 }
@@ -80,15 +112,6 @@ pub enum Theme {
     System,
 }
 
-/// PR list sort order.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum PrSortOrder {
-    CreatedAt,
-    UpdatedAt,
-    ReviewStatus,
-}
-
 /// GitHub-specific configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitHubConfig {
@@ -120,6 +143,34 @@ impl Default for GitHubConfig {
     }
 }
 
+/// Configuration for the optional webhook receiver.
+///
+/// The receiver listens locally; to receive deliveries from GitHub, point a
+/// tunnel or relay (ngrok, smee.io, ...) at it. The webhook secret is not part
+/// of the config and must be supplied when starting the receiver.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// Address to listen on (default: 127.0.0.1)
+    pub bind_address: String,
+
+    /// Port to listen on (default: 8787)
+    pub port: u16,
+
+    /// Directory to record accepted deliveries to for later replay
+    pub record_dir: Option<PathBuf>,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: "127.0.0.1".to_string(),
+            port: 8787,
+            record_dir: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,6 +192,15 @@ mod tests {
         assert_eq!(config.interval(), Duration::from_secs(120));
     }
 
+    #[test]
+    fn test_sync_config_partial_deserialization() {
+        let config: SyncConfig =
+            serde_json::from_str(r#"{"interval_secs": 30, "enabled": false}"#).unwrap();
+        assert_eq!(config.interval_secs, 30);
+        assert!(!config.enabled);
+        assert_eq!(config.unfocused_interval_secs, 300);
+    }
+
     #[test]
     fn test_config_serialization() {
         let config = AppConfig::default();
@@ -0,0 +1,177 @@
+//! Check run domain types.
+//!
+//! This module contains the detailed view of a single check run (output,
+//! annotations) and the chunks in which GitHub Actions job logs are read.
+
+use crate::domain::pr::{CheckConclusion, CheckRunStatus};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// App slug of GitHub Actions, whose check runs are workflow jobs.
+pub const GITHUB_ACTIONS_APP: &str = "github-actions";
+
+/// Full details of a check run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckRunDetails {
+    /// Check run database ID (the job ID for GitHub Actions)
+    pub id: i64,
+
+    /// Name of the check
+    pub name: String,
+
+    /// Check status
+    pub status: CheckRunStatus,
+
+    /// Check conclusion (only valid when completed)
+    pub conclusion: Option<CheckConclusion>,
+
+    /// Commit the check ran on
+    pub head_sha: String,
+
+    /// Check suite the run belongs to
+    pub check_suite_id: i64,
+
+    /// Slug of the app that created the check run
+    pub app: Option<String>,
+
+    /// Check run page on GitHub
+    pub html_url: Option<String>,
+
+    /// Details page provided by the app
+    pub details_url: Option<String>,
+
+    /// When the check started
+    pub started_at: Option<DateTime<Utc>>,
+
+    /// When the check completed
+    pub completed_at: Option<DateTime<Utc>>,
+
+    /// Output title
+    pub title: Option<String>,
+
+    /// Output summary (Markdown)
+    pub summary: Option<String>,
+
+    /// Output details (Markdown)
+    pub text: Option<String>,
+
+    /// Annotations on the changed code
+    pub annotations: Vec<CheckAnnotation>,
+}
+
+impl CheckRunDetails {
+    /// Whether the check run is a GitHub Actions job, i.e. has job logs and
+    /// can be re-run.
+    pub fn is_github_actions(&self) -> bool {
+        self.app.as_deref() == Some(GITHUB_ACTIONS_APP)
+    }
+}
+
+/// An annotation a check run attached to a line range.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckAnnotation {
+    /// File path
+    pub path: String,
+
+    /// First annotated line
+    pub start_line: i32,
+
+    /// Last annotated line
+    pub end_line: i32,
+
+    /// First annotated column, for single-line annotations
+    pub start_column: Option<i32>,
+
+    /// Last annotated column, for single-line annotations
+    pub end_column: Option<i32>,
+
+    /// Severity
+    pub level: AnnotationLevel,
+
+    /// Short title
+    pub title: Option<String>,
+
+    /// Annotation message
+    pub message: String,
+
+    /// Extra details, such as a stack trace
+    pub raw_details: Option<String>,
+}
+
+/// Severity of a check annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationLevel {
+    Notice,
+    Warning,
+    Failure,
+}
+
+/// A chunk of a job log, read from the local log cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobLogChunk {
+    /// Job (check run) ID
+    pub job_id: i64,
+
+    /// Byte offset the chunk starts at
+    pub offset: u64,
+
+    /// Byte offset to read the next chunk from
+    pub next_offset: u64,
+
+    /// Log text
+    pub content: String,
+
+    /// Whether the chunk reaches the end of the fully downloaded log
+    pub complete: bool,
+}
+
+impl JobLogChunk {
+    /// Build a chunk from raw log bytes read at `offset`.
+    ///
+    /// Chunks are cut at byte offsets, so a multi-byte character may be split
+    /// across reads. An incomplete trailing character is left for the next
+    /// read, unless the chunk is `complete` and no more bytes will follow.
+    pub fn decode(job_id: i64, offset: u64, bytes: &[u8], complete: bool) -> Self {
+        let valid = match std::str::from_utf8(bytes) {
+            Err(e) if e.error_len().is_none() && !complete => e.valid_up_to(),
+            _ => bytes.len(),
+        };
+
+        Self {
+            job_id,
+            offset,
+            next_offset: offset + valid as u64,
+            content: String::from_utf8_lossy(&bytes[..valid]).into_owned(),
+            complete,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_keeps_split_character_for_next_read() {
+        let log = "build ✓\n".as_bytes();
+        let split = log.len() - 2; // inside the three-byte check mark
+
+        let first = JobLogChunk::decode(7, 0, &log[..split], false);
+        assert_eq!(first.content, "build ");
+        assert_eq!(first.next_offset, 6);
+
+        let second = JobLogChunk::decode(7, first.next_offset, &log[6..], true);
+        assert_eq!(second.content, "✓\n");
+        assert_eq!(second.next_offset, log.len() as u64);
+    }
+
+    #[test]
+    fn test_decode_replaces_truncated_character_at_end() {
+        let log = "ok ✓".as_bytes();
+        let chunk = JobLogChunk::decode(7, 0, &log[..log.len() - 1], true);
+
+        assert_eq!(chunk.content, "ok \u{FFFD}");
+        assert_eq!(chunk.next_offset, log.len() as u64 - 1);
+    }
+}
@@ -4,21 +4,39 @@
 //! for the Git client application.
 
 pub mod auth;
+pub mod checks;
 pub mod pr;
 pub mod repo;
+pub mod review;
+pub mod search;
 pub mod stack;
 pub mod sync;
 pub mod template;
+pub mod timeline;
 
 // Re-export commonly used types
 pub use auth::{AuthState, AuthenticatedUser, DeviceFlowPending};
+pub use checks::{AnnotationLevel, CheckAnnotation, CheckRunDetails, JobLogChunk};
 pub use pr::{
-    Author, ChangeType, ChangedFile, Commit, CreatePrOptions, Label, MergeMethod, Mergeable,
-    PrState, PullRequest, PullRequestDetails, Review, ReviewDecision, ReviewState,
+    Author, AutoMerge, BranchUpdate, BranchUpdateMethod, ChangeType, ChangedFile, Commit,
+    CreatePrOptions, DetailsConnection, DetailsPage, DetailsPageItems, DetailsPagination, Label,
+    MergeMethod, MergeQueueEntry, MergeQueueState, Mergeable, Milestone, PageInfo, PrEdit, PrQuery,
+    PrSortOrder, PrState, PullRequest, PullRequestDetails, PullRequestRef, RepoLabel, Review,
+    ReviewDecision, ReviewState, SavedView,
 };
 pub use repo::{GitHubRemote, RecentRepository, Repository, SyncState};
+pub use review::{
+    DiffSide, DraftReviewComment, FilePatch, HunkRange, ReviewComment, ReviewEvent, ReviewThread,
+    ReviewThreads,
+};
+pub use search::{SearchHit, TextSpan};
 pub use stack::{
-    BranchStatus, ReconcileReport, RestackResult, RestackStatus, Stack, StackBranch, StackMetadata,
+    BranchCi, BranchStatus, DiscoveredBranch, DiscoveredStack, ImportedBranch, ParentCandidate,
+    ParentSource, ReconcileReport, RestackResult, RestackStatus, Stack, StackBranch, StackCiRollup,
+    StackImport, StackMetadata,
 };
-pub use sync::{RateLimitInfo, SyncChange, SyncStatus};
+pub use sync::{OperationCost, RateLimitInfo, SyncChange, SyncStatus};
 pub use template::{PrTemplate, TemplateContext};
+pub use timeline::{
+    Comment, CommentKind, ReactionContent, ReactionGroup, TimelineCommit, TimelineItem,
+};
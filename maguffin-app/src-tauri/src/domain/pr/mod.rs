@@ -62,6 +62,30 @@ pub struct PullRequest {
 
     /// Number of changed files
     pub changed_files: i32,
+
+    /// Combined check state of the head commit (`None` if it has no checks)
+    #[serde(default)]
+    pub check_state: Option<CheckState>,
+
+    /// Pending auto-merge, if enabled
+    #[serde(default)]
+    pub auto_merge: Option<AutoMerge>,
+
+    /// Merge queue entry, if the PR is queued
+    #[serde(default)]
+    pub merge_queue: Option<MergeQueueEntry>,
+
+    /// Logins of assigned users
+    #[serde(default)]
+    pub assignees: Vec<String>,
+
+    /// Milestone the PR belongs to
+    #[serde(default)]
+    pub milestone: Option<Milestone>,
+
+    /// Pending review requests: user logins and team `org/slug`s
+    #[serde(default)]
+    pub requested_reviewers: Vec<String>,
 }
 
 /// PR author information.
@@ -93,6 +117,132 @@ pub struct Label {
     pub color: String,
 }
 
+/// A label defined in the repository.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoLabel {
+    /// GraphQL node ID
+    pub id: String,
+
+    /// Label name
+    pub name: String,
+
+    /// Label color (hex without #)
+    pub color: String,
+
+    /// Label description
+    pub description: Option<String>,
+}
+
+/// A repository milestone.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Milestone {
+    /// GraphQL node ID
+    pub id: String,
+
+    /// Milestone number
+    pub number: i64,
+
+    /// Milestone title
+    pub title: String,
+
+    /// Due date
+    pub due_on: Option<DateTime<Utc>>,
+}
+
+/// An edit to an existing pull request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PrEdit {
+    /// Change the title
+    Title { title: String },
+
+    /// Change the description
+    Body { body: String },
+
+    /// Convert to a draft (`true`) or mark ready for review (`false`)
+    Draft { is_draft: bool },
+
+    /// Add labels by name
+    AddLabels { labels: Vec<String> },
+
+    /// Remove labels by name
+    RemoveLabels { labels: Vec<String> },
+
+    /// Request reviews from users (logins) and teams (slugs)
+    RequestReviewers {
+        users: Vec<String>,
+        teams: Vec<String>,
+    },
+
+    /// Remove review requests from users (logins) and teams (slugs)
+    RemoveReviewers {
+        users: Vec<String>,
+        teams: Vec<String>,
+    },
+
+    /// Replace the assignees (logins)
+    SetAssignees { assignees: Vec<String> },
+
+    /// Set the milestone by number, or clear it
+    SetMilestone { milestone: Option<i64> },
+
+    /// Reopen a closed PR
+    Reopen,
+}
+
+impl PrEdit {
+    /// Apply the edit to a local copy of the PR, ahead of the server.
+    ///
+    /// `labels` and `milestones` supply the colors and titles for added
+    /// labels and milestones; unknown names and numbers are skipped. Team
+    /// review requests are recorded as `org/slug`.
+    pub fn apply(
+        &self,
+        pr: &mut PullRequest,
+        org: &str,
+        labels: &[RepoLabel],
+        milestones: &[Milestone],
+    ) {
+        match self {
+            PrEdit::Title { title } => pr.title = title.clone(),
+            PrEdit::Body { body } => pr.body = Some(body.clone()),
+            PrEdit::Draft { is_draft } => pr.is_draft = *is_draft,
+            PrEdit::AddLabels { labels: names } => {
+                for label in labels.iter().filter(|l| names.contains(&l.name)) {
+                    if !pr.labels.iter().any(|l| l.name == label.name) {
+                        pr.labels.push(Label {
+                            name: label.name.clone(),
+                            color: label.color.clone(),
+                        });
+                    }
+                }
+            }
+            PrEdit::RemoveLabels { labels: names } => {
+                pr.labels.retain(|l| !names.contains(&l.name));
+            }
+            PrEdit::RequestReviewers { users, teams } => {
+                let teams = teams.iter().map(|slug| format!("{}/{}", org, slug));
+                for reviewer in users.iter().cloned().chain(teams) {
+                    if !pr.requested_reviewers.contains(&reviewer) {
+                        pr.requested_reviewers.push(reviewer);
+                    }
+                }
+            }
+            PrEdit::RemoveReviewers { users, teams } => {
+                let teams: Vec<String> = teams.iter().map(|s| format!("{}/{}", org, s)).collect();
+                pr.requested_reviewers
+                    .retain(|r| !users.contains(r) && !teams.contains(r));
+            }
+            PrEdit::SetAssignees { assignees } => pr.assignees = assignees.clone(),
+            PrEdit::SetMilestone { milestone } => {
+                pr.milestone = milestone
+                    .and_then(|number| milestones.iter().find(|m| m.number == number).cloned());
+            }
+            PrEdit::Reopen => pr.state = PrState::Open,
+        }
+    }
+}
+
 /// Review decision for a PR.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -102,6 +252,32 @@ pub enum ReviewDecision {
     ReviewRequired,
 }
 
+/// Minimal reference to a PR, enough to address it in mutations.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PullRequestRef {
+    /// GraphQL node ID
+    pub id: String,
+
+    /// PR number
+    pub number: i64,
+
+    /// Head branch name
+    pub head_ref: String,
+
+    /// Head commit SHA
+    pub head_oid: String,
+
+    /// Base branch name
+    pub base_ref: String,
+
+    /// Whether the head branch is in a fork
+    #[serde(default)]
+    pub is_cross_repository: bool,
+
+    /// Current state
+    pub state: PrState,
+}
+
 /// Whether a PR is mergeable.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -132,6 +308,127 @@ pub struct PullRequestDetails {
 
     /// CI/check status for the head commit
     pub check_status: Option<CheckStatus>,
+
+    /// How far each connection above has been loaded
+    #[serde(default)]
+    pub pagination: DetailsPagination,
+}
+
+impl PullRequestDetails {
+    /// Whether any connection has more items than were loaded.
+    pub fn is_truncated(&self) -> bool {
+        self.pagination.is_truncated()
+    }
+
+    /// Append a lazily fetched page to the matching connection.
+    pub fn append_page(&mut self, page: DetailsPage) {
+        match page.items {
+            DetailsPageItems::Commits(commits) => {
+                self.commits.extend(commits);
+                self.pagination.commits = page.page_info;
+            }
+            DetailsPageItems::Files(files) => {
+                self.files.extend(files);
+                self.pagination.files = page.page_info;
+            }
+            DetailsPageItems::Reviews(reviews) => {
+                self.reviews.extend(reviews);
+                self.pagination.reviews = page.page_info;
+            }
+            DetailsPageItems::ReviewRequests(requests) => {
+                self.review_requests.extend(requests);
+                self.pagination.review_requests = page.page_info;
+            }
+            DetailsPageItems::Checks(checks) => {
+                if let Some(status) = &mut self.check_status {
+                    status.checks.extend(checks);
+                }
+                self.pagination.checks = page.page_info;
+            }
+        }
+    }
+}
+
+/// A paginated connection of [`PullRequestDetails`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetailsConnection {
+    Commits,
+    Files,
+    Reviews,
+    ReviewRequests,
+    Checks,
+}
+
+/// Pagination state of a connection.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageInfo {
+    /// Total number of items in the connection
+    pub total_count: i32,
+
+    /// Whether more items can be fetched after `end_cursor`
+    pub has_next_page: bool,
+
+    /// Cursor to pass when fetching the next page
+    pub end_cursor: Option<String>,
+}
+
+/// Pagination state of every connection in [`PullRequestDetails`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetailsPagination {
+    pub commits: PageInfo,
+    pub files: PageInfo,
+    pub reviews: PageInfo,
+    pub review_requests: PageInfo,
+    pub checks: PageInfo,
+}
+
+impl DetailsPagination {
+    /// Whether any connection has more items than were loaded.
+    pub fn is_truncated(&self) -> bool {
+        [
+            &self.commits,
+            &self.files,
+            &self.reviews,
+            &self.review_requests,
+            &self.checks,
+        ]
+        .iter()
+        .any(|page| page.has_next_page)
+    }
+
+    /// Pagination state of a single connection.
+    pub fn get(&self, connection: DetailsConnection) -> &PageInfo {
+        match connection {
+            DetailsConnection::Commits => &self.commits,
+            DetailsConnection::Files => &self.files,
+            DetailsConnection::Reviews => &self.reviews,
+            DetailsConnection::ReviewRequests => &self.review_requests,
+            DetailsConnection::Checks => &self.checks,
+        }
+    }
+}
+
+/// A page of items fetched after the initial [`PullRequestDetails`] load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetailsPage {
+    /// Items in this page
+    #[serde(flatten)]
+    pub items: DetailsPageItems,
+
+    /// Pagination state of the connection after this page
+    pub page_info: PageInfo,
+}
+
+/// Items of a [`DetailsPage`], tagged with the connection they belong to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "connection", content = "items", rename_all = "snake_case")]
+pub enum DetailsPageItems {
+    Commits(Vec<Commit>),
+    Files(Vec<ChangedFile>),
+    Reviews(Vec<Review>),
+    ReviewRequests(Vec<String>),
+    Checks(Vec<CheckRun>),
 }
 
 /// CI/check status summary.
@@ -142,6 +439,10 @@ pub struct CheckStatus {
 
     /// Individual check runs
     pub checks: Vec<CheckRun>,
+
+    /// Commit the checks belong to; later pages are fetched for it
+    #[serde(default)]
+    pub commit_oid: String,
 }
 
 /// Overall check state.
@@ -161,6 +462,10 @@ pub enum CheckState {
 /// An individual check run or status context.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckRun {
+    /// Database ID of a check run; `None` for commit status contexts
+    #[serde(default)]
+    pub id: Option<i64>,
+
     /// Name of the check
     pub name: String,
 
@@ -292,6 +597,153 @@ pub enum MergeMethod {
     Rebase,
 }
 
+/// How to bring a PR branch up to date with its base.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BranchUpdateMethod {
+    /// Merge the base into the head branch
+    Merge,
+    /// Rebase the head branch onto the base
+    Rebase,
+}
+
+/// Outcome of updating a PR branch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchUpdate {
+    /// New head commit SHA
+    pub head_oid: String,
+
+    /// Method the branch was updated with
+    pub method: BranchUpdateMethod,
+
+    /// Whether the branch was rebased locally and force-pushed because
+    /// GitHub couldn't update it
+    pub local: bool,
+}
+
+/// Auto-merge settings of a PR, merged once all requirements are met.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoMerge {
+    /// Method the PR will be merged with
+    pub merge_method: MergeMethod,
+
+    /// When auto-merge was enabled
+    pub enabled_at: Option<DateTime<Utc>>,
+
+    /// Login of the user who enabled it
+    pub enabled_by: Option<String>,
+}
+
+/// A PR's entry in the merge queue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergeQueueEntry {
+    /// Position in the queue, starting at 1
+    pub position: i32,
+
+    /// State of the entry
+    pub state: MergeQueueState,
+
+    /// When the PR was added to the queue
+    pub enqueued_at: DateTime<Utc>,
+
+    /// Estimated seconds until the PR is merged
+    pub estimated_time_to_merge: Option<i32>,
+}
+
+/// State of a merge queue entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MergeQueueState {
+    /// Waiting for its turn
+    Queued,
+    /// Checks are running on the merge group
+    AwaitingChecks,
+    /// Will be merged when it reaches the head of the queue
+    Mergeable,
+    /// Can't be merged and will be removed from the queue
+    Unmergeable,
+    /// Queue is locked, e.g. while its rules change
+    Locked,
+}
+
+/// PR list sort order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrSortOrder {
+    /// Newest first
+    CreatedAt,
+    /// Most recently updated first
+    #[default]
+    UpdatedAt,
+    /// PRs waiting on a review first, then those with changes requested,
+    /// then approved ones, then those needing no review; most recently
+    /// updated first within each group
+    ReviewStatus,
+}
+
+/// Filters and sort order for the PR list.
+///
+/// Empty lists and `None` match everything. Values within a list are
+/// alternatives, except `labels`, which a PR must all carry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrQuery {
+    /// Author logins
+    pub authors: Vec<String>,
+
+    /// Label names, all required
+    pub labels: Vec<String>,
+
+    /// Review decisions
+    pub review_decisions: Vec<ReviewDecision>,
+
+    /// Draft (`true`) or ready for review (`false`)
+    pub draft: Option<bool>,
+
+    /// Combined check states
+    pub check_states: Vec<CheckState>,
+
+    /// Mergeable states
+    pub mergeable: Vec<Mergeable>,
+
+    /// Glob pattern for the base branch, e.g. `release/*`
+    pub base: Option<String>,
+
+    /// Exact base branch name
+    pub base_branch: Option<String>,
+
+    /// Glob pattern for the head branch
+    pub head: Option<String>,
+
+    /// Words to find in the title, body, head branch or author; `#123`
+    /// matches PR number 123
+    pub text: Option<String>,
+
+    /// Sort order
+    pub sort: PrSortOrder,
+}
+
+impl PrQuery {
+    /// Whitespace-separated words of the free-text filter, each of which a
+    /// PR must match.
+    pub fn terms(&self) -> Vec<&str> {
+        self.text
+            .as_deref()
+            .map(|text| text.split_whitespace().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// A named PR list query saved by the user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedView {
+    /// Unique view name
+    pub name: String,
+
+    /// The query the view shows
+    pub query: PrQuery,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -310,10 +762,194 @@ mod tests {
         assert_eq!(json, "\"APPROVED\"");
     }
 
+    #[test]
+    fn test_append_page_extends_connection() {
+        let pagination = DetailsPagination {
+            files: PageInfo {
+                total_count: 2,
+                has_next_page: true,
+                end_cursor: Some("c1".to_string()),
+            },
+            ..Default::default()
+        };
+        assert!(pagination.is_truncated());
+
+        let file = |path: &str| ChangedFile {
+            path: path.to_string(),
+            additions: 1,
+            deletions: 0,
+            change_type: ChangeType::Modified,
+        };
+        let mut details: PullRequestDetails = serde_json::from_value(serde_json::json!({
+            "id": "PR_1",
+            "number": 1,
+            "title": "t",
+            "body": null,
+            "state": "OPEN",
+            "is_draft": false,
+            "author": { "login": "octo", "avatar_url": "" },
+            "head_ref": "feature",
+            "base_ref": "main",
+            "labels": [],
+            "review_decision": null,
+            "mergeable": "UNKNOWN",
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+            "commit_count": 1,
+            "additions": 1,
+            "deletions": 0,
+            "changed_files": 2,
+            "commits": [],
+            "files": [file("a.rs")],
+            "reviews": [],
+            "review_requests": [],
+            "check_status": null
+        }))
+        .unwrap();
+        details.pagination = pagination;
+
+        details.append_page(DetailsPage {
+            items: DetailsPageItems::Files(vec![file("b.rs")]),
+            page_info: PageInfo {
+                total_count: 2,
+                has_next_page: false,
+                end_cursor: Some("c2".to_string()),
+            },
+        });
+
+        assert_eq!(details.files.len(), 2);
+        assert_eq!(details.files[1].path, "b.rs");
+        assert!(!details.is_truncated());
+    }
+
+    #[test]
+    fn test_details_page_serialization() {
+        let page = DetailsPage {
+            items: DetailsPageItems::ReviewRequests(vec!["octo".to_string()]),
+            page_info: PageInfo::default(),
+        };
+        let json = serde_json::to_value(&page).unwrap();
+        assert_eq!(json["connection"], "review_requests");
+        assert_eq!(json["items"][0], "octo");
+        assert_eq!(json["page_info"]["has_next_page"], false);
+    }
+
+    #[test]
+    fn test_pr_edit_apply() {
+        let mut pr: PullRequest = serde_json::from_value(serde_json::json!({
+            "id": "PR_1",
+            "number": 1,
+            "title": "t",
+            "body": null,
+            "state": "OPEN",
+            "is_draft": true,
+            "author": { "login": "octo", "avatar_url": "" },
+            "head_ref": "feature",
+            "base_ref": "main",
+            "labels": [{ "name": "bug", "color": "d73a4a" }],
+            "review_decision": null,
+            "mergeable": "UNKNOWN",
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+            "commit_count": 1,
+            "additions": 1,
+            "deletions": 0,
+            "changed_files": 1
+        }))
+        .unwrap();
+        let labels = vec![RepoLabel {
+            id: "LA_1".to_string(),
+            name: "ready".to_string(),
+            color: "0e8a16".to_string(),
+            description: None,
+        }];
+        let milestones = vec![Milestone {
+            id: "MI_1".to_string(),
+            number: 3,
+            title: "v1.0".to_string(),
+            due_on: None,
+        }];
+        let apply =
+            |pr: &mut PullRequest, edit: PrEdit| edit.apply(pr, "octo-org", &labels, &milestones);
+
+        apply(&mut pr, PrEdit::Draft { is_draft: false });
+        assert!(!pr.is_draft);
+
+        apply(
+            &mut pr,
+            PrEdit::AddLabels {
+                labels: vec![
+                    "ready".to_string(),
+                    "ready".to_string(),
+                    "unknown".to_string(),
+                ],
+            },
+        );
+        apply(
+            &mut pr,
+            PrEdit::RemoveLabels {
+                labels: vec!["bug".to_string()],
+            },
+        );
+        let names: Vec<_> = pr.labels.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, ["ready"]);
+        assert_eq!(pr.labels[0].color, "0e8a16");
+
+        apply(
+            &mut pr,
+            PrEdit::RequestReviewers {
+                users: vec!["hubot".to_string()],
+                teams: vec!["core".to_string()],
+            },
+        );
+        assert_eq!(pr.requested_reviewers, ["hubot", "octo-org/core"]);
+        apply(
+            &mut pr,
+            PrEdit::RemoveReviewers {
+                users: Vec::new(),
+                teams: vec!["core".to_string()],
+            },
+        );
+        assert_eq!(pr.requested_reviewers, ["hubot"]);
+
+        apply(&mut pr, PrEdit::SetMilestone { milestone: Some(3) });
+        assert_eq!(
+            pr.milestone.as_ref().map(|m| m.title.as_str()),
+            Some("v1.0")
+        );
+        apply(&mut pr, PrEdit::SetMilestone { milestone: None });
+        assert!(pr.milestone.is_none());
+    }
+
+    #[test]
+    fn test_pr_edit_serialization() {
+        let edit: PrEdit =
+            serde_json::from_str(r#"{"type": "set_milestone", "milestone": null}"#).unwrap();
+        assert_eq!(edit, PrEdit::SetMilestone { milestone: None });
+        assert_eq!(
+            serde_json::to_value(PrEdit::Reopen).unwrap(),
+            serde_json::json!({ "type": "reopen" })
+        );
+    }
+
     #[test]
     fn test_merge_method_serialization() {
         let method = MergeMethod::Squash;
         let json = serde_json::to_string(&method).unwrap();
         assert_eq!(json, "\"SQUASH\"");
     }
+
+    #[test]
+    fn test_pr_query_partial_deserialization() {
+        let query: PrQuery = serde_json::from_str(
+            r#"{"draft": false, "text": " fix  #12 ", "sort": "review_status"}"#,
+        )
+        .unwrap();
+        assert_eq!(query.draft, Some(false));
+        assert_eq!(query.sort, PrSortOrder::ReviewStatus);
+        assert!(query.authors.is_empty());
+        assert_eq!(query.terms(), ["fix", "#12"]);
+
+        assert_eq!(PrQuery::default().sort, PrSortOrder::UpdatedAt);
+    }
 }
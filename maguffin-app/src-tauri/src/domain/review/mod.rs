@@ -0,0 +1,313 @@
+//! Pull request review domain types.
+//!
+//! This module contains the types for reviewing a pull request inline:
+//! per-file patches, review threads with their comments, and the draft
+//! comments and events that make up a pending review.
+
+use crate::domain::pr::{Author, ChangeType};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Side of a diff a review comment is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DiffSide {
+    /// The base version (deleted and context lines)
+    Left,
+    /// The head version (added and context lines)
+    Right,
+}
+
+/// Line ranges covered by one hunk of a unified diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HunkRange {
+    /// First line of the hunk in the base version
+    pub old_start: i32,
+
+    /// Number of base lines in the hunk
+    pub old_lines: i32,
+
+    /// First line of the hunk in the head version
+    pub new_start: i32,
+
+    /// Number of head lines in the hunk
+    pub new_lines: i32,
+}
+
+impl HunkRange {
+    /// Parse a hunk header such as `@@ -12,7 +12,9 @@ fn main() {`.
+    ///
+    /// A missing line count means one line, as in `@@ -1 +1 @@`.
+    pub fn parse(header: &str) -> Option<Self> {
+        let ranges = header.strip_prefix("@@ ")?;
+        let (ranges, _) = ranges.split_once(" @@")?;
+        let (old, new) = ranges.split_once(' ')?;
+
+        let parse_range = |range: &str| -> Option<(i32, i32)> {
+            match range.split_once(',') {
+                Some((start, lines)) => Some((start.parse().ok()?, lines.parse().ok()?)),
+                None => Some((range.parse().ok()?, 1)),
+            }
+        };
+        let (old_start, old_lines) = parse_range(old.strip_prefix('-')?)?;
+        let (new_start, new_lines) = parse_range(new.strip_prefix('+')?)?;
+
+        Some(Self {
+            old_start,
+            old_lines,
+            new_start,
+            new_lines,
+        })
+    }
+
+    /// Check whether `line` on `side` falls inside this hunk.
+    pub fn contains(&self, side: DiffSide, line: i32) -> bool {
+        let (start, lines) = match side {
+            DiffSide::Left => (self.old_start, self.old_lines),
+            DiffSide::Right => (self.new_start, self.new_lines),
+        };
+        line >= start && line < start + lines
+    }
+}
+
+/// Patch of a single changed file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilePatch {
+    /// File path in the head version
+    pub path: String,
+
+    /// Path before a rename, if the file was renamed
+    pub previous_path: Option<String>,
+
+    /// Type of change
+    pub change_type: ChangeType,
+
+    /// Lines added
+    pub additions: i32,
+
+    /// Lines deleted
+    pub deletions: i32,
+
+    /// Unified diff of the file; `None` for binary files and diffs too large
+    /// for GitHub to return
+    pub patch: Option<String>,
+
+    /// Hunks of the patch, i.e. the lines that can be commented on
+    pub hunks: Vec<HunkRange>,
+}
+
+impl FilePatch {
+    /// Parse the hunk headers of a unified diff.
+    pub fn parse_hunks(patch: &str) -> Vec<HunkRange> {
+        patch
+            .lines()
+            .filter(|line| line.starts_with("@@"))
+            .filter_map(HunkRange::parse)
+            .collect()
+    }
+
+    /// Check whether `line` on `side` can be commented on.
+    ///
+    /// GitHub only accepts review comments on lines inside a hunk.
+    pub fn contains_line(&self, side: DiffSide, line: i32) -> bool {
+        self.hunks.iter().any(|hunk| hunk.contains(side, line))
+    }
+}
+
+/// A comment in a review thread.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewComment {
+    /// GraphQL node ID
+    pub id: String,
+
+    /// Comment author
+    pub author: Author,
+
+    /// Comment body (Markdown)
+    pub body: String,
+
+    /// Creation timestamp
+    pub created_at: DateTime<Utc>,
+
+    /// Whether the comment belongs to the viewer's unsubmitted review
+    pub is_pending: bool,
+}
+
+/// A review thread attached to a line or range of lines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewThread {
+    /// GraphQL node ID
+    pub id: String,
+
+    /// File the thread is attached to
+    pub path: String,
+
+    /// Last line of the commented range; `None` once the thread is outdated
+    pub line: Option<i32>,
+
+    /// First line of a multi-line comment
+    pub start_line: Option<i32>,
+
+    /// Side of the diff `line` refers to
+    pub side: DiffSide,
+
+    /// Side of the diff `start_line` refers to
+    pub start_side: Option<DiffSide>,
+
+    /// Whether the thread has been resolved
+    pub is_resolved: bool,
+
+    /// Whether the commented lines have changed since
+    pub is_outdated: bool,
+
+    /// Whether the viewer can resolve or unresolve the thread
+    pub can_resolve: bool,
+
+    /// Comments in the thread, oldest first
+    pub comments: Vec<ReviewComment>,
+}
+
+/// A comment to add to a pending review.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftReviewComment {
+    /// File to comment on
+    pub path: String,
+
+    /// Line to comment on, or the last line of a multi-line comment
+    pub line: i32,
+
+    /// Side of the diff `line` refers to
+    pub side: DiffSide,
+
+    /// First line of a multi-line comment
+    #[serde(default)]
+    pub start_line: Option<i32>,
+
+    /// Side of the diff `start_line` refers to; defaults to `side`
+    #[serde(default)]
+    pub start_side: Option<DiffSide>,
+
+    /// Comment body (Markdown)
+    pub body: String,
+}
+
+impl DraftReviewComment {
+    /// Check the comment is well-formed before sending it.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.body.trim().is_empty() {
+            return Err("Comment body is empty".to_string());
+        }
+        if self.line < 1 {
+            return Err(format!("Invalid line number: {}", self.line));
+        }
+        if let Some(start_line) = self.start_line {
+            // Ranges spanning both sides are ordered by position in the diff,
+            // which line numbers alone can't tell.
+            let same_side = self.start_side.unwrap_or(self.side) == self.side;
+            if start_line < 1 || (same_side && start_line >= self.line) {
+                return Err(format!("Invalid line range: {}-{}", start_line, self.line));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of submitting a review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReviewEvent {
+    Approve,
+    Comment,
+    RequestChanges,
+}
+
+/// Review threads of a pull request and the viewer's pending review.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewThreads {
+    /// ID of the viewer's unsubmitted review, if one is in progress
+    pub pending_review_id: Option<String>,
+
+    /// All review threads, in the order GitHub returns them
+    pub threads: Vec<ReviewThread>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hunk_header() {
+        assert_eq!(
+            HunkRange::parse("@@ -12,7 +12,9 @@ fn main() {"),
+            Some(HunkRange {
+                old_start: 12,
+                old_lines: 7,
+                new_start: 12,
+                new_lines: 9,
+            })
+        );
+        assert_eq!(
+            HunkRange::parse("@@ -1 +0,0 @@"),
+            Some(HunkRange {
+                old_start: 1,
+                old_lines: 1,
+                new_start: 0,
+                new_lines: 0,
+            })
+        );
+        assert_eq!(HunkRange::parse("+@@ not a header"), None);
+    }
+
+    #[test]
+    fn test_contains_line() {
+        let patch = "@@ -1,3 +1,4 @@\n a\n+b\n c\n d\n@@ -20,2 +21,2 @@\n-x\n+y\n z";
+        let file = FilePatch {
+            path: "src/lib.rs".to_string(),
+            previous_path: None,
+            change_type: ChangeType::Modified,
+            additions: 2,
+            deletions: 1,
+            patch: Some(patch.to_string()),
+            hunks: FilePatch::parse_hunks(patch),
+        };
+
+        assert_eq!(file.hunks.len(), 2);
+        assert!(file.contains_line(DiffSide::Right, 4));
+        assert!(!file.contains_line(DiffSide::Right, 5));
+        assert!(file.contains_line(DiffSide::Left, 21));
+        assert!(!file.contains_line(DiffSide::Left, 22));
+        assert!(file.contains_line(DiffSide::Right, 22));
+    }
+
+    #[test]
+    fn test_validate_draft_comment() {
+        let comment = DraftReviewComment {
+            path: "src/lib.rs".to_string(),
+            line: 10,
+            side: DiffSide::Right,
+            start_line: Some(8),
+            start_side: None,
+            body: "Could this be simpler?".to_string(),
+        };
+        assert!(comment.validate().is_ok());
+
+        let reversed = DraftReviewComment {
+            start_line: Some(12),
+            ..comment.clone()
+        };
+        assert!(reversed.validate().is_err());
+
+        let across_sides = DraftReviewComment {
+            start_line: Some(12),
+            start_side: Some(DiffSide::Left),
+            ..comment.clone()
+        };
+        assert!(across_sides.validate().is_ok());
+
+        let empty = DraftReviewComment {
+            body: "  ".to_string(),
+            ..comment
+        };
+        assert!(empty.validate().is_err());
+    }
+}
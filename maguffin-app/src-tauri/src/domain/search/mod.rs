@@ -0,0 +1,140 @@
+//! Local search domain types.
+//!
+//! This module contains the results of searching the cached pull requests of
+//! every opened repository, and the helpers that turn user input into an
+//! FTS5 query and FTS5 highlights into text spans.
+
+use crate::domain::pr::PullRequest;
+use serde::{Deserialize, Serialize};
+
+/// Marks the start of a match in FTS5 `highlight()`/`snippet()` output.
+pub const MATCH_START: char = '\u{2}';
+
+/// Marks the end of a match in FTS5 `highlight()`/`snippet()` output.
+pub const MATCH_END: char = '\u{3}';
+
+/// A run of text, either matching the search or not.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextSpan {
+    /// The text
+    pub text: String,
+
+    /// Whether the text matches a search term
+    pub matched: bool,
+}
+
+/// A pull request found by a local search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// Repository owner
+    pub owner: String,
+
+    /// Repository name
+    pub repo: String,
+
+    /// The matching PR as last synced
+    pub pull_request: PullRequest,
+
+    /// Relevance; higher is better
+    pub score: f64,
+
+    /// PR title with matches marked
+    pub title: Vec<TextSpan>,
+
+    /// Excerpt of the best matching field with matches marked
+    pub snippet: Vec<TextSpan>,
+}
+
+/// Build an FTS5 query from free text.
+///
+/// Every word must match, as a prefix so results show up while typing.
+/// Words are quoted, so FTS5 operators and punctuation in the input are
+/// searched for literally rather than parsed. Returns `None` if there is
+/// nothing to search for.
+pub fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .map(|word| word.replace('"', ""))
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Split text marked with [`MATCH_START`] and [`MATCH_END`] into spans.
+pub fn parse_highlight(marked: &str) -> Vec<TextSpan> {
+    let mut spans = Vec::new();
+    let mut text = String::new();
+    let mut matched = false;
+
+    for c in marked.chars() {
+        let boundary = match c {
+            MATCH_START => Some(true),
+            MATCH_END => Some(false),
+            _ => None,
+        };
+        match boundary {
+            Some(starts_match) => {
+                if !text.is_empty() {
+                    spans.push(TextSpan {
+                        text: std::mem::take(&mut text),
+                        matched,
+                    });
+                }
+                matched = starts_match;
+            }
+            None => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        spans.push(TextSpan { text, matched });
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(
+            fts_query("dark mode").as_deref(),
+            Some("\"dark\"* \"mode\"*")
+        );
+        assert_eq!(
+            fts_query("fix \"NOT\" -- feature/x").as_deref(),
+            Some("\"fix\"* \"NOT\"* \"feature/x\"*")
+        );
+        assert_eq!(fts_query("  -- \" "), None);
+    }
+
+    #[test]
+    fn test_parse_highlight() {
+        let spans = parse_highlight("Add \u{2}dark\u{3} \u{2}mode\u{3}");
+        assert_eq!(
+            spans,
+            [
+                TextSpan {
+                    text: "Add ".to_string(),
+                    matched: false,
+                },
+                TextSpan {
+                    text: "dark".to_string(),
+                    matched: true,
+                },
+                TextSpan {
+                    text: " ".to_string(),
+                    matched: false,
+                },
+                TextSpan {
+                    text: "mode".to_string(),
+                    matched: true,
+                },
+            ]
+        );
+        assert!(parse_highlight("").is_empty());
+    }
+}
@@ -0,0 +1,536 @@
+//! Changes to the shape of stacks.
+//!
+//! An edit moves branches to new parents in the metadata. Every moved branch
+//! and everything stacked on it then has to be rebased from its old base
+//! onto its new parent, which the stack service does in the order the plan
+//! gives.
+
+use super::{RestackBranchPreview, Stack, StackBranch, StackMetadata};
+use crate::error::{GitError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// A change to where branches sit in their stacks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StackEdit {
+    /// Give `branch` a new parent, taking its descendants along.
+    ///
+    /// `parent` is either a stack branch, whose stack the subtree joins, or
+    /// the root of `stack_id`, which defaults to the branch's own stack.
+    Reparent {
+        branch: String,
+        parent: String,
+        #[serde(default)]
+        stack_id: Option<Uuid>,
+    },
+
+    /// Put `branch` between `parent` and its children in the stack, creating
+    /// the branch at `parent` if it doesn't exist.
+    Insert {
+        stack_id: Uuid,
+        branch: String,
+        parent: String,
+    },
+
+    /// Swap `branch` with its parent branch. The children of both end up on
+    /// the old parent, which now comes after `branch`.
+    Swap { branch: String },
+}
+
+/// A branch getting a new parent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchMove {
+    /// Branch name
+    pub branch: String,
+
+    /// Previous parent, if the branch was already in a stack
+    pub from: Option<String>,
+
+    /// New parent
+    pub to: String,
+
+    /// The branch's PR, whose base follows the move
+    pub pr_number: Option<i64>,
+}
+
+/// What an edit changes.
+#[derive(Debug, Clone, Default)]
+pub struct EditPlan {
+    /// Stacks the edit touches
+    pub stacks: Vec<Uuid>,
+
+    /// Branches with a new parent
+    pub moves: Vec<BranchMove>,
+
+    /// Branches whose base changes, parents before children
+    pub rebase: Vec<String>,
+}
+
+/// Preview of what a stack edit will do.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackEditPreview {
+    /// Branches that get a new parent
+    pub moves: Vec<BranchMove>,
+
+    /// Branches that will be rebased, in order
+    pub will_rebase: Vec<RestackBranchPreview>,
+
+    /// Total number of commits that will be replayed
+    pub total_commits: i32,
+}
+
+/// Result of a stack edit, or of undoing one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StackEditResult {
+    /// Branches that got a new parent
+    pub moves: Vec<BranchMove>,
+
+    /// Branches whose commits were rewritten
+    pub rebased: Vec<String>,
+
+    /// PR closed because its branch was folded into its parent
+    #[serde(default)]
+    pub closed_pr: Option<i64>,
+}
+
+/// A branch's entry in the stacks before an edit, for undoing it.
+#[derive(Debug, Clone)]
+pub struct BranchSnapshot {
+    /// Branch name
+    pub name: String,
+
+    /// The stack the branch was in and its entry, if it was tracked
+    pub entry: Option<(Uuid, StackBranch)>,
+}
+
+/// A commit of a stack branch, for choosing where to split it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchCommit {
+    /// Commit SHA
+    pub sha: String,
+
+    /// First line of the commit message
+    pub summary: String,
+}
+
+impl StackMetadata {
+    /// Find a tracked branch in any stack.
+    pub fn find_branch(&self, name: &str) -> Option<&StackBranch> {
+        self.stacks.iter().find_map(|s| s.find_branch(name))
+    }
+
+    /// Find a tracked branch in any stack (mutable).
+    pub fn find_branch_mut(&mut self, name: &str) -> Option<&mut StackBranch> {
+        self.stacks.iter_mut().find_map(|s| s.find_branch_mut(name))
+    }
+
+    /// Record where `names` sit in the stacks, for [`Self::restore_branches`].
+    pub fn snapshot_branches<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<BranchSnapshot> {
+        let mut seen = HashSet::new();
+        names
+            .into_iter()
+            .filter(|name| seen.insert(*name))
+            .map(|name| BranchSnapshot {
+                name: name.to_string(),
+                entry: self
+                    .stacks
+                    .iter()
+                    .find_map(|s| s.find_branch(name).map(|b| (s.id, b.clone()))),
+            })
+            .collect()
+    }
+
+    /// Put branches back in the stacks, parents and heads they had when
+    /// `snapshots` were taken.
+    ///
+    /// Only what an edit changes is restored: everything else about the
+    /// branches, such as a PR recorded since, and all other branches are
+    /// kept as they are now. Branches that weren't tracked are removed, and
+    /// branches that have since been removed are tracked again.
+    pub fn restore_branches(&mut self, snapshots: &[BranchSnapshot]) {
+        for snapshot in snapshots {
+            let current = self.stacks.iter().enumerate().find_map(|(i, s)| {
+                s.branches
+                    .iter()
+                    .position(|b| b.name == snapshot.name)
+                    .map(|j| (i, j))
+            });
+            let current = current.map(|(i, j)| (i, self.stacks[i].branches.remove(j)));
+
+            let Some((stack_id, before)) = &snapshot.entry else {
+                continue;
+            };
+            let (fallback, mut branch) = match current {
+                Some((i, branch)) => (Some(i), branch),
+                None => (None, before.clone()),
+            };
+            branch.parent = before.parent.clone();
+            branch.head_sha = before.head_sha.clone();
+            branch.status = before.status;
+
+            // The branch stays where it is if its old stack is gone
+            let Some(index) = self
+                .stacks
+                .iter()
+                .position(|s| s.id == *stack_id)
+                .or(fallback)
+            else {
+                continue;
+            };
+            let stack = &mut self.stacks[index];
+            stack.branches.push(branch);
+            stack.updated_at = chrono::Utc::now();
+        }
+    }
+
+    /// Apply `edit` to the metadata, returning what has to happen in git.
+    ///
+    /// Nothing changes if the edit is invalid.
+    pub fn apply_edit(&mut self, edit: &StackEdit) -> Result<EditPlan> {
+        match edit {
+            StackEdit::Reparent {
+                branch,
+                parent,
+                stack_id,
+            } => self.reparent(branch, parent, *stack_id),
+            StackEdit::Insert {
+                stack_id,
+                branch,
+                parent,
+            } => self.insert(*stack_id, branch, parent),
+            StackEdit::Swap { branch } => self.swap(branch),
+        }
+    }
+
+    fn stack_index(&self, branch: &str) -> Result<usize> {
+        self.stacks
+            .iter()
+            .position(|s| s.find_branch(branch).is_some())
+            .ok_or_else(|| GitError::Branch(format!("{} is not in a stack", branch)).into())
+    }
+
+    fn stack_index_by_id(&self, id: Uuid) -> Result<usize> {
+        self.stacks
+            .iter()
+            .position(|s| s.id == id)
+            .ok_or_else(|| GitError::Branch("Stack not found".to_string()).into())
+    }
+
+    fn reparent(&mut self, branch: &str, parent: &str, stack_id: Option<Uuid>) -> Result<EditPlan> {
+        let source = self.stack_index(branch)?;
+        let subtree = self.stacks[source].descendants_of(branch);
+        if parent == branch || subtree.iter().any(|b| b == parent) {
+            return Err(GitError::Branch(format!(
+                "Can't move {} onto its own descendant {}",
+                branch, parent
+            ))
+            .into());
+        }
+
+        let target = match self.stack_index(parent) {
+            Ok(target) => {
+                if stack_id.is_some_and(|id| id != self.stacks[target].id) {
+                    return Err(
+                        GitError::Branch(format!("{} is in a different stack", parent)).into(),
+                    );
+                }
+                target
+            }
+            Err(_) => {
+                let target = match stack_id {
+                    Some(id) => self.stack_index_by_id(id)?,
+                    None => source,
+                };
+                if self.stacks[target].root != parent {
+                    return Err(GitError::Branch(format!("{} is not in the stack", parent)).into());
+                }
+                target
+            }
+        };
+
+        let stack = &mut self.stacks[source];
+        let moved = stack
+            .find_branch_mut(branch)
+            .ok_or_else(|| GitError::Branch(format!("{} is not in a stack", branch)))?;
+        if moved.parent == parent && source == target {
+            return Ok(EditPlan::default());
+        }
+        let from = std::mem::replace(&mut moved.parent, parent.to_string());
+        let pr_number = moved.pr_number;
+        stack.updated_at = chrono::Utc::now();
+
+        if source != target {
+            let mut names: HashSet<&str> = subtree.iter().map(String::as_str).collect();
+            names.insert(branch);
+            let (leaving, staying) = std::mem::take(&mut self.stacks[source].branches)
+                .into_iter()
+                .partition(|b| names.contains(b.name.as_str()));
+            self.stacks[source].branches = staying;
+            let destination = &mut self.stacks[target];
+            destination.branches.extend::<Vec<StackBranch>>(leaving);
+            destination.updated_at = chrono::Utc::now();
+        }
+
+        let moves = vec![BranchMove {
+            branch: branch.to_string(),
+            from: Some(from),
+            to: parent.to_string(),
+            pr_number,
+        }];
+        let mut stacks = vec![self.stacks[source].id];
+        if source != target {
+            stacks.push(self.stacks[target].id);
+        }
+        Ok(EditPlan {
+            stacks,
+            rebase: rebase_order(&self.stacks[target], &moves),
+            moves,
+        })
+    }
+
+    fn insert(&mut self, stack_id: Uuid, branch: &str, parent: &str) -> Result<EditPlan> {
+        if self.find_branch(branch).is_some() {
+            return Err(GitError::Branch(format!("{} is already in a stack", branch)).into());
+        }
+        let index = self.stack_index_by_id(stack_id)?;
+        let stack = &mut self.stacks[index];
+        if stack.root != parent && stack.find_branch(parent).is_none() {
+            return Err(GitError::Branch(format!("{} is not in the stack", parent)).into());
+        }
+
+        let mut moves = vec![BranchMove {
+            branch: branch.to_string(),
+            from: None,
+            to: parent.to_string(),
+            pr_number: None,
+        }];
+        for child in stack.branches.iter_mut().filter(|b| b.parent == parent) {
+            child.parent = branch.to_string();
+            moves.push(BranchMove {
+                branch: child.name.clone(),
+                from: Some(parent.to_string()),
+                to: branch.to_string(),
+                pr_number: child.pr_number,
+            });
+        }
+        stack.add_branch(StackBranch::new(branch.to_string(), parent.to_string()));
+
+        Ok(EditPlan {
+            stacks: vec![stack_id],
+            rebase: rebase_order(stack, &moves),
+            moves,
+        })
+    }
+
+    fn swap(&mut self, branch: &str) -> Result<EditPlan> {
+        let index = self.stack_index(branch)?;
+        let stack = &mut self.stacks[index];
+        let upper = stack
+            .find_branch(branch)
+            .ok_or_else(|| GitError::Branch(format!("{} is not in a stack", branch)))?
+            .parent
+            .clone();
+        let grandparent = match stack.find_branch(&upper) {
+            Some(upper) => upper.parent.clone(),
+            None => {
+                return Err(
+                    GitError::Branch(format!("{} is at the bottom of its stack", branch)).into(),
+                )
+            }
+        };
+
+        let mut moves = Vec::new();
+        let mut relink = |b: &mut StackBranch, to: &str| {
+            moves.push(BranchMove {
+                branch: b.name.clone(),
+                from: Some(std::mem::replace(&mut b.parent, to.to_string())),
+                to: to.to_string(),
+                pr_number: b.pr_number,
+            });
+        };
+        for b in stack.branches.iter_mut() {
+            if b.name == branch {
+                relink(b, &grandparent);
+            } else if b.name == upper {
+                relink(b, branch);
+            } else if b.parent == branch {
+                relink(b, &upper);
+            }
+        }
+        stack.updated_at = chrono::Utc::now();
+
+        Ok(EditPlan {
+            stacks: vec![stack.id],
+            rebase: rebase_order(stack, &moves),
+            moves,
+        })
+    }
+}
+
+/// The moved branches and everything stacked on them, parents first.
+fn rebase_order(stack: &Stack, moves: &[BranchMove]) -> Vec<String> {
+    let mut found: HashSet<&str> = moves.iter().map(|m| m.branch.as_str()).collect();
+    let mut result = Vec::new();
+    for b in stack.topological_order() {
+        if found.contains(b.name.as_str()) || found.contains(b.parent.as_str()) {
+            found.insert(&b.name);
+            result.push(b.name.clone());
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    /// `main` <- a <- b <- c in one stack, `main` <- d in another.
+    fn metadata() -> StackMetadata {
+        let mut metadata = StackMetadata::default();
+        let mut first = Stack::new("main".to_string());
+        for (name, parent) in [("a", "main"), ("b", "a"), ("c", "b")] {
+            first.add_branch(StackBranch::new(name.to_string(), parent.to_string()));
+        }
+        let mut second = Stack::new("main".to_string());
+        second.add_branch(StackBranch::new("d".to_string(), "main".to_string()).with_pr(4));
+        metadata.add_stack(first);
+        metadata.add_stack(second);
+        metadata
+    }
+
+    fn parent(metadata: &StackMetadata, branch: &str) -> String {
+        metadata.find_branch(branch).unwrap().parent.clone()
+    }
+
+    #[test]
+    fn test_reparent_moves_subtree_to_another_stack() {
+        let mut metadata = metadata();
+        let plan = metadata
+            .apply_edit(&StackEdit::Reparent {
+                branch: "b".to_string(),
+                parent: "d".to_string(),
+                stack_id: None,
+            })
+            .unwrap();
+
+        assert_eq!(plan.rebase, ["b", "c"]);
+        assert_eq!(plan.moves[0].from.as_deref(), Some("a"));
+        assert_eq!(plan.stacks.len(), 2);
+        assert_eq!(parent(&metadata, "b"), "d");
+        assert_eq!(metadata.stacks[0].branches.len(), 1);
+        assert!(metadata.stacks[1].find_branch("c").is_some());
+    }
+
+    #[test]
+    fn test_reparent_rejects_cycles_and_strangers() {
+        let mut metadata = metadata();
+        for parent in ["c", "b", "develop"] {
+            let err = metadata
+                .apply_edit(&StackEdit::Reparent {
+                    branch: "b".to_string(),
+                    parent: parent.to_string(),
+                    stack_id: None,
+                })
+                .unwrap_err();
+            assert!(matches!(err, AppError::Git(GitError::Branch(_))));
+        }
+        assert_eq!(parent(&metadata, "b"), "a");
+
+        // Moving to the root of the branch's own stack is fine
+        let plan = metadata
+            .apply_edit(&StackEdit::Reparent {
+                branch: "b".to_string(),
+                parent: "main".to_string(),
+                stack_id: None,
+            })
+            .unwrap();
+        assert_eq!(plan.rebase, ["b", "c"]);
+    }
+
+    #[test]
+    fn test_insert_takes_over_children() {
+        let mut metadata = metadata();
+        let id = metadata.stacks[0].id;
+        let plan = metadata
+            .apply_edit(&StackEdit::Insert {
+                stack_id: id,
+                branch: "n".to_string(),
+                parent: "a".to_string(),
+            })
+            .unwrap();
+
+        assert_eq!(plan.rebase, ["n", "b", "c"]);
+        assert_eq!(plan.moves.len(), 2);
+        assert_eq!(parent(&metadata, "n"), "a");
+        assert_eq!(parent(&metadata, "b"), "n");
+
+        assert!(metadata
+            .apply_edit(&StackEdit::Insert {
+                stack_id: id,
+                branch: "d".to_string(),
+                parent: "a".to_string(),
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_restore_keeps_later_changes() {
+        let mut metadata = metadata();
+        let first = metadata.stacks[0].id;
+        let snapshots = metadata.snapshot_branches(["b", "c", "n"]);
+        metadata
+            .apply_edit(&StackEdit::Reparent {
+                branch: "b".to_string(),
+                parent: "d".to_string(),
+                stack_id: None,
+            })
+            .unwrap();
+        let second = metadata.stacks[1].id;
+        metadata
+            .apply_edit(&StackEdit::Insert {
+                stack_id: second,
+                branch: "n".to_string(),
+                parent: "c".to_string(),
+            })
+            .unwrap();
+
+        // Changes made after the edit
+        metadata.find_branch_mut("b").unwrap().pr_number = Some(7);
+        metadata.stacks[1].add_branch(StackBranch::new("e".to_string(), "d".to_string()));
+
+        metadata.restore_branches(&snapshots);
+        assert_eq!(parent(&metadata, "b"), "a");
+        assert_eq!(parent(&metadata, "c"), "b");
+        assert!(metadata.find_branch("n").is_none());
+        assert_eq!(metadata.find_stack_containing("c").unwrap().id, first);
+        assert_eq!(metadata.find_branch("b").unwrap().pr_number, Some(7));
+        assert_eq!(parent(&metadata, "e"), "d");
+    }
+
+    #[test]
+    fn test_swap_with_parent() {
+        let mut metadata = metadata();
+        let plan = metadata
+            .apply_edit(&StackEdit::Swap {
+                branch: "b".to_string(),
+            })
+            .unwrap();
+
+        assert_eq!(plan.rebase, ["b", "a", "c"]);
+        assert_eq!(parent(&metadata, "b"), "main");
+        assert_eq!(parent(&metadata, "a"), "b");
+        assert_eq!(parent(&metadata, "c"), "a");
+
+        assert!(metadata
+            .apply_edit(&StackEdit::Swap {
+                branch: "d".to_string(),
+            })
+            .is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! Stack metadata schema migrations.
+//!
+//! `StackMetadata.version` records the schema a copy was written with.
+//! Loading runs every migration past that version on the raw JSON before
+//! deserializing it, so copies written by older versions keep loading.
+//! Copies written by a newer version are refused rather than misread, as
+//! saving them again would drop whatever this version doesn't know about.
+
+use super::StackMetadata;
+use crate::error::{Result, StorageError};
+use serde_json::{Map, Value};
+
+/// One step of the schema history.
+pub struct Migration {
+    /// Version the metadata is at once the migration has run
+    pub version: u32,
+
+    /// What the migration changes
+    pub description: &'static str,
+
+    /// Rewrites the metadata object in place
+    pub migrate: fn(&mut Map<String, Value>),
+}
+
+/// Every migration, in order. Never edit a released migration; add a new
+/// one instead.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    version: 2,
+    description: "Revision counter for detecting concurrent writes",
+    migrate: |metadata| {
+        metadata.entry("revision").or_insert(Value::from(0));
+    },
+}];
+
+/// Version of the first schema, which predates migrations.
+const FIRST_VERSION: u32 = 1;
+
+/// The schema version this build reads and writes.
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(FIRST_VERSION, |m| m.version)
+}
+
+/// The schema version `content` was written with, if it is readable at all.
+pub fn version_of(content: &[u8]) -> Option<u32> {
+    let value: Value = serde_json::from_slice(content).ok()?;
+    read_version(value.as_object()?).ok()
+}
+
+/// Parse metadata written by this or any earlier version.
+///
+/// Fails with [`StorageError::UnsupportedVersion`] for metadata written by a
+/// newer version, and with [`StorageError::Serialization`] if `content`
+/// isn't metadata at all.
+pub fn parse(content: &[u8]) -> Result<StackMetadata> {
+    let mut value: Value =
+        serde_json::from_slice(content).map_err(|e| StorageError::Serialization(e.to_string()))?;
+    let object = value.as_object_mut().ok_or_else(|| {
+        StorageError::Serialization("Stack metadata is not a JSON object".to_string())
+    })?;
+
+    let version = read_version(object)?;
+    let latest = latest_version();
+    if version > latest {
+        return Err(StorageError::UnsupportedVersion {
+            found: version,
+            supported: latest,
+        }
+        .into());
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > version) {
+        tracing::debug!(
+            "Migrating stack metadata to version {}: {}",
+            migration.version,
+            migration.description
+        );
+        (migration.migrate)(object);
+    }
+    object.insert("version".to_string(), Value::from(latest));
+
+    serde_json::from_value(value).map_err(|e| StorageError::Serialization(e.to_string()).into())
+}
+
+fn read_version(object: &Map<String, Value>) -> Result<u32> {
+    match object.get("version") {
+        None => Ok(FIRST_VERSION),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| {
+                StorageError::Serialization(format!("Invalid stack metadata version {}", version))
+                    .into()
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        let mut version = FIRST_VERSION;
+        for migration in MIGRATIONS {
+            assert_eq!(migration.version, version + 1, "{}", migration.description);
+            version = migration.version;
+        }
+        assert_eq!(StackMetadata::default().version, latest_version());
+    }
+
+    #[test]
+    fn test_parse_migrates_first_version() {
+        let metadata = parse(
+            br#"{
+                "version": 1,
+                "stacks": [{
+                    "id": "6f2c1a4e-8f1b-4c4e-9d3a-2b7e5c9a1f00",
+                    "root": "main",
+                    "branches": [{
+                        "name": "feature-a",
+                        "parent": "main",
+                        "pr_number": 12,
+                        "status": "up_to_date",
+                        "created_at": "2024-01-01T00:00:00Z",
+                        "head_sha": null
+                    }],
+                    "created_at": "2024-01-01T00:00:00Z",
+                    "updated_at": "2024-01-01T00:00:00Z"
+                }],
+                "last_sync": null
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(metadata.version, latest_version());
+        assert_eq!(metadata.revision, 0);
+        assert_eq!(metadata.stacks[0].branches[0].pr_number, Some(12));
+    }
+
+    #[test]
+    fn test_parse_refuses_newer_version() {
+        let content = format!(
+            r#"{{"version": {}, "stacks": [], "last_sync": null}}"#,
+            latest_version() + 1
+        );
+        let err = parse(content.as_bytes()).unwrap_err();
+        assert!(matches!(
+            err,
+            AppError::Storage(StorageError::UnsupportedVersion { found, supported })
+                if found == latest_version() + 1 && supported == latest_version()
+        ));
+        assert!(err.to_string().contains("newer"));
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        for content in [&b"{\"stacks\": ["[..], b"[]", b"{\"version\": \"two\"}"] {
+            assert!(matches!(
+                parse(content),
+                Err(AppError::Storage(StorageError::Serialization(_)))
+            ));
+        }
+        assert_eq!(version_of(b"{\"version\": 1}"), Some(1));
+        assert_eq!(version_of(b"not json"), None);
+    }
+}
@@ -3,8 +3,13 @@
 //! This module implements the Graphite-style stacked PR workflow,
 //! allowing branches to form parent-child relationships.
 
+pub mod edit;
+pub mod migrations;
+
+use crate::domain::pr::{CheckState, PullRequest};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use uuid::Uuid;
 
 /// A stack of related branches with parent-child relationships.
@@ -24,6 +29,11 @@ pub struct Stack {
 
     /// When the stack was last modified
     pub updated_at: DateTime<Utc>,
+
+    /// CI state of the stack's PRs, filled in from synced PR data when the
+    /// stack is listed; never persisted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ci: Option<StackCiRollup>,
 }
 
 impl Stack {
@@ -36,6 +46,7 @@ impl Stack {
             branches: Vec::new(),
             created_at: now,
             updated_at: now,
+            ci: None,
         }
     }
 
@@ -63,6 +74,19 @@ impl Stack {
             .collect()
     }
 
+    /// Get every branch stacked on a branch, parents before children.
+    pub fn descendants_of(&self, branch: &str) -> Vec<String> {
+        let mut found = std::collections::HashSet::from([branch]);
+        let mut result = Vec::new();
+        for b in self.topological_order() {
+            if found.contains(b.parent.as_str()) {
+                found.insert(&b.name);
+                result.push(b.name.clone());
+            }
+        }
+        result
+    }
+
     /// Get branches in topological order (parents before children).
     pub fn topological_order(&self) -> Vec<&StackBranch> {
         let mut result = Vec::new();
@@ -86,6 +110,85 @@ impl Stack {
 
         result
     }
+
+    /// Compute the CI rollup of the stack from the current PR list.
+    ///
+    /// A branch's PR is found by its recorded PR number, falling back to the
+    /// open PR for the branch.
+    pub fn ci_rollup(&self, prs: &[PullRequest]) -> StackCiRollup {
+        let branches: Vec<BranchCi> = self
+            .topological_order()
+            .into_iter()
+            .map(|branch| {
+                let pr = prs.iter().find(|pr| match branch.pr_number {
+                    Some(number) => pr.number == number,
+                    None => pr.head_ref == branch.name,
+                });
+                BranchCi {
+                    branch: branch.name.clone(),
+                    pr_number: pr.map(|pr| pr.number).or(branch.pr_number),
+                    state: pr.and_then(|pr| pr.check_state),
+                }
+            })
+            .collect();
+
+        let has = |state: CheckState| branches.iter().any(|b| b.state == Some(state));
+        let state = if has(CheckState::Failure) {
+            CheckState::Failure
+        } else if has(CheckState::Pending) {
+            CheckState::Pending
+        } else if has(CheckState::Success)
+            && branches
+                .iter()
+                .all(|b| matches!(b.state, None | Some(CheckState::Success)))
+        {
+            CheckState::Success
+        } else {
+            CheckState::Unknown
+        };
+
+        StackCiRollup {
+            state,
+            first_failing: branches
+                .iter()
+                .find(|b| b.state == Some(CheckState::Failure))
+                .map(|b| b.branch.clone()),
+            branches,
+        }
+    }
+
+    /// Attach the CI rollup computed from `prs`.
+    pub fn with_ci(mut self, prs: &[PullRequest]) -> Self {
+        self.ci = Some(self.ci_rollup(prs));
+        self
+    }
+}
+
+/// CI state of every branch in a stack.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackCiRollup {
+    /// Worst state across the stack's branches
+    pub state: CheckState,
+
+    /// Branch closest to the root whose checks are failing
+    pub first_failing: Option<String>,
+
+    /// Per-branch state, parents before children
+    pub branches: Vec<BranchCi>,
+}
+
+/// CI state of a single stack branch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchCi {
+    /// Branch name
+    pub branch: String,
+
+    /// PR opened for the branch, if any
+    pub pr_number: Option<i64>,
+
+    /// Combined check state of the PR's head commit; `None` without a PR
+    /// or checks
+    pub state: Option<CheckState>,
 }
 
 /// A branch in a stack with its parent relationship.
@@ -157,12 +260,18 @@ pub enum BranchStatus {
 }
 
 /// Metadata file structure for persisting stack information.
-/// Stored in `.git/stack-metadata.json`.
+/// Stored in `.git/stack-metadata.json`, or in the `refs/maguffin/stacks` ref
+/// when it is shared through the remote.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StackMetadata {
     /// Schema version
     pub version: u32,
 
+    /// Number of times the metadata has been saved, used to detect writes
+    /// by another process since it was loaded
+    #[serde(default)]
+    pub revision: u64,
+
     /// All stacks in this repository
     pub stacks: Vec<Stack>,
 
@@ -173,7 +282,8 @@ pub struct StackMetadata {
 impl Default for StackMetadata {
     fn default() -> Self {
         Self {
-            version: 1,
+            version: migrations::latest_version(),
+            revision: 0,
             stacks: Vec::new(),
             last_sync: None,
         }
@@ -197,6 +307,415 @@ impl StackMetadata {
     pub fn add_stack(&mut self, stack: Stack) {
         self.stacks.push(stack);
     }
+
+    /// Track branches recorded by another stacking tool.
+    ///
+    /// Branches already in a stack are skipped. Branches stacked on a tracked
+    /// branch join its stack; the others form a new stack for each branch
+    /// based outside the imported set. Branches whose parents form a cycle
+    /// are skipped too.
+    pub fn import(&mut self, branches: Vec<ImportedBranch>) -> StackImport {
+        let mut report = StackImport::default();
+        let mut pending: BTreeMap<String, ImportedBranch> = BTreeMap::new();
+        for branch in branches {
+            if self.find_stack_containing(&branch.name).is_some() {
+                report.skipped.push(branch.name);
+            } else {
+                pending.insert(branch.name.clone(), branch);
+            }
+        }
+
+        let roots: Vec<String> = pending
+            .values()
+            .filter(|b| !pending.contains_key(&b.parent))
+            .map(|b| b.name.clone())
+            .collect();
+
+        for root in roots {
+            let base = pending[&root].parent.clone();
+            let mut tree = Vec::new();
+            let mut queue = vec![root];
+            while let Some(name) = queue.pop() {
+                queue.extend(
+                    pending
+                        .values()
+                        .filter(|b| b.parent == name)
+                        .map(|b| b.name.clone())
+                        .rev(),
+                );
+                tree.extend(pending.remove(&name));
+            }
+
+            let index = match self
+                .stacks
+                .iter()
+                .position(|s| s.branches.iter().any(|b| b.name == base))
+            {
+                Some(index) => index,
+                None => {
+                    self.add_stack(Stack::new(base));
+                    self.stacks.len() - 1
+                }
+            };
+            for imported in tree {
+                report.imported.push(imported.name.clone());
+                let mut branch = StackBranch::new(imported.name, imported.parent);
+                branch.pr_number = imported.pr_number;
+                self.stacks[index].add_branch(branch);
+            }
+        }
+
+        report.skipped.extend(pending.into_keys());
+        report
+    }
+
+    /// Three-way merge of two copies of the metadata that diverged from `base`.
+    ///
+    /// Stacks are matched by ID and branches by name. A change made on one
+    /// side only is kept, and an edit wins over a deletion. When both sides
+    /// changed the same stack, branches edited on both take the copy from the
+    /// more recently updated stack. The result doesn't depend on which copy
+    /// is `ours`, so machines merging the same pair of copies agree.
+    pub fn merge(
+        base: Option<&StackMetadata>,
+        ours: &StackMetadata,
+        theirs: &StackMetadata,
+    ) -> Self {
+        let ids: BTreeSet<Uuid> = base
+            .into_iter()
+            .chain([ours, theirs])
+            .flat_map(|m| m.stacks.iter().map(|s| s.id))
+            .collect();
+
+        let mut stacks: Vec<Stack> = ids
+            .into_iter()
+            .filter_map(|id| {
+                merge_three(
+                    base.and_then(|m| m.find_stack(&id)),
+                    ours.find_stack(&id),
+                    theirs.find_stack(&id),
+                    merge_stacks,
+                )
+            })
+            .collect();
+        stacks.sort_by_key(|s| (s.created_at, s.id));
+
+        Self {
+            version: ours.version.max(theirs.version),
+            revision: ours.revision.max(theirs.revision),
+            stacks,
+            last_sync: ours.last_sync.max(theirs.last_sync),
+        }
+    }
+}
+
+/// Merge one item, given its base and two later versions; `None` is absent.
+///
+/// `both` resolves an item changed on both sides.
+fn merge_three<T: Clone + Serialize>(
+    base: Option<&T>,
+    ours: Option<&T>,
+    theirs: Option<&T>,
+    both: impl FnOnce(Option<&T>, &T, &T) -> T,
+) -> Option<T> {
+    let same = |a: Option<&T>, b: Option<&T>| {
+        a.map(|v| serde_json::to_value(v).ok()) == b.map(|v| serde_json::to_value(v).ok())
+    };
+
+    if same(ours, theirs) || same(base, theirs) {
+        return ours.cloned();
+    }
+    if same(base, ours) {
+        return theirs.cloned();
+    }
+    match (ours, theirs) {
+        (Some(ours), Some(theirs)) => Some(both(base, ours, theirs)),
+        (ours, theirs) => ours.or(theirs).cloned(),
+    }
+}
+
+/// Merge a stack changed on both sides, branch by branch.
+fn merge_stacks(base: Option<&Stack>, ours: &Stack, theirs: &Stack) -> Stack {
+    // Order the copies by recency, then content, so either side picks the same winner
+    let rank = |s: &Stack| (s.updated_at, serde_json::to_string(s).unwrap_or_default());
+    let (winner, loser) = if rank(ours) >= rank(theirs) {
+        (ours, theirs)
+    } else {
+        (theirs, ours)
+    };
+
+    let names: BTreeSet<&str> = base
+        .into_iter()
+        .chain([ours, theirs])
+        .flat_map(|s| s.branches.iter().map(|b| b.name.as_str()))
+        .collect();
+
+    let mut branches: Vec<StackBranch> = names
+        .into_iter()
+        .filter_map(|name| {
+            merge_three(
+                base.and_then(|s| s.find_branch(name)),
+                winner.find_branch(name),
+                loser.find_branch(name),
+                |_, winner, _| winner.clone(),
+            )
+        })
+        .collect();
+    branches.sort_by(|a, b| (a.created_at, &a.name).cmp(&(b.created_at, &b.name)));
+
+    Stack {
+        branches,
+        updated_at: ours.updated_at.max(theirs.updated_at),
+        ..winner.clone()
+    }
+}
+
+/// A branch as recorded by another stacking tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportedBranch {
+    /// Branch name
+    pub name: String,
+
+    /// Parent branch name
+    pub parent: String,
+
+    /// PR opened for the branch, if the tool records it
+    pub pr_number: Option<i64>,
+}
+
+/// Outcome of importing another tool's stacks.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StackImport {
+    /// Branches added to stacks
+    pub imported: Vec<String>,
+
+    /// Branches left out, because they were already in a stack or their
+    /// parents form a cycle
+    pub skipped: Vec<String>,
+}
+
+/// A stack found among existing branches and PRs, not yet adopted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveredStack {
+    /// Branch the stack is based on
+    pub root: String,
+
+    /// Branches of the stack, parents before children
+    pub branches: Vec<DiscoveredBranch>,
+}
+
+impl DiscoveredStack {
+    /// Turn into a stack, with each branch under its chosen parent.
+    pub fn into_stack(self) -> Stack {
+        let mut stack = Stack::new(self.root);
+        for discovered in self.branches {
+            let mut branch = StackBranch::new(discovered.name, discovered.parent);
+            if let Some(number) = discovered.pr_number {
+                branch = branch.with_pr(number);
+            }
+            stack.add_branch(branch);
+        }
+        stack
+    }
+}
+
+/// A branch of a discovered stack.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveredBranch {
+    /// Branch name
+    pub name: String,
+
+    /// Parent the branch is placed under, initially the likeliest candidate
+    pub parent: String,
+
+    /// Open PR of the branch, if any
+    pub pr_number: Option<i64>,
+
+    /// Possible parents, likeliest first
+    pub candidates: Vec<ParentCandidate>,
+}
+
+/// A possible parent of a discovered branch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParentCandidate {
+    /// Parent branch name
+    pub parent: String,
+
+    /// Share of the evidence pointing at this parent, from 0 to 1
+    pub confidence: f64,
+
+    /// Evidence for this parent
+    pub sources: Vec<ParentSource>,
+}
+
+/// Evidence that a branch is stacked on another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParentSource {
+    /// The branch's PR targets the parent
+    PrBase,
+
+    /// The parent is a nearest local branch the branch descends from
+    Ancestry,
+}
+
+/// Evidence weight of a PR's base branch.
+const PR_BASE_WEIGHT: f64 = 0.6;
+
+/// Evidence weight of local ancestry, split between equally near ancestors.
+const ANCESTRY_WEIGHT: f64 = 0.4;
+
+/// Find stacks among the repository's open PRs and local branches.
+///
+/// A branch's candidate parents are the base branch of its PR and its
+/// nearest local ancestors, as reported by `is_ancestor(ancestor,
+/// descendant)`. Each branch is placed under its likeliest parent, and every
+/// tree of two or more branches hanging off a branch outside the graph
+/// (usually `trunk`) becomes a stack. Branches already in `known` stacks are
+/// left out.
+///
+/// Ancestry is only inferred for local branches with an open PR or sharing
+/// their `fork_point`, the merge base with `trunk`, with another branch, and
+/// only between branches with the same fork point, as branches of one stack
+/// fork from trunk together. That keeps the `is_ancestor` walks well below
+/// one per pair of branches.
+pub fn discover_stacks(
+    prs: &[PullRequest],
+    local_branches: &[String],
+    trunk: &str,
+    known: &StackMetadata,
+    fork_point: impl Fn(&str) -> Option<String>,
+    is_ancestor: impl Fn(&str, &str) -> bool,
+) -> Vec<DiscoveredStack> {
+    let names: BTreeSet<&str> = prs
+        .iter()
+        .map(|pr| pr.head_ref.as_str())
+        .chain(local_branches.iter().map(String::as_str))
+        .filter(|&name| name != trunk && known.find_stack_containing(name).is_none())
+        .collect();
+
+    let fork_points: HashMap<&str, String> = local_branches
+        .iter()
+        .filter(|&branch| branch != trunk)
+        .filter_map(|branch| Some((branch.as_str(), fork_point(branch)?)))
+        .collect();
+    let mut forks: HashMap<&str, usize> = HashMap::new();
+    for fork in fork_points.values() {
+        *forks.entry(fork).or_default() += 1;
+    }
+    let candidates: Vec<&str> = local_branches
+        .iter()
+        .map(String::as_str)
+        .filter(|&branch| {
+            branch != trunk
+                && (prs.iter().any(|pr| pr.head_ref == branch)
+                    || fork_points
+                        .get(branch)
+                        .is_some_and(|fork| forks[fork.as_str()] > 1))
+        })
+        .collect();
+    let has_trunk = local_branches.iter().any(|branch| branch == trunk);
+
+    // The local ancestors of every candidate: trunk, and the candidates
+    // forking from it at the same point
+    let ancestors: HashMap<&str, Vec<&str>> = candidates
+        .iter()
+        .map(|&branch| {
+            let fork = fork_points.get(branch);
+            let same_fork = candidates
+                .iter()
+                .copied()
+                .filter(|&a| a != branch && fork.is_some() && fork_points.get(a) == fork);
+            let found = has_trunk
+                .then_some(trunk)
+                .into_iter()
+                .chain(same_fork)
+                .filter(|a| is_ancestor(a, branch))
+                .collect();
+            (branch, found)
+        })
+        .collect();
+
+    let mut branches: BTreeMap<&str, DiscoveredBranch> = BTreeMap::new();
+    for &name in &names {
+        let mut evidence: BTreeMap<&str, (f64, Vec<ParentSource>)> = BTreeMap::new();
+
+        let pr = prs.iter().find(|pr| pr.head_ref == name);
+        if let Some(pr) = pr.filter(|pr| pr.base_ref != name) {
+            let entry = evidence.entry(pr.base_ref.as_str()).or_default();
+            entry.0 += PR_BASE_WEIGHT;
+            entry.1.push(ParentSource::PrBase);
+        }
+
+        // Ancestors that aren't also ancestors of another ancestor
+        let all = ancestors.get(name).map(Vec::as_slice).unwrap_or_default();
+        let nearest: Vec<&str> = all
+            .iter()
+            .copied()
+            .filter(|a| {
+                !all.iter()
+                    .any(|b| ancestors.get(b).is_some_and(|above| above.contains(a)))
+            })
+            .collect();
+        for &ancestor in &nearest {
+            let entry = evidence.entry(ancestor).or_default();
+            entry.0 += ANCESTRY_WEIGHT / nearest.len() as f64;
+            entry.1.push(ParentSource::Ancestry);
+        }
+
+        let total: f64 = evidence.values().map(|(weight, _)| weight).sum();
+        let mut candidates: Vec<ParentCandidate> = evidence
+            .into_iter()
+            .map(|(parent, (weight, sources))| ParentCandidate {
+                parent: parent.to_string(),
+                confidence: weight / total,
+                sources,
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+        if let Some(best) = candidates.first() {
+            branches.insert(
+                name,
+                DiscoveredBranch {
+                    name: name.to_string(),
+                    parent: best.parent.clone(),
+                    pr_number: pr.map(|pr| pr.number),
+                    candidates,
+                },
+            );
+        }
+    }
+
+    // Grow a stack from each branch whose parent is outside the graph
+    let mut stacks = Vec::new();
+    for (name, branch) in &branches {
+        if branches.contains_key(branch.parent.as_str()) {
+            continue;
+        }
+
+        let mut members = Vec::new();
+        let mut pending = vec![*name];
+        while let Some(current) = pending.pop() {
+            members.push(branches[current].clone());
+            pending.extend(
+                branches
+                    .values()
+                    .filter(|b| b.parent == current)
+                    .map(|b| b.name.as_str())
+                    .rev(),
+            );
+        }
+
+        if members.len() >= 2 {
+            stacks.push(DiscoveredStack {
+                root: branch.parent.clone(),
+                branches: members,
+            });
+        }
+    }
+
+    stacks
 }
 
 /// Result of a restack operation.
@@ -423,4 +942,297 @@ mod tests {
         assert!(json.contains("\"total_commits\":3"));
         assert!(json.contains("\"has_pr\":true"));
     }
+
+    fn make_pr(number: i64, head_ref: &str, check_state: Option<CheckState>) -> PullRequest {
+        use crate::domain::pr::{Author, Mergeable, PrState};
+
+        PullRequest {
+            id: format!("PR_{}", number),
+            number,
+            title: head_ref.to_string(),
+            body: None,
+            state: PrState::Open,
+            is_draft: false,
+            author: Author {
+                login: "test".to_string(),
+                avatar_url: String::new(),
+            },
+            head_ref: head_ref.to_string(),
+            base_ref: "main".to_string(),
+            labels: Vec::new(),
+            review_decision: None,
+            mergeable: Mergeable::Unknown,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            commit_count: 1,
+            additions: 0,
+            deletions: 0,
+            changed_files: 0,
+            check_state,
+            auto_merge: None,
+            merge_queue: None,
+            assignees: Vec::new(),
+            milestone: None,
+            requested_reviewers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_ci_rollup() {
+        let mut stack = Stack::new("main".to_string());
+        stack.add_branch(StackBranch::new("feature-a".to_string(), "main".to_string()).with_pr(1));
+        stack.add_branch(StackBranch::new(
+            "feature-b".to_string(),
+            "feature-a".to_string(),
+        ));
+        stack.add_branch(StackBranch::new(
+            "feature-c".to_string(),
+            "feature-b".to_string(),
+        ));
+
+        let prs = vec![
+            make_pr(1, "renamed-head", Some(CheckState::Success)),
+            make_pr(2, "feature-b", Some(CheckState::Failure)),
+        ];
+        let rollup = stack.ci_rollup(&prs);
+
+        assert_eq!(rollup.state, CheckState::Failure);
+        assert_eq!(rollup.first_failing.as_deref(), Some("feature-b"));
+        assert_eq!(rollup.branches[0].state, Some(CheckState::Success));
+        assert_eq!(rollup.branches[1].pr_number, Some(2));
+        assert_eq!(rollup.branches[2].pr_number, None);
+
+        // Branches without PRs don't hold back a green stack
+        let prs = vec![make_pr(1, "feature-a", Some(CheckState::Success))];
+        assert_eq!(stack.ci_rollup(&prs).state, CheckState::Success);
+        assert_eq!(stack.ci_rollup(&[]).state, CheckState::Unknown);
+    }
+
+    #[test]
+    fn test_ci_rollup_only_serialized_when_set() {
+        let stack = Stack::new("main".to_string()).with_ci(&[]);
+        let json = serde_json::to_string(&stack).unwrap();
+        assert!(json.contains("\"ci\""));
+
+        let metadata = serde_json::to_string(&Stack::new("main".to_string())).unwrap();
+        assert!(!metadata.contains("\"ci\""));
+    }
+
+    #[test]
+    fn test_metadata_merge_keeps_changes_from_both_sides() {
+        let mut base = StackMetadata::default();
+        let mut stack = Stack::new("main".to_string());
+        stack.add_branch(StackBranch::new(
+            "feature-a".to_string(),
+            "main".to_string(),
+        ));
+        let unchanged = Stack::new("develop".to_string());
+        base.add_stack(stack.clone());
+        base.add_stack(unchanged.clone());
+
+        // Ours records a PR, theirs adds a branch and deletes a stack
+        let mut ours = base.clone();
+        ours.stacks[0]
+            .find_branch_mut("feature-a")
+            .unwrap()
+            .pr_number = Some(1);
+        let mut theirs = base.clone();
+        theirs.stacks[0].add_branch(StackBranch::new(
+            "feature-b".to_string(),
+            "feature-a".to_string(),
+        ));
+        theirs.stacks.retain(|s| s.id != unchanged.id);
+        let created = Stack::new("release".to_string());
+        theirs.add_stack(created.clone());
+
+        let merged = StackMetadata::merge(Some(&base), &ours, &theirs);
+        assert_eq!(merged.stacks.len(), 2);
+        assert!(merged.find_stack(&unchanged.id).is_none());
+        assert!(merged.find_stack(&created.id).is_some());
+
+        let stack = merged.find_stack(&stack.id).unwrap();
+        assert_eq!(stack.find_branch("feature-a").unwrap().pr_number, Some(1));
+        assert!(stack.find_branch("feature-b").is_some());
+    }
+
+    #[test]
+    fn test_metadata_merge_is_deterministic() {
+        let mut base = StackMetadata::default();
+        let mut stack = Stack::new("main".to_string());
+        stack.add_branch(StackBranch::new(
+            "feature-a".to_string(),
+            "main".to_string(),
+        ));
+        base.add_stack(stack);
+
+        // Both sides edit the same branch; the later edit wins
+        let mut ours = base.clone();
+        ours.stacks[0]
+            .find_branch_mut("feature-a")
+            .unwrap()
+            .pr_number = Some(1);
+        let mut theirs = base.clone();
+        theirs.stacks[0]
+            .find_branch_mut("feature-a")
+            .unwrap()
+            .pr_number = Some(2);
+        theirs.stacks[0].updated_at = ours.stacks[0].updated_at + chrono::Duration::seconds(1);
+
+        let merged = StackMetadata::merge(Some(&base), &ours, &theirs);
+        let flipped = StackMetadata::merge(Some(&base), &theirs, &ours);
+        assert_eq!(merged.stacks[0].branches[0].pr_number, Some(2));
+        assert_eq!(
+            serde_json::to_string(&merged).unwrap(),
+            serde_json::to_string(&flipped).unwrap()
+        );
+
+        // An edit survives a concurrent delete
+        let mut deleted = base.clone();
+        deleted.stacks.clear();
+        let merged = StackMetadata::merge(Some(&base), &deleted, &ours);
+        assert_eq!(merged.stacks[0].branches[0].pr_number, Some(1));
+
+        // Without a common base everything is kept
+        let mut other = StackMetadata::default();
+        other.add_stack(Stack::new("develop".to_string()));
+        assert_eq!(StackMetadata::merge(None, &ours, &other).stacks.len(), 2);
+    }
+
+    #[test]
+    fn test_discover_stacks() {
+        let pr = |number: i64, head: &str, base: &str| {
+            let mut pr = make_pr(number, head, None);
+            pr.base_ref = base.to_string();
+            pr
+        };
+
+        // feature-a <- feature-b <- feature-c via PRs; feature-d is a lone PR;
+        // local-x and local-y sit on feature-a's commit; old forked from main
+        // earlier and is unrelated
+        let prs = vec![
+            pr(1, "feature-a", "main"),
+            pr(2, "feature-b", "feature-a"),
+            pr(3, "feature-c", "feature-b"),
+            pr(4, "feature-d", "main"),
+        ];
+        let local: Vec<String> = [
+            "main",
+            "feature-a",
+            "feature-b",
+            "local-x",
+            "local-y",
+            "old",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let lineage = [
+            ("main", "feature-a"),
+            ("main", "feature-b"),
+            ("main", "local-x"),
+            ("main", "local-y"),
+            ("feature-a", "feature-b"),
+            ("local-x", "feature-b"),
+            ("local-y", "feature-b"),
+        ];
+        let fork_point = |branch: &str| Some(if branch == "old" { "f0" } else { "f1" }.to_string());
+        let walks = std::cell::Cell::new(0);
+        let is_ancestor = |a: &str, d: &str| {
+            walks.set(walks.get() + 1);
+            assert!(a != "old" && d != "old", "old has nothing to compare to");
+            lineage.contains(&(a, d))
+        };
+
+        let stacks = discover_stacks(
+            &prs,
+            &local,
+            "main",
+            &StackMetadata::default(),
+            fork_point,
+            is_ancestor,
+        );
+        // main and the three others with the same fork point, for each of
+        // the four candidates
+        assert_eq!(walks.get(), 16);
+        assert_eq!(stacks.len(), 1);
+        let stack = &stacks[0];
+        assert_eq!(stack.root, "main");
+        let names: Vec<&str> = stack.branches.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["feature-a", "feature-b", "feature-c"]);
+
+        // PR base and ancestry agree on feature-a
+        assert_eq!(stack.branches[0].candidates.len(), 1);
+        assert_eq!(stack.branches[0].candidates[0].confidence, 1.0);
+
+        // feature-b's ancestry is split three ways, so its PR base wins
+        let b = &stack.branches[1];
+        assert_eq!(b.parent, "feature-a");
+        assert_eq!(b.candidates.len(), 3);
+        assert!(b.candidates[0].confidence > 0.7);
+        assert_eq!(
+            b.candidates[0].sources,
+            [ParentSource::PrBase, ParentSource::Ancestry]
+        );
+        assert_eq!(b.pr_number, Some(2));
+
+        // Adopting keeps the chosen parents and PRs
+        let adopted = stack.clone().into_stack();
+        assert_eq!(adopted.root, "main");
+        assert_eq!(
+            adopted.find_branch("feature-c").unwrap().parent,
+            "feature-b"
+        );
+        assert_eq!(adopted.find_branch("feature-c").unwrap().pr_number, Some(3));
+
+        // Branches already in a stack are left out
+        let mut known = StackMetadata::default();
+        known.add_stack(adopted);
+        assert!(discover_stacks(&prs, &local, "main", &known, fork_point, is_ancestor).is_empty());
+    }
+
+    #[test]
+    fn test_import_branches() {
+        let imported = |name: &str, parent: &str| ImportedBranch {
+            name: name.to_string(),
+            parent: parent.to_string(),
+            pr_number: None,
+        };
+
+        let mut metadata = StackMetadata::default();
+        let mut stack = Stack::new("main".to_string());
+        stack.add_branch(StackBranch::new(
+            "feature-a".to_string(),
+            "main".to_string(),
+        ));
+        metadata.add_stack(stack);
+
+        let report = metadata.import(vec![
+            imported("feature-a", "main"),
+            imported("feature-b", "feature-a"),
+            imported("fix-1", "main"),
+            ImportedBranch {
+                pr_number: Some(12),
+                ..imported("fix-2", "fix-1")
+            },
+            imported("loop-1", "loop-2"),
+            imported("loop-2", "loop-1"),
+        ]);
+
+        assert_eq!(report.imported, ["feature-b", "fix-1", "fix-2"]);
+        assert_eq!(report.skipped, ["feature-a", "loop-1", "loop-2"]);
+
+        // Stacked on a tracked branch: joins its stack
+        assert_eq!(metadata.stacks.len(), 2);
+        assert_eq!(metadata.stacks[0].branches.len(), 2);
+        assert_eq!(
+            metadata.stacks[0].find_branch("feature-b").unwrap().parent,
+            "feature-a"
+        );
+
+        // Otherwise: a new stack
+        let fixes = metadata.find_stack_containing("fix-2").unwrap();
+        assert_eq!(fixes.root, "main");
+        assert_eq!(fixes.find_branch("fix-2").unwrap().pr_number, Some(12));
+        assert_eq!(fixes.topological_order()[0].name, "fix-1");
+    }
 }
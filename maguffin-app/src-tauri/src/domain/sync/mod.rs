@@ -2,8 +2,10 @@
 //!
 //! This module handles background data synchronization with GitHub.
 
+use crate::domain::pr::{CheckState, MergeQueueState};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// Current sync state.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,6 +67,25 @@ pub enum SyncChange {
     /// A PR's review status changed
     PrReviewChanged { number: i64, new_status: String },
 
+    /// A PR's combined check state changed
+    PrChecksChanged {
+        number: i64,
+        new_state: Option<CheckState>,
+    },
+
+    /// A PR's auto-merge was enabled or disabled
+    PrAutoMergeChanged { number: i64, enabled: bool },
+
+    /// A PR joined the merge queue, or its position or state in it changed
+    PrMergeQueueChanged {
+        number: i64,
+        position: i32,
+        state: MergeQueueState,
+    },
+
+    /// A PR was removed from the merge queue without being merged
+    PrDequeued { number: i64, reason: Option<String> },
+
     /// A stack parent was merged
     StackParentMerged { stack_id: String, branch: String },
 }
@@ -80,6 +101,10 @@ pub struct RateLimitInfo {
 
     /// When the limit resets
     pub resets_at: DateTime<Utc>,
+
+    /// Point cost of the most recent GraphQL query (if reported)
+    #[serde(default)]
+    pub cost: Option<u32>,
 }
 
 impl RateLimitInfo {
@@ -100,6 +125,45 @@ impl RateLimitInfo {
     pub fn should_slow_down(&self) -> bool {
         self.usage_percent() > 80.0
     }
+
+    /// Minimum spacing between queries that keeps the remaining budget
+    /// from running out before the window resets.
+    ///
+    /// Uses the last reported query cost (or 1 point if unknown) to estimate
+    /// how many more queries fit into the current window.
+    pub fn budget_interval(&self, now: DateTime<Utc>) -> Duration {
+        let until_reset = (self.resets_at - now).num_seconds().max(0) as u64;
+        let cost = self.cost.unwrap_or(1).max(1);
+        let queries_left = u64::from(self.remaining / cost);
+
+        if queries_left == 0 {
+            return Duration::from_secs(until_reset);
+        }
+
+        Duration::from_secs(until_reset / queries_left)
+    }
+}
+
+/// Accumulated GraphQL point cost of one operation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationCost {
+    /// Operation name (e.g. `ListPullRequests`)
+    pub operation: String,
+
+    /// Number of times the operation ran
+    pub calls: u64,
+
+    /// Total points spent on the operation
+    pub total_cost: u64,
+
+    /// Points spent on the most recent call
+    pub last_cost: u32,
+
+    /// Estimated points of the most recent call
+    pub last_estimated_cost: u32,
+
+    /// Most points spent on a single call
+    pub max_cost: u32,
 }
 
 /// Sync statistics.
@@ -137,6 +201,7 @@ mod tests {
             remaining: 0,
             limit: 5000,
             resets_at: Utc::now(),
+            cost: None,
         };
         assert!(info.is_limited());
     }
@@ -147,6 +212,7 @@ mod tests {
             remaining: 500,
             limit: 5000,
             resets_at: Utc::now(),
+            cost: None,
         };
         assert!(info.should_slow_down()); // 90% used
     }
@@ -157,10 +223,31 @@ mod tests {
             remaining: 2500,
             limit: 5000,
             resets_at: Utc::now(),
+            cost: None,
         };
         assert!((info.usage_percent() - 50.0).abs() < 0.01);
     }
 
+    #[test]
+    fn test_rate_limit_budget_interval() {
+        let now = Utc::now();
+        let info = RateLimitInfo {
+            remaining: 100,
+            limit: 5000,
+            resets_at: now + chrono::Duration::seconds(1000),
+            cost: Some(2),
+        };
+        // 50 queries left over 1000 seconds
+        assert_eq!(info.budget_interval(now), Duration::from_secs(20));
+
+        let exhausted = RateLimitInfo {
+            remaining: 1,
+            cost: Some(5),
+            ..info
+        };
+        assert_eq!(exhausted.budget_interval(now), Duration::from_secs(1000));
+    }
+
     #[test]
     fn test_sync_change_serialization() {
         let change = SyncChange::PrCreated {
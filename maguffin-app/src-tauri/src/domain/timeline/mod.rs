@@ -0,0 +1,336 @@
+//! Pull request conversation domain types.
+//!
+//! This module contains the typed timeline of a pull request's
+//! conversation: comments, reviews, pushes and the events that changed the
+//! PR, plus the reactions on comments and reviews.
+
+use crate::domain::pr::{Author, Label, ReviewState};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Emoji reaction on a comment or review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReactionContent {
+    ThumbsUp,
+    ThumbsDown,
+    Laugh,
+    Hooray,
+    Confused,
+    Heart,
+    Rocket,
+    Eyes,
+}
+
+/// Reactions of one kind on a comment or review.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReactionGroup {
+    /// Reaction emoji
+    pub content: ReactionContent,
+
+    /// Number of people who reacted
+    pub count: i64,
+
+    /// Whether the viewer is one of them
+    pub viewer_has_reacted: bool,
+}
+
+impl ReactionGroup {
+    /// Drop the groups nobody has reacted with.
+    ///
+    /// GitHub returns a group for every emoji, most of them empty.
+    pub fn non_empty(groups: impl IntoIterator<Item = Self>) -> Vec<Self> {
+        groups.into_iter().filter(|group| group.count > 0).collect()
+    }
+}
+
+/// Where a comment lives, which decides how it's edited and deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentKind {
+    /// A comment on the PR's conversation
+    Issue,
+    /// A comment in a review thread
+    Review,
+}
+
+/// A comment on the conversation or in a review.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comment {
+    /// GraphQL node ID
+    pub id: String,
+
+    /// Where the comment lives
+    pub kind: CommentKind,
+
+    /// Comment author
+    pub author: Author,
+
+    /// Comment body (Markdown)
+    pub body: String,
+
+    /// Creation timestamp
+    pub created_at: DateTime<Utc>,
+
+    /// When the body was last edited, if ever
+    pub last_edited_at: Option<DateTime<Utc>>,
+
+    /// File a review comment is attached to
+    pub path: Option<String>,
+
+    /// ID of the review comment this one replies to
+    pub reply_to: Option<String>,
+
+    /// Reactions, without empty groups
+    pub reactions: Vec<ReactionGroup>,
+
+    /// Whether the viewer can edit the comment
+    pub can_edit: bool,
+
+    /// Whether the viewer can delete the comment
+    pub can_delete: bool,
+
+    /// Whether the viewer can react to the comment
+    pub can_react: bool,
+}
+
+/// A commit pushed to the PR.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineCommit {
+    /// Commit SHA
+    pub oid: String,
+
+    /// First line of the commit message
+    pub headline: String,
+
+    /// GitHub login of the author, or their Git name if it isn't linked to
+    /// an account
+    pub author: Option<String>,
+
+    /// Commit timestamp
+    pub committed_at: DateTime<Utc>,
+}
+
+/// An entry in a pull request's conversation, oldest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimelineItem {
+    /// A comment on the conversation
+    Comment(Comment),
+
+    /// A submitted review with its comments
+    Review {
+        id: String,
+        author: Author,
+        state: ReviewState,
+        /// Review summary; empty if the reviewer left none
+        body: String,
+        created_at: DateTime<Utc>,
+        submitted_at: Option<DateTime<Utc>>,
+        reactions: Vec<ReactionGroup>,
+        can_react: bool,
+        /// Review comments, replies after the comment they reply to
+        comments: Vec<Comment>,
+    },
+
+    /// A commit pushed to the head branch
+    Commit(TimelineCommit),
+
+    /// The head branch was force-pushed
+    ForcePush {
+        actor: Option<Author>,
+        before_oid: Option<String>,
+        after_oid: Option<String>,
+        created_at: DateTime<Utc>,
+    },
+
+    /// A label was added
+    Labeled {
+        actor: Option<Author>,
+        label: Label,
+        created_at: DateTime<Utc>,
+    },
+
+    /// A label was removed
+    Unlabeled {
+        actor: Option<Author>,
+        label: Label,
+        created_at: DateTime<Utc>,
+    },
+
+    /// The base branch was changed
+    BaseChanged {
+        actor: Option<Author>,
+        from: String,
+        to: String,
+        created_at: DateTime<Utc>,
+    },
+
+    /// The PR was merged
+    Merged {
+        actor: Option<Author>,
+        /// Merge commit SHA
+        commit_oid: Option<String>,
+        /// Branch the PR was merged into
+        base_ref: String,
+        created_at: DateTime<Utc>,
+    },
+}
+
+impl TimelineItem {
+    /// When the item happened.
+    pub fn created_at(&self) -> DateTime<Utc> {
+        match self {
+            Self::Comment(comment) => comment.created_at,
+            Self::Review {
+                created_at,
+                submitted_at,
+                ..
+            } => submitted_at.unwrap_or(*created_at),
+            Self::Commit(commit) => commit.committed_at,
+            Self::ForcePush { created_at, .. }
+            | Self::Labeled { created_at, .. }
+            | Self::Unlabeled { created_at, .. }
+            | Self::BaseChanged { created_at, .. }
+            | Self::Merged { created_at, .. } => *created_at,
+        }
+    }
+}
+
+/// Bodies of every comment and review in a timeline, for search indexing.
+pub fn comment_bodies(items: &[TimelineItem]) -> Vec<String> {
+    let mut bodies = Vec::new();
+    for item in items {
+        match item {
+            TimelineItem::Comment(comment) => bodies.push(comment.body.clone()),
+            TimelineItem::Review { body, comments, .. } => {
+                bodies.push(body.clone());
+                bodies.extend(comments.iter().map(|comment| comment.body.clone()));
+            }
+            _ => {}
+        }
+    }
+    bodies.retain(|body| !body.trim().is_empty());
+    bodies
+}
+
+/// Order review comments so each reply follows the comment it answers.
+///
+/// GitHub lists a review's comments by creation time, interleaving replies
+/// to different comments. Comments replying to a comment outside the review
+/// keep their position.
+pub fn thread_comments(comments: Vec<Comment>) -> Vec<Comment> {
+    let ids: Vec<String> = comments.iter().map(|c| c.id.clone()).collect();
+    let is_root = |comment: &Comment| {
+        comment
+            .reply_to
+            .as_ref()
+            .is_none_or(|parent| !ids.contains(parent))
+    };
+
+    let (roots, mut replies): (Vec<_>, Vec<_>) = comments.into_iter().partition(is_root);
+    let mut ordered = Vec::with_capacity(roots.len() + replies.len());
+
+    for root in roots {
+        let mut stack = vec![root];
+        while let Some(comment) = stack.pop() {
+            let (children, rest): (Vec<_>, Vec<_>) = replies
+                .into_iter()
+                .partition(|reply| reply.reply_to.as_deref() == Some(comment.id.as_str()));
+            replies = rest;
+            ordered.push(comment);
+            // Push in reverse so the oldest reply comes out first
+            stack.extend(children.into_iter().rev());
+        }
+    }
+
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(id: &str, reply_to: Option<&str>) -> Comment {
+        Comment {
+            id: id.to_string(),
+            kind: CommentKind::Review,
+            author: Author {
+                login: "octocat".to_string(),
+                avatar_url: String::new(),
+            },
+            body: String::new(),
+            created_at: Utc::now(),
+            last_edited_at: None,
+            path: Some("src/lib.rs".to_string()),
+            reply_to: reply_to.map(str::to_string),
+            reactions: Vec::new(),
+            can_edit: false,
+            can_delete: false,
+            can_react: true,
+        }
+    }
+
+    #[test]
+    fn test_thread_comments() {
+        let comments = vec![
+            comment("a", None),
+            comment("b", None),
+            comment("a1", Some("a")),
+            comment("b1", Some("b")),
+            comment("a2", Some("a1")),
+            comment("x1", Some("outside")),
+        ];
+
+        let ids: Vec<String> = thread_comments(comments)
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, ["a", "a1", "a2", "b", "b1", "x1"]);
+    }
+
+    #[test]
+    fn test_comment_bodies() {
+        let mut issue = comment("c", None);
+        issue.kind = CommentKind::Issue;
+        issue.body = "Ship it".to_string();
+        let mut inline = comment("r1", None);
+        inline.body = "Typo here".to_string();
+        let items = vec![
+            TimelineItem::Comment(issue),
+            TimelineItem::Review {
+                id: "r".to_string(),
+                author: inline.author.clone(),
+                state: ReviewState::Commented,
+                body: String::new(),
+                created_at: Utc::now(),
+                submitted_at: None,
+                reactions: Vec::new(),
+                can_react: true,
+                comments: vec![inline],
+            },
+        ];
+
+        assert_eq!(comment_bodies(&items), ["Ship it", "Typo here"]);
+    }
+
+    #[test]
+    fn test_timeline_item_serialization() {
+        let item = TimelineItem::BaseChanged {
+            actor: None,
+            from: "main".to_string(),
+            to: "release".to_string(),
+            created_at: Utc::now(),
+        };
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(json["type"], "base_changed");
+        assert_eq!(json["from"], "main");
+
+        let json = serde_json::to_value(TimelineItem::Comment(comment("c", None))).unwrap();
+        assert_eq!(json["type"], "comment");
+        assert_eq!(json["kind"], "review");
+
+        let reaction = serde_json::to_value(ReactionContent::ThumbsUp).unwrap();
+        assert_eq!(reaction, "THUMBS_UP");
+    }
+}
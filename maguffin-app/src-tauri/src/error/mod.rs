@@ -72,6 +72,11 @@ pub enum GitHubError {
     #[error("GraphQL error: {0}")]
     GraphQL(String),
 
+    /// GraphQL error GitHub classified with a `type`, such as `NOT_FOUND`,
+    /// or an `extensions.code`, such as `argumentNotAccepted`
+    #[error("GraphQL error: {message}")]
+    GraphQLCode { code: String, message: String },
+
     /// Rate limit exceeded
     #[error("Rate limit exceeded. Resets at: {reset_at}")]
     RateLimited { reset_at: String },
@@ -91,6 +96,18 @@ pub enum GitHubError {
     /// HTTP request error
     #[error("HTTP error: {0}")]
     Http(String),
+
+    /// Webhook receiver error
+    #[error("Webhook error: {0}")]
+    Webhook(String),
+
+    /// Request rejected before it was sent
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    /// Request cancelled because the repository context changed
+    #[error("Request cancelled")]
+    Cancelled,
 }
 
 impl From<reqwest::Error> for GitHubError {
@@ -105,6 +122,12 @@ impl From<reqwest::Error> for AppError {
     }
 }
 
+impl From<git2::Error> for AppError {
+    fn from(err: git2::Error) -> Self {
+        AppError::Git(GitError::from(err))
+    }
+}
+
 /// Errors related to authentication.
 #[derive(Error, Debug)]
 pub enum AuthError {
@@ -143,6 +166,20 @@ pub enum StorageError {
     /// SQLite error
     #[error("SQLite error: {0}")]
     Sqlite(#[from] rusqlite::Error),
+
+    /// Data was written by a newer version of the application
+    #[error(
+        "Written by a newer Maguffin (schema {found}, supported up to {supported}); please upgrade"
+    )]
+    UnsupportedVersion { found: u32, supported: u32 },
+
+    /// Another writer held the lock for too long
+    #[error("Timed out waiting for another process to finish writing")]
+    Locked,
+
+    /// Data was changed by another writer since it was read
+    #[error("Revision conflict: expected revision {expected}, found {found}")]
+    Conflict { expected: u64, found: u64 },
 }
 
 /// Result type alias using AppError.
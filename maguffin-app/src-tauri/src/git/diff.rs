@@ -0,0 +1,681 @@
+//! Structured diffs between two revisions.
+//!
+//! Computes diffs locally with git2, so pull request and stack branch diffs
+//! are available offline and cost no API budget. Results are paged by file,
+//! and only the files on the requested page have their hunks loaded.
+
+use super::Git2Backend;
+use crate::domain::pr::ChangeType;
+use crate::error::{GitError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Number of files returned per page unless asked otherwise.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Maximum number of files returned per page.
+pub const MAX_PAGE_SIZE: usize = 200;
+
+/// Lines loaded per file before the rest of its hunks are dropped.
+const MAX_LINES_PER_FILE: usize = 20_000;
+
+/// Upper bound on the word diff table size for a pair of lines.
+const MAX_WORD_DIFF_CELLS: usize = 250_000;
+
+/// Options for computing a diff.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiffOptions {
+    /// Diff from the merge base of the two revisions (`from...to`) rather
+    /// than from `from` itself, as GitHub does for pull requests
+    pub merge_base: bool,
+
+    /// Unchanged lines shown around each change
+    pub context_lines: u32,
+
+    /// Detect renamed files
+    pub detect_renames: bool,
+
+    /// Mark changed words within modified lines
+    pub word_diff: bool,
+
+    /// Ignore whitespace-only changes
+    pub ignore_whitespace: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            merge_base: true,
+            context_lines: 3,
+            detect_renames: true,
+            word_diff: true,
+            ignore_whitespace: false,
+        }
+    }
+}
+
+/// Kind of a line in a hunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineKind {
+    Context,
+    Addition,
+    Deletion,
+}
+
+/// Range of a changed word within a line, in UTF-16 code units so the
+/// frontend can slice the line's JavaScript string with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A line in a hunk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    /// Whether the line was added, deleted or is context
+    pub kind: LineKind,
+
+    /// Line number in the old version
+    pub old_lineno: Option<u32>,
+
+    /// Line number in the new version
+    pub new_lineno: Option<u32>,
+
+    /// Line content without the trailing newline
+    pub content: String,
+
+    /// Changed words, for modified lines paired with their counterpart
+    pub changes: Vec<WordSpan>,
+}
+
+/// A hunk of a file diff.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    /// Hunk header, e.g. `@@ -12,7 +12,9 @@ fn main() {`
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Diff of a single file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    /// File path in the new version (old version for deleted files)
+    pub path: String,
+
+    /// Path before a rename or copy
+    pub old_path: Option<String>,
+
+    /// Type of change
+    pub change_type: ChangeType,
+
+    /// Whether either side is binary; binary files have no hunks
+    pub is_binary: bool,
+
+    /// Lines added
+    pub additions: usize,
+
+    /// Lines deleted
+    pub deletions: usize,
+
+    /// Hunks of the diff
+    pub hunks: Vec<DiffHunk>,
+
+    /// Whether hunks were dropped because the file diff is too large
+    pub truncated: bool,
+}
+
+/// A page of files from a diff between two revisions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffPage {
+    /// Commit the diff starts from (the merge base, if requested)
+    pub from_oid: String,
+
+    /// Commit the diff ends at
+    pub to_oid: String,
+
+    /// Files on this page
+    pub files: Vec<FileDiff>,
+
+    /// Index of the first file on this page
+    pub offset: usize,
+
+    /// Number of changed files in the whole diff
+    pub total_files: usize,
+
+    /// Whether more files follow this page
+    pub has_more: bool,
+
+    /// Lines added in the whole diff
+    pub additions: usize,
+
+    /// Lines deleted in the whole diff
+    pub deletions: usize,
+}
+
+impl Git2Backend {
+    /// Compute a page of the diff between two revisions.
+    ///
+    /// `from` and `to` accept anything `git rev-parse` does (branches,
+    /// remote branches, SHAs). Files are listed in path order, `limit` at a
+    /// time starting at `offset`.
+    pub fn diff(
+        &self,
+        from: &str,
+        to: &str,
+        options: &DiffOptions,
+        offset: usize,
+        limit: usize,
+    ) -> Result<DiffPage> {
+        let from_commit = self.resolve_commit(from)?;
+        let to_commit = self.resolve_commit(to)?;
+
+        let base_oid = if options.merge_base {
+            self.repo
+                .merge_base(from_commit.id(), to_commit.id())
+                .map_err(|e| GitError::Branch(format!("Failed to find merge base: {}", e)))?
+        } else {
+            from_commit.id()
+        };
+        let base_tree = self.repo.find_commit(base_oid)?.tree()?;
+        let to_tree = to_commit.tree()?;
+
+        let mut diff_options = git2::DiffOptions::new();
+        diff_options
+            .context_lines(options.context_lines)
+            .ignore_whitespace(options.ignore_whitespace);
+        let mut diff = self.repo.diff_tree_to_tree(
+            Some(&base_tree),
+            Some(&to_tree),
+            Some(&mut diff_options),
+        )?;
+
+        if options.detect_renames {
+            let mut find_options = git2::DiffFindOptions::new();
+            find_options.renames(true);
+            diff.find_similar(Some(&mut find_options))?;
+        }
+
+        let stats = diff.stats()?;
+        let total_files = diff.deltas().len();
+        let limit = limit.clamp(1, MAX_PAGE_SIZE);
+        let end = offset.saturating_add(limit).min(total_files);
+
+        let files = (offset.min(end)..end)
+            .map(|idx| Self::file_diff(&diff, idx, options.word_diff))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(DiffPage {
+            from_oid: base_oid.to_string(),
+            to_oid: to_commit.id().to_string(),
+            files,
+            offset,
+            total_files,
+            has_more: end < total_files,
+            additions: stats.insertions(),
+            deletions: stats.deletions(),
+        })
+    }
+
+    /// Revision of a branch as last seen on `origin`, falling back to the
+    /// local branch.
+    ///
+    /// Pull request diffs on GitHub use the pushed branches, which may differ
+    /// from local ones.
+    pub fn remote_branch_revision(&self, branch: &str) -> String {
+        let remote = format!("refs/remotes/origin/{}", branch);
+        if self.repo.revparse_single(&remote).is_ok() {
+            remote
+        } else {
+            format!("refs/heads/{}", branch)
+        }
+    }
+
+    /// Resolve a revision to a commit.
+    pub(crate) fn resolve_commit(&self, spec: &str) -> Result<git2::Commit<'_>> {
+        self.repo
+            .revparse_single(spec)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| GitError::Branch(format!("Revision not found: {}: {}", spec, e)).into())
+    }
+
+    /// Load the hunks of one file of a diff.
+    fn file_diff(diff: &git2::Diff<'_>, idx: usize, word_diff: bool) -> Result<FileDiff> {
+        let delta = diff
+            .get_delta(idx)
+            .ok_or_else(|| GitError::Branch(format!("Diff has no file {}", idx)))?;
+
+        let change_type = match delta.status() {
+            git2::Delta::Added => ChangeType::Added,
+            git2::Delta::Deleted => ChangeType::Deleted,
+            git2::Delta::Modified => ChangeType::Modified,
+            git2::Delta::Renamed => ChangeType::Renamed,
+            git2::Delta::Copied => ChangeType::Copied,
+            _ => ChangeType::Changed,
+        };
+
+        let path_of = |file: git2::DiffFile<'_>| {
+            file.path()
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        let old_path = path_of(delta.old_file());
+        let path = match change_type {
+            ChangeType::Deleted => old_path.clone(),
+            _ => path_of(delta.new_file()),
+        };
+        let old_path =
+            matches!(change_type, ChangeType::Renamed | ChangeType::Copied).then_some(old_path);
+
+        let mut file = FileDiff {
+            path,
+            old_path,
+            change_type,
+            is_binary: delta.flags().is_binary(),
+            additions: 0,
+            deletions: 0,
+            hunks: Vec::new(),
+            truncated: false,
+        };
+
+        // Loading the patch reads the blobs, which is when binary content is
+        // detected for files not marked by attributes.
+        let Some(patch) = git2::Patch::from_diff(diff, idx)? else {
+            file.is_binary = true;
+            return Ok(file);
+        };
+        if patch.delta().flags().is_binary() {
+            file.is_binary = true;
+            return Ok(file);
+        }
+
+        let (_, additions, deletions) = patch.line_stats()?;
+        file.additions = additions;
+        file.deletions = deletions;
+
+        let mut loaded_lines = 0;
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(hunk_idx)?;
+            if loaded_lines + line_count > MAX_LINES_PER_FILE {
+                file.truncated = true;
+                break;
+            }
+            loaded_lines += line_count;
+
+            let mut lines = Vec::with_capacity(line_count);
+            for line_idx in 0..line_count {
+                let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                let kind = match line.origin() {
+                    ' ' => LineKind::Context,
+                    '+' => LineKind::Addition,
+                    '-' => LineKind::Deletion,
+                    // "No newline at end of file" markers
+                    _ => continue,
+                };
+                let content = String::from_utf8_lossy(line.content());
+                let content = content.trim_end_matches(['\n', '\r']).to_string();
+
+                lines.push(DiffLine {
+                    kind,
+                    old_lineno: line.old_lineno(),
+                    new_lineno: line.new_lineno(),
+                    content,
+                    changes: Vec::new(),
+                });
+            }
+
+            if word_diff {
+                mark_word_changes(&mut lines);
+            }
+
+            file.hunks.push(DiffHunk {
+                header: String::from_utf8_lossy(hunk.header())
+                    .trim_end()
+                    .to_string(),
+                old_start: hunk.old_start(),
+                old_lines: hunk.old_lines(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new_lines(),
+                lines,
+            });
+        }
+
+        Ok(file)
+    }
+}
+
+/// Mark changed words on modified lines.
+///
+/// A run of deletions directly followed by a run of additions is treated as
+/// a modification, pairing lines in order.
+fn mark_word_changes(lines: &mut [DiffLine]) {
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].kind != LineKind::Deletion {
+            i += 1;
+            continue;
+        }
+
+        let deletions = i;
+        while i < lines.len() && lines[i].kind == LineKind::Deletion {
+            i += 1;
+        }
+        let additions = i;
+        while i < lines.len() && lines[i].kind == LineKind::Addition {
+            i += 1;
+        }
+
+        let pairs = (additions - deletions).min(i - additions);
+        for k in 0..pairs {
+            let (old, new) = (deletions + k, additions + k);
+            if let Some((old_changes, new_changes)) =
+                word_changes(&lines[old].content, &lines[new].content)
+            {
+                lines[old].changes = old_changes;
+                lines[new].changes = new_changes;
+            }
+        }
+    }
+}
+
+/// Split a line into words, whitespace runs and single punctuation characters.
+fn tokenize(text: &str) -> Vec<(usize, usize)> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Other,
+    }
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            Class::Word
+        } else if c.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Other
+        }
+    };
+
+    let mut tokens: Vec<(usize, usize)> = Vec::new();
+    let mut current: Option<(usize, Class)> = None;
+    for (idx, c) in text.char_indices() {
+        let c_class = class(c);
+        match &current {
+            Some((_, prev)) if *prev == c_class && c_class != Class::Other => {}
+            Some((start, _)) => {
+                tokens.push((*start, idx));
+                current = Some((idx, c_class));
+            }
+            None => current = Some((idx, c_class)),
+        }
+    }
+    if let Some((start, _)) = current {
+        tokens.push((start, text.len()));
+    }
+    tokens
+}
+
+/// Find the changed words between two versions of a line.
+///
+/// Returns `None` when the lines share no words (highlighting everything
+/// adds nothing) or are too long to compare.
+fn word_changes(old: &str, new: &str) -> Option<(Vec<WordSpan>, Vec<WordSpan>)> {
+    let a = tokenize(old);
+    let b = tokenize(new);
+    if a.is_empty() || b.is_empty() || a.len().saturating_mul(b.len()) > MAX_WORD_DIFF_CELLS {
+        return None;
+    }
+
+    let token = |text: &str, (start, end): (usize, usize)| text[start..end].to_string();
+    let a_tokens: Vec<String> = a.iter().map(|&t| token(old, t)).collect();
+    let b_tokens: Vec<String> = b.iter().map(|&t| token(new, t)).collect();
+
+    // Longest common subsequence over tokens
+    let (n, m) = (a.len(), b.len());
+    let mut table = vec![0u32; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[at(i, j)] = if a_tokens[i] == b_tokens[j] {
+                table[at(i + 1, j + 1)] + 1
+            } else {
+                table[at(i + 1, j)].max(table[at(i, j + 1)])
+            };
+        }
+    }
+
+    let mut a_common = vec![false; n];
+    let mut b_common = vec![false; m];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a_tokens[i] == b_tokens[j] {
+            a_common[i] = true;
+            b_common[j] = true;
+            i += 1;
+            j += 1;
+        } else if table[at(i + 1, j)] >= table[at(i, j + 1)] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    let shares_words = a_common
+        .iter()
+        .zip(&a_tokens)
+        .any(|(common, t)| *common && !t.trim().is_empty());
+    if !shares_words {
+        return None;
+    }
+
+    Some((spans(old, &a, &a_common), spans(new, &b, &b_common)))
+}
+
+/// Merge the tokens not in common into contiguous spans.
+///
+/// Tokens are byte ranges of `text`; spans are UTF-16 ranges.
+fn spans(text: &str, tokens: &[(usize, usize)], common: &[bool]) -> Vec<WordSpan> {
+    let utf16 = |byte: usize| text[..byte].encode_utf16().count();
+    let mut spans: Vec<WordSpan> = Vec::new();
+    for (&(start, end), _) in tokens.iter().zip(common).filter(|(_, c)| !**c) {
+        let (start, end) = (utf16(start), utf16(end));
+        match spans.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => spans.push(WordSpan { start, end }),
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// Commit `files` (path, content) on top of HEAD, removing `remove`.
+    fn commit(repo: &git2::Repository, files: &[(&str, &[u8])], remove: &[&str]) -> git2::Oid {
+        let workdir = repo.workdir().unwrap().to_path_buf();
+        let mut index = repo.index().unwrap();
+        for (path, content) in files {
+            let full = workdir.join(path);
+            std::fs::create_dir_all(full.parent().unwrap()).unwrap();
+            std::fs::write(&full, content).unwrap();
+            index.add_path(Path::new(path)).unwrap();
+        }
+        for path in remove {
+            std::fs::remove_file(workdir.join(path)).unwrap();
+            index.remove_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parents: Vec<git2::Commit> = repo
+            .head()
+            .ok()
+            .and_then(|h| h.peel_to_commit().ok())
+            .into_iter()
+            .collect();
+        let parents: Vec<&git2::Commit> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, "commit", &tree, &parents)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_diff_hunks_renames_and_binary() {
+        let dir = tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let long_file: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        let base = commit(
+            &repo,
+            &[
+                ("src/lib.rs", b"fn main() {\n    let x = 1;\n}\n"),
+                ("old_name.txt", long_file.as_bytes()),
+            ],
+            &[],
+        );
+        let head = commit(
+            &repo,
+            &[
+                ("src/lib.rs", b"fn main() {\n    let y = 1;\n}\n"),
+                ("new_name.txt", long_file.as_bytes()),
+                ("logo.png", b"\x89PNG\r\n\x1a\n\0\0\0binary"),
+            ],
+            &["old_name.txt"],
+        );
+
+        let backend = Git2Backend::open(dir.path()).unwrap();
+        let page = backend
+            .diff(
+                &base.to_string(),
+                &head.to_string(),
+                &DiffOptions::default(),
+                0,
+                50,
+            )
+            .unwrap();
+
+        assert_eq!(page.total_files, 3);
+        assert!(!page.has_more);
+
+        let renamed = page
+            .files
+            .iter()
+            .find(|f| f.path == "new_name.txt")
+            .unwrap();
+        assert_eq!(renamed.change_type, ChangeType::Renamed);
+        assert_eq!(renamed.old_path.as_deref(), Some("old_name.txt"));
+
+        let binary = page.files.iter().find(|f| f.path == "logo.png").unwrap();
+        assert!(binary.is_binary);
+        assert!(binary.hunks.is_empty());
+
+        let source = page.files.iter().find(|f| f.path == "src/lib.rs").unwrap();
+        assert_eq!((source.additions, source.deletions), (1, 1));
+        let lines = &source.hunks[0].lines;
+        let deleted = lines.iter().find(|l| l.kind == LineKind::Deletion).unwrap();
+        let added = lines.iter().find(|l| l.kind == LineKind::Addition).unwrap();
+        assert_eq!(deleted.old_lineno, Some(2));
+        assert_eq!(added.new_lineno, Some(2));
+        assert_eq!(
+            &added.content[added.changes[0].start..added.changes[0].end],
+            "y"
+        );
+        assert_eq!(
+            &deleted.content[deleted.changes[0].start..deleted.changes[0].end],
+            "x"
+        );
+    }
+
+    #[test]
+    fn test_diff_pages_and_merge_base() {
+        let dir = tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let base = commit(&repo, &[("README.md", b"readme\n")], &[]);
+        repo.branch("parent", &repo.find_commit(base).unwrap(), false)
+            .unwrap();
+
+        let head = commit(
+            &repo,
+            &[("a.txt", b"a\n"), ("b.txt", b"b\n"), ("c.txt", b"c\n")],
+            &[],
+        );
+
+        // Move the parent ahead; the merge base diff ignores its changes
+        repo.set_head("refs/heads/parent").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        commit(&repo, &[("parent_only.txt", b"p\n")], &[]);
+
+        let backend = Git2Backend::open(dir.path()).unwrap();
+        let options = DiffOptions::default();
+        let first = backend
+            .diff("parent", &head.to_string(), &options, 0, 2)
+            .unwrap();
+        assert_eq!(first.from_oid, base.to_string());
+        assert_eq!(first.total_files, 3);
+        assert_eq!(first.files.len(), 2);
+        assert!(first.has_more);
+
+        let second = backend
+            .diff("parent", &head.to_string(), &options, 2, 2)
+            .unwrap();
+        assert_eq!(second.files.len(), 1);
+        assert_eq!(second.files[0].path, "c.txt");
+        assert!(!second.has_more);
+
+        let direct = DiffOptions {
+            merge_base: false,
+            ..Default::default()
+        };
+        let page = backend
+            .diff("parent", &head.to_string(), &direct, 0, 50)
+            .unwrap();
+        assert_eq!(page.total_files, 4);
+    }
+
+    #[test]
+    fn test_word_changes() {
+        let (old, new) = word_changes("let total = a + b;", "let sum = a + c;").unwrap();
+        assert_eq!(
+            old,
+            vec![
+                WordSpan { start: 4, end: 9 },
+                WordSpan { start: 16, end: 17 }
+            ]
+        );
+        assert_eq!(
+            new,
+            vec![
+                WordSpan { start: 4, end: 7 },
+                WordSpan { start: 14, end: 15 }
+            ]
+        );
+
+        // Offsets count UTF-16 code units, not bytes
+        let (old, new) = word_changes("café = \"né\";", "café = \"😀 né\";").unwrap();
+        assert!(old.is_empty());
+        assert_eq!(new, vec![WordSpan { start: 8, end: 11 }]);
+
+        // Nothing in common: no word highlighting
+        assert!(word_changes("foo", "bar").is_none());
+    }
+
+    #[test]
+    fn test_unknown_revision() {
+        let dir = tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        commit(&repo, &[("a.txt", b"a\n")], &[]);
+
+        let backend = Git2Backend::open(dir.path()).unwrap();
+        assert!(backend
+            .diff("missing", "HEAD", &DiffOptions::default(), 0, 10)
+            .is_err());
+    }
+}
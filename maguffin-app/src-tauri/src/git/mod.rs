@@ -3,7 +3,12 @@
 //! This module provides abstractions for Git operations using git2 (libgit2)
 //! with a CLI fallback for complex operations.
 
-use crate::error::{GitError, Result};
+pub mod diff;
+pub mod stack_interop;
+pub mod stack_store;
+
+use crate::domain::stack::edit::BranchCommit;
+use crate::error::{AppError, GitError, Result};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -82,6 +87,18 @@ impl Git2Backend {
         Ok(commit.id().to_string())
     }
 
+    /// Get the names of all local branches.
+    pub fn local_branches(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for branch in self.repo.branches(Some(git2::BranchType::Local))? {
+            let (branch, _) = branch?;
+            if let Some(name) = branch.name()? {
+                names.push(name.to_string());
+            }
+        }
+        Ok(names)
+    }
+
     /// Check if one branch is an ancestor of another.
     pub fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool> {
         let ancestor_oid = self
@@ -158,6 +175,101 @@ impl Git2Backend {
         }
     }
 
+    /// Replay the commits of `branch` after `upstream` onto `onto`.
+    pub fn rebase_onto(&self, branch: &str, onto: &str, upstream: &str) -> Result<()> {
+        let workdir = self
+            .workdir()
+            .ok_or_else(|| GitError::RepositoryNotFound("No working directory".to_string()))?;
+
+        let output = std::process::Command::new("git")
+            .args(["rebase", "--onto", onto, upstream, branch])
+            .current_dir(workdir)
+            .output()
+            .map_err(|e| GitError::RebaseFailed(e.to_string()))?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("conflict") || stderr.contains("CONFLICT") {
+                Err(GitError::Conflict { files: Vec::new() }.into())
+            } else {
+                Err(GitError::RebaseFailed(stderr.to_string()).into())
+            }
+        }
+    }
+
+    /// Get the SHA of the best common ancestor of two revisions.
+    pub fn merge_base(&self, a: &str, b: &str) -> Result<String> {
+        let a = self.repo.revparse_single(a)?.peel_to_commit()?.id();
+        let b = self.repo.revparse_single(b)?.peel_to_commit()?.id();
+        let base = self
+            .repo
+            .merge_base(a, b)
+            .map_err(|e| GitError::Branch(format!("Failed to find merge base: {}", e)))?;
+        Ok(base.to_string())
+    }
+
+    /// Count the commits reachable from `head` but not from `base`.
+    pub fn count_commits(&self, base: &str, head: &str) -> Result<i32> {
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push(self.repo.revparse_single(head)?.peel_to_commit()?.id())?;
+        revwalk.hide(self.repo.revparse_single(base)?.peel_to_commit()?.id())?;
+        Ok(revwalk.count() as i32)
+    }
+
+    /// List the commits reachable from `head` but not from `base`, oldest
+    /// first.
+    pub fn list_commits(&self, base: &str, head: &str) -> Result<Vec<BranchCommit>> {
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+        revwalk.push(self.repo.revparse_single(head)?.peel_to_commit()?.id())?;
+        revwalk.hide(self.repo.revparse_single(base)?.peel_to_commit()?.id())?;
+
+        revwalk
+            .map(|oid| {
+                let commit = self.repo.find_commit(oid?)?;
+                Ok(BranchCommit {
+                    sha: commit.id().to_string(),
+                    summary: commit.summary().unwrap_or_default().to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Point branches at the given commits, creating them if needed.
+    ///
+    /// The checked-out branch is moved along with the working tree, which
+    /// must be clean.
+    pub fn reset_branches(&self, heads: &[(String, String)]) -> Result<()> {
+        let current = self.current_branch().ok();
+        let moves_current = current
+            .as_ref()
+            .is_some_and(|c| heads.iter().any(|(name, _)| name == c));
+        if moves_current {
+            let head = self.repo.head()?.peel_to_commit()?.id();
+            self.repo.set_head_detached(head)?;
+        }
+
+        for (name, sha) in heads {
+            let commit = self.repo.find_commit(git2::Oid::from_str(sha)?)?;
+            self.repo.branch(name, &commit, true)?;
+        }
+
+        if let Some(current) = current.filter(|_| moves_current) {
+            self.checkout_branch(&current)?;
+        }
+        Ok(())
+    }
+
+    /// Delete a local branch.
+    pub fn delete_branch(&self, name: &str) -> Result<()> {
+        self.repo
+            .find_branch(name, git2::BranchType::Local)
+            .and_then(|mut branch| branch.delete())
+            .map_err(|e| GitError::Branch(format!("Failed to delete {}: {}", name, e)).into())
+    }
+
     /// Abort an in-progress rebase.
     pub fn abort_rebase(&self) -> Result<()> {
         let workdir = self
@@ -319,6 +431,45 @@ impl Git2Backend {
         Vec::new()
     }
 
+    /// The remote `branch` is pushed to: its push remote or the remote it
+    /// tracks, falling back to [`Git2Backend::default_remote`].
+    pub fn push_remote(&self, branch: &str) -> String {
+        if let Ok(config) = self.repo.config().and_then(|mut c| c.snapshot()) {
+            for key in [
+                format!("branch.{}.pushRemote", branch),
+                "remote.pushDefault".to_string(),
+                format!("branch.{}.remote", branch),
+            ] {
+                // "." means the branch tracks another local branch
+                match config.get_string(&key) {
+                    Ok(remote) if remote != "." => return remote,
+                    _ => {}
+                }
+            }
+        }
+        self.default_remote()
+    }
+
+    /// The remote pushes go to when nothing more specific is configured:
+    /// `remote.pushDefault`, `origin`, or the only remote there is.
+    pub fn default_remote(&self) -> String {
+        if let Ok(remote) = self
+            .repo
+            .config()
+            .and_then(|mut c| c.snapshot())
+            .and_then(|c| c.get_string("remote.pushDefault"))
+        {
+            return remote;
+        }
+
+        let remotes = self.repo.remotes().ok();
+        let names: Vec<&str> = remotes.iter().flat_map(|r| r.iter().flatten()).collect();
+        match names.as_slice() {
+            [only] => only.to_string(),
+            _ => "origin".to_string(),
+        }
+    }
+
     /// Force push a branch to remote.
     pub fn force_push(&self, branch: &str, remote: &str) -> Result<()> {
         let workdir = self
@@ -338,6 +489,114 @@ impl Git2Backend {
             Err(GitError::Remote(stderr.to_string()).into())
         }
     }
+
+    /// Delete a branch on a remote.
+    pub fn delete_remote_branch(&self, branch: &str, remote: &str) -> Result<()> {
+        let workdir = self
+            .workdir()
+            .ok_or_else(|| GitError::RepositoryNotFound("No working directory".to_string()))?;
+
+        let output = std::process::Command::new("git")
+            .args(["push", "--delete", remote, branch])
+            .current_dir(workdir)
+            .output()
+            .map_err(|e| GitError::Remote(e.to_string()))?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(GitError::Remote(stderr.to_string()).into())
+        }
+    }
+
+    /// Rebase a branch onto the latest `base` from `remote` and force-push it.
+    ///
+    /// Brings a PR branch up to date locally when GitHub can't. The remote
+    /// branch must still be at `expected_head`, and the push is leased on it,
+    /// so commits pushed in the meantime are never overwritten. A local copy
+    /// of the branch that differs from the remote is refused for the same
+    /// reason. On conflicts the rebase is aborted. Returns the new head SHA.
+    pub fn rebase_and_push(
+        &self,
+        branch: &str,
+        base: &str,
+        remote: &str,
+        expected_head: &str,
+    ) -> Result<String> {
+        let workdir = self
+            .workdir()
+            .ok_or_else(|| GitError::RepositoryNotFound("No working directory".to_string()))?;
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(args)
+                .current_dir(workdir)
+                .output()
+        };
+
+        // Fetch through the CLI so credential helpers apply
+        let output =
+            git(&["fetch", remote, branch, base]).map_err(|e| GitError::Remote(e.to_string()))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(GitError::Remote(stderr.to_string()).into());
+        }
+
+        let remote_head = self
+            .repo
+            .revparse_single(&format!("refs/remotes/{}/{}", remote, branch))?
+            .id()
+            .to_string();
+        if remote_head != expected_head {
+            return Err(GitError::Branch(format!(
+                "{} has moved on {} (expected {}, found {})",
+                branch, remote, expected_head, remote_head
+            ))
+            .into());
+        }
+
+        if self.branch_exists(branch)? {
+            if self.get_head_sha(branch)? != remote_head {
+                return Err(GitError::Branch(format!(
+                    "Local {} differs from {}/{}; push or reset it first",
+                    branch, remote, branch
+                ))
+                .into());
+            }
+        } else {
+            let commit = self.repo.find_commit(git2::Oid::from_str(&remote_head)?)?;
+            self.repo.branch(branch, &commit, false)?;
+        }
+
+        let original = self.current_branch().ok();
+
+        if let Err(e) = self.rebase(branch, &format!("{}/{}", remote, base)) {
+            let files = self.get_conflict_files();
+            // The rebase error is the one worth reporting
+            if let Err(abort) = self.abort_rebase() {
+                tracing::error!("Failed to abort rebase of {}: {}", branch, abort);
+            }
+            return Err(match e {
+                AppError::Git(GitError::Conflict { .. }) => GitError::Conflict { files }.into(),
+                e => e,
+            });
+        }
+
+        let new_head = self.get_head_sha(branch)?;
+        if let Some(original) = original.filter(|name| name != branch) {
+            self.checkout_branch(&original)?;
+        }
+
+        let lease = format!("--force-with-lease=refs/heads/{}:{}", branch, expected_head);
+        let output =
+            git(&["push", &lease, remote, branch]).map_err(|e| GitError::Remote(e.to_string()))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(GitError::Remote(stderr.to_string()).into());
+        }
+
+        Ok(new_head)
+    }
 }
 
 impl GitOperations for Git2Backend {
@@ -559,4 +818,86 @@ mod tests {
         backend.checkout_branch("feature-test").unwrap();
         assert_eq!(backend.current_branch().unwrap(), "feature-test");
     }
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn commit_file(dir: &Path, name: &str) {
+        std::fs::write(dir.join(name), name).unwrap();
+        git(dir, &["add", name]);
+        git(dir, &["commit", "-qm", name]);
+    }
+
+    #[test]
+    fn test_push_remote() {
+        let (dir, _repo) = init_test_repo();
+        let work = dir.path();
+        let backend = Git2Backend::open(work).unwrap();
+        assert_eq!(backend.default_remote(), "origin");
+
+        git(
+            work,
+            &["remote", "add", "fork", "https://example.com/fork.git"],
+        );
+        assert_eq!(backend.default_remote(), "fork");
+        assert_eq!(backend.push_remote("feature"), "fork");
+
+        git(
+            work,
+            &["remote", "add", "upstream", "https://example.com/up.git"],
+        );
+        assert_eq!(backend.push_remote("feature"), "origin");
+        git(work, &["config", "branch.feature.remote", "upstream"]);
+        assert_eq!(backend.push_remote("feature"), "upstream");
+        git(work, &["config", "remote.pushDefault", "fork"]);
+        assert_eq!(backend.push_remote("feature"), "fork");
+        git(work, &["config", "branch.feature.pushRemote", "upstream"]);
+        assert_eq!(backend.push_remote("feature"), "upstream");
+        assert_eq!(backend.default_remote(), "fork");
+    }
+
+    #[test]
+    fn test_rebase_and_push() {
+        let origin = tempdir().unwrap();
+        git(origin.path(), &["init", "-q", "--bare", "-b", "main"]);
+
+        let (dir, _repo) = init_test_repo();
+        let work = dir.path();
+        git(work, &["config", "user.name", "Test"]);
+        git(work, &["config", "user.email", "test@example.com"]);
+        git(
+            work,
+            &["remote", "add", "origin", origin.path().to_str().unwrap()],
+        );
+        git(work, &["checkout", "-qb", "feature"]);
+        commit_file(work, "feature.txt");
+        git(work, &["checkout", "-q", "main"]);
+        commit_file(work, "main.txt");
+        git(work, &["push", "-q", "origin", "main", "feature"]);
+        let head = git(work, &["rev-parse", "feature"]);
+
+        let backend = Git2Backend::open(work).unwrap();
+
+        // A stale expected head is refused before anything is rewritten
+        let err = backend
+            .rebase_and_push("feature", "main", "origin", "0000000")
+            .unwrap_err();
+        assert!(matches!(err, AppError::Git(GitError::Branch(_))));
+        assert_eq!(git(work, &["rev-parse", "origin/feature"]), head);
+
+        let new_head = backend
+            .rebase_and_push("feature", "main", "origin", &head)
+            .unwrap();
+        assert_ne!(new_head, head);
+        assert_eq!(git(work, &["rev-parse", "origin/feature"]), new_head);
+        assert!(backend.is_ancestor("main", "feature").unwrap());
+        assert_eq!(backend.current_branch().unwrap(), "main");
+    }
 }
@@ -0,0 +1,312 @@
+//! Stack metadata of other stacking tools.
+//!
+//! Reads the parent branches recorded by Graphite and git-town so their
+//! stacks can be imported, and writes ours back as git-town parent entries
+//! so both tools can be used on the same branches. git-branchless keeps no
+//! parent records of its own, as it works from the commit graph; its stacks
+//! are found by stack discovery instead.
+
+use super::Git2Backend;
+use crate::domain::stack::{ImportedBranch, StackMetadata};
+use crate::error::{Result, StorageError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Prefix of the refs Graphite stores per-branch metadata under.
+const GRAPHITE_REF_PREFIX: &str = "refs/branch-metadata/";
+
+/// Graphite's branch cache, in the git directory.
+const GRAPHITE_CACHE_FILE: &str = ".graphite_cache_persist";
+
+/// Stacking tools whose metadata can be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StackTool {
+    /// Graphite (`gt`)
+    Graphite,
+
+    /// git-town
+    GitTown,
+}
+
+/// Graphite's metadata for one branch, from a branch metadata ref or the
+/// branch cache.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphiteBranch {
+    parent_branch_name: Option<String>,
+    pr_info: Option<GraphitePrInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphitePrInfo {
+    number: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphiteCache {
+    #[serde(default)]
+    branches: Vec<(String, GraphiteBranch)>,
+}
+
+impl Git2Backend {
+    /// Read the branches `tool` has recorded, with their parents.
+    ///
+    /// Branches that no longer exist locally are left out.
+    pub fn read_stack_parents(&self, tool: StackTool) -> Result<Vec<ImportedBranch>> {
+        let branches = match tool {
+            StackTool::Graphite => self.read_graphite_parents()?,
+            StackTool::GitTown => self.read_git_town_parents()?,
+        };
+
+        Ok(branches
+            .into_values()
+            .filter(|b| {
+                self.repo
+                    .find_branch(&b.name, git2::BranchType::Local)
+                    .is_ok()
+            })
+            .collect())
+    }
+
+    /// Record every stack branch's parent as a git-town parent entry.
+    ///
+    /// Also sets git-town's main branch to `trunk`, unless one is set.
+    /// Returns the number of branches written.
+    pub fn write_git_town_parents(&self, metadata: &StackMetadata, trunk: &str) -> Result<usize> {
+        let mut config = self.repo.config()?;
+
+        let has_main = ["git-town.main-branch", "git-town.main-branch-name"]
+            .iter()
+            .any(|key| config.get_string(key).is_ok());
+        if !has_main {
+            config.set_str("git-town.main-branch", trunk)?;
+        }
+
+        let mut written = 0;
+        for branch in metadata.stacks.iter().flat_map(|s| &s.branches) {
+            config.set_str(
+                &format!("git-town-branch.{}.parent", branch.name),
+                &branch.parent,
+            )?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// Graphite's branch metadata refs, falling back to its branch cache for
+    /// branches without one.
+    fn read_graphite_parents(&self) -> Result<BTreeMap<String, ImportedBranch>> {
+        let mut branches = BTreeMap::new();
+
+        let cache_path = self.repo.path().join(GRAPHITE_CACHE_FILE);
+        if cache_path.exists() {
+            let content = std::fs::read_to_string(&cache_path).map_err(StorageError::Io)?;
+            let cache: GraphiteCache = serde_json::from_str(&content)
+                .map_err(|e| StorageError::Serialization(e.to_string()))?;
+            for (name, branch) in cache.branches {
+                Self::add_graphite_branch(&mut branches, name, branch);
+            }
+        }
+
+        for reference in self
+            .repo
+            .references_glob(&format!("{}*", GRAPHITE_REF_PREFIX))?
+        {
+            let reference = reference?;
+            let Some(name) = reference
+                .name()
+                .and_then(|n| n.strip_prefix(GRAPHITE_REF_PREFIX))
+                .map(str::to_string)
+            else {
+                continue;
+            };
+            let blob = reference.peel_to_blob()?;
+            match serde_json::from_slice::<GraphiteBranch>(blob.content()) {
+                Ok(branch) => Self::add_graphite_branch(&mut branches, name, branch),
+                Err(e) => tracing::warn!("Skipping Graphite metadata of {}: {}", name, e),
+            }
+        }
+
+        Ok(branches)
+    }
+
+    fn add_graphite_branch(
+        branches: &mut BTreeMap<String, ImportedBranch>,
+        name: String,
+        branch: GraphiteBranch,
+    ) {
+        // Trunk branches have no parent
+        let Some(parent) = branch.parent_branch_name else {
+            return;
+        };
+        branches.insert(
+            name.clone(),
+            ImportedBranch {
+                name,
+                parent,
+                pr_number: branch.pr_info.and_then(|pr| pr.number),
+            },
+        );
+    }
+
+    /// git-town's `git-town-branch.<branch>.parent` config entries.
+    fn read_git_town_parents(&self) -> Result<BTreeMap<String, ImportedBranch>> {
+        let config = self.repo.config()?;
+        let mut branches = BTreeMap::new();
+
+        let mut entries = config.entries(Some(r"^git-town-branch\..*\.parent$"))?;
+        while let Some(entry) = entries.next() {
+            let entry = entry?;
+            let (Some(key), Some(parent)) = (entry.name(), entry.value()) else {
+                continue;
+            };
+            let Some(name) = key
+                .strip_prefix("git-town-branch.")
+                .and_then(|k| k.strip_suffix(".parent"))
+            else {
+                continue;
+            };
+            branches.insert(
+                name.to_string(),
+                ImportedBranch {
+                    name: name.to_string(),
+                    parent: parent.to_string(),
+                    pr_number: None,
+                },
+            );
+        }
+
+        Ok(branches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::stack::{Stack, StackBranch};
+    use crate::git::GitOperations;
+    use tempfile::TempDir;
+
+    /// A repository with `main` and the given branches, all on one commit.
+    fn repo_with_branches(names: &[&str]) -> (TempDir, Git2Backend) {
+        let dir = TempDir::new().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        {
+            let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+            let tree_id = repo.index().unwrap().write_tree().unwrap();
+            let tree = repo.find_tree(tree_id).unwrap();
+            let commit = repo
+                .commit(None, &signature, &signature, "Initial", &tree, &[])
+                .unwrap();
+            let commit = repo.find_commit(commit).unwrap();
+            for name in std::iter::once(&"main").chain(names) {
+                repo.branch(name, &commit, true).unwrap();
+            }
+        }
+        let backend = Git2Backend::open(dir.path()).unwrap();
+        (dir, backend)
+    }
+
+    fn parents(branches: Vec<ImportedBranch>) -> Vec<(String, String)> {
+        branches.into_iter().map(|b| (b.name, b.parent)).collect()
+    }
+
+    #[test]
+    fn test_read_graphite_parents() {
+        let (dir, git) = repo_with_branches(&["feature-a", "feature-b", "feature-c"]);
+        let repo = git2::Repository::open(dir.path()).unwrap();
+
+        // The cache knows feature-a and a deleted branch
+        std::fs::write(
+            dir.path().join(".git").join(GRAPHITE_CACHE_FILE),
+            r#"{"sha": "abc", "branches": [
+                ["main", {"validationResult": "TRUNK", "children": ["feature-a"]}],
+                ["feature-a", {"validationResult": "VALID", "parentBranchName": "main"}],
+                ["deleted", {"validationResult": "VALID", "parentBranchName": "main"}]
+            ]}"#,
+        )
+        .unwrap();
+
+        // Metadata refs know feature-b and feature-c
+        for (name, json) in [
+            (
+                "feature-b",
+                r#"{"parentBranchName": "feature-a", "prInfo": {"number": 42, "base": "feature-a"}}"#,
+            ),
+            ("feature-c", r#"{"parentBranchName": "feature-b"}"#),
+        ] {
+            let blob = repo.blob(json.as_bytes()).unwrap();
+            repo.reference(&format!("{}{}", GRAPHITE_REF_PREFIX, name), blob, true, "")
+                .unwrap();
+        }
+
+        let branches = git.read_stack_parents(StackTool::Graphite).unwrap();
+        assert_eq!(
+            branches
+                .iter()
+                .find(|b| b.name == "feature-b")
+                .unwrap()
+                .pr_number,
+            Some(42)
+        );
+        assert_eq!(
+            parents(branches),
+            [
+                ("feature-a".to_string(), "main".to_string()),
+                ("feature-b".to_string(), "feature-a".to_string()),
+                ("feature-c".to_string(), "feature-b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_git_town_roundtrip() {
+        let (dir, git) = repo_with_branches(&["feature-a", "feature-b"]);
+
+        let mut metadata = StackMetadata::default();
+        let mut stack = Stack::new("main".to_string());
+        stack.add_branch(StackBranch::new(
+            "feature-a".to_string(),
+            "main".to_string(),
+        ));
+        stack.add_branch(StackBranch::new(
+            "feature-b".to_string(),
+            "feature-a".to_string(),
+        ));
+        metadata.add_stack(stack);
+
+        assert_eq!(git.write_git_town_parents(&metadata, "main").unwrap(), 2);
+
+        let config = git2::Repository::open(dir.path())
+            .unwrap()
+            .config()
+            .unwrap()
+            .snapshot()
+            .unwrap();
+        assert_eq!(config.get_str("git-town.main-branch").unwrap(), "main");
+        assert_eq!(
+            config.get_str("git-town-branch.feature-b.parent").unwrap(),
+            "feature-a"
+        );
+
+        assert_eq!(
+            parents(git.read_stack_parents(StackTool::GitTown).unwrap()),
+            [
+                ("feature-a".to_string(), "main".to_string()),
+                ("feature-b".to_string(), "feature-a".to_string()),
+            ]
+        );
+
+        // An existing main branch setting is kept
+        git.create_branch("develop", "main").unwrap();
+        git.write_git_town_parents(&metadata, "develop").unwrap();
+        let config = git2::Repository::open(dir.path())
+            .unwrap()
+            .config()
+            .unwrap()
+            .snapshot()
+            .unwrap();
+        assert_eq!(config.get_str("git-town.main-branch").unwrap(), "main");
+    }
+}
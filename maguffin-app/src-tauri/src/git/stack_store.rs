@@ -0,0 +1,756 @@
+//! Storage for stack metadata.
+//!
+//! By default stack metadata lives in `.git/stack-metadata.json`, which
+//! never leaves the machine. A repository can instead keep it as a history
+//! of commits on the `refs/maguffin/stacks` ref, which is pushed and fetched
+//! like a branch so stacks follow the repository to other machines and
+//! teammates. Copies that diverged are merged with [`StackMetadata::merge`].
+
+use crate::domain::stack::{migrations, StackMetadata};
+use crate::error::{AppError, GitError, Result, StorageError};
+use git2::{Commit, ErrorCode, Oid, Repository, Signature};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Ref holding the shared stack metadata.
+pub const STACKS_REF: &str = "refs/maguffin/stacks";
+
+/// Repository config key selecting the storage.
+const STORAGE_CONFIG_KEY: &str = "maguffin.stackStorage";
+
+/// Name of the metadata file, in the git directory and in ref commits.
+const METADATA_FILE: &str = "stack-metadata.json";
+
+/// Lock file serializing writers, in the git directory.
+const LOCK_FILE: &str = "stack-metadata.lock";
+
+/// How long to wait for another writer to release the lock.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often to check whether the lock has been released.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Where a repository's stack metadata is kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StackStorage {
+    /// `.git/stack-metadata.json`, local to this clone
+    #[default]
+    File,
+
+    /// The `refs/maguffin/stacks` ref, shared through the remote
+    Ref,
+}
+
+/// Identifies a saved copy of the metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fingerprint {
+    /// Modification time and size of the metadata file
+    File { modified: SystemTime, len: u64 },
+
+    /// Commit the stacks ref points at
+    Ref(Oid),
+}
+
+/// Reads and writes the stack metadata of a repository.
+///
+/// Writers take an advisory lock on `.git/stack-metadata.lock`, and a save
+/// only succeeds if the stored revision is the one the metadata was loaded
+/// at, so concurrent writers, such as the app and a CLI, never silently
+/// overwrite each other.
+#[derive(Debug, Clone)]
+pub struct StackStore {
+    git_dir: PathBuf,
+    storage: StackStorage,
+}
+
+impl StackStore {
+    /// Open the store of the repository at `repo_path`, using the storage
+    /// configured for it.
+    pub fn open(repo_path: &Path) -> Result<Self> {
+        let repo =
+            Repository::open(repo_path).map_err(|e| GitError::RepositoryNotFound(e.to_string()))?;
+        let storage = match repo.config()?.get_string(STORAGE_CONFIG_KEY) {
+            Ok(value) if value == "ref" => StackStorage::Ref,
+            _ => StackStorage::File,
+        };
+
+        Ok(Self {
+            git_dir: repo.path().to_path_buf(),
+            storage,
+        })
+    }
+
+    /// The storage in use.
+    pub fn storage(&self) -> StackStorage {
+        self.storage
+    }
+
+    /// Load the metadata, or `None` if none was saved yet.
+    pub fn load(&self) -> Result<Option<StackMetadata>> {
+        match self.storage {
+            StackStorage::File => {
+                let path = self.git_dir.join(METADATA_FILE);
+                if !path.exists() {
+                    return Ok(None);
+                }
+                let content = std::fs::read(&path).map_err(StorageError::Io)?;
+                migrations::parse(&content).map(Some)
+            }
+            StackStorage::Ref => {
+                let repo = self.repository()?;
+                let commit = Self::find_commit(&repo, STACKS_REF)?;
+                commit.map(|c| Self::read_commit(&repo, &c)).transpose()
+            }
+        }
+    }
+
+    /// Identify the saved metadata without reading it, or `None` if none was
+    /// saved yet. Changes whenever the metadata is saved.
+    pub fn fingerprint(&self) -> Result<Option<Fingerprint>> {
+        match self.storage {
+            StackStorage::File => match std::fs::metadata(self.git_dir.join(METADATA_FILE)) {
+                Ok(file) => Ok(Some(Fingerprint::File {
+                    modified: file.modified().map_err(StorageError::Io)?,
+                    len: file.len(),
+                })),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(StorageError::Io(e).into()),
+            },
+            StackStorage::Ref => {
+                let repo = self.repository()?;
+                let target = match repo.find_reference(STACKS_REF) {
+                    Ok(reference) => reference.target(),
+                    Err(e) if e.code() == ErrorCode::NotFound => None,
+                    Err(e) => return Err(e.into()),
+                };
+                Ok(target.map(Fingerprint::Ref))
+            }
+        }
+    }
+
+    /// Save the metadata, provided nobody else saved it since it was loaded.
+    ///
+    /// Fails with [`StorageError::Conflict`] if the stored revision isn't
+    /// `metadata.revision`, and otherwise bumps it. Saving unchanged
+    /// metadata writes nothing.
+    pub fn save(&self, metadata: &mut StackMetadata) -> Result<()> {
+        let _lock = self.lock()?;
+
+        let current = match self.load() {
+            // Unreadable metadata is replaced by the rebuilt copy
+            Err(AppError::Storage(StorageError::Serialization(_))) => None,
+            result => result?,
+        };
+        let found = current.as_ref().map_or(0, |m| m.revision);
+        if found != metadata.revision {
+            return Err(StorageError::Conflict {
+                expected: metadata.revision,
+                found,
+            }
+            .into());
+        }
+        if let Some(current) = current {
+            if serde_json::to_value(&current)? == serde_json::to_value(&*metadata)? {
+                return Ok(());
+            }
+        }
+
+        self.write(metadata, found + 1)
+    }
+
+    /// Move unreadable metadata out of the way, so it can be rebuilt.
+    ///
+    /// The metadata file is renamed to `stack-metadata.json.corrupt`; with
+    /// ref storage the unreadable commit stays in the ref's history.
+    pub fn set_aside(&self) -> Result<()> {
+        if self.storage != StackStorage::File {
+            return Ok(());
+        }
+
+        let _lock = self.lock()?;
+        let path = self.git_dir.join(METADATA_FILE);
+        let corrupt = path.with_extension("json.corrupt");
+        match std::fs::rename(&path, &corrupt) {
+            Ok(()) => {
+                tracing::warn!("Moved unreadable stack metadata to {}", corrupt.display());
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(StorageError::Io(e).into()),
+        }
+    }
+
+    /// Switch the repository to `storage`, carrying `metadata` over.
+    ///
+    /// Whatever `storage` held is replaced. Switching to ref storage also
+    /// adds a fetch refspec for the stacks ref to every remote, so a plain
+    /// `git fetch` brings in teammates' stacks.
+    pub fn set_storage(
+        &mut self,
+        storage: StackStorage,
+        metadata: &mut StackMetadata,
+    ) -> Result<()> {
+        let _lock = self.lock()?;
+        let repo = self.repository()?;
+
+        if storage == StackStorage::Ref {
+            for remote in repo.remotes()?.iter().flatten() {
+                let refspec = format!("+{}:{}", STACKS_REF, Self::tracking_ref(remote));
+                let configured = repo
+                    .find_remote(remote)?
+                    .fetch_refspecs()?
+                    .iter()
+                    .any(|r| r == Some(refspec.as_str()));
+                if !configured {
+                    repo.remote_add_fetch(remote, &refspec)?;
+                }
+            }
+        }
+
+        self.storage = storage;
+        let found = match self.load() {
+            Err(AppError::Storage(StorageError::Serialization(_))) => None,
+            result => result?,
+        }
+        .map_or(0, |m| m.revision);
+        self.write(metadata, found.max(metadata.revision) + 1)?;
+
+        let value = match storage {
+            StackStorage::File => "file",
+            StackStorage::Ref => "ref",
+        };
+        repo.config()?.set_str(STORAGE_CONFIG_KEY, value)?;
+
+        Ok(())
+    }
+
+    /// Fetch the stacks ref from `remote`, merge it with ours and push the
+    /// result back.
+    ///
+    /// Returns the merged metadata. Fails if the remote ref moved again
+    /// between the fetch and the push; syncing again then merges that too.
+    pub fn sync(&self, remote: &str) -> Result<StackMetadata> {
+        self.fetch(remote)?;
+        let (metadata, unpushed) = self.merge_remote(remote)?;
+        if unpushed {
+            self.push(remote)?;
+        }
+        Ok(metadata)
+    }
+
+    /// Fetch the stacks ref from `remote` into its tracking ref, to merge
+    /// with [`StackStore::merge_remote`].
+    ///
+    /// Takes no lock, as only the tracking ref changes.
+    pub fn fetch(&self, remote: &str) -> Result<()> {
+        self.require_ref()?;
+
+        let tracking = Self::tracking_ref(remote);
+        let output = self.git(&["fetch", remote, &format!("+{}:{}", STACKS_REF, tracking)])?;
+        if !output.status.success() && self.remote_has_ref(remote)? {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(GitError::Remote(stderr.to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Whether `remote` has a stacks ref; nobody may have pushed stacks yet.
+    ///
+    /// Asked with `ls-remote --exit-code` rather than read from error
+    /// messages, which depend on the locale.
+    fn remote_has_ref(&self, remote: &str) -> Result<bool> {
+        let output = self.git(&["ls-remote", "--exit-code", remote, STACKS_REF])?;
+        match output.status.code() {
+            Some(0) => Ok(true),
+            // No matching refs
+            Some(2) => Ok(false),
+            _ => Err(GitError::Remote(String::from_utf8_lossy(&output.stderr).to_string()).into()),
+        }
+    }
+
+    /// Merge the last fetched copy of `remote`'s stacks into ours.
+    ///
+    /// Returns the merged metadata and whether `remote` lacks it, so it has
+    /// to be pushed. Our ref is only moved if nobody saved since it was
+    /// read.
+    pub fn merge_remote(&self, remote: &str) -> Result<(StackMetadata, bool)> {
+        self.require_ref()?;
+        let _lock = self.lock()?;
+
+        let repo = self.repository()?;
+        let ours = Self::find_commit(&repo, STACKS_REF)?;
+        let theirs = Self::find_commit(&repo, &Self::tracking_ref(remote))?;
+
+        let merged = match (&ours, &theirs) {
+            (None, None) => return Ok((StackMetadata::default(), false)),
+            (Some(ours), None) => ours.id(),
+            (None, Some(theirs)) => theirs.id(),
+            (Some(ours), Some(theirs)) => {
+                if ours.id() == theirs.id() || repo.graph_descendant_of(ours.id(), theirs.id())? {
+                    ours.id()
+                } else if repo.graph_descendant_of(theirs.id(), ours.id())? {
+                    theirs.id()
+                } else {
+                    let base = match repo.merge_base(ours.id(), theirs.id()) {
+                        Ok(oid) => Some(Self::read_commit(&repo, &repo.find_commit(oid)?)?),
+                        Err(e) if e.code() == ErrorCode::NotFound => None,
+                        Err(e) => return Err(e.into()),
+                    };
+                    let mut metadata = StackMetadata::merge(
+                        base.as_ref(),
+                        &Self::read_commit(&repo, ours)?,
+                        &Self::read_commit(&repo, theirs)?,
+                    );
+                    metadata.revision += 1;
+                    let tree = Self::write_tree(&repo, &metadata)?;
+                    Self::commit(&repo, tree, &[ours, theirs], "Merge stacks")?
+                }
+            }
+        };
+
+        if ours.as_ref().map(|c| c.id()) != Some(merged) {
+            Self::set_ref(&repo, merged, ours.as_ref().map(|c| c.id()))?;
+        }
+        let unpushed = theirs.as_ref().map(|c| c.id()) != Some(merged);
+
+        let commit = repo.find_commit(merged)?;
+        Ok((Self::read_commit(&repo, &commit)?, unpushed))
+    }
+
+    /// Push our stacks ref to `remote`.
+    ///
+    /// Fails if the remote ref moved since it was fetched, as the push
+    /// isn't forced; fetching and merging again then takes that in.
+    pub fn push(&self, remote: &str) -> Result<()> {
+        self.require_ref()?;
+
+        let refspec = format!("{}:{}", STACKS_REF, STACKS_REF);
+        let output = self.git(&["push", remote, &refspec])?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(GitError::Remote(stderr.to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Fail unless the metadata is kept on the stacks ref.
+    fn require_ref(&self) -> Result<()> {
+        if self.storage != StackStorage::Ref {
+            return Err(GitError::Remote(
+                "Stack metadata is stored locally; switch to ref storage to share it".to_string(),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Local ref holding the last fetched copy of `remote`'s stacks.
+    fn tracking_ref(remote: &str) -> String {
+        format!("refs/maguffin/remotes/{}/stacks", remote)
+    }
+
+    /// Take the lock serializing writers, across processes. It is released
+    /// when the returned file is dropped.
+    ///
+    /// Gives up with [`StorageError::Locked`] if another writer holds it for
+    /// longer than [`LOCK_TIMEOUT`].
+    fn lock(&self) -> Result<File> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.git_dir.join(LOCK_FILE))
+            .map_err(StorageError::Io)?;
+        let deadline = Instant::now() + LOCK_TIMEOUT;
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(file),
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    std::thread::sleep(LOCK_RETRY_INTERVAL);
+                }
+                Err(TryLockError::WouldBlock) => return Err(StorageError::Locked.into()),
+                Err(TryLockError::Error(e)) => return Err(StorageError::Io(e).into()),
+            }
+        }
+    }
+
+    /// Store `metadata` as `revision`, updating its revision on success.
+    /// Callers hold the lock.
+    fn write(&self, metadata: &mut StackMetadata, revision: u64) -> Result<()> {
+        let previous = std::mem::replace(&mut metadata.revision, revision);
+        let result = match self.storage {
+            StackStorage::File => self.write_file(metadata),
+            StackStorage::Ref => {
+                let repo = self.repository()?;
+                let parent = Self::find_commit(&repo, STACKS_REF)?;
+                let tree = Self::write_tree(&repo, metadata)?;
+                let parents: Vec<&Commit> = parent.iter().collect();
+                Self::commit(&repo, tree, &parents, "Update stacks")
+                    .and_then(|oid| Self::set_ref(&repo, oid, parent.map(|c| c.id())))
+            }
+        };
+        if result.is_err() {
+            metadata.revision = previous;
+        }
+        result
+    }
+
+    /// Write the metadata file through a temporary file renamed over it, so
+    /// readers never see a partial write.
+    fn write_file(&self, metadata: &StackMetadata) -> Result<()> {
+        let content = serde_json::to_string_pretty(metadata)
+            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+        let path = self.git_dir.join(METADATA_FILE);
+        let temp = path.with_extension("json.tmp");
+
+        // Keep a copy of metadata written by an older version before replacing it
+        if let Ok(existing) = std::fs::read(&path) {
+            if migrations::version_of(&existing).is_some_and(|v| v < migrations::latest_version()) {
+                let backup = path.with_extension("json.bak");
+                std::fs::write(&backup, existing).map_err(StorageError::Io)?;
+                tracing::info!("Backed up stack metadata to {}", backup.display());
+            }
+        }
+
+        let mut file = File::create(&temp).map_err(StorageError::Io)?;
+        file.write_all(content.as_bytes())
+            .and_then(|_| file.sync_all())
+            .and_then(|_| std::fs::rename(&temp, &path))
+            .map_err(|e| StorageError::Io(e).into())
+    }
+
+    fn repository(&self) -> Result<Repository> {
+        Repository::open(&self.git_dir)
+            .map_err(|e| GitError::RepositoryNotFound(e.to_string()).into())
+    }
+
+    /// Run the git CLI, so credential helpers apply to fetches and pushes.
+    fn git(&self, args: &[&str]) -> Result<std::process::Output> {
+        Command::new("git")
+            .arg("--git-dir")
+            .arg(&self.git_dir)
+            .args(args)
+            .output()
+            .map_err(|e| GitError::Remote(e.to_string()).into())
+    }
+
+    fn find_commit<'r>(repo: &'r Repository, refname: &str) -> Result<Option<Commit<'r>>> {
+        match repo.find_reference(refname) {
+            Ok(reference) => Ok(Some(reference.peel_to_commit()?)),
+            Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn read_commit(repo: &Repository, commit: &Commit<'_>) -> Result<StackMetadata> {
+        let entry = commit.tree()?.get_name(METADATA_FILE).map(|e| e.id());
+        let blob = repo.find_blob(entry.ok_or_else(|| {
+            StorageError::Serialization(format!("{} has no {}", commit.id(), METADATA_FILE))
+        })?)?;
+        migrations::parse(blob.content())
+    }
+
+    fn write_tree(repo: &Repository, metadata: &StackMetadata) -> Result<Oid> {
+        let content = serde_json::to_vec_pretty(metadata)
+            .map_err(|e| StorageError::Serialization(e.to_string()))?;
+        let blob = repo.blob(&content)?;
+        let mut builder = repo.treebuilder(None)?;
+        builder.insert(METADATA_FILE, blob, 0o100644)?;
+        Ok(builder.write()?)
+    }
+
+    fn commit(repo: &Repository, tree: Oid, parents: &[&Commit<'_>], message: &str) -> Result<Oid> {
+        let signature = repo
+            .signature()
+            .or_else(|_| Signature::now("Maguffin", "maguffin@localhost"))?;
+        let tree = repo.find_tree(tree)?;
+        Ok(repo.commit(None, &signature, &signature, message, &tree, parents)?)
+    }
+
+    /// Point the stacks ref at `oid`, provided it still points at `expected`.
+    fn set_ref(repo: &Repository, oid: Oid, expected: Option<Oid>) -> Result<()> {
+        match expected {
+            Some(expected) => {
+                repo.reference_matching(STACKS_REF, oid, true, expected, "maguffin: update stacks")?
+            }
+            None => repo.reference(STACKS_REF, oid, false, "maguffin: update stacks")?,
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::stack::{Stack, StackBranch};
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    /// A bare remote and two clones of it.
+    fn clones() -> (TempDir, PathBuf, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let remote = dir.path().join("remote.git");
+        git(dir.path(), &["init", "--bare", remote.to_str().unwrap()]);
+        for name in ["laptop", "desktop"] {
+            git(dir.path(), &["clone", "-q", remote.to_str().unwrap(), name]);
+        }
+        let (laptop, desktop) = (dir.path().join("laptop"), dir.path().join("desktop"));
+        (dir, laptop, desktop)
+    }
+
+    fn ref_store(path: &Path) -> StackStore {
+        let mut store = StackStore::open(path).unwrap();
+        store
+            .set_storage(StackStorage::Ref, &mut StackMetadata::default())
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn test_file_storage_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        Repository::init(dir.path()).unwrap();
+        let store = StackStore::open(dir.path()).unwrap();
+        assert_eq!(store.storage(), StackStorage::File);
+        assert!(store.load().unwrap().is_none());
+
+        let mut metadata = StackMetadata::default();
+        metadata.add_stack(Stack::new("main".to_string()));
+        store.save(&mut metadata).unwrap();
+        assert_eq!(metadata.revision, 1);
+
+        assert!(dir.path().join(".git").join(METADATA_FILE).exists());
+        assert!(!dir
+            .path()
+            .join(".git")
+            .join("stack-metadata.json.tmp")
+            .exists());
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(loaded.stacks.len(), 1);
+        assert_eq!(loaded.revision, 1);
+    }
+
+    #[test]
+    fn test_save_backs_up_older_versions() {
+        let dir = TempDir::new().unwrap();
+        Repository::init(dir.path()).unwrap();
+        let path = dir.path().join(".git").join(METADATA_FILE);
+        let original = r#"{"version": 1, "stacks": [], "last_sync": null}"#;
+        std::fs::write(&path, original).unwrap();
+
+        let store = StackStore::open(dir.path()).unwrap();
+        let mut metadata = store.load().unwrap().unwrap();
+        assert_eq!(metadata.version, migrations::latest_version());
+        metadata.add_stack(Stack::new("main".to_string()));
+        store.save(&mut metadata).unwrap();
+
+        let backup = path.with_extension("json.bak");
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), original);
+        assert_eq!(
+            migrations::version_of(&std::fs::read(&path).unwrap()),
+            Some(migrations::latest_version())
+        );
+
+        // Current files aren't backed up again
+        std::fs::remove_file(&backup).unwrap();
+        metadata.add_stack(Stack::new("develop".to_string()));
+        store.save(&mut metadata).unwrap();
+        assert!(!backup.exists());
+    }
+
+    #[test]
+    fn test_save_detects_concurrent_writes() {
+        let dir = TempDir::new().unwrap();
+        Repository::init(dir.path()).unwrap();
+        let store = StackStore::open(dir.path()).unwrap();
+        store.save(&mut StackMetadata::default()).unwrap();
+
+        // Two writers load the same revision
+        let mut first = store.load().unwrap().unwrap();
+        let mut second = first.clone();
+
+        first.add_stack(Stack::new("main".to_string()));
+        store.save(&mut first).unwrap();
+
+        second.add_stack(Stack::new("develop".to_string()));
+        let err = store.save(&mut second).unwrap_err();
+        assert!(matches!(
+            err,
+            AppError::Storage(StorageError::Conflict {
+                expected: 1,
+                found: 2
+            })
+        ));
+        assert_eq!(second.revision, 1);
+        assert_eq!(store.load().unwrap().unwrap().stacks[0].root, "main");
+
+        // Saving unchanged metadata leaves the file alone
+        let fingerprint = store.fingerprint().unwrap();
+        store.save(&mut first).unwrap();
+        assert_eq!(first.revision, 2);
+        assert_eq!(store.fingerprint().unwrap(), fingerprint);
+    }
+
+    #[test]
+    fn test_save_waits_for_lock() {
+        let dir = TempDir::new().unwrap();
+        Repository::init(dir.path()).unwrap();
+        let store = StackStore::open(dir.path()).unwrap();
+
+        // Another writer holds the lock for a moment
+        let held = store.lock().unwrap();
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            drop(held);
+        });
+
+        store.save(&mut StackMetadata::default()).unwrap();
+        release.join().unwrap();
+        assert_eq!(store.load().unwrap().unwrap().revision, 1);
+    }
+
+    #[test]
+    fn test_concurrent_saves_never_lose_revisions() {
+        let dir = TempDir::new().unwrap();
+        Repository::init(dir.path()).unwrap();
+
+        let saved: usize = std::thread::scope(|scope| {
+            let writers: Vec<_> = (0..8)
+                .map(|i| {
+                    let path = dir.path();
+                    scope.spawn(move || {
+                        let store = StackStore::open(path).unwrap();
+                        let mut metadata = store.load().unwrap().unwrap_or_default();
+                        metadata.add_stack(Stack::new(format!("root-{}", i)));
+                        store.save(&mut metadata).is_ok() as usize
+                    })
+                })
+                .collect();
+            writers.into_iter().map(|w| w.join().unwrap()).sum()
+        });
+
+        // Every successful save is one revision and kept its stack
+        let metadata = StackStore::open(dir.path())
+            .unwrap()
+            .load()
+            .unwrap()
+            .unwrap();
+        assert!(saved >= 1);
+        assert_eq!(metadata.revision, saved as u64);
+        assert_eq!(metadata.stacks.len(), saved);
+    }
+
+    #[test]
+    fn test_switch_to_ref_storage() {
+        let (_dir, laptop, _) = clones();
+        let mut metadata = StackMetadata::default();
+        metadata.add_stack(Stack::new("main".to_string()));
+
+        let mut store = StackStore::open(&laptop).unwrap();
+        store.set_storage(StackStorage::Ref, &mut metadata).unwrap();
+
+        // The choice is remembered, and the ref holds the metadata
+        let store = StackStore::open(&laptop).unwrap();
+        assert_eq!(store.storage(), StackStorage::Ref);
+        assert_eq!(store.load().unwrap().unwrap().stacks.len(), 1);
+        let repo = Repository::open(&laptop).unwrap();
+        let head = repo.find_reference(STACKS_REF).unwrap().target().unwrap();
+
+        // Saving unchanged metadata adds no commit
+        store.save(&mut metadata).unwrap();
+        assert_eq!(
+            repo.find_reference(STACKS_REF).unwrap().target(),
+            Some(head)
+        );
+
+        let refspecs = repo
+            .find_remote("origin")
+            .unwrap()
+            .fetch_refspecs()
+            .unwrap();
+        assert!(refspecs
+            .iter()
+            .any(|r| r == Some("+refs/maguffin/stacks:refs/maguffin/remotes/origin/stacks")));
+    }
+
+    #[test]
+    fn test_sync_merges_concurrent_edits() {
+        let (_dir, laptop, desktop) = clones();
+        let laptop_store = ref_store(&laptop);
+        let desktop_store = ref_store(&desktop);
+
+        // Nothing to sync yet
+        assert!(laptop_store.sync("origin").unwrap().stacks.is_empty());
+        // An unreachable remote is an error, not an empty one
+        assert!(laptop_store.fetch("missing").is_err());
+
+        // Both machines share one stack
+        let mut stack = Stack::new("main".to_string());
+        stack.add_branch(StackBranch::new(
+            "feature-a".to_string(),
+            "main".to_string(),
+        ));
+        let mut shared = laptop_store.load().unwrap().unwrap();
+        shared.add_stack(stack.clone());
+        laptop_store.save(&mut shared).unwrap();
+        laptop_store.sync("origin").unwrap();
+        let shared = desktop_store.sync("origin").unwrap();
+        assert_eq!(shared.stacks.len(), 1);
+
+        // Then edit it independently
+        let mut on_laptop = laptop_store.sync("origin").unwrap();
+        on_laptop.stacks[0]
+            .find_branch_mut("feature-a")
+            .unwrap()
+            .pr_number = Some(7);
+        laptop_store.save(&mut on_laptop).unwrap();
+
+        let mut on_desktop = shared.clone();
+        on_desktop.stacks[0].add_branch(StackBranch::new(
+            "feature-b".to_string(),
+            "feature-a".to_string(),
+        ));
+        desktop_store.save(&mut on_desktop).unwrap();
+
+        laptop_store.sync("origin").unwrap();
+        let merged = desktop_store.sync("origin").unwrap();
+        let stack = merged.find_stack(&stack.id).unwrap();
+        assert_eq!(stack.find_branch("feature-a").unwrap().pr_number, Some(7));
+        assert!(stack.find_branch("feature-b").is_some());
+
+        // The laptop picks up the merge as a fast-forward
+        let synced = laptop_store.sync("origin").unwrap();
+        assert_eq!(
+            serde_json::to_string(&synced).unwrap(),
+            serde_json::to_string(&merged).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_string(&laptop_store.load().unwrap().unwrap()).unwrap(),
+            serde_json::to_string(&merged).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sync_requires_ref_storage() {
+        let (_dir, laptop, _) = clones();
+        let store = StackStore::open(&laptop).unwrap();
+        assert!(store.sync("origin").is_err());
+    }
+}
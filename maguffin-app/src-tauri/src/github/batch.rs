@@ -0,0 +1,394 @@
+//! Request batching for GraphQL queries.
+//!
+//! Several small queries can be sent as one HTTP request by merging them into a
+//! single document: each query's root fields are aliased with a per-query
+//! prefix and its variables are renamed the same way, so they can't collide.
+//! The combined response is then split back into one response per query.
+//!
+//! Only single-operation `query` documents without fragments can be batched,
+//! which covers the documents under `graphql/`.
+
+use crate::error::{GitHubError, Result};
+use crate::github::query_scan::{scan, skip_ignored, Token};
+use graphql_client::GraphQLQuery;
+use serde_json::{Map, Value};
+
+/// Maximum number of queries combined into one request.
+///
+/// Keeps the combined document well below GitHub's node limit even when each
+/// query selects a few small connections.
+pub const MAX_BATCH_SIZE: usize = 20;
+
+/// A query waiting to be combined into a batch.
+#[derive(Debug, Clone)]
+pub struct BatchItem {
+    /// Full GraphQL document of the query
+    pub document: String,
+
+    /// Variables of the query, as a JSON object
+    pub variables: Value,
+}
+
+impl BatchItem {
+    /// Create a batch item from a generated GraphQL operation.
+    pub fn new<Q: GraphQLQuery>(variables: Q::Variables) -> Result<Self> {
+        let body = Q::build_query(variables);
+        Ok(Self {
+            document: body.query.to_string(),
+            variables: serde_json::to_value(body.variables)?,
+        })
+    }
+}
+
+/// Several queries merged into one document.
+#[derive(Debug, Clone)]
+pub struct BatchDocument {
+    /// Combined query document
+    pub query: String,
+
+    /// Combined variables
+    pub variables: Value,
+
+    /// Number of queries in the batch
+    len: usize,
+}
+
+impl BatchDocument {
+    /// Merge `items` into a single document.
+    ///
+    /// The combined document also selects `rateLimit`, so the cost of the
+    /// whole batch is reported even if none of the queries asks for it.
+    pub fn build(items: &[BatchItem]) -> Result<Self> {
+        let mut definitions = Vec::new();
+        let mut selections = Vec::new();
+        let mut variables = Map::new();
+
+        for (index, item) in items.iter().enumerate() {
+            let prefix = Self::prefix(index);
+            let operation = parse_operation(&item.document)?;
+
+            if !operation.variable_definitions.trim().is_empty() {
+                definitions.push(prefix_variables(operation.variable_definitions, &prefix));
+            }
+            selections.push(alias_root_fields(
+                &prefix_variables(operation.selection, &prefix),
+                &prefix,
+            ));
+
+            if let Value::Object(vars) = &item.variables {
+                for (name, value) in vars {
+                    variables.insert(format!("{}{}", prefix, name), value.clone());
+                }
+            }
+        }
+
+        let definitions = if definitions.is_empty() {
+            String::new()
+        } else {
+            format!("({})", definitions.join(", "))
+        };
+
+        let query = format!(
+            "query Batch{} {{\n{}\n  rateLimit {{ cost limit remaining resetAt }}\n}}\n",
+            definitions,
+            selections.join("\n")
+        );
+
+        Ok(Self {
+            query,
+            variables: Value::Object(variables),
+            len: items.len(),
+        })
+    }
+
+    /// Split the combined response data into one response per query, in the
+    /// order the queries were added.
+    pub fn split(&self, data: Value) -> Result<Vec<Value>> {
+        let Value::Object(fields) = data else {
+            return Err(GitHubError::GraphQL("Batch response is not an object".to_string()).into());
+        };
+
+        let mut responses = vec![Map::new(); self.len];
+        for (key, value) in fields {
+            let Some((index, field)) = Self::parse_alias(&key) else {
+                continue;
+            };
+            if let Some(response) = responses.get_mut(index) {
+                response.insert(field.to_string(), value);
+            }
+        }
+
+        Ok(responses.into_iter().map(Value::Object).collect())
+    }
+
+    fn prefix(index: usize) -> String {
+        format!("b{}_", index)
+    }
+
+    /// Parse an alias of the form `b<index>_<field>`.
+    fn parse_alias(alias: &str) -> Option<(usize, &str)> {
+        let rest = alias.strip_prefix('b')?;
+        let (index, field) = rest.split_once('_')?;
+        Some((index.parse().ok()?, field))
+    }
+}
+
+/// The parts of a single-operation document needed to merge it.
+struct Operation<'a> {
+    /// Text between the parentheses after the operation name
+    variable_definitions: &'a str,
+
+    /// Text between the outermost braces
+    selection: &'a str,
+}
+
+/// Split a `query` document into its variable definitions and selection set.
+fn parse_operation(document: &str) -> Result<Operation<'_>> {
+    let unsupported = |reason: &str| -> crate::error::AppError {
+        GitHubError::GraphQL(format!("Cannot batch document: {}", reason)).into()
+    };
+
+    let trimmed = skip_ignored(document);
+    if !trimmed.starts_with("query") {
+        return Err(unsupported("only queries can be batched"));
+    }
+    if contains_keyword(document, "fragment") {
+        return Err(unsupported("fragments are not supported"));
+    }
+
+    let start = document.len() - trimmed.len();
+    let open_paren = document[start..].find('(').map(|i| i + start);
+    let open_brace = document[start..]
+        .find('{')
+        .map(|i| i + start)
+        .ok_or_else(|| unsupported("missing selection set"))?;
+
+    let (variable_definitions, body_start) = match open_paren {
+        Some(open) if open < open_brace => {
+            let close = matching(document, open, '(', ')')
+                .ok_or_else(|| unsupported("unbalanced parentheses"))?;
+            let brace = document[close..]
+                .find('{')
+                .map(|i| i + close)
+                .ok_or_else(|| unsupported("missing selection set"))?;
+            (&document[open + 1..close], brace)
+        }
+        _ => ("", open_brace),
+    };
+
+    let body_end =
+        matching(document, body_start, '{', '}').ok_or_else(|| unsupported("unbalanced braces"))?;
+
+    Ok(Operation {
+        variable_definitions,
+        selection: &document[body_start + 1..body_end],
+    })
+}
+
+/// Whether `keyword` appears as a standalone word outside comments and strings.
+fn contains_keyword(text: &str, keyword: &str) -> bool {
+    let mut found = false;
+    scan(text, |token| {
+        if let Token::Name(_, name) = token {
+            found |= name == keyword;
+        }
+    });
+    found
+}
+
+/// Find the index of the delimiter closing the one at `open`.
+fn matching(text: &str, open: usize, open_char: char, close_char: char) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut result = None;
+    scan(&text[open..], |token| {
+        if let Token::Punct(index, c) = token {
+            if result.is_some() {
+                return;
+            }
+            if c == open_char {
+                depth += 1;
+            } else if c == close_char {
+                depth -= 1;
+                if depth == 0 {
+                    result = Some(open + index);
+                }
+            }
+        }
+    });
+    result
+}
+
+/// Rename every `$variable` in `text` to `$<prefix>variable`.
+fn prefix_variables(text: &str, prefix: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    scan(text, |token| {
+        if let Token::Variable(index, _) = token {
+            out.push_str(&text[last..=index]);
+            out.push_str(prefix);
+            last = index + 1;
+        }
+    });
+    out.push_str(&text[last..]);
+    out
+}
+
+/// Alias every root field of a selection set with `prefix`.
+///
+/// `repository(...) { ... }` becomes `b0_repository: repository(...) { ... }`
+/// and an existing alias `head: commits` becomes `b0_head: commits`.
+fn alias_root_fields(selection: &str, prefix: &str) -> String {
+    let mut out = String::with_capacity(selection.len() + 32);
+    let mut edits = Vec::new();
+    let mut depth = 0usize;
+    // Set when the next root-level name is not a field to alias: the field
+    // name following an alias, or a directive name
+    let mut skip_next = false;
+
+    scan(selection, |token| match token {
+        Token::Punct(_, '{' | '(') => depth += 1,
+        Token::Punct(_, '}' | ')') => depth = depth.saturating_sub(1),
+        Token::Punct(_, '@') if depth == 0 => skip_next = true,
+        Token::Name(index, name) if depth == 0 => {
+            if std::mem::take(&mut skip_next) {
+                return;
+            }
+            let is_alias = selection[index + name.len()..]
+                .trim_start()
+                .starts_with(':');
+            skip_next = is_alias;
+            edits.push((index, name, is_alias));
+        }
+        _ => {}
+    });
+
+    let mut last = 0;
+    for (index, name, is_alias) in edits {
+        out.push_str(&selection[last..index]);
+        if is_alias {
+            out.push_str(&format!("{}{}", prefix, name));
+        } else {
+            out.push_str(&format!("{}{}: {}", prefix, name, name));
+        }
+        last = index + name.len();
+    }
+    out.push_str(&selection[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::queries::{get_pull_request_ref, GetPullRequestRef};
+
+    const REF_QUERY: &str = "query Ref($owner: String!, $number: Int!) {\n  repository(owner: $owner, name: \"r\") {\n    pullRequest(number: $number) { id }\n  }\n}\n";
+
+    fn item(number: i64) -> BatchItem {
+        BatchItem {
+            document: REF_QUERY.to_string(),
+            variables: serde_json::json!({ "owner": "octo", "number": number }),
+        }
+    }
+
+    #[test]
+    fn test_build_aliases_fields_and_variables() {
+        let batch = BatchDocument::build(&[item(1), item(2)]).unwrap();
+
+        assert!(batch
+            .query
+            .starts_with("query Batch($b0_owner: String!, $b0_number: Int!, $b1_owner: String!, $b1_number: Int!)"));
+        assert!(batch
+            .query
+            .contains("b0_repository: repository(owner: $b0_owner, name: \"r\")"));
+        assert!(batch
+            .query
+            .contains("pullRequest(number: $b1_number) { id }"));
+        assert!(batch
+            .query
+            .contains("rateLimit { cost limit remaining resetAt }"));
+        assert_eq!(batch.variables["b1_number"], 2);
+        assert_eq!(batch.variables["b0_owner"], "octo");
+    }
+
+    #[test]
+    fn test_existing_alias_is_prefixed() {
+        let aliased = alias_root_fields(
+            "\n  head: commits(last: 1) { id }\n  viewer { login }\n",
+            "b3_",
+        );
+        assert_eq!(
+            aliased,
+            "\n  b3_head: commits(last: 1) { id }\n  b3_viewer: viewer { login }\n"
+        );
+    }
+
+    #[test]
+    fn test_split_response() {
+        let batch = BatchDocument::build(&[item(1), item(2)]).unwrap();
+        let responses = batch
+            .split(serde_json::json!({
+                "b0_repository": { "pullRequest": { "id": "PR_1" } },
+                "b1_repository": { "pullRequest": { "id": "PR_2" } },
+                "rateLimit": { "cost": 1 }
+            }))
+            .unwrap();
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["repository"]["pullRequest"]["id"], "PR_1");
+        assert_eq!(responses[1]["repository"]["pullRequest"]["id"], "PR_2");
+    }
+
+    #[test]
+    fn test_rejects_mutations_and_fragments() {
+        let mutation = BatchItem {
+            document: "mutation M { closePullRequest(input: {}) { clientMutationId } }".to_string(),
+            variables: Value::Null,
+        };
+        assert!(BatchDocument::build(&[mutation]).is_err());
+
+        let fragment = BatchItem {
+            document: "query Q { viewer { ...F } }\nfragment F on User { login }".to_string(),
+            variables: Value::Null,
+        };
+        assert!(BatchDocument::build(&[fragment]).is_err());
+    }
+
+    #[test]
+    fn test_generated_operation_round_trip() {
+        let items: Vec<BatchItem> = [7, 8]
+            .into_iter()
+            .map(|number| {
+                BatchItem::new::<GetPullRequestRef>(get_pull_request_ref::Variables {
+                    owner: "octo".to_string(),
+                    repo: "repo".to_string(),
+                    number,
+                })
+                .unwrap()
+            })
+            .collect();
+        let batch = BatchDocument::build(&items).unwrap();
+        assert!(batch.query.contains("pullRequest(number: $b1_number)"));
+
+        let pr = |id: &str, number: i64| {
+            serde_json::json!({ "pullRequest": {
+                "id": id,
+                "number": number,
+                "headRefName": "feature",
+                "headRefOid": "abc123",
+                "baseRefName": "main",
+                "isCrossRepository": false,
+                "state": "OPEN"
+            }})
+        };
+        let responses = batch
+            .split(serde_json::json!({
+                "b0_repository": pr("PR_7", 7),
+                "b1_repository": pr("PR_8", 8),
+            }))
+            .unwrap();
+
+        let data: get_pull_request_ref::ResponseData =
+            serde_json::from_value(responses[1].clone()).unwrap();
+        assert_eq!(data.repository.unwrap().pull_request.unwrap().id, "PR_8");
+    }
+}
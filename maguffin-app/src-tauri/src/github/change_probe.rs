@@ -0,0 +1,299 @@
+//! Conditional-request change probes.
+//!
+//! GraphQL queries always cost rate limit points, even when nothing changed.
+//! Before running them, a REST `GET` with `If-None-Match` tells us whether the
+//! underlying resource changed; GitHub does not count 304 responses against
+//! the primary rate limit. ETags and the data derived from the last full
+//! fetch are persisted in the SQLite cache so they survive restarts.
+
+use crate::cache::{Cache, CachedResponse};
+use crate::error::Result;
+use crate::github::{ConditionalResponse, GitHubClient};
+use chrono::{DateTime, Duration, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Default maximum age of cached data before a full refresh (seconds).
+///
+/// Some changes (e.g. check runs) don't touch the pull request resource and
+/// therefore don't change its ETag, so cached data is refreshed periodically
+/// regardless of what the probe says.
+pub const DEFAULT_MAX_AGE_SECS: u64 = 600;
+
+/// Result of probing a resource for changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Probe {
+    /// Resource is unchanged and the cached payload is fresh enough to reuse
+    Unchanged {
+        /// ETag the resource was revalidated against
+        etag: String,
+
+        /// Payload recorded by the last refetch
+        payload: String,
+    },
+
+    /// Resource changed (or the probe was inconclusive) and must be refetched
+    Changed {
+        /// ETag to record once the refetch succeeds
+        etag: Option<String>,
+    },
+}
+
+/// Probes GitHub REST resources with ETags to avoid redundant GraphQL queries.
+pub struct ChangeProbe {
+    client: Arc<GitHubClient>,
+    cache: Arc<Cache>,
+    max_age: Duration,
+}
+
+impl ChangeProbe {
+    /// Create a new change probe.
+    pub fn new(client: Arc<GitHubClient>, cache: Arc<Cache>) -> Self {
+        Self {
+            client,
+            cache,
+            max_age: Duration::seconds(DEFAULT_MAX_AGE_SECS as i64),
+        }
+    }
+
+    /// Set the maximum age of cached data before a full refresh.
+    pub fn with_max_age(mut self, max_age_secs: u64) -> Self {
+        self.max_age = Duration::seconds(max_age_secs as i64);
+        self
+    }
+
+    /// Probe key for the pull request list of a repository.
+    ///
+    /// The most recently updated pull request in any state is enough: opening,
+    /// pushing to, commenting on, closing or merging a PR bumps its `updated_at`
+    /// and so changes the ETag of this page.
+    pub fn pulls_key(owner: &str, repo: &str) -> String {
+        format!(
+            "repos/{}/{}/pulls?state=all&sort=updated&direction=desc&per_page=1",
+            owner, repo
+        )
+    }
+
+    /// Probe key for a single pull request.
+    pub fn pull_key(owner: &str, repo: &str, number: i64) -> String {
+        format!("repos/{}/{}/pulls/{}", owner, repo, number)
+    }
+
+    /// Probe key for the first page of a repository's labels.
+    ///
+    /// Label edits on later pages don't change its ETag; those are picked up
+    /// once the cached list reaches its maximum age.
+    pub fn labels_key(owner: &str, repo: &str) -> String {
+        format!("repos/{}/{}/labels?per_page=100", owner, repo)
+    }
+
+    /// Check whether the resource behind `key` changed since it was recorded.
+    ///
+    /// Errors are logged and treated as a change, so a failing probe never
+    /// prevents a full fetch.
+    pub async fn probe(&self, key: &str) -> Probe {
+        let cached = match self.cache.get_cached_response(key).await {
+            Ok(cached) => cached,
+            Err(e) => {
+                tracing::warn!("Failed to read cached response for {}: {}", key, e);
+                None
+            }
+        };
+
+        let etag = cached.as_ref().map(|c| c.etag.as_str());
+        match self.client.conditional_get(key, etag).await {
+            Ok(response) => Self::decide(cached, response, self.max_age, Utc::now()),
+            Err(e) => {
+                tracing::debug!("Change probe for {} failed: {}", key, e);
+                Probe::Changed { etag: None }
+            }
+        }
+    }
+
+    /// Record the ETag and derived payload after a successful refetch.
+    ///
+    /// Without an ETag there is nothing to revalidate against, so nothing is stored.
+    pub async fn record(&self, key: &str, etag: Option<&str>, payload: &str) {
+        let Some(etag) = etag else {
+            return;
+        };
+
+        if let Err(e) = self
+            .cache
+            .save_cached_response(key, etag, Some(payload))
+            .await
+        {
+            tracing::warn!("Failed to save cached response for {}: {}", key, e);
+        }
+    }
+
+    /// Probe `key` and reuse its cached payload if unchanged, otherwise run
+    /// `fetch` and record its result.
+    ///
+    /// A cached payload that no longer deserializes (e.g. after an app update
+    /// changed the type) is treated like a change.
+    pub async fn fetch_if_changed<T, F, Fut>(&self, key: &str, fetch: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let etag = match self.probe(key).await {
+            Probe::Unchanged { etag, payload } => match serde_json::from_str(&payload) {
+                Ok(value) => return Ok(value),
+                Err(e) => {
+                    tracing::debug!("Discarding unreadable cached payload for {}: {}", key, e);
+                    Some(etag)
+                }
+            },
+            Probe::Changed { etag } => etag,
+        };
+
+        let value = fetch().await?;
+        self.record(key, etag.as_deref(), &serde_json::to_string(&value)?)
+            .await;
+        Ok(value)
+    }
+
+    /// Decide whether a cached payload can be reused for a probe response.
+    fn decide(
+        cached: Option<CachedResponse>,
+        response: ConditionalResponse,
+        max_age: Duration,
+        now: DateTime<Utc>,
+    ) -> Probe {
+        match (response, cached) {
+            (ConditionalResponse::NotModified, Some(cached)) => {
+                let fresh = now - cached.updated_at < max_age;
+                match cached.payload {
+                    Some(payload) if fresh => Probe::Unchanged {
+                        etag: cached.etag,
+                        payload,
+                    },
+                    _ => Probe::Changed {
+                        etag: Some(cached.etag),
+                    },
+                }
+            }
+            (ConditionalResponse::NotModified, None) => Probe::Changed { etag: None },
+            (ConditionalResponse::Modified { etag }, _) => Probe::Changed { etag },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(payload: Option<&str>, age_secs: i64, now: DateTime<Utc>) -> CachedResponse {
+        CachedResponse {
+            etag: "\"v1\"".to_string(),
+            payload: payload.map(String::from),
+            updated_at: now - Duration::seconds(age_secs),
+        }
+    }
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(
+            ChangeProbe::pulls_key("octo", "repo"),
+            "repos/octo/repo/pulls?state=all&sort=updated&direction=desc&per_page=1"
+        );
+        assert_eq!(
+            ChangeProbe::pull_key("octo", "repo", 42),
+            "repos/octo/repo/pulls/42"
+        );
+    }
+
+    #[test]
+    fn test_not_modified_reuses_fresh_payload() {
+        let now = Utc::now();
+        let probe = ChangeProbe::decide(
+            Some(cached(Some("[]"), 10, now)),
+            ConditionalResponse::NotModified,
+            Duration::seconds(600),
+            now,
+        );
+        assert_eq!(
+            probe,
+            Probe::Unchanged {
+                etag: "\"v1\"".to_string(),
+                payload: "[]".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_not_modified_refreshes_stale_payload() {
+        let now = Utc::now();
+        let probe = ChangeProbe::decide(
+            Some(cached(Some("[]"), 601, now)),
+            ConditionalResponse::NotModified,
+            Duration::seconds(600),
+            now,
+        );
+        assert_eq!(
+            probe,
+            Probe::Changed {
+                etag: Some("\"v1\"".to_string())
+            }
+        );
+    }
+
+    #[test]
+    fn test_not_modified_without_payload_refetches() {
+        let now = Utc::now();
+        let probe = ChangeProbe::decide(
+            Some(cached(None, 10, now)),
+            ConditionalResponse::NotModified,
+            Duration::seconds(600),
+            now,
+        );
+        assert!(matches!(probe, Probe::Changed { .. }));
+    }
+
+    #[test]
+    fn test_modified_returns_new_etag() {
+        let now = Utc::now();
+        let probe = ChangeProbe::decide(
+            Some(cached(Some("[]"), 10, now)),
+            ConditionalResponse::Modified {
+                etag: Some("\"v2\"".to_string()),
+            },
+            Duration::seconds(600),
+            now,
+        );
+        assert_eq!(
+            probe,
+            Probe::Changed {
+                etag: Some("\"v2\"".to_string())
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_requires_etag() {
+        let cache = Arc::new(Cache::in_memory().unwrap());
+        let probe = ChangeProbe::new(Arc::new(GitHubClient::default()), cache.clone());
+
+        probe.record("repos/o/r/pulls/1", None, "{}").await;
+        assert!(cache
+            .get_cached_response("repos/o/r/pulls/1")
+            .await
+            .unwrap()
+            .is_none());
+
+        probe
+            .record("repos/o/r/pulls/1", Some("\"v1\""), "{}")
+            .await;
+        let stored = cache
+            .get_cached_response("repos/o/r/pulls/1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.etag, "\"v1\"");
+        assert_eq!(stored.payload.as_deref(), Some("{}"));
+    }
+}
@@ -0,0 +1,524 @@
+//! Check Run Service.
+//!
+//! This module provides the detail view of CI checks: check run output and
+//! annotations, GitHub Actions job logs (streamed to a local cache), and
+//! re-running failed jobs or whole check suites.
+
+use crate::domain::checks::{AnnotationLevel, CheckAnnotation, CheckRunDetails, JobLogChunk};
+use crate::domain::pr::{CheckConclusion, CheckRunStatus};
+use crate::error::{AppError, GitHubError, Result, StorageError};
+use crate::github::GitHubClient;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+/// Annotations per page of the REST annotations endpoint (its maximum).
+const ANNOTATIONS_PER_PAGE: usize = 50;
+
+/// Upper bound on annotation pages fetched for one check run.
+const MAX_ANNOTATION_PAGES: usize = 20;
+
+/// Cached logs beyond this size are pruned, oldest first.
+const MAX_LOG_CACHE_BYTES: u64 = 256 * 1024 * 1024;
+
+/// A check run as returned by `GET /repos/{owner}/{repo}/check-runs/{id}`.
+#[derive(Debug, Deserialize)]
+struct RestCheckRun {
+    id: i64,
+    name: String,
+    head_sha: String,
+    status: String,
+    conclusion: Option<String>,
+    html_url: Option<String>,
+    details_url: Option<String>,
+    started_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+    output: RestCheckOutput,
+    check_suite: RestId,
+    app: Option<RestApp>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestCheckOutput {
+    title: Option<String>,
+    summary: Option<String>,
+    text: Option<String>,
+    #[serde(default)]
+    annotations_count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestId {
+    id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestApp {
+    slug: Option<String>,
+}
+
+/// An annotation as returned by the check run annotations endpoint.
+#[derive(Debug, Deserialize)]
+struct RestAnnotation {
+    path: String,
+    start_line: i32,
+    end_line: i32,
+    start_column: Option<i32>,
+    end_column: Option<i32>,
+    annotation_level: Option<String>,
+    title: Option<String>,
+    message: String,
+    raw_details: Option<String>,
+}
+
+/// A workflow job as returned by `GET /repos/{owner}/{repo}/actions/jobs/{id}`.
+#[derive(Debug, Deserialize)]
+struct RestJob {
+    run_id: i64,
+}
+
+/// Service for check run details and actions.
+pub struct ChecksService {
+    client: Arc<GitHubClient>,
+    owner: String,
+    repo: String,
+}
+
+impl ChecksService {
+    /// Create a new checks service.
+    pub fn new(client: Arc<GitHubClient>, owner: String, repo: String) -> Self {
+        Self {
+            client,
+            owner,
+            repo,
+        }
+    }
+
+    /// Get a check run's output and annotations.
+    pub async fn get_check_run(&self, check_run_id: i64) -> Result<CheckRunDetails> {
+        let path = format!(
+            "repos/{}/{}/check-runs/{}",
+            self.owner, self.repo, check_run_id
+        );
+        let run: RestCheckRun = self.client.rest_get(&path).await?;
+
+        let mut annotations = Vec::new();
+        let pages = run.output.annotations_count.div_ceil(ANNOTATIONS_PER_PAGE);
+        for page in 1..=pages.min(MAX_ANNOTATION_PAGES) {
+            let path = format!(
+                "repos/{}/{}/check-runs/{}/annotations?per_page={}&page={}",
+                self.owner, self.repo, check_run_id, ANNOTATIONS_PER_PAGE, page
+            );
+            let items: Vec<RestAnnotation> = self.client.rest_get(&path).await?;
+            let last_page = items.len() < ANNOTATIONS_PER_PAGE;
+
+            annotations.extend(items.into_iter().map(Self::convert_annotation));
+
+            if last_page {
+                break;
+            }
+        }
+
+        Ok(Self::convert_check_run(run, annotations))
+    }
+
+    /// Re-run the failed jobs of the workflow run a GitHub Actions job
+    /// belongs to.
+    pub async fn rerun_failed_jobs(&self, job_id: i64) -> Result<()> {
+        let path = format!("repos/{}/{}/actions/jobs/{}", self.owner, self.repo, job_id);
+        let job: RestJob = self.client.rest_get(&path).await.map_err(|e| match e {
+            AppError::GitHub(GitHubError::NotFound(_)) => GitHubError::InvalidRequest(format!(
+                "Check run {} is not a GitHub Actions job",
+                job_id
+            ))
+            .into(),
+            e => e,
+        })?;
+
+        self.client
+            .rest_post(&format!(
+                "repos/{}/{}/actions/runs/{}/rerun-failed-jobs",
+                self.owner, self.repo, job.run_id
+            ))
+            .await
+    }
+
+    /// Ask the app behind a check suite to run its checks again.
+    pub async fn rerequest_check_suite(&self, check_suite_id: i64) -> Result<()> {
+        self.client
+            .rest_post(&format!(
+                "repos/{}/{}/check-suites/{}/rerequest",
+                self.owner, self.repo, check_suite_id
+            ))
+            .await
+    }
+
+    /// Download a GitHub Actions job log into `store`, writing it to disk as
+    /// it arrives so it can be read while the download runs.
+    ///
+    /// The outcome is recorded in the store rather than returned; the
+    /// download must have been registered with [`JobLogStore::begin_download`].
+    pub async fn download_job_log(&self, job_id: i64, store: &JobLogStore) {
+        let path = format!(
+            "repos/{}/{}/actions/jobs/{}/logs",
+            self.owner, self.repo, job_id
+        );
+
+        let result = async {
+            let mut response = self.client.rest_get_streaming(&path).await?;
+            let mut file = store.create_partial(job_id).await?;
+            while let Some(chunk) = response.chunk().await? {
+                file.write_all(&chunk).await.map_err(StorageError::from)?;
+            }
+            file.flush().await.map_err(StorageError::from)?;
+            Ok::<_, AppError>(())
+        }
+        .await;
+
+        store.finish(job_id, result).await;
+    }
+
+    /// Convert a REST check run to the domain type.
+    fn convert_check_run(run: RestCheckRun, annotations: Vec<CheckAnnotation>) -> CheckRunDetails {
+        let status = match run.status.as_str() {
+            "queued" | "waiting" | "requested" | "pending" => CheckRunStatus::Queued,
+            "in_progress" => CheckRunStatus::InProgress,
+            _ => CheckRunStatus::Completed,
+        };
+
+        let conclusion = run.conclusion.map(|c| match c.as_str() {
+            "success" => CheckConclusion::Success,
+            "failure" | "startup_failure" => CheckConclusion::Failure,
+            "cancelled" => CheckConclusion::Cancelled,
+            "skipped" => CheckConclusion::Skipped,
+            "timed_out" => CheckConclusion::TimedOut,
+            "action_required" => CheckConclusion::ActionRequired,
+            _ => CheckConclusion::Neutral,
+        });
+
+        CheckRunDetails {
+            id: run.id,
+            name: run.name,
+            status,
+            conclusion,
+            head_sha: run.head_sha,
+            check_suite_id: run.check_suite.id,
+            app: run.app.and_then(|app| app.slug),
+            html_url: run.html_url,
+            details_url: run.details_url,
+            started_at: run.started_at,
+            completed_at: run.completed_at,
+            title: run.output.title,
+            summary: run.output.summary,
+            text: run.output.text,
+            annotations,
+        }
+    }
+
+    /// Convert a REST annotation to the domain type.
+    fn convert_annotation(annotation: RestAnnotation) -> CheckAnnotation {
+        let level = match annotation.annotation_level.as_deref() {
+            Some("failure") => AnnotationLevel::Failure,
+            Some("warning") => AnnotationLevel::Warning,
+            _ => AnnotationLevel::Notice,
+        };
+
+        CheckAnnotation {
+            path: annotation.path,
+            start_line: annotation.start_line,
+            end_line: annotation.end_line,
+            start_column: annotation.start_column,
+            end_column: annotation.end_column,
+            level,
+            title: annotation.title.filter(|t| !t.is_empty()),
+            message: annotation.message,
+            raw_details: annotation.raw_details.filter(|d| !d.is_empty()),
+        }
+    }
+}
+
+/// State of a job log download.
+#[derive(Debug, Clone)]
+enum Download {
+    Running,
+    Failed(String),
+}
+
+/// Local cache of GitHub Actions job logs.
+///
+/// Logs of finished jobs never change, so a downloaded log is kept as
+/// `{job_id}.log` and served from disk from then on. While downloading, the
+/// log is written to `{job_id}.log.partial`, which can already be read.
+pub struct JobLogStore {
+    dir: PathBuf,
+    downloads: Mutex<HashMap<i64, Download>>,
+}
+
+impl JobLogStore {
+    /// Create a store keeping logs in `dir`, which is created on demand.
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            downloads: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn log_path(&self, job_id: i64) -> PathBuf {
+        self.dir.join(format!("{}.log", job_id))
+    }
+
+    fn partial_path(&self, job_id: i64) -> PathBuf {
+        self.dir.join(format!("{}.log.partial", job_id))
+    }
+
+    /// Check whether a job's log has been fully downloaded.
+    pub async fn is_cached(&self, job_id: i64) -> bool {
+        tokio::fs::try_exists(self.log_path(job_id))
+            .await
+            .unwrap_or(false)
+    }
+
+    /// Register a download of a job's log.
+    ///
+    /// Returns `false` if one is already running.
+    pub fn begin_download(&self, job_id: i64) -> bool {
+        let mut downloads = self.downloads.lock().expect("downloads lock poisoned");
+        if matches!(downloads.get(&job_id), Some(Download::Running)) {
+            return false;
+        }
+        downloads.insert(job_id, Download::Running);
+        true
+    }
+
+    /// Take the error of a failed download, so the next read retries it.
+    pub fn take_error(&self, job_id: i64) -> Option<String> {
+        let mut downloads = self.downloads.lock().expect("downloads lock poisoned");
+        match downloads.remove(&job_id) {
+            Some(Download::Failed(error)) => Some(error),
+            Some(running) => {
+                downloads.insert(job_id, running);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn is_downloading(&self, job_id: i64) -> bool {
+        matches!(
+            self.downloads
+                .lock()
+                .expect("downloads lock poisoned")
+                .get(&job_id),
+            Some(Download::Running)
+        )
+    }
+
+    async fn create_partial(&self, job_id: i64) -> Result<tokio::fs::File> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(StorageError::from)?;
+        Ok(tokio::fs::File::create(self.partial_path(job_id))
+            .await
+            .map_err(StorageError::from)?)
+    }
+
+    /// Record the outcome of a download: move the log into place, or
+    /// remember the error.
+    async fn finish(&self, job_id: i64, result: Result<()>) {
+        let result = match result {
+            Ok(()) => tokio::fs::rename(self.partial_path(job_id), self.log_path(job_id))
+                .await
+                .map_err(|e| StorageError::from(e).into()),
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(()) => {
+                self.downloads
+                    .lock()
+                    .expect("downloads lock poisoned")
+                    .remove(&job_id);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to download log of job {}: {}", job_id, e);
+                self.downloads
+                    .lock()
+                    .expect("downloads lock poisoned")
+                    .insert(job_id, Download::Failed(e.to_string()));
+            }
+        }
+
+        let _ = tokio::fs::remove_file(self.partial_path(job_id)).await;
+        if let Err(e) = self.prune(MAX_LOG_CACHE_BYTES).await {
+            tracing::warn!("Failed to prune job log cache: {}", e);
+        }
+    }
+
+    /// Read up to `limit` bytes of a job's log from `offset`.
+    ///
+    /// Reads the partial log while a download is running; a log that is
+    /// neither cached nor downloading reads as empty.
+    pub async fn read(&self, job_id: i64, offset: u64, limit: usize) -> Result<JobLogChunk> {
+        let empty = JobLogChunk::decode(job_id, offset, &[], false);
+
+        let (mut file, downloaded) = match tokio::fs::File::open(self.log_path(job_id)).await {
+            Ok(file) => (file, true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if !self.is_downloading(job_id) {
+                    return Ok(empty);
+                }
+                match tokio::fs::File::open(self.partial_path(job_id)).await {
+                    Ok(file) => (file, false),
+                    // Not created yet, or just moved into place
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(empty),
+                    Err(e) => return Err(StorageError::from(e).into()),
+                }
+            }
+            Err(e) => return Err(StorageError::from(e).into()),
+        };
+
+        let len = file.metadata().await.map_err(StorageError::from)?.len();
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .map_err(StorageError::from)?;
+        let mut bytes = Vec::new();
+        file.take(limit as u64)
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(StorageError::from)?;
+
+        let complete = downloaded && offset + bytes.len() as u64 >= len;
+        Ok(JobLogChunk::decode(job_id, offset, &bytes, complete))
+    }
+
+    /// Delete the oldest cached logs until the cache fits in `max_bytes`.
+    async fn prune(&self, max_bytes: u64) -> std::io::Result<()> {
+        let mut logs = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "log") {
+                let metadata = entry.metadata().await?;
+                logs.push((metadata.modified()?, metadata.len(), path));
+            }
+        }
+
+        let mut total: u64 = logs.iter().map(|(_, len, _)| len).sum();
+        logs.sort();
+        for (_, len, path) in logs {
+            if total <= max_bytes {
+                break;
+            }
+            tokio::fs::remove_file(&path).await?;
+            total -= len;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_rest_check_run() {
+        let run: RestCheckRun = serde_json::from_value(serde_json::json!({
+            "id": 4,
+            "name": "test (ubuntu-latest)",
+            "head_sha": "abc123",
+            "status": "completed",
+            "conclusion": "startup_failure",
+            "html_url": "https://github.com/o/r/runs/4",
+            "details_url": "https://github.com/o/r/actions/runs/9/job/4",
+            "started_at": "2025-11-27T12:00:00Z",
+            "completed_at": "2025-11-27T12:03:00Z",
+            "output": {
+                "title": "1 error",
+                "summary": "Tests failed",
+                "text": null,
+                "annotations_count": 1
+            },
+            "check_suite": { "id": 17 },
+            "app": { "slug": "github-actions" }
+        }))
+        .unwrap();
+        let annotation: RestAnnotation = serde_json::from_value(serde_json::json!({
+            "path": "src/lib.rs",
+            "start_line": 12,
+            "end_line": 12,
+            "start_column": null,
+            "end_column": null,
+            "annotation_level": "failure",
+            "title": "",
+            "message": "assertion failed",
+            "raw_details": ""
+        }))
+        .unwrap();
+
+        let details = ChecksService::convert_check_run(
+            run,
+            vec![ChecksService::convert_annotation(annotation)],
+        );
+
+        assert!(details.is_github_actions());
+        assert_eq!(details.status, CheckRunStatus::Completed);
+        assert_eq!(details.conclusion, Some(CheckConclusion::Failure));
+        assert_eq!(details.check_suite_id, 17);
+        assert_eq!(details.annotations[0].level, AnnotationLevel::Failure);
+        assert_eq!(details.annotations[0].title, None);
+        assert_eq!(details.annotations[0].raw_details, None);
+    }
+
+    #[tokio::test]
+    async fn test_read_partial_then_cached_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = JobLogStore::new(dir.path().to_path_buf());
+
+        // Nothing to read before a download starts
+        let chunk = store.read(1, 0, 1024).await.unwrap();
+        assert!(chunk.content.is_empty() && !chunk.complete);
+
+        assert!(store.begin_download(1));
+        assert!(!store.begin_download(1));
+        let mut file = store.create_partial(1).await.unwrap();
+        file.write_all(b"line 1\n").await.unwrap();
+        file.flush().await.unwrap();
+
+        let chunk = store.read(1, 0, 1024).await.unwrap();
+        assert_eq!(chunk.content, "line 1\n");
+        assert!(!chunk.complete);
+
+        file.write_all(b"line 2\n").await.unwrap();
+        file.flush().await.unwrap();
+        store.finish(1, Ok(())).await;
+
+        assert!(store.is_cached(1).await);
+        let first = store.read(1, 0, 4).await.unwrap();
+        assert_eq!(first.content, "line");
+        assert!(!first.complete);
+        let rest = store.read(1, first.next_offset, 1024).await.unwrap();
+        assert_eq!(rest.content, " 1\nline 2\n");
+        assert!(rest.complete);
+    }
+
+    #[tokio::test]
+    async fn test_failed_download_is_reported_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = JobLogStore::new(dir.path().to_path_buf());
+
+        assert!(store.begin_download(2));
+        store.create_partial(2).await.unwrap();
+        store
+            .finish(2, Err(GitHubError::NotFound("logs".to_string()).into()))
+            .await;
+
+        assert!(!store.is_cached(2).await);
+        assert!(store.take_error(2).unwrap().contains("logs"));
+        assert_eq!(store.take_error(2), None);
+        assert!(store.begin_download(2));
+    }
+}
@@ -0,0 +1,221 @@
+//! GraphQL query cost estimation and tracking.
+//!
+//! GitHub charges GraphQL queries in points rather than requests. A query's
+//! cost is the number of requests needed to fulfil every connection it
+//! selects, assuming each connection returns as many nodes as asked for,
+//! divided by 100 and rounded (minimum 1). Estimating it before sending lets
+//! the client wait for enough budget; queries that select `rateLimit` report
+//! the actual cost afterwards.
+
+use crate::domain::sync::OperationCost;
+use crate::github::query_scan::{is_name_char, scan, skip_ignored, Token};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Page size GitHub assumes for connection arguments it can't resolve.
+const DEFAULT_PAGE_SIZE: u64 = 100;
+
+/// Estimate the point cost of a GraphQL document.
+///
+/// `first`/`last` arguments given as variables are resolved from `variables`.
+pub fn estimate_cost(query: &str, variables: &Value) -> u32 {
+    // Product of the page sizes of the enclosing connections, per brace level
+    let mut multipliers: Vec<u64> = vec![1];
+    // Page size of the connection whose selection set opens next
+    let mut pending: Option<u64> = None;
+    let mut requests: u64 = 0;
+    // Progress through a `first:`/`last:` argument: its name, then the colon
+    let mut argument = Argument::None;
+
+    scan(query, |token| {
+        let size = match (argument, token) {
+            (Argument::Name, Token::Punct(_, ':')) => {
+                argument = Argument::Colon;
+                return;
+            }
+            (Argument::Colon, Token::Variable(_, name)) => Some(
+                variables
+                    .get(name)
+                    .and_then(Value::as_u64)
+                    .unwrap_or(DEFAULT_PAGE_SIZE),
+            ),
+            (Argument::Colon, Token::Number(_, digits)) => digits.parse().ok(),
+            _ => None,
+        };
+        argument = Argument::None;
+        if let Some(size) = size {
+            pending = Some(pending.map_or(size, |p: u64| p.max(size)));
+            return;
+        }
+
+        match token {
+            Token::Punct(_, '{') => {
+                let parent = *multipliers.last().unwrap_or(&1);
+                match pending.take() {
+                    Some(size) => {
+                        requests = requests.saturating_add(parent);
+                        multipliers.push(parent.saturating_mul(size));
+                    }
+                    None => multipliers.push(parent),
+                }
+            }
+            Token::Punct(_, '}') => {
+                multipliers.pop();
+            }
+            Token::Name(_, "first" | "last") => argument = Argument::Name,
+            _ => {}
+        }
+    });
+
+    let points = (requests + 50) / 100;
+    points.clamp(1, u32::MAX as u64) as u32
+}
+
+/// Position within a page size argument while scanning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Argument {
+    None,
+    Name,
+    Colon,
+}
+
+/// Extract the operation name from a GraphQL document.
+pub fn operation_name(query: &str) -> Option<&str> {
+    let rest = skip_ignored(query);
+    let rest = rest
+        .strip_prefix("query")
+        .or_else(|| rest.strip_prefix("mutation"))
+        .or_else(|| rest.strip_prefix("subscription"))?
+        .trim_start();
+    let end = rest
+        .bytes()
+        .position(|b| !is_name_char(b))
+        .unwrap_or(rest.len());
+
+    (end > 0).then(|| &rest[..end])
+}
+
+/// Per-operation query cost metrics.
+#[derive(Debug, Default)]
+pub struct CostTracker {
+    operations: Mutex<HashMap<String, OperationCost>>,
+}
+
+impl CostTracker {
+    /// Create an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a completed query.
+    ///
+    /// `actual` is the cost GitHub reported, if the query selected `rateLimit`;
+    /// otherwise the estimate is counted.
+    pub fn record(&self, operation: &str, estimated: u32, actual: Option<u32>) {
+        let cost = actual.unwrap_or(estimated);
+        let mut operations = self.operations.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = operations
+            .entry(operation.to_string())
+            .or_insert_with(|| OperationCost {
+                operation: operation.to_string(),
+                ..Default::default()
+            });
+
+        entry.calls += 1;
+        entry.total_cost += u64::from(cost);
+        entry.last_cost = cost;
+        entry.last_estimated_cost = estimated;
+        entry.max_cost = entry.max_cost.max(cost);
+    }
+
+    /// Metrics for every operation seen so far, most expensive first.
+    pub fn snapshot(&self) -> Vec<OperationCost> {
+        let operations = self.operations.lock().unwrap_or_else(|e| e.into_inner());
+        let mut metrics: Vec<OperationCost> = operations.values().cloned().collect();
+        metrics.sort_by(|a, b| {
+            b.total_cost
+                .cmp(&a.total_cost)
+                .then_with(|| a.operation.cmp(&b.operation))
+        });
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_matches_github_example() {
+        // Example from GitHub's rate limit documentation: 1 + 100 + 5000
+        // requests = 5101, which is 51 points.
+        let query = r#"
+            query {
+              viewer {
+                repositories(first: 100) {
+                  nodes {
+                    issues(first: 50) {
+                      nodes {
+                        labels(first: 60) {
+                          nodes { name }
+                        }
+                      }
+                    }
+                  }
+                }
+              }
+            }
+        "#;
+        assert_eq!(estimate_cost(query, &Value::Null), 51);
+    }
+
+    #[test]
+    fn test_estimate_resolves_variables() {
+        let query = "query Q($first: Int!) { viewer { repositories(first: $first) { nodes { pullRequests(first: 100) { nodes { commits(first: 10) { totalCount } } } } } } }";
+
+        // 1 + 50 + 50 * 100 requests
+        assert_eq!(
+            estimate_cost(query, &serde_json::json!({ "first": 50 })),
+            51
+        );
+        // Unresolved page sizes assume the maximum: 1 + 100 + 100 * 100 requests
+        assert_eq!(estimate_cost(query, &Value::Null), 101);
+    }
+
+    #[test]
+    fn test_estimate_minimum_is_one() {
+        assert_eq!(estimate_cost("query { viewer { login } }", &Value::Null), 1);
+    }
+
+    #[test]
+    fn test_operation_name() {
+        assert_eq!(
+            operation_name("# comment\nquery ListPullRequests($owner: String!) {}"),
+            Some("ListPullRequests")
+        );
+        assert_eq!(
+            operation_name("mutation ClosePullRequest {}"),
+            Some("ClosePullRequest")
+        );
+        assert_eq!(operation_name("query { viewer { login } }"), None);
+        assert_eq!(operation_name("{ viewer { login } }"), None);
+    }
+
+    #[test]
+    fn test_tracker_aggregates_per_operation() {
+        let tracker = CostTracker::new();
+        tracker.record("ListPullRequests", 1, Some(1));
+        tracker.record("ListPullRequests", 1, Some(2));
+        tracker.record("GetPullRequestRef", 1, None);
+
+        let metrics = tracker.snapshot();
+        assert_eq!(metrics[0].operation, "ListPullRequests");
+        assert_eq!(metrics[0].calls, 2);
+        assert_eq!(metrics[0].total_cost, 3);
+        assert_eq!(metrics[0].last_cost, 2);
+        assert_eq!(metrics[0].max_cost, 2);
+        assert_eq!(metrics[1].operation, "GetPullRequestRef");
+        assert_eq!(metrics[1].total_cost, 1);
+    }
+}
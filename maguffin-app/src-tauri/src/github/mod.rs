@@ -4,23 +4,43 @@
 //! It handles authentication, rate limiting, pagination, and query execution.
 
 pub mod auth_service;
+pub mod batch;
+pub mod change_probe;
+pub mod checks_service;
+pub mod cost;
 pub mod pr_service;
 pub mod queries;
+pub mod query_scan;
+pub mod review_service;
+pub mod scheduler;
 pub mod stack_service;
 pub mod sync_service;
+pub mod timeline_service;
+pub mod webhook;
 
-use crate::domain::sync::RateLimitInfo;
+use crate::domain::sync::{OperationCost, RateLimitInfo};
 use crate::error::{GitHubError, Result};
-use chrono::{TimeZone, Utc};
+use crate::github::batch::{BatchDocument, BatchItem, MAX_BATCH_SIZE};
+use crate::github::cost::CostTracker;
+use crate::github::queries::GqlRateLimit;
+use crate::github::scheduler::{Priority, RequestScheduler};
+use chrono::{DateTime, TimeZone, Utc};
+use graphql_client::GraphQLQuery;
+use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
 use tokio::time::sleep;
 
 pub use auth_service::AuthService;
+pub use change_probe::ChangeProbe;
+pub use checks_service::{ChecksService, JobLogStore};
 pub use pr_service::PrService;
+pub use review_service::ReviewService;
 pub use stack_service::StackService;
 pub use sync_service::SyncService;
+pub use timeline_service::TimelineService;
+pub use webhook::WebhookReceiver;
 
 /// Default backoff duration in seconds when rate limited
 const DEFAULT_BACKOFF_SECS: u64 = 60;
@@ -49,6 +69,7 @@ impl RateLimitState {
             remaining,
             limit,
             resets_at,
+            cost: self.info.as_ref().and_then(|i| i.cost),
         });
 
         // Reset consecutive hits if we have remaining quota
@@ -57,6 +78,27 @@ impl RateLimitState {
         }
     }
 
+    /// Update rate limit info from the GraphQL `rateLimit` object.
+    ///
+    /// This is more precise than the headers as it includes the point cost
+    /// of the query that was just executed.
+    pub fn update_from_graphql(&mut self, rate_limit: &GqlRateLimit) {
+        let resets_at = DateTime::parse_from_rfc3339(&rate_limit.reset_at)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+
+        self.info = Some(RateLimitInfo {
+            remaining: rate_limit.remaining,
+            limit: rate_limit.limit,
+            resets_at,
+            cost: Some(rate_limit.cost),
+        });
+
+        if rate_limit.remaining > 0 {
+            self.consecutive_hits = 0;
+        }
+    }
+
     /// Mark that we hit a rate limit.
     pub fn mark_rate_limited(&mut self, reset_timestamp: Option<i64>) {
         self.consecutive_hits += 1;
@@ -69,6 +111,7 @@ impl RateLimitState {
             remaining: 0,
             limit: self.info.as_ref().map(|i| i.limit).unwrap_or(5000),
             resets_at,
+            cost: self.info.as_ref().and_then(|i| i.cost),
         });
     }
 
@@ -91,9 +134,15 @@ impl RateLimitState {
 
     /// Get the duration to wait before the rate limit resets.
     pub fn wait_duration(&self) -> Option<Duration> {
+        self.wait_duration_for(1)
+    }
+
+    /// Get the duration to wait before a query costing `cost` points fits
+    /// into the remaining budget.
+    pub fn wait_duration_for(&self, cost: u32) -> Option<Duration> {
         self.info.as_ref().and_then(|info| {
             let now = Utc::now();
-            if info.remaining == 0 && info.resets_at > now {
+            if info.remaining < cost.max(1) && info.resets_at > now {
                 let secs = (info.resets_at - now).num_seconds();
                 if secs > 0 {
                     return Some(Duration::from_secs(secs as u64 + 1)); // Add 1 second buffer
@@ -104,6 +153,35 @@ impl RateLimitState {
     }
 }
 
+/// Outcome of a conditional REST request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConditionalResponse {
+    /// Server answered 304; the resource is unchanged since the given ETag
+    NotModified,
+
+    /// Resource changed (or no ETag was sent)
+    Modified {
+        /// New ETag, if the server returned one
+        etag: Option<String>,
+    },
+}
+
+/// Derive the REST API base URL from a GraphQL endpoint.
+///
+/// `https://api.github.com/graphql` maps to `https://api.github.com`, and
+/// GitHub Enterprise's `https://host/api/graphql` maps to `https://host/api/v3`.
+fn rest_base_url(graphql_endpoint: &str) -> String {
+    let endpoint = graphql_endpoint.trim_end_matches('/');
+    if let Some(base) = endpoint.strip_suffix("/api/graphql") {
+        format!("{}/api/v3", base)
+    } else {
+        endpoint
+            .strip_suffix("/graphql")
+            .unwrap_or(endpoint)
+            .to_string()
+    }
+}
+
 /// GitHub GraphQL API client.
 pub struct GitHubClient {
     /// HTTP client
@@ -115,8 +193,17 @@ pub struct GitHubClient {
     /// API endpoint
     endpoint: String,
 
+    /// REST API base URL, used for conditional change probes
+    rest_base: String,
+
     /// Rate limit state
     rate_limit: Arc<RwLock<RateLimitState>>,
+
+    /// Per-operation query cost metrics
+    costs: Arc<CostTracker>,
+
+    /// Concurrency limit and priority queue for outgoing requests
+    scheduler: Arc<RequestScheduler>,
 }
 
 impl Default for GitHubClient {
@@ -125,7 +212,10 @@ impl Default for GitHubClient {
             http: reqwest::Client::new(),
             token: Arc::new(RwLock::new(None)),
             endpoint: "https://api.github.com/graphql".to_string(),
+            rest_base: "https://api.github.com".to_string(),
             rate_limit: Arc::new(RwLock::new(RateLimitState::default())),
+            costs: Arc::new(CostTracker::new()),
+            scheduler: Arc::new(RequestScheduler::default()),
         }
     }
 }
@@ -145,11 +235,20 @@ impl GitHubClient {
         Ok(Self {
             http,
             token: Arc::new(RwLock::new(None)),
+            rest_base: rest_base_url(&endpoint),
             endpoint,
             rate_limit: Arc::new(RwLock::new(RateLimitState::default())),
+            costs: Arc::new(CostTracker::new()),
+            scheduler: Arc::new(RequestScheduler::default()),
         })
     }
 
+    /// Set the maximum number of requests in flight at once.
+    pub fn with_max_concurrent_requests(mut self, max: usize) -> Self {
+        self.scheduler = Arc::new(RequestScheduler::new(max));
+        self
+    }
+
     /// Set the access token.
     pub async fn set_token(&self, token: String) {
         let mut guard = self.token.write().await;
@@ -172,6 +271,21 @@ impl GitHubClient {
         self.rate_limit.read().await.info.clone()
     }
 
+    /// Get the accumulated query cost of every operation run so far.
+    pub fn cost_metrics(&self) -> Vec<OperationCost> {
+        self.costs.snapshot()
+    }
+
+    /// Cancel requests that are queued, backing off, or running in the
+    /// background.
+    ///
+    /// Called when the repository context changes so stale sync work doesn't
+    /// hold up requests for the new repository. In-flight user requests run
+    /// to completion.
+    pub fn cancel_pending(&self) {
+        self.scheduler.cancel_all();
+    }
+
     /// Parse rate limit headers from response.
     fn parse_rate_limit_headers(response: &reqwest::Response) -> Option<(u32, u32, i64)> {
         let headers = response.headers();
@@ -196,11 +310,76 @@ impl GitHubClient {
         Some((remaining, limit, reset))
     }
 
-    /// Wait for rate limit if needed.
-    async fn wait_for_rate_limit(&self) -> Result<()> {
+    /// Parse the `retry-after` header GitHub sends with secondary rate limits.
+    fn parse_retry_after(response: &reqwest::Response) -> Option<Duration> {
+        response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs)
+    }
+
+    /// Handle a 403/429 response, following GitHub's guidance.
+    ///
+    /// A `retry-after` header or a secondary rate limit pauses every request
+    /// for the given time (at least a minute); an exhausted primary budget is
+    /// tracked until `x-ratelimit-reset`. Other 403s are plain HTTP errors.
+    async fn rate_limit_error(&self, response: reqwest::Response) -> crate::error::AppError {
+        let status = response.status();
+        let headers = response.headers();
+        let retry_after = Self::parse_retry_after(&response);
+        let primary_exhausted = headers
+            .get("x-ratelimit-remaining")
+            .and_then(|v| v.to_str().ok())
+            == Some("0");
+        let reset_timestamp = headers
+            .get("x-ratelimit-reset")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<i64>().ok());
+
+        if retry_after.is_none() && primary_exhausted {
+            let mut state = self.rate_limit.write().await;
+            state.mark_rate_limited(reset_timestamp);
+
+            let reset_at = state
+                .info
+                .as_ref()
+                .map(|i| i.resets_at.to_rfc3339())
+                .unwrap_or_else(|| "unknown".to_string());
+
+            return GitHubError::RateLimited { reset_at }.into();
+        }
+
+        let pause = match retry_after {
+            Some(pause) => pause,
+            None => {
+                let text = response.text().await.unwrap_or_default();
+                if status == reqwest::StatusCode::FORBIDDEN
+                    && !text.to_lowercase().contains("rate limit")
+                {
+                    return GitHubError::Http(format!("{}: {}", status, text)).into();
+                }
+                Duration::from_secs(DEFAULT_BACKOFF_SECS)
+            }
+        };
+
+        tracing::warn!(
+            "Secondary rate limit hit. Pausing requests for {} seconds.",
+            pause.as_secs()
+        );
+        self.scheduler.pause_for(pause);
+
+        let reset_at =
+            (Utc::now() + chrono::Duration::from_std(pause).unwrap_or_default()).to_rfc3339();
+        GitHubError::RateLimited { reset_at }.into()
+    }
+
+    /// Wait until the remaining budget covers a query costing `cost` points.
+    async fn wait_for_rate_limit(&self, cost: u32) -> Result<()> {
         let state = self.rate_limit.read().await;
 
-        if let Some(wait_duration) = state.wait_duration() {
+        if let Some(wait_duration) = state.wait_duration_for(cost) {
             drop(state); // Release read lock before sleeping
 
             let wait_secs = wait_duration.as_secs();
@@ -217,19 +396,92 @@ impl GitHubClient {
         Ok(())
     }
 
+    /// Execute a typed GraphQL operation generated from `graphql/*.graphql`.
+    ///
+    /// Goes through [`GitHubClient::query`], so rate limit handling and
+    /// retries apply in the same way.
+    pub async fn execute<Q: GraphQLQuery>(
+        &self,
+        variables: Q::Variables,
+    ) -> Result<Q::ResponseData> {
+        let body = Q::build_query(variables);
+        self.query(body.query, serde_json::to_value(body.variables)?)
+            .await
+    }
+
+    /// Execute several queries in as few HTTP requests as possible.
+    ///
+    /// Queries are merged into aliased documents of up to
+    /// [`MAX_BATCH_SIZE`] queries each; the response data of each query is
+    /// returned in order.
+    pub async fn query_batch(&self, items: Vec<BatchItem>) -> Result<Vec<serde_json::Value>> {
+        let mut responses = Vec::with_capacity(items.len());
+
+        for chunk in items.chunks(MAX_BATCH_SIZE) {
+            let batch = BatchDocument::build(chunk)?;
+            let data: serde_json::Value = self.query(&batch.query, batch.variables.clone()).await?;
+            responses.extend(batch.split(data)?);
+        }
+
+        Ok(responses)
+    }
+
+    /// Execute the same typed operation for several sets of variables in as
+    /// few HTTP requests as possible.
+    pub async fn execute_many<Q: GraphQLQuery>(
+        &self,
+        variables: Vec<Q::Variables>,
+    ) -> Result<Vec<Q::ResponseData>> {
+        let items = variables
+            .into_iter()
+            .map(BatchItem::new::<Q>)
+            .collect::<Result<Vec<_>>>()?;
+
+        self.query_batch(items)
+            .await?
+            .into_iter()
+            .map(|data| Ok(serde_json::from_value(data)?))
+            .collect()
+    }
+
     /// Execute a GraphQL query with rate limit handling and retry.
+    ///
+    /// The query's point cost is estimated up front so it only goes out once
+    /// the remaining budget covers it, and is recorded per operation.
+    ///
+    /// Requests wait for a slot in the scheduler at the priority of the
+    /// calling task (see [`scheduler::background`]). Background requests are
+    /// cancelled outright by [`GitHubClient::cancel_pending`]; user requests
+    /// only while waiting, so a mutation is never abandoned mid-flight.
     pub async fn query<T>(&self, query: &str, variables: serde_json::Value) -> Result<T>
     where
         T: serde::de::DeserializeOwned,
     {
+        let operation = cost::operation_name(query).unwrap_or("anonymous");
+        let estimated = cost::estimate_cost(query, &variables);
+        let priority = scheduler::current_priority();
+        let generation = self.scheduler.generation();
         let mut retries = 0;
 
         loop {
             // Check and wait for rate limit before making request
-            self.wait_for_rate_limit().await?;
-
-            match self.execute_query::<T>(query, variables.clone()).await {
-                Ok(result) => return Ok(result),
+            self.scheduler
+                .cancellable(generation, self.wait_for_rate_limit(estimated))
+                .await??;
+
+            let permit = self.scheduler.acquire(priority, generation).await?;
+            let request = self.execute_query::<T>(query, variables.clone());
+            let outcome = match priority {
+                Priority::Background => self.scheduler.cancellable(generation, request).await?,
+                Priority::Interactive => request.await,
+            };
+            drop(permit);
+
+            match outcome {
+                Ok((result, actual)) => {
+                    self.costs.record(operation, estimated, actual);
+                    return Ok(result);
+                }
                 Err(e) => {
                     // Check if it's a rate limit error
                     let is_rate_limit = matches!(
@@ -240,8 +492,15 @@ impl GitHubClient {
                     if is_rate_limit && retries < MAX_RETRIES {
                         retries += 1;
 
+                        // A secondary rate limit pauses the scheduler, which
+                        // the next acquire waits out on its own.
+                        if self.scheduler.pause_remaining().is_some() {
+                            tracing::warn!("Rate limit hit. Retry {}/{}", retries, MAX_RETRIES);
+                            continue;
+                        }
+
                         let state = self.rate_limit.read().await;
-                        let backoff = state.backoff_duration();
+                        let backoff = scheduler::jittered(state.backoff_duration());
                         drop(state);
 
                         tracing::warn!(
@@ -251,7 +510,9 @@ impl GitHubClient {
                             backoff
                         );
 
-                        sleep(backoff).await;
+                        self.scheduler
+                            .cancellable(generation, sleep(backoff))
+                            .await?;
                         continue;
                     }
 
@@ -261,8 +522,198 @@ impl GitHubClient {
         }
     }
 
+    /// Issue a conditional REST `GET` for `path` (relative to the API root).
+    ///
+    /// When `etag` is given it is sent as `If-None-Match`; GitHub answers 304
+    /// without counting the request against the primary rate limit. The body
+    /// is never read, so this is only useful as a change probe.
+    ///
+    /// REST responses report the `core` rate limit bucket, which is separate
+    /// from the GraphQL budget, so the tracked rate limit state is left alone.
+    /// Probes still share the scheduler's concurrency limit and pauses.
+    pub async fn conditional_get(
+        &self,
+        path: &str,
+        etag: Option<&str>,
+    ) -> Result<ConditionalResponse> {
+        self.scheduled(self.send_conditional_get(path, etag)).await
+    }
+
+    /// Issue a REST `GET` for `path` (relative to the API root) and
+    /// deserialize the JSON body.
+    ///
+    /// Used for data the GraphQL API doesn't expose, such as file patches.
+    /// Like [`GitHubClient::conditional_get`], this counts against the `core`
+    /// bucket rather than the GraphQL budget.
+    pub async fn rest_get<T>(&self, path: &str) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.scheduled(async {
+            let response = self
+                .rest_request(reqwest::Method::GET, path, None, None)
+                .await?;
+            Ok(response.json::<T>().await?)
+        })
+        .await
+    }
+
+    /// Issue a REST `POST` without a body for `path` (relative to the API
+    /// root), for actions such as re-running checks. The response body is
+    /// ignored.
+    pub async fn rest_post(&self, path: &str) -> Result<()> {
+        self.scheduled(async {
+            self.rest_request(reqwest::Method::POST, path, None, None)
+                .await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Issue a REST `DELETE` with a JSON body for `path` (relative to the API
+    /// root), for removals the GraphQL API lacks, such as review requests.
+    /// The response body is ignored.
+    pub async fn rest_delete(&self, path: &str, body: &serde_json::Value) -> Result<()> {
+        self.scheduled(async {
+            self.rest_request(reqwest::Method::DELETE, path, None, Some(body))
+                .await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Issue a REST `GET` for `path` and return the response once its
+    /// headers arrive, for bodies too large to buffer such as job logs.
+    ///
+    /// Redirects (e.g. to log storage) are followed. Only sending the request
+    /// holds a scheduler slot; reading the body doesn't.
+    pub async fn rest_get_streaming(&self, path: &str) -> Result<reqwest::Response> {
+        self.scheduled(self.rest_request(reqwest::Method::GET, path, None, None))
+            .await
+    }
+
+    /// Run a REST request in a scheduler slot at the calling task's priority.
+    async fn scheduled<T>(&self, request: impl Future<Output = Result<T>>) -> Result<T> {
+        let priority = scheduler::current_priority();
+        let generation = self.scheduler.generation();
+        let _permit = self.scheduler.acquire(priority, generation).await?;
+
+        match priority {
+            Priority::Background => self.scheduler.cancellable(generation, request).await?,
+            Priority::Interactive => request.await,
+        }
+    }
+
+    async fn send_conditional_get(
+        &self,
+        path: &str,
+        etag: Option<&str>,
+    ) -> Result<ConditionalResponse> {
+        let response = self
+            .rest_request(reqwest::Method::GET, path, etag, None)
+            .await?;
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(ConditionalResponse::NotModified);
+        }
+
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+
+        Ok(ConditionalResponse::Modified { etag })
+    }
+
+    /// Send a REST request and map error statuses; 304 is passed through.
+    async fn rest_request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        etag: Option<&str>,
+        body: Option<&serde_json::Value>,
+    ) -> Result<reqwest::Response> {
+        let token = self.token.read().await;
+        let token = token
+            .as_ref()
+            .ok_or_else(|| GitHubError::Unauthorized("No token set".to_string()))?;
+
+        let url = format!("{}/{}", self.rest_base, path.trim_start_matches('/'));
+        let mut request = self
+            .http
+            .request(method, &url)
+            .bearer_auth(token)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json");
+        if let Some(etag) = etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+
+        if status == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(response);
+        }
+
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(GitHubError::Unauthorized("Invalid token".to_string()).into());
+        }
+
+        // A 403 is only a rate limit when GitHub says so; otherwise it's a
+        // permission error, e.g. re-running a workflow without write access
+        let retry_after = Self::parse_retry_after(&response);
+        let exhausted = response
+            .headers()
+            .get("x-ratelimit-remaining")
+            .and_then(|v| v.to_str().ok())
+            == Some("0");
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || (status == reqwest::StatusCode::FORBIDDEN && (retry_after.is_some() || exhausted))
+        {
+            if let Some(pause) = retry_after {
+                self.scheduler.pause_for(pause);
+            }
+            let reset_at = response
+                .headers()
+                .get("x-ratelimit-reset")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<i64>().ok())
+                .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+                .map(|dt| dt.to_rfc3339())
+                .unwrap_or_else(|| "unknown".to_string());
+            return Err(GitHubError::RateLimited { reset_at }.into());
+        }
+
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(GitHubError::NotFound(path.to_string()).into());
+        }
+
+        if !status.is_success() {
+            // REST errors carry a human-readable `message`
+            let message = response
+                .json::<serde_json::Value>()
+                .await
+                .ok()
+                .and_then(|body| body.get("message")?.as_str().map(String::from))
+                .unwrap_or(url);
+            return Err(GitHubError::Http(format!("{}: {}", status, message)).into());
+        }
+
+        Ok(response)
+    }
+
     /// Execute a single GraphQL query without retry logic.
-    async fn execute_query<T>(&self, query: &str, variables: serde_json::Value) -> Result<T>
+    ///
+    /// Also returns the query's point cost, if the query selected `rateLimit`.
+    async fn execute_query<T>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<(T, Option<u32>)>
     where
         T: serde::de::DeserializeOwned,
     {
@@ -294,22 +745,7 @@ impl GitHubClient {
         if response.status() == reqwest::StatusCode::FORBIDDEN
             || response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
         {
-            let reset_timestamp = response
-                .headers()
-                .get("x-ratelimit-reset")
-                .and_then(|v| v.to_str().ok())
-                .and_then(|v| v.parse::<i64>().ok());
-
-            let mut state = self.rate_limit.write().await;
-            state.mark_rate_limited(reset_timestamp);
-
-            let reset_at = state
-                .info
-                .as_ref()
-                .map(|i| i.resets_at.to_rfc3339())
-                .unwrap_or_else(|| "unknown".to_string());
-
-            return Err(GitHubError::RateLimited { reset_at }.into());
+            return Err(self.rate_limit_error(response).await);
         }
 
         if response.status() == reqwest::StatusCode::UNAUTHORIZED {
@@ -323,26 +759,78 @@ impl GitHubClient {
         }
 
         #[derive(serde::Deserialize)]
-        struct GraphQLResponse<D> {
-            data: Option<D>,
+        struct GraphQLResponse {
+            data: Option<serde_json::Value>,
             errors: Option<Vec<GraphQLError>>,
         }
 
         #[derive(serde::Deserialize)]
         struct GraphQLError {
             message: String,
+            #[serde(rename = "type")]
+            error_type: Option<String>,
+            extensions: Option<serde_json::Value>,
         }
 
-        let response: GraphQLResponse<T> = response.json().await?;
+        let reset_header = response
+            .headers()
+            .get("x-ratelimit-reset")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<i64>().ok());
+
+        let response: GraphQLResponse = response.json().await?;
 
         if let Some(errors) = response.errors {
-            let messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
-            return Err(GitHubError::GraphQL(messages.join("; ")).into());
+            // GitHub reports GraphQL rate limiting as a 200 with a typed error
+            if errors
+                .iter()
+                .any(|e| e.error_type.as_deref() == Some("RATE_LIMITED"))
+            {
+                let mut state = self.rate_limit.write().await;
+                state.mark_rate_limited(reset_header);
+                let reset_at = state
+                    .info
+                    .as_ref()
+                    .map(|i| i.resets_at.to_rfc3339())
+                    .unwrap_or_else(|| "unknown".to_string());
+                return Err(GitHubError::RateLimited { reset_at }.into());
+            }
+
+            let message = errors
+                .iter()
+                .map(|e| e.message.as_str())
+                .collect::<Vec<_>>()
+                .join("; ");
+            let code = errors.iter().find_map(|e| {
+                e.error_type.clone().or_else(|| {
+                    let code = e.extensions.as_ref()?.get("code")?.as_str()?;
+                    Some(code.to_string())
+                })
+            });
+            return Err(match code {
+                Some(code) => GitHubError::GraphQLCode { code, message },
+                None => GitHubError::GraphQL(message),
+            }
+            .into());
         }
 
-        response
+        let data = response
             .data
-            .ok_or_else(|| GitHubError::GraphQL("Empty response".to_string()).into())
+            .ok_or_else(|| GitHubError::GraphQL("Empty response".to_string()))?;
+
+        // Queries that select `rateLimit { cost limit remaining resetAt }` report
+        // the exact budget, which supersedes the header values.
+        let rate_limit = data
+            .get("rateLimit")
+            .and_then(|v| serde_json::from_value::<GqlRateLimit>(v.clone()).ok());
+        if let Some(rate_limit) = &rate_limit {
+            self.rate_limit
+                .write()
+                .await
+                .update_from_graphql(rate_limit);
+        }
+
+        Ok((serde_json::from_value(data)?, rate_limit.map(|r| r.cost)))
     }
 }
 
@@ -356,6 +844,22 @@ mod tests {
         assert!(!client.has_token().await);
     }
 
+    #[test]
+    fn test_rest_base_url() {
+        assert_eq!(
+            rest_base_url("https://api.github.com/graphql"),
+            "https://api.github.com"
+        );
+        assert_eq!(
+            rest_base_url("https://ghe.example.com/api/graphql"),
+            "https://ghe.example.com/api/v3"
+        );
+        assert_eq!(
+            rest_base_url("http://localhost:8080"),
+            "http://localhost:8080"
+        );
+    }
+
     #[tokio::test]
     async fn test_set_token() {
         let client = GitHubClient::new("https://api.github.com/graphql".to_string()).unwrap();
@@ -385,6 +889,25 @@ mod tests {
         assert_eq!(state.consecutive_hits, 0);
     }
 
+    #[test]
+    fn test_rate_limit_state_update_from_graphql() {
+        let mut state = RateLimitState::default();
+        state.mark_rate_limited(None);
+
+        state.update_from_graphql(&GqlRateLimit {
+            cost: 3,
+            limit: 5000,
+            remaining: 4200,
+            reset_at: "2030-01-01T00:00:00Z".to_string(),
+        });
+
+        let info = state.info.as_ref().unwrap();
+        assert_eq!(info.remaining, 4200);
+        assert_eq!(info.cost, Some(3));
+        assert_eq!(info.resets_at.to_rfc3339(), "2030-01-01T00:00:00+00:00");
+        assert_eq!(state.consecutive_hits, 0);
+    }
+
     #[test]
     fn test_rate_limit_state_mark_rate_limited() {
         let mut state = RateLimitState::default();
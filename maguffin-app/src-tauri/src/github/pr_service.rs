@@ -4,28 +4,54 @@
 //! bridging the domain types with the GitHub GraphQL API.
 
 use crate::domain::pr::{
-    Author, ChangeType, ChangedFile, CheckConclusion, CheckRun, CheckRunStatus, CheckState,
-    CheckStatus, Commit, Label, MergeMethod, Mergeable, PrState, PullRequest, PullRequestDetails,
-    Review, ReviewDecision, ReviewState,
+    Author, AutoMerge, BranchUpdateMethod, ChangeType, ChangedFile, CheckConclusion, CheckRun,
+    CheckRunStatus, CheckState, CheckStatus, Commit, DetailsConnection, DetailsPage,
+    DetailsPageItems, DetailsPagination, Label, MergeMethod, MergeQueueEntry, MergeQueueState,
+    Mergeable, Milestone, PageInfo, PrEdit, PrState, PullRequest, PullRequestDetails,
+    PullRequestRef, RepoLabel, Review, ReviewDecision, ReviewState,
 };
-use crate::error::{GitHubError, Result};
+use crate::error::{AppError, GitHubError, Result};
 use crate::github::queries::{
-    ClosePullRequestVariables, CreatePullRequestVariables, GetPullRequestDetailsResponse,
-    GetPullRequestDetailsVariables, GetRepositoryIdResponse, GetRepositoryIdVariables,
-    GqlCheckContext, GqlPullRequestDetails, GqlPullRequestNode, ListPullRequestsResponse,
-    ListPullRequestsVariables, MergePullRequestVariables, UpdatePullRequestVariables,
-    CLOSE_PULL_REQUEST, CREATE_PULL_REQUEST, GET_PULL_REQUEST_DETAILS, GET_REPOSITORY_ID,
-    LIST_PULL_REQUESTS, MERGE_PULL_REQUEST, UPDATE_PULL_REQUEST,
+    add_labels_to_labelable, close_pull_request, convert_pull_request_to_draft,
+    create_pull_request, dequeue_pull_request, disable_pull_request_auto_merge, edit_pull_request,
+    enable_pull_request_auto_merge, enqueue_pull_request, get_pull_request_checks as checks,
+    get_pull_request_commits as commits, get_pull_request_details as details,
+    get_pull_request_files as files, get_pull_request_ref as pr_ref,
+    get_pull_request_review_requests as review_requests, get_pull_request_reviews as reviews,
+    get_repository_id, get_team_id, get_user_id, list_labels, list_milestones,
+    list_pull_requests as list, mark_pull_request_ready_for_review, merge_pull_request,
+    remove_labels_from_labelable, reopen_pull_request, request_reviews, set_pull_request_assignees,
+    set_pull_request_milestone, update_pull_request, update_pull_request_branch,
+    AddLabelsToLabelable, ClosePullRequest, ConvertPullRequestToDraft, CreatePullRequest,
+    DequeuePullRequest, DisablePullRequestAutoMerge, EditPullRequest, EnablePullRequestAutoMerge,
+    EnqueuePullRequest, GetPullRequestChecks, GetPullRequestCommits, GetPullRequestDetails,
+    GetPullRequestFiles, GetPullRequestRef, GetPullRequestReviewRequests, GetPullRequestReviews,
+    GetRepositoryId, GetTeamId, GetUserId, ListLabels, ListMilestones, ListPullRequests,
+    MarkPullRequestReadyForReview, MergePullRequest, RemoveLabelsFromLabelable, ReopenPullRequest,
+    RequestReviews, SetPullRequestAssignees, SetPullRequestMilestone, UpdatePullRequest,
+    UpdatePullRequestBranch,
 };
-use crate::github::GitHubClient;
+use crate::github::{ChangeProbe, GitHubClient};
 use chrono::{DateTime, Utc};
+use graphql_client::GraphQLQuery;
 use std::sync::Arc;
 
+type GqlPullRequestNode = list::ListPullRequestsRepositoryPullRequestsNodes;
+type GqlPullRequestDetails = details::GetPullRequestDetailsRepositoryPullRequest;
+type GqlCheckedObject = checks::GetPullRequestChecksRepositoryObject;
+type GqlCheckContext =
+    checks::GetPullRequestChecksRepositoryObjectOnCommitStatusCheckRollupContextsNodes;
+type GqlListRequestedReviewer =
+    list::ListPullRequestsRepositoryPullRequestsNodesReviewRequestsNodesRequestedReviewer;
+type GqlRequestedReviewer =
+    review_requests::GetPullRequestReviewRequestsRepositoryPullRequestReviewRequestsNodesRequestedReviewer;
+
 /// Service for pull request operations.
 pub struct PrService {
     client: Arc<GitHubClient>,
     owner: String,
     repo: String,
+    change_probe: Option<Arc<ChangeProbe>>,
 }
 
 impl PrService {
@@ -35,16 +61,45 @@ impl PrService {
             client,
             owner,
             repo,
+            change_probe: None,
         }
     }
 
+    /// Gate list and detail queries on a conditional-request change probe.
+    ///
+    /// When set, the GraphQL queries only run if the probe reports a change;
+    /// otherwise the result of the previous query is served from the cache.
+    pub fn with_change_probe(mut self, change_probe: Arc<ChangeProbe>) -> Self {
+        self.change_probe = Some(change_probe);
+        self
+    }
+
     /// List open pull requests.
     pub async fn list_prs(&self, base_branch: Option<String>) -> Result<Vec<PullRequest>> {
+        let Some(probe) = &self.change_probe else {
+            return self.fetch_prs(base_branch).await;
+        };
+
+        // The cached payload is always the unfiltered list, so it can serve
+        // any base branch filter.
+        let key = ChangeProbe::pulls_key(&self.owner, &self.repo);
+        let prs = probe
+            .fetch_if_changed(&key, || self.fetch_prs(None))
+            .await?;
+
+        Ok(match base_branch {
+            Some(base) => prs.into_iter().filter(|pr| pr.base_ref == base).collect(),
+            None => prs,
+        })
+    }
+
+    /// Fetch open pull requests from the GraphQL API.
+    async fn fetch_prs(&self, base_branch: Option<String>) -> Result<Vec<PullRequest>> {
         let mut all_prs = Vec::new();
         let mut cursor: Option<String> = None;
 
         loop {
-            let variables = ListPullRequestsVariables {
+            let variables = list::Variables {
                 owner: self.owner.clone(),
                 repo: self.repo.clone(),
                 base_ref_name: base_branch.clone(),
@@ -52,22 +107,20 @@ impl PrService {
                 after: cursor.clone(),
             };
 
-            let response: ListPullRequestsResponse = self
-                .client
-                .query(LIST_PULL_REQUESTS, serde_json::to_value(variables)?)
-                .await?;
+            let response = self.client.execute::<ListPullRequests>(variables).await?;
 
-            let repository = response
-                .repository
-                .ok_or_else(|| GitHubError::NotFound("Repository not found".to_string()))?;
+            let repository = response.repository.ok_or_else(Self::repository_not_found)?;
 
             let connection = repository.pull_requests;
 
-            if let Some(nodes) = connection.nodes {
-                for node in nodes {
-                    all_prs.push(Self::convert_pr_node(node));
-                }
-            }
+            all_prs.extend(
+                connection
+                    .nodes
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flatten()
+                    .map(Self::convert_pr_node),
+            );
 
             if connection.page_info.has_next_page {
                 cursor = connection.page_info.end_cursor;
@@ -81,26 +134,503 @@ impl PrService {
 
     /// Get details for a specific pull request.
     pub async fn get_pr_details(&self, number: i64) -> Result<PullRequestDetails> {
-        let variables = GetPullRequestDetailsVariables {
+        match &self.change_probe {
+            Some(probe) => {
+                let key = ChangeProbe::pull_key(&self.owner, &self.repo, number);
+                probe
+                    .fetch_if_changed(&key, || self.fetch_pr_details(number))
+                    .await
+            }
+            None => self.fetch_pr_details(number).await,
+        }
+    }
+
+    /// Fetch details for a specific pull request from the GraphQL API.
+    ///
+    /// The first page of each connection is loaded in the same request; the
+    /// rest is fetched on demand with [`PrService::get_pr_details_page`].
+    async fn fetch_pr_details(&self, number: i64) -> Result<PullRequestDetails> {
+        let body = GetPullRequestDetails::build_query(details::Variables {
             owner: self.owner.clone(),
             repo: self.repo.clone(),
-            number: number as i32,
+            number,
+        });
+        let data: serde_json::Value = self
+            .client
+            .query(body.query, serde_json::to_value(body.variables)?)
+            .await?;
+
+        Self::details_from_response(number, data)
+    }
+
+    /// Convert a `GetPullRequestDetails` response into details with the first
+    /// page of each connection.
+    fn details_from_response(number: i64, data: serde_json::Value) -> Result<PullRequestDetails> {
+        // Each connection is selected exactly as in its page query, so the
+        // first pages parse as responses of those queries.
+        let commits = Self::commits_page(number, serde_json::from_value(data.clone())?)?;
+        let files = Self::files_page(number, serde_json::from_value(data.clone())?)?;
+        let reviews = Self::reviews_page(number, serde_json::from_value(data.clone())?)?;
+        let review_requests =
+            Self::review_requests_page(number, serde_json::from_value(data.clone())?)?;
+
+        // Later check pages are pinned to the head commit returned here
+        let head_commit = data
+            .pointer("/repository/pullRequest/headCommit/nodes/0/commit")
+            .cloned()
+            .unwrap_or_default();
+        let head_oid = head_commit["oid"].as_str().unwrap_or_default().to_string();
+        let (check_state, checks) =
+            Self::checks_page(serde_json::from_value(serde_json::json!({
+                "repository": { "object": head_commit },
+                "rateLimit": data["rateLimit"],
+            }))?)?;
+
+        let response: details::ResponseData = serde_json::from_value(data)?;
+        let pr = response
+            .repository
+            .ok_or_else(Self::repository_not_found)?
+            .pull_request
+            .ok_or_else(|| Self::pr_not_found(number))?;
+
+        let mut details = PullRequestDetails {
+            pr: Self::convert_pr_details(pr),
+            commits: Vec::new(),
+            files: Vec::new(),
+            reviews: Vec::new(),
+            review_requests: Vec::new(),
+            check_status: check_state.map(|state| CheckStatus {
+                state,
+                checks: Vec::new(),
+                commit_oid: head_oid,
+            }),
+            pagination: DetailsPagination::default(),
         };
+        for page in [commits, files, reviews, review_requests, checks] {
+            details.append_page(page);
+        }
+        details.pr.commit_count = details.pagination.commits.total_count;
+        details.pr.requested_reviewers = details.review_requests.clone();
+        details.pr.check_state = check_state;
+
+        Ok(details)
+    }
 
-        let response: GetPullRequestDetailsResponse = self
+    /// Fetch the page of `connection` that follows `cursor`.
+    ///
+    /// Check pages are fetched for `commit_oid`, the commit of the
+    /// [`CheckStatus`] they continue.
+    pub async fn get_pr_details_page(
+        &self,
+        number: i64,
+        connection: DetailsConnection,
+        cursor: String,
+        commit_oid: Option<String>,
+    ) -> Result<DetailsPage> {
+        let after = Some(cursor);
+        match connection {
+            DetailsConnection::Commits => self.fetch_commits(number, after).await,
+            DetailsConnection::Files => self.fetch_files(number, after).await,
+            DetailsConnection::Reviews => self.fetch_reviews(number, after).await,
+            DetailsConnection::ReviewRequests => self.fetch_review_requests(number, after).await,
+            DetailsConnection::Checks => {
+                let oid = commit_oid.ok_or_else(|| {
+                    GitHubError::InvalidRequest(
+                        "Check pages need the commit the checks belong to".to_string(),
+                    )
+                })?;
+                Ok(self.fetch_checks(oid, after).await?.1)
+            }
+        }
+    }
+
+    /// Fetch a page of commits.
+    async fn fetch_commits(&self, number: i64, after: Option<String>) -> Result<DetailsPage> {
+        let variables = commits::Variables {
+            owner: self.owner.clone(),
+            repo: self.repo.clone(),
+            number,
+            after,
+        };
+
+        let response = self
             .client
-            .query(GET_PULL_REQUEST_DETAILS, serde_json::to_value(variables)?)
+            .execute::<GetPullRequestCommits>(variables)
             .await?;
+        Self::commits_page(number, response)
+    }
 
-        let repository = response
+    /// Convert a commits response into a page.
+    fn commits_page(number: i64, response: commits::ResponseData) -> Result<DetailsPage> {
+        let connection = response
             .repository
-            .ok_or_else(|| GitHubError::NotFound("Repository not found".to_string()))?;
+            .ok_or_else(Self::repository_not_found)?
+            .pull_request
+            .ok_or_else(|| Self::pr_not_found(number))?
+            .commits;
+
+        let items = connection
+            .nodes
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .map(|c| {
+                let author = c.commit.author;
+                let authored_date = author
+                    .as_ref()
+                    .and_then(|a| a.date.as_deref())
+                    .map(Self::parse_datetime)
+                    .unwrap_or_else(Utc::now);
+
+                Commit {
+                    oid: c.commit.oid,
+                    message: c.commit.message,
+                    author_name: author
+                        .and_then(|a| a.name)
+                        .unwrap_or_else(|| "Unknown".to_string()),
+                    authored_date,
+                }
+            })
+            .collect();
+
+        Ok(DetailsPage {
+            items: DetailsPageItems::Commits(items),
+            page_info: PageInfo {
+                total_count: connection.total_count as i32,
+                has_next_page: connection.page_info.has_next_page,
+                end_cursor: connection.page_info.end_cursor,
+            },
+        })
+    }
 
-        let pr = repository
+    /// Fetch a page of changed files.
+    async fn fetch_files(&self, number: i64, after: Option<String>) -> Result<DetailsPage> {
+        let variables = files::Variables {
+            owner: self.owner.clone(),
+            repo: self.repo.clone(),
+            number,
+            after,
+        };
+
+        let response = self
+            .client
+            .execute::<GetPullRequestFiles>(variables)
+            .await?;
+        Self::files_page(number, response)
+    }
+
+    /// Convert a files response into a page.
+    fn files_page(number: i64, response: files::ResponseData) -> Result<DetailsPage> {
+        let Some(connection) = response
+            .repository
+            .ok_or_else(Self::repository_not_found)?
+            .pull_request
+            .ok_or_else(|| Self::pr_not_found(number))?
+            .files
+        else {
+            return Ok(DetailsPage {
+                items: DetailsPageItems::Files(Vec::new()),
+                page_info: PageInfo::default(),
+            });
+        };
+
+        let items = connection
+            .nodes
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .map(|f| {
+                let change_type = match f.change_type {
+                    files::PatchStatus::ADDED => ChangeType::Added,
+                    files::PatchStatus::DELETED => ChangeType::Deleted,
+                    files::PatchStatus::MODIFIED => ChangeType::Modified,
+                    files::PatchStatus::RENAMED => ChangeType::Renamed,
+                    files::PatchStatus::COPIED => ChangeType::Copied,
+                    files::PatchStatus::CHANGED | files::PatchStatus::Other(_) => {
+                        ChangeType::Changed
+                    }
+                };
+
+                ChangedFile {
+                    path: f.path,
+                    additions: f.additions as i32,
+                    deletions: f.deletions as i32,
+                    change_type,
+                }
+            })
+            .collect();
+
+        Ok(DetailsPage {
+            items: DetailsPageItems::Files(items),
+            page_info: PageInfo {
+                total_count: connection.total_count as i32,
+                has_next_page: connection.page_info.has_next_page,
+                end_cursor: connection.page_info.end_cursor,
+            },
+        })
+    }
+
+    /// Fetch a page of reviews.
+    async fn fetch_reviews(&self, number: i64, after: Option<String>) -> Result<DetailsPage> {
+        let variables = reviews::Variables {
+            owner: self.owner.clone(),
+            repo: self.repo.clone(),
+            number,
+            after,
+        };
+
+        let response = self
+            .client
+            .execute::<GetPullRequestReviews>(variables)
+            .await?;
+        Self::reviews_page(number, response)
+    }
+
+    /// Convert a reviews response into a page.
+    fn reviews_page(number: i64, response: reviews::ResponseData) -> Result<DetailsPage> {
+        let Some(connection) = response
+            .repository
+            .ok_or_else(Self::repository_not_found)?
             .pull_request
-            .ok_or_else(|| GitHubError::NotFound(format!("PR #{} not found", number)))?;
+            .ok_or_else(|| Self::pr_not_found(number))?
+            .reviews
+        else {
+            return Ok(DetailsPage {
+                items: DetailsPageItems::Reviews(Vec::new()),
+                page_info: PageInfo::default(),
+            });
+        };
+
+        let items = connection
+            .nodes
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .filter_map(|r| {
+                let author = r.author?.login;
+                let state = match r.state {
+                    reviews::PullRequestReviewState::PENDING => ReviewState::Pending,
+                    reviews::PullRequestReviewState::COMMENTED => ReviewState::Commented,
+                    reviews::PullRequestReviewState::APPROVED => ReviewState::Approved,
+                    reviews::PullRequestReviewState::CHANGES_REQUESTED => {
+                        ReviewState::ChangesRequested
+                    }
+                    reviews::PullRequestReviewState::DISMISSED => ReviewState::Dismissed,
+                    reviews::PullRequestReviewState::Other(_) => ReviewState::Pending,
+                };
+                let submitted_at = r
+                    .submitted_at
+                    .as_deref()
+                    .map(Self::parse_datetime)
+                    .unwrap_or_else(Utc::now);
 
-        Ok(Self::convert_pr_details(pr))
+                Some(Review {
+                    author,
+                    state,
+                    submitted_at,
+                })
+            })
+            .collect();
+
+        Ok(DetailsPage {
+            items: DetailsPageItems::Reviews(items),
+            page_info: PageInfo {
+                total_count: connection.total_count as i32,
+                has_next_page: connection.page_info.has_next_page,
+                end_cursor: connection.page_info.end_cursor,
+            },
+        })
+    }
+
+    /// Fetch a page of pending review requests.
+    async fn fetch_review_requests(
+        &self,
+        number: i64,
+        after: Option<String>,
+    ) -> Result<DetailsPage> {
+        let variables = review_requests::Variables {
+            owner: self.owner.clone(),
+            repo: self.repo.clone(),
+            number,
+            after,
+        };
+
+        let response = self
+            .client
+            .execute::<GetPullRequestReviewRequests>(variables)
+            .await?;
+        Self::review_requests_page(number, response)
+    }
+
+    /// Convert a review requests response into a page.
+    fn review_requests_page(
+        number: i64,
+        response: review_requests::ResponseData,
+    ) -> Result<DetailsPage> {
+        let Some(connection) = response
+            .repository
+            .ok_or_else(Self::repository_not_found)?
+            .pull_request
+            .ok_or_else(|| Self::pr_not_found(number))?
+            .review_requests
+        else {
+            return Ok(DetailsPage {
+                items: DetailsPageItems::ReviewRequests(Vec::new()),
+                page_info: PageInfo::default(),
+            });
+        };
+
+        // Teams are listed by `org/slug`
+        let items = connection
+            .nodes
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .filter_map(|r| match r.requested_reviewer? {
+                GqlRequestedReviewer::User(user) => Some(user.login),
+                GqlRequestedReviewer::Team(team) => Some(team.combined_slug),
+                _ => None,
+            })
+            .collect();
+
+        Ok(DetailsPage {
+            items: DetailsPageItems::ReviewRequests(items),
+            page_info: PageInfo {
+                total_count: connection.total_count as i32,
+                has_next_page: connection.page_info.has_next_page,
+                end_cursor: connection.page_info.end_cursor,
+            },
+        })
+    }
+
+    /// Fetch a page of check contexts for a commit, along with the overall
+    /// check state (`None` if the commit has no checks).
+    async fn fetch_checks(
+        &self,
+        oid: String,
+        after: Option<String>,
+    ) -> Result<(Option<CheckState>, DetailsPage)> {
+        let variables = checks::Variables {
+            owner: self.owner.clone(),
+            repo: self.repo.clone(),
+            oid,
+            after,
+        };
+
+        let response = self
+            .client
+            .execute::<GetPullRequestChecks>(variables)
+            .await?;
+        Self::checks_page(response)
+    }
+
+    /// Convert a checks response into a page and the overall check state.
+    fn checks_page(response: checks::ResponseData) -> Result<(Option<CheckState>, DetailsPage)> {
+        let rollup = response
+            .repository
+            .ok_or_else(Self::repository_not_found)?
+            .object
+            .and_then(|object| match object {
+                GqlCheckedObject::Commit(commit) => commit.status_check_rollup,
+            });
+
+        let Some(rollup) = rollup else {
+            return Ok((
+                None,
+                DetailsPage {
+                    items: DetailsPageItems::Checks(Vec::new()),
+                    page_info: PageInfo::default(),
+                },
+            ));
+        };
+
+        let state = match rollup.state {
+            checks::StatusState::SUCCESS => CheckState::Success,
+            checks::StatusState::PENDING | checks::StatusState::EXPECTED => CheckState::Pending,
+            checks::StatusState::FAILURE | checks::StatusState::ERROR => CheckState::Failure,
+            checks::StatusState::Other(_) => CheckState::Unknown,
+        };
+
+        let connection = rollup.contexts;
+        let items = connection
+            .nodes
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .map(Self::convert_check_context)
+            .collect();
+
+        Ok((
+            Some(state),
+            DetailsPage {
+                items: DetailsPageItems::Checks(items),
+                page_info: PageInfo {
+                    total_count: connection.total_count as i32,
+                    has_next_page: connection.page_info.has_next_page,
+                    end_cursor: connection.page_info.end_cursor,
+                },
+            },
+        ))
+    }
+
+    /// Look up a PR's node ID and branches by number.
+    ///
+    /// Much cheaper than [`PrService::get_pr_details`] when a PR only needs
+    /// to be addressed, e.g. to retarget its base.
+    pub async fn get_pr_ref(&self, number: i64) -> Result<PullRequestRef> {
+        self.get_pr_refs(&[number])
+            .await?
+            .pop()
+            .ok_or_else(|| Self::pr_not_found(number).into())
+    }
+
+    /// Look up several PRs by number in a single batched request.
+    ///
+    /// Refs are returned in the order of `numbers`; a PR that doesn't exist
+    /// fails the whole lookup.
+    pub async fn get_pr_refs(&self, numbers: &[i64]) -> Result<Vec<PullRequestRef>> {
+        let variables = numbers
+            .iter()
+            .map(|&number| pr_ref::Variables {
+                owner: self.owner.clone(),
+                repo: self.repo.clone(),
+                number,
+            })
+            .collect();
+
+        let responses = self
+            .client
+            .execute_many::<GetPullRequestRef>(variables)
+            .await?;
+
+        numbers
+            .iter()
+            .zip(responses)
+            .map(|(&number, response)| {
+                let pr = response
+                    .repository
+                    .ok_or_else(Self::repository_not_found)?
+                    .pull_request
+                    .ok_or_else(|| Self::pr_not_found(number))?;
+
+                let state = match pr.state {
+                    pr_ref::PullRequestState::OPEN => PrState::Open,
+                    pr_ref::PullRequestState::CLOSED => PrState::Closed,
+                    pr_ref::PullRequestState::MERGED => PrState::Merged,
+                    pr_ref::PullRequestState::Other(_) => PrState::Open,
+                };
+
+                Ok(PullRequestRef {
+                    id: pr.id,
+                    number: pr.number,
+                    head_ref: pr.head_ref_name,
+                    head_oid: pr.head_ref_oid,
+                    base_ref: pr.base_ref_name,
+                    is_cross_repository: pr.is_cross_repository,
+                    state,
+                })
+            })
+            .collect()
     }
 
     /// Create a new pull request.
@@ -115,7 +645,7 @@ impl PrService {
         // First, get the repository ID
         let repo_id = self.get_repository_id().await?;
 
-        let variables = CreatePullRequestVariables {
+        let variables = create_pull_request::Variables {
             repository_id: repo_id,
             base_ref_name: base,
             head_ref_name: head,
@@ -124,13 +654,12 @@ impl PrService {
             draft: Some(draft),
         };
 
-        let response: serde_json::Value = self
-            .client
-            .query(CREATE_PULL_REQUEST, serde_json::to_value(variables)?)
-            .await?;
+        let response = self.client.execute::<CreatePullRequest>(variables).await?;
 
-        let number = response["createPullRequest"]["pullRequest"]["number"]
-            .as_i64()
+        let number = response
+            .create_pull_request
+            .and_then(|payload| payload.pull_request)
+            .map(|pr| pr.number)
             .ok_or_else(|| GitHubError::GraphQL("Failed to get PR number".to_string()))?;
 
         Ok(number)
@@ -139,85 +668,572 @@ impl PrService {
     /// Merge a pull request.
     pub async fn merge_pr(&self, pr_id: String, method: MergeMethod) -> Result<bool> {
         let merge_method = match method {
-            MergeMethod::Merge => "MERGE",
-            MergeMethod::Squash => "SQUASH",
-            MergeMethod::Rebase => "REBASE",
+            MergeMethod::Merge => merge_pull_request::PullRequestMergeMethod::MERGE,
+            MergeMethod::Squash => merge_pull_request::PullRequestMergeMethod::SQUASH,
+            MergeMethod::Rebase => merge_pull_request::PullRequestMergeMethod::REBASE,
+        };
+
+        let variables = merge_pull_request::Variables {
+            pull_request_id: pr_id,
+            merge_method: Some(merge_method),
+        };
+
+        let response = self.client.execute::<MergePullRequest>(variables).await?;
+
+        let merged = response
+            .merge_pull_request
+            .and_then(|payload| payload.pull_request)
+            .is_some_and(|pr| pr.merged);
+
+        Ok(merged)
+    }
+
+    /// Enable auto-merge, merging the PR with `method` once its required
+    /// reviews and checks pass.
+    ///
+    /// On repositories with a merge queue, the PR is queued at that point
+    /// instead.
+    pub async fn enable_auto_merge(&self, pr_id: String, method: MergeMethod) -> Result<AutoMerge> {
+        let merge_method = match method {
+            MergeMethod::Merge => enable_pull_request_auto_merge::PullRequestMergeMethod::MERGE,
+            MergeMethod::Squash => enable_pull_request_auto_merge::PullRequestMergeMethod::SQUASH,
+            MergeMethod::Rebase => enable_pull_request_auto_merge::PullRequestMergeMethod::REBASE,
         };
 
-        let variables = MergePullRequestVariables {
+        let variables = enable_pull_request_auto_merge::Variables {
             pull_request_id: pr_id,
-            merge_method: Some(merge_method.to_string()),
+            merge_method: Some(merge_method),
         };
 
-        let response: serde_json::Value = self
+        let response = self
             .client
-            .query(MERGE_PULL_REQUEST, serde_json::to_value(variables)?)
+            .execute::<EnablePullRequestAutoMerge>(variables)
             .await?;
 
-        let merged = response["mergePullRequest"]["pullRequest"]["merged"]
-            .as_bool()
-            .unwrap_or(false);
+        let request = response
+            .enable_pull_request_auto_merge
+            .and_then(|payload| payload.pull_request)
+            .and_then(|pr| pr.auto_merge_request)
+            .ok_or_else(|| GitHubError::GraphQL("Failed to enable auto-merge".to_string()))?;
 
-        Ok(merged)
+        Ok(AutoMerge {
+            merge_method: match request.merge_method {
+                enable_pull_request_auto_merge::PullRequestMergeMethod::SQUASH => {
+                    MergeMethod::Squash
+                }
+                enable_pull_request_auto_merge::PullRequestMergeMethod::REBASE => {
+                    MergeMethod::Rebase
+                }
+                _ => MergeMethod::Merge,
+            },
+            enabled_at: request.enabled_at.as_deref().map(Self::parse_datetime),
+            enabled_by: request.enabled_by.map(|actor| actor.login),
+        })
     }
 
-    /// Close a pull request without merging.
-    pub async fn close_pr(&self, pr_id: String) -> Result<bool> {
-        let variables = ClosePullRequestVariables {
+    /// Disable auto-merge. Returns whether auto-merge is now off.
+    pub async fn disable_auto_merge(&self, pr_id: String) -> Result<bool> {
+        let variables = disable_pull_request_auto_merge::Variables {
+            pull_request_id: pr_id,
+        };
+
+        let response = self
+            .client
+            .execute::<DisablePullRequestAutoMerge>(variables)
+            .await?;
+
+        let disabled = response
+            .disable_pull_request_auto_merge
+            .and_then(|payload| payload.pull_request)
+            .is_some_and(|pr| pr.auto_merge_request.is_none());
+
+        Ok(disabled)
+    }
+
+    /// Add a PR to the merge queue.
+    ///
+    /// With `jump`, the PR is put at the front of the queue, which requires
+    /// admin rights.
+    pub async fn enqueue_pr(&self, pr_id: String, jump: bool) -> Result<MergeQueueEntry> {
+        let variables = enqueue_pull_request::Variables {
+            pull_request_id: pr_id,
+            jump: Some(jump),
+        };
+
+        let response = self.client.execute::<EnqueuePullRequest>(variables).await?;
+
+        let entry = response
+            .enqueue_pull_request
+            .and_then(|payload| payload.merge_queue_entry)
+            .ok_or_else(|| GitHubError::GraphQL("Failed to enqueue pull request".to_string()))?;
+
+        Ok(MergeQueueEntry {
+            position: entry.position as i32,
+            state: match entry.state {
+                enqueue_pull_request::MergeQueueEntryState::QUEUED => MergeQueueState::Queued,
+                enqueue_pull_request::MergeQueueEntryState::AWAITING_CHECKS => {
+                    MergeQueueState::AwaitingChecks
+                }
+                enqueue_pull_request::MergeQueueEntryState::MERGEABLE => MergeQueueState::Mergeable,
+                enqueue_pull_request::MergeQueueEntryState::UNMERGEABLE => {
+                    MergeQueueState::Unmergeable
+                }
+                enqueue_pull_request::MergeQueueEntryState::LOCKED => MergeQueueState::Locked,
+                enqueue_pull_request::MergeQueueEntryState::Other(_) => MergeQueueState::Queued,
+            },
+            enqueued_at: Self::parse_datetime(&entry.enqueued_at),
+            estimated_time_to_merge: entry.estimated_time_to_merge.map(|secs| secs as i32),
+        })
+    }
+
+    /// Remove a PR from the merge queue. Returns whether it was queued.
+    pub async fn dequeue_pr(&self, pr_id: String) -> Result<bool> {
+        let variables = dequeue_pull_request::Variables {
+            pull_request_id: pr_id,
+        };
+
+        let response = self.client.execute::<DequeuePullRequest>(variables).await?;
+
+        let dequeued = response
+            .dequeue_pull_request
+            .is_some_and(|payload| payload.merge_queue_entry.is_some());
+
+        Ok(dequeued)
+    }
+
+    /// Bring a PR branch up to date with its base.
+    ///
+    /// With `expected_head_oid`, the update is refused if the branch has
+    /// moved since it was read. Returns the new head SHA.
+    pub async fn update_branch(
+        &self,
+        pr_id: String,
+        method: BranchUpdateMethod,
+        expected_head_oid: Option<String>,
+    ) -> Result<String> {
+        let update_method = match method {
+            BranchUpdateMethod::Merge => {
+                update_pull_request_branch::PullRequestBranchUpdateMethod::MERGE
+            }
+            BranchUpdateMethod::Rebase => {
+                update_pull_request_branch::PullRequestBranchUpdateMethod::REBASE
+            }
+        };
+
+        let variables = update_pull_request_branch::Variables {
             pull_request_id: pr_id,
+            expected_head_oid,
+            update_method: Some(update_method),
         };
 
-        let response: serde_json::Value = self
+        let response = self
             .client
-            .query(CLOSE_PULL_REQUEST, serde_json::to_value(variables)?)
+            .execute::<UpdatePullRequestBranch>(variables)
             .await?;
 
-        let state = response["closePullRequest"]["pullRequest"]["state"]
-            .as_str()
-            .unwrap_or("");
+        response
+            .update_pull_request_branch
+            .and_then(|payload| payload.pull_request)
+            .map(|pr| pr.head_ref_oid)
+            .ok_or_else(|| GitHubError::GraphQL("Failed to update PR branch".to_string()).into())
+    }
+
+    /// Whether a failed [`PrService::update_branch`] means GitHub can't
+    /// perform the update method at all, rather than that this update failed.
+    ///
+    /// Older GitHub Enterprise Server versions reject the `updateMethod`
+    /// argument and its type, and repositories can disallow rebase updates,
+    /// which GitHub reports as `UNPROCESSABLE`. A local rebase checks the
+    /// head and conflicts again, so other unprocessable updates fail there.
+    pub fn is_update_method_unavailable(error: &AppError) -> bool {
+        matches!(
+            error,
+            AppError::GitHub(GitHubError::GraphQLCode { code, .. })
+                if matches!(
+                    code.as_str(),
+                    "argumentNotAccepted" | "variableRequiresValidType" | "UNPROCESSABLE"
+                )
+        )
+    }
+
+    /// List the repository's labels.
+    ///
+    /// Gated on the change probe when one is set, so resolving label names
+    /// for edits usually reuses the cached list.
+    pub async fn list_labels(&self) -> Result<Vec<RepoLabel>> {
+        match &self.change_probe {
+            Some(probe) => {
+                let key = ChangeProbe::labels_key(&self.owner, &self.repo);
+                probe.fetch_if_changed(&key, || self.fetch_labels()).await
+            }
+            None => self.fetch_labels().await,
+        }
+    }
+
+    /// Fetch the repository's labels from the GraphQL API.
+    async fn fetch_labels(&self) -> Result<Vec<RepoLabel>> {
+        let mut labels = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let variables = list_labels::Variables {
+                owner: self.owner.clone(),
+                repo: self.repo.clone(),
+                after: cursor.clone(),
+            };
+
+            let response = self.client.execute::<ListLabels>(variables).await?;
+            let Some(connection) = response
+                .repository
+                .ok_or_else(Self::repository_not_found)?
+                .labels
+            else {
+                break;
+            };
+
+            labels.extend(
+                connection
+                    .nodes
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flatten()
+                    .map(|label| RepoLabel {
+                        id: label.id,
+                        name: label.name,
+                        color: label.color,
+                        description: label.description,
+                    }),
+            );
 
-        Ok(state == "CLOSED")
+            if connection.page_info.has_next_page {
+                cursor = connection.page_info.end_cursor;
+            } else {
+                break;
+            }
+        }
+
+        Ok(labels)
+    }
+
+    /// List the repository's open milestones.
+    pub async fn list_milestones(&self) -> Result<Vec<Milestone>> {
+        let mut milestones = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let variables = list_milestones::Variables {
+                owner: self.owner.clone(),
+                repo: self.repo.clone(),
+                after: cursor.clone(),
+            };
+
+            let response = self.client.execute::<ListMilestones>(variables).await?;
+            let Some(connection) = response
+                .repository
+                .ok_or_else(Self::repository_not_found)?
+                .milestones
+            else {
+                break;
+            };
+
+            milestones.extend(
+                connection
+                    .nodes
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flatten()
+                    .map(|m| Milestone {
+                        id: m.id,
+                        number: m.number,
+                        title: m.title,
+                        due_on: m.due_on.as_deref().map(Self::parse_datetime),
+                    }),
+            );
+
+            if connection.page_info.has_next_page {
+                cursor = connection.page_info.end_cursor;
+            } else {
+                break;
+            }
+        }
+
+        Ok(milestones)
+    }
+
+    /// Apply an edit to a pull request.
+    ///
+    /// Label names are resolved against `labels` and milestone numbers
+    /// against `milestones` (see [`PrService::list_labels`] and
+    /// [`PrService::list_milestones`]); user logins and team slugs are
+    /// looked up in one batched request.
+    pub async fn edit_pr(
+        &self,
+        pr: &PullRequestRef,
+        edit: &PrEdit,
+        labels: &[RepoLabel],
+        milestones: &[Milestone],
+    ) -> Result<()> {
+        let pr_id = pr.id.clone();
+        match edit {
+            PrEdit::Title { title } => {
+                self.client
+                    .execute::<EditPullRequest>(edit_pull_request::Variables {
+                        pull_request_id: pr_id,
+                        title: Some(title.clone()),
+                        body: None,
+                    })
+                    .await?;
+            }
+            PrEdit::Body { body } => {
+                self.client
+                    .execute::<EditPullRequest>(edit_pull_request::Variables {
+                        pull_request_id: pr_id,
+                        title: None,
+                        body: Some(body.clone()),
+                    })
+                    .await?;
+            }
+            PrEdit::Draft { is_draft: true } => {
+                self.client
+                    .execute::<ConvertPullRequestToDraft>(
+                        convert_pull_request_to_draft::Variables {
+                            pull_request_id: pr_id,
+                        },
+                    )
+                    .await?;
+            }
+            PrEdit::Draft { is_draft: false } => {
+                self.client
+                    .execute::<MarkPullRequestReadyForReview>(
+                        mark_pull_request_ready_for_review::Variables {
+                            pull_request_id: pr_id,
+                        },
+                    )
+                    .await?;
+            }
+            PrEdit::AddLabels { labels: names } => {
+                self.client
+                    .execute::<AddLabelsToLabelable>(add_labels_to_labelable::Variables {
+                        labelable_id: pr_id,
+                        label_ids: Self::label_ids(labels, names)?,
+                    })
+                    .await?;
+            }
+            PrEdit::RemoveLabels { labels: names } => {
+                self.client
+                    .execute::<RemoveLabelsFromLabelable>(remove_labels_from_labelable::Variables {
+                        labelable_id: pr_id,
+                        label_ids: Self::label_ids(labels, names)?,
+                    })
+                    .await?;
+            }
+            PrEdit::RequestReviewers { users, teams } => {
+                let (user_ids, team_ids) =
+                    tokio::try_join!(self.user_ids(users), self.team_ids(teams))?;
+                self.client
+                    .execute::<RequestReviews>(request_reviews::Variables {
+                        pull_request_id: pr_id,
+                        user_ids: Some(user_ids),
+                        team_ids: Some(team_ids),
+                    })
+                    .await?;
+            }
+            PrEdit::RemoveReviewers { users, teams } => {
+                // The GraphQL API can only add review requests
+                let path = format!(
+                    "repos/{}/{}/pulls/{}/requested_reviewers",
+                    self.owner, self.repo, pr.number
+                );
+                let body = serde_json::json!({ "reviewers": users, "team_reviewers": teams });
+                self.client.rest_delete(&path, &body).await?;
+            }
+            PrEdit::SetAssignees { assignees } => {
+                self.client
+                    .execute::<SetPullRequestAssignees>(set_pull_request_assignees::Variables {
+                        pull_request_id: pr_id,
+                        assignee_ids: self.user_ids(assignees).await?,
+                    })
+                    .await?;
+            }
+            PrEdit::SetMilestone { milestone } => {
+                let milestone_id = match milestone {
+                    Some(number) => Some(
+                        milestones
+                            .iter()
+                            .find(|m| m.number == *number)
+                            .map(|m| m.id.clone())
+                            .ok_or_else(|| {
+                                GitHubError::InvalidRequest(format!(
+                                    "Unknown milestone: #{}",
+                                    number
+                                ))
+                            })?,
+                    ),
+                    None => None,
+                };
+                self.client
+                    .execute::<SetPullRequestMilestone>(set_pull_request_milestone::Variables {
+                        pull_request_id: pr_id,
+                        milestone_id,
+                    })
+                    .await?;
+            }
+            PrEdit::Reopen => {
+                self.client
+                    .execute::<ReopenPullRequest>(reopen_pull_request::Variables {
+                        pull_request_id: pr_id,
+                    })
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolve label names to node IDs.
+    fn label_ids(labels: &[RepoLabel], names: &[String]) -> Result<Vec<String>> {
+        names
+            .iter()
+            .map(|name| {
+                labels
+                    .iter()
+                    .find(|label| &label.name == name)
+                    .map(|label| label.id.clone())
+                    .ok_or_else(|| {
+                        GitHubError::InvalidRequest(format!("Unknown label: {}", name)).into()
+                    })
+            })
+            .collect()
+    }
+
+    /// Resolve user logins to node IDs in a single batched request.
+    async fn user_ids(&self, logins: &[String]) -> Result<Vec<String>> {
+        if logins.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let variables = logins
+            .iter()
+            .map(|login| get_user_id::Variables {
+                login: login.clone(),
+            })
+            .collect();
+        let responses = self.client.execute_many::<GetUserId>(variables).await?;
+
+        logins
+            .iter()
+            .zip(responses)
+            .map(|(login, response)| {
+                response
+                    .user
+                    .map(|user| user.id)
+                    .ok_or_else(|| GitHubError::NotFound(format!("User {}", login)).into())
+            })
+            .collect()
+    }
+
+    /// Resolve team slugs of the repository owner to node IDs in a single
+    /// batched request.
+    async fn team_ids(&self, slugs: &[String]) -> Result<Vec<String>> {
+        if slugs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let variables = slugs
+            .iter()
+            .map(|slug| get_team_id::Variables {
+                org: self.owner.clone(),
+                slug: slug.clone(),
+            })
+            .collect();
+        let responses = self.client.execute_many::<GetTeamId>(variables).await?;
+
+        slugs
+            .iter()
+            .zip(responses)
+            .map(|(slug, response)| {
+                response
+                    .organization
+                    .and_then(|org| org.team)
+                    .map(|team| team.id)
+                    .ok_or_else(|| {
+                        GitHubError::NotFound(format!("Team {}/{}", self.owner, slug)).into()
+                    })
+            })
+            .collect()
+    }
+
+    /// Close a pull request without merging.
+    pub async fn close_pr(&self, pr_id: String) -> Result<bool> {
+        let variables = close_pull_request::Variables {
+            pull_request_id: pr_id,
+        };
+
+        let response = self.client.execute::<ClosePullRequest>(variables).await?;
+
+        let closed = response
+            .close_pull_request
+            .and_then(|payload| payload.pull_request)
+            .is_some_and(|pr| matches!(pr.state, close_pull_request::PullRequestState::CLOSED));
+
+        Ok(closed)
     }
 
     /// Update a pull request's base branch.
     /// This is used after a parent PR merges to update child PR to target the new base.
     pub async fn update_pr_base(&self, pr_id: String, new_base: String) -> Result<bool> {
-        let variables = UpdatePullRequestVariables {
+        let variables = update_pull_request::Variables {
             pull_request_id: pr_id,
             base_ref_name: Some(new_base.clone()),
         };
 
-        let response: serde_json::Value = self
-            .client
-            .query(UPDATE_PULL_REQUEST, serde_json::to_value(variables)?)
-            .await?;
+        let response = self.client.execute::<UpdatePullRequest>(variables).await?;
 
-        let base_ref = response["updatePullRequest"]["pullRequest"]["baseRefName"]
-            .as_str()
-            .unwrap_or("");
+        let updated = response
+            .update_pull_request
+            .and_then(|payload| payload.pull_request)
+            .is_some_and(|pr| pr.base_ref_name == new_base);
 
-        Ok(base_ref == new_base)
+        Ok(updated)
     }
 
     /// Get the repository ID.
     async fn get_repository_id(&self) -> Result<String> {
-        let variables = GetRepositoryIdVariables {
+        let variables = get_repository_id::Variables {
             owner: self.owner.clone(),
             repo: self.repo.clone(),
         };
 
-        let response: GetRepositoryIdResponse = self
-            .client
-            .query(GET_REPOSITORY_ID, serde_json::to_value(variables)?)
-            .await?;
+        let response = self.client.execute::<GetRepositoryId>(variables).await?;
 
-        let repository = response
-            .repository
-            .ok_or_else(|| GitHubError::NotFound("Repository not found".to_string()))?;
+        let repository = response.repository.ok_or_else(Self::repository_not_found)?;
 
         Ok(repository.id)
     }
 
+    /// Parse a GraphQL `DateTime` scalar, falling back to now.
+    pub(crate) fn parse_datetime(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now())
+    }
+
+    /// Author shown for pull requests whose author account was deleted.
+    pub(crate) fn ghost() -> Author {
+        Author {
+            login: "ghost".to_string(),
+            avatar_url: String::new(),
+        }
+    }
+
+    pub(crate) fn repository_not_found() -> GitHubError {
+        GitHubError::NotFound("Repository not found".to_string())
+    }
+
+    pub(crate) fn pr_not_found(number: i64) -> GitHubError {
+        GitHubError::NotFound(format!("PR #{} not found", number))
+    }
+
     /// Convert a GraphQL PR node to domain type.
     fn convert_pr_node(node: GqlPullRequestNode) -> PullRequest {
         let author = node
@@ -226,55 +1242,115 @@ impl PrService {
                 login: a.login,
                 avatar_url: a.avatar_url,
             })
-            .unwrap_or(Author {
-                login: "ghost".to_string(),
-                avatar_url: String::new(),
-            });
+            .unwrap_or_else(Self::ghost);
 
         let labels: Vec<Label> = node
             .labels
-            .nodes
+            .and_then(|labels| labels.nodes)
             .unwrap_or_default()
             .into_iter()
+            .flatten()
             .map(|l| Label {
                 name: l.name,
                 color: l.color,
             })
             .collect();
 
-        let state = match node.state.as_str() {
-            "OPEN" => PrState::Open,
-            "CLOSED" => PrState::Closed,
-            "MERGED" => PrState::Merged,
-            _ => PrState::Open,
+        let state = match node.state {
+            list::PullRequestState::OPEN => PrState::Open,
+            list::PullRequestState::CLOSED => PrState::Closed,
+            list::PullRequestState::MERGED => PrState::Merged,
+            list::PullRequestState::Other(_) => PrState::Open,
         };
 
-        let review_decision = node.review_decision.as_deref().map(|d| match d {
-            "APPROVED" => ReviewDecision::Approved,
-            "CHANGES_REQUESTED" => ReviewDecision::ChangesRequested,
-            "REVIEW_REQUIRED" => ReviewDecision::ReviewRequired,
-            _ => ReviewDecision::ReviewRequired,
+        let review_decision = node.review_decision.map(|d| match d {
+            list::PullRequestReviewDecision::APPROVED => ReviewDecision::Approved,
+            list::PullRequestReviewDecision::CHANGES_REQUESTED => ReviewDecision::ChangesRequested,
+            list::PullRequestReviewDecision::REVIEW_REQUIRED
+            | list::PullRequestReviewDecision::Other(_) => ReviewDecision::ReviewRequired,
         });
 
-        let mergeable = match node.mergeable.as_str() {
-            "MERGEABLE" => Mergeable::Mergeable,
-            "CONFLICTING" => Mergeable::Conflicting,
-            _ => Mergeable::Unknown,
+        let mergeable = match node.mergeable {
+            list::MergeableState::MERGEABLE => Mergeable::Mergeable,
+            list::MergeableState::CONFLICTING => Mergeable::Conflicting,
+            list::MergeableState::UNKNOWN | list::MergeableState::Other(_) => Mergeable::Unknown,
         };
 
-        let created_at = DateTime::parse_from_rfc3339(&node.created_at)
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(|_| Utc::now());
+        let auto_merge = node.auto_merge_request.map(|request| AutoMerge {
+            merge_method: match request.merge_method {
+                list::PullRequestMergeMethod::SQUASH => MergeMethod::Squash,
+                list::PullRequestMergeMethod::REBASE => MergeMethod::Rebase,
+                list::PullRequestMergeMethod::MERGE | list::PullRequestMergeMethod::Other(_) => {
+                    MergeMethod::Merge
+                }
+            },
+            enabled_at: request.enabled_at.as_deref().map(Self::parse_datetime),
+            enabled_by: request.enabled_by.map(|actor| actor.login),
+        });
 
-        let updated_at = DateTime::parse_from_rfc3339(&node.updated_at)
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(|_| Utc::now());
+        let merge_queue = node.merge_queue_entry.map(|entry| MergeQueueEntry {
+            position: entry.position as i32,
+            state: match entry.state {
+                list::MergeQueueEntryState::QUEUED => MergeQueueState::Queued,
+                list::MergeQueueEntryState::AWAITING_CHECKS => MergeQueueState::AwaitingChecks,
+                list::MergeQueueEntryState::MERGEABLE => MergeQueueState::Mergeable,
+                list::MergeQueueEntryState::UNMERGEABLE => MergeQueueState::Unmergeable,
+                list::MergeQueueEntryState::LOCKED => MergeQueueState::Locked,
+                list::MergeQueueEntryState::Other(_) => MergeQueueState::Queued,
+            },
+            enqueued_at: Self::parse_datetime(&entry.enqueued_at),
+            estimated_time_to_merge: entry.estimated_time_to_merge.map(|secs| secs as i32),
+        });
+
+        let check_state = node
+            .head_commit
+            .nodes
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .next_back()
+            .and_then(|node| node.commit.status_check_rollup)
+            .map(|rollup| match rollup.state {
+                list::StatusState::SUCCESS => CheckState::Success,
+                list::StatusState::PENDING | list::StatusState::EXPECTED => CheckState::Pending,
+                list::StatusState::FAILURE | list::StatusState::ERROR => CheckState::Failure,
+                list::StatusState::Other(_) => CheckState::Unknown,
+            });
+
+        let assignees = node
+            .assignees
+            .nodes
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .map(|user| user.login)
+            .collect();
+
+        let milestone = node.milestone.map(|m| Milestone {
+            id: m.id,
+            number: m.number,
+            title: m.title,
+            due_on: m.due_on.as_deref().map(Self::parse_datetime),
+        });
+
+        let requested_reviewers = node
+            .review_requests
+            .and_then(|connection| connection.nodes)
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .filter_map(|request| match request.requested_reviewer? {
+                GqlListRequestedReviewer::User(user) => Some(user.login),
+                GqlListRequestedReviewer::Team(team) => Some(team.combined_slug),
+                _ => None,
+            })
+            .collect();
 
         PullRequest {
             id: node.id,
             number: node.number,
             title: node.title,
-            body: node.body,
+            body: Some(node.body),
             state,
             is_draft: node.is_draft,
             author,
@@ -283,178 +1359,94 @@ impl PrService {
             labels,
             review_decision,
             mergeable,
-            created_at,
-            updated_at,
-            commit_count: node.commits.total_count,
-            additions: node.additions,
-            deletions: node.deletions,
-            changed_files: node.changed_files,
+            created_at: Self::parse_datetime(&node.created_at),
+            updated_at: Self::parse_datetime(&node.updated_at),
+            commit_count: node.commits.total_count as i32,
+            additions: node.additions as i32,
+            deletions: node.deletions as i32,
+            changed_files: node.changed_files as i32,
+            check_state,
+            auto_merge,
+            merge_queue,
+            assignees,
+            milestone,
+            requested_reviewers,
         }
     }
 
     /// Convert detailed PR from GraphQL to domain type.
-    fn convert_pr_details(pr: GqlPullRequestDetails) -> PullRequestDetails {
+    ///
+    /// Connections are fetched separately, so this only covers the PR itself.
+    fn convert_pr_details(pr: GqlPullRequestDetails) -> PullRequest {
         let author = pr
             .author
             .map(|a| Author {
                 login: a.login,
                 avatar_url: a.avatar_url,
             })
-            .unwrap_or(Author {
-                login: "ghost".to_string(),
-                avatar_url: String::new(),
-            });
+            .unwrap_or_else(Self::ghost);
 
-        let state = match pr.state.as_str() {
-            "OPEN" => PrState::Open,
-            "CLOSED" => PrState::Closed,
-            "MERGED" => PrState::Merged,
-            _ => PrState::Open,
+        let state = match pr.state {
+            details::PullRequestState::OPEN => PrState::Open,
+            details::PullRequestState::CLOSED => PrState::Closed,
+            details::PullRequestState::MERGED => PrState::Merged,
+            details::PullRequestState::Other(_) => PrState::Open,
         };
 
-        let mergeable = match pr.mergeable.as_str() {
-            "MERGEABLE" => Mergeable::Mergeable,
-            "CONFLICTING" => Mergeable::Conflicting,
-            _ => Mergeable::Unknown,
+        let mergeable = match pr.mergeable {
+            details::MergeableState::MERGEABLE => Mergeable::Mergeable,
+            details::MergeableState::CONFLICTING => Mergeable::Conflicting,
+            details::MergeableState::UNKNOWN | details::MergeableState::Other(_) => {
+                Mergeable::Unknown
+            }
         };
 
-        let created_at = DateTime::parse_from_rfc3339(&pr.created_at)
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(|_| Utc::now());
-
-        let updated_at = DateTime::parse_from_rfc3339(&pr.updated_at)
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(|_| Utc::now());
-
-        // Extract check status from the last (head) commit
-        let check_status = pr
-            .commits
-            .nodes
-            .as_ref()
-            .and_then(|commits| commits.last())
-            .and_then(|commit_node| commit_node.commit.status_check_rollup.as_ref())
-            .map(|rollup| {
-                let overall_state = match rollup.state.as_deref() {
-                    Some("SUCCESS") => CheckState::Success,
-                    Some("PENDING") | Some("EXPECTED") => CheckState::Pending,
-                    Some("FAILURE") | Some("ERROR") => CheckState::Failure,
-                    _ => CheckState::Unknown,
-                };
-
-                let checks: Vec<CheckRun> = rollup
-                    .contexts
-                    .as_ref()
-                    .and_then(|ctx| ctx.nodes.as_ref())
-                    .map(|nodes| {
-                        nodes
-                            .iter()
-                            .filter_map(Self::convert_check_context)
-                            .collect()
-                    })
-                    .unwrap_or_default();
-
-                CheckStatus {
-                    state: overall_state,
-                    checks,
-                }
-            });
-
-        let commits: Vec<Commit> = pr
-            .commits
-            .nodes
-            .unwrap_or_default()
-            .into_iter()
-            .map(|c| {
-                let authored_date = c
-                    .commit
-                    .author
-                    .as_ref()
-                    .and_then(|a| a.date.as_ref())
-                    .and_then(|d| DateTime::parse_from_rfc3339(d).ok())
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(Utc::now);
-
-                Commit {
-                    oid: c.commit.oid,
-                    message: c.commit.message,
-                    author_name: c
-                        .commit
-                        .author
-                        .and_then(|a| a.name)
-                        .unwrap_or_else(|| "Unknown".to_string()),
-                    authored_date,
-                }
-            })
-            .collect();
-
-        let files: Vec<ChangedFile> = pr
-            .files
-            .nodes
-            .unwrap_or_default()
-            .into_iter()
-            .map(|f| {
-                let change_type = match f.change_type.as_str() {
-                    "ADDED" => ChangeType::Added,
-                    "DELETED" => ChangeType::Deleted,
-                    "MODIFIED" => ChangeType::Modified,
-                    "RENAMED" => ChangeType::Renamed,
-                    "COPIED" => ChangeType::Copied,
-                    _ => ChangeType::Changed,
-                };
-
-                ChangedFile {
-                    path: f.path,
-                    additions: f.additions,
-                    deletions: f.deletions,
-                    change_type,
-                }
-            })
-            .collect();
-
-        let reviews: Vec<Review> = pr
-            .reviews
-            .nodes
-            .unwrap_or_default()
-            .into_iter()
-            .filter_map(|r| {
-                let author = r.author?.login;
-                let state = match r.state.as_str() {
-                    "PENDING" => ReviewState::Pending,
-                    "COMMENTED" => ReviewState::Commented,
-                    "APPROVED" => ReviewState::Approved,
-                    "CHANGES_REQUESTED" => ReviewState::ChangesRequested,
-                    "DISMISSED" => ReviewState::Dismissed,
-                    _ => ReviewState::Pending,
-                };
-                let submitted_at = r
-                    .submitted_at
-                    .and_then(|d| DateTime::parse_from_rfc3339(&d).ok())
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .unwrap_or_else(Utc::now);
+        let auto_merge = pr.auto_merge_request.map(|request| AutoMerge {
+            merge_method: match request.merge_method {
+                details::PullRequestMergeMethod::SQUASH => MergeMethod::Squash,
+                details::PullRequestMergeMethod::REBASE => MergeMethod::Rebase,
+                details::PullRequestMergeMethod::MERGE
+                | details::PullRequestMergeMethod::Other(_) => MergeMethod::Merge,
+            },
+            enabled_at: request.enabled_at.as_deref().map(Self::parse_datetime),
+            enabled_by: request.enabled_by.map(|actor| actor.login),
+        });
 
-                Some(Review {
-                    author,
-                    state,
-                    submitted_at,
-                })
-            })
-            .collect();
+        let merge_queue = pr.merge_queue_entry.map(|entry| MergeQueueEntry {
+            position: entry.position as i32,
+            state: match entry.state {
+                details::MergeQueueEntryState::QUEUED => MergeQueueState::Queued,
+                details::MergeQueueEntryState::AWAITING_CHECKS => MergeQueueState::AwaitingChecks,
+                details::MergeQueueEntryState::MERGEABLE => MergeQueueState::Mergeable,
+                details::MergeQueueEntryState::UNMERGEABLE => MergeQueueState::Unmergeable,
+                details::MergeQueueEntryState::LOCKED => MergeQueueState::Locked,
+                details::MergeQueueEntryState::Other(_) => MergeQueueState::Queued,
+            },
+            enqueued_at: Self::parse_datetime(&entry.enqueued_at),
+            estimated_time_to_merge: entry.estimated_time_to_merge.map(|secs| secs as i32),
+        });
 
-        let review_requests: Vec<String> = pr
-            .review_requests
+        let assignees = pr
+            .assignees
             .nodes
             .unwrap_or_default()
             .into_iter()
-            .filter_map(|r| r.requested_reviewer?.login)
+            .flatten()
+            .map(|user| user.login)
             .collect();
 
-        let commit_count = commits.len() as i32;
+        let milestone = pr.milestone.map(|m| Milestone {
+            id: m.id,
+            number: m.number,
+            title: m.title,
+            due_on: m.due_on.as_deref().map(Self::parse_datetime),
+        });
 
-        let base_pr = PullRequest {
+        PullRequest {
             id: pr.id,
             number: pr.number,
             title: pr.title,
-            body: pr.body,
+            body: Some(pr.body),
             state,
             is_draft: pr.is_draft,
             author,
@@ -463,86 +1455,81 @@ impl PrService {
             labels: Vec::new(),    // Not included in details query
             review_decision: None, // Could add to query if needed
             mergeable,
-            created_at,
-            updated_at,
-            commit_count,
-            additions: pr.additions,
-            deletions: pr.deletions,
-            changed_files: pr.changed_files,
-        };
-
-        PullRequestDetails {
-            pr: base_pr,
-            commits,
-            files,
-            reviews,
-            review_requests,
-            check_status,
+            created_at: Self::parse_datetime(&pr.created_at),
+            updated_at: Self::parse_datetime(&pr.updated_at),
+            commit_count: 0, // Filled in from the commits connection
+            additions: pr.additions as i32,
+            deletions: pr.deletions as i32,
+            changed_files: pr.changed_files as i32,
+            check_state: None, // Filled in from the checks connection
+            auto_merge,
+            merge_queue,
+            assignees,
+            milestone,
+            requested_reviewers: Vec::new(), // Filled in from the review requests connection
         }
     }
 
     /// Convert a GraphQL check context to domain CheckRun.
-    fn convert_check_context(ctx: &GqlCheckContext) -> Option<CheckRun> {
+    fn convert_check_context(ctx: GqlCheckContext) -> CheckRun {
         match ctx {
+            // Check runs have status and conclusion
             GqlCheckContext::CheckRun(check) => {
-                let name = check.name.clone()?;
-                let typename = check.typename.as_deref();
-
-                // Check runs have status and conclusion
-                if typename == Some("CheckRun") {
-                    let status = match check.status.as_deref() {
-                        Some("QUEUED") => CheckRunStatus::Queued,
-                        Some("IN_PROGRESS") => CheckRunStatus::InProgress,
-                        _ => CheckRunStatus::Completed,
-                    };
-
-                    let conclusion = check.conclusion.as_deref().map(|c| match c {
-                        "SUCCESS" => CheckConclusion::Success,
-                        "FAILURE" => CheckConclusion::Failure,
-                        "NEUTRAL" => CheckConclusion::Neutral,
-                        "CANCELLED" => CheckConclusion::Cancelled,
-                        "SKIPPED" => CheckConclusion::Skipped,
-                        "TIMED_OUT" => CheckConclusion::TimedOut,
-                        "ACTION_REQUIRED" => CheckConclusion::ActionRequired,
-                        _ => CheckConclusion::Neutral,
-                    });
-
-                    Some(CheckRun {
-                        name,
-                        status,
-                        conclusion,
-                        details_url: check.details_url.clone(),
-                    })
-                } else {
-                    None
+                let status = match check.status {
+                    checks::CheckStatusState::QUEUED
+                    | checks::CheckStatusState::WAITING
+                    | checks::CheckStatusState::PENDING
+                    | checks::CheckStatusState::REQUESTED => CheckRunStatus::Queued,
+                    checks::CheckStatusState::IN_PROGRESS => CheckRunStatus::InProgress,
+                    checks::CheckStatusState::COMPLETED | checks::CheckStatusState::Other(_) => {
+                        CheckRunStatus::Completed
+                    }
+                };
+
+                let conclusion = check.conclusion.map(|c| match c {
+                    checks::CheckConclusionState::SUCCESS => CheckConclusion::Success,
+                    checks::CheckConclusionState::FAILURE
+                    | checks::CheckConclusionState::STARTUP_FAILURE => CheckConclusion::Failure,
+                    checks::CheckConclusionState::CANCELLED => CheckConclusion::Cancelled,
+                    checks::CheckConclusionState::SKIPPED => CheckConclusion::Skipped,
+                    checks::CheckConclusionState::TIMED_OUT => CheckConclusion::TimedOut,
+                    checks::CheckConclusionState::ACTION_REQUIRED => {
+                        CheckConclusion::ActionRequired
+                    }
+                    _ => CheckConclusion::Neutral,
+                });
+
+                CheckRun {
+                    id: check.database_id,
+                    name: check.name,
+                    status,
+                    conclusion,
+                    details_url: check.details_url,
                 }
             }
+            // Status contexts have context and state
             GqlCheckContext::StatusContext(status) => {
-                let typename = status.typename.as_deref();
-
-                // Status contexts have context and state
-                if typename == Some("StatusContext") {
-                    let name = status.context.clone()?;
-
-                    let (status_val, conclusion) = match status.state.as_deref() {
-                        Some("SUCCESS") => {
-                            (CheckRunStatus::Completed, Some(CheckConclusion::Success))
-                        }
-                        Some("FAILURE") | Some("ERROR") => {
-                            (CheckRunStatus::Completed, Some(CheckConclusion::Failure))
-                        }
-                        Some("PENDING") | Some("EXPECTED") => (CheckRunStatus::InProgress, None),
-                        _ => (CheckRunStatus::Completed, Some(CheckConclusion::Neutral)),
-                    };
-
-                    Some(CheckRun {
-                        name,
-                        status: status_val,
-                        conclusion,
-                        details_url: status.target_url.clone(),
-                    })
-                } else {
-                    None
+                let (status_val, conclusion) = match status.state {
+                    checks::StatusState::SUCCESS => {
+                        (CheckRunStatus::Completed, Some(CheckConclusion::Success))
+                    }
+                    checks::StatusState::FAILURE | checks::StatusState::ERROR => {
+                        (CheckRunStatus::Completed, Some(CheckConclusion::Failure))
+                    }
+                    checks::StatusState::PENDING | checks::StatusState::EXPECTED => {
+                        (CheckRunStatus::InProgress, None)
+                    }
+                    checks::StatusState::Other(_) => {
+                        (CheckRunStatus::Completed, Some(CheckConclusion::Neutral))
+                    }
+                };
+
+                CheckRun {
+                    id: None,
+                    name: status.context,
+                    status: status_val,
+                    conclusion,
+                    details_url: status.target_url,
                 }
             }
         }
@@ -564,4 +1551,129 @@ mod tests {
             "SQUASH"
         );
     }
+
+    #[test]
+    fn test_label_ids() {
+        let labels = vec![RepoLabel {
+            id: "LA_1".to_string(),
+            name: "bug".to_string(),
+            color: "d73a4a".to_string(),
+            description: None,
+        }];
+
+        assert_eq!(
+            PrService::label_ids(&labels, &["bug".to_string()]).unwrap(),
+            ["LA_1"]
+        );
+        assert!(matches!(
+            PrService::label_ids(&labels, &["bug".to_string(), "wontfix".to_string()]),
+            Err(AppError::GitHub(GitHubError::InvalidRequest(message))) if message.contains("wontfix")
+        ));
+    }
+
+    #[test]
+    fn test_is_update_method_unavailable() {
+        let unavailable = |code: &str| {
+            PrService::is_update_method_unavailable(
+                &GitHubError::GraphQLCode {
+                    code: code.into(),
+                    message: "Rebase updates are not allowed".into(),
+                }
+                .into(),
+            )
+        };
+
+        assert!(unavailable("argumentNotAccepted"));
+        assert!(unavailable("variableRequiresValidType"));
+        assert!(unavailable("UNPROCESSABLE"));
+        assert!(!unavailable("FORBIDDEN"));
+        assert!(!unavailable("NOT_FOUND"));
+        // Untyped errors are never taken for a missing update method
+        assert!(!PrService::is_update_method_unavailable(
+            &GitHubError::GraphQL("updateMethod is not allowed".into()).into()
+        ));
+        assert!(!PrService::is_update_method_unavailable(
+            &GitHubError::Network("timeout".into()).into()
+        ));
+    }
+
+    #[test]
+    fn test_details_from_response() {
+        let page = |nodes: serde_json::Value| {
+            serde_json::json!({
+                "totalCount": 150,
+                "pageInfo": { "hasNextPage": true, "endCursor": "Y3Vyc29y" },
+                "nodes": nodes,
+            })
+        };
+        let head_commit = serde_json::json!({
+            "__typename": "Commit",
+            "oid": "abc123",
+            "statusCheckRollup": {
+                "state": "PENDING",
+                "contexts": page(serde_json::json!([
+                    { "__typename": "StatusContext", "context": "ci/lint", "state": "PENDING", "targetUrl": null, "description": null }
+                ])),
+            },
+        });
+        let data = serde_json::json!({
+            "repository": {
+                "pullRequest": {
+                    "id": "PR_1",
+                    "number": 7,
+                    "title": "Add paging",
+                    "body": "",
+                    "state": "OPEN",
+                    "isDraft": false,
+                    "mergeable": "MERGEABLE",
+                    "autoMergeRequest": null,
+                    "assignees": { "nodes": [] },
+                    "milestone": null,
+                    "mergeQueueEntry": null,
+                    "createdAt": "2024-01-01T00:00:00Z",
+                    "updatedAt": "2024-01-02T00:00:00Z",
+                    "author": { "__typename": "User", "login": "octocat", "avatarUrl": "https://example.com/a.png" },
+                    "headRefName": "feature",
+                    "baseRefName": "main",
+                    "headRefOid": "abc123",
+                    "baseRefOid": "def456",
+                    "additions": 10,
+                    "deletions": 2,
+                    "changedFiles": 1,
+                    "commits": page(serde_json::json!([
+                        { "commit": { "oid": "abc123", "message": "Add paging", "author": { "name": "Octo", "date": null } } }
+                    ])),
+                    "files": page(serde_json::json!([
+                        { "path": "src/lib.rs", "additions": 10, "deletions": 2, "changeType": "MODIFIED" }
+                    ])),
+                    "reviews": page(serde_json::json!([
+                        { "author": { "__typename": "User", "login": "reviewer" }, "state": "APPROVED", "submittedAt": null }
+                    ])),
+                    "reviewRequests": page(serde_json::json!([
+                        { "requestedReviewer": { "__typename": "Team", "combinedSlug": "org/core" } }
+                    ])),
+                    "headCommit": { "nodes": [{ "commit": head_commit }] },
+                },
+            },
+            "rateLimit": { "cost": 1, "limit": 5000, "remaining": 4999, "resetAt": "2024-01-01T01:00:00Z" },
+        });
+
+        let details = PrService::details_from_response(7, data).unwrap();
+
+        assert_eq!(details.pr.title, "Add paging");
+        assert_eq!(details.commits[0].oid, "abc123");
+        assert_eq!(details.files[0].path, "src/lib.rs");
+        assert_eq!(details.reviews[0].author, "reviewer");
+        assert_eq!(details.review_requests, ["org/core"]);
+        let status = details.check_status.unwrap();
+        assert_eq!(status.state, CheckState::Pending);
+        assert_eq!(status.checks[0].name, "ci/lint");
+        assert_eq!(status.commit_oid, "abc123");
+        assert_eq!(details.pr.commit_count, 150);
+        assert!(details.pagination.checks.has_next_page);
+        assert_eq!(
+            details.pagination.files.end_cursor.as_deref(),
+            Some("Y3Vyc29y")
+        );
+    }
 }
@@ -1,614 +1,534 @@
 //! GraphQL query definitions for GitHub API.
 //!
-//! This module contains the GraphQL queries and response types
-//! for interacting with the GitHub GraphQL API.
+//! Each operation lives in its own `.graphql` document under `graphql/` and
+//! is compiled into a typed module by `#[derive(GraphQLQuery)]`, checked
+//! against the vendored schema in `graphql/schema.graphql`. Selecting a field
+//! that doesn't exist, or reading a nullable field as non-null, is a build
+//! error rather than a runtime deserialization failure. The schema only holds
+//! what the operations use; regenerate it with `npm run update-graphql-schema`
+//! after adding or changing one.
 
-use serde::{Deserialize, Serialize};
+#![allow(clippy::upper_case_acronyms)]
 
-/// Query to list pull requests for a repository.
-pub const LIST_PULL_REQUESTS: &str = r#"
-query ListPullRequests($owner: String!, $repo: String!, $baseRefName: String, $first: Int!, $after: String) {
-  repository(owner: $owner, name: $repo) {
-    pullRequests(
-      baseRefName: $baseRefName
-      states: [OPEN]
-      first: $first
-      after: $after
-      orderBy: { field: UPDATED_AT, direction: DESC }
-    ) {
-      pageInfo {
-        hasNextPage
-        endCursor
-      }
-      nodes {
-        id
-        number
-        title
-        body
-        state
-        isDraft
-        createdAt
-        updatedAt
-        author {
-          login
-          avatarUrl
-        }
-        labels(first: 10) {
-          nodes {
-            name
-            color
-          }
-        }
-        reviewDecision
-        headRefName
-        baseRefName
-        mergeable
-        commits {
-          totalCount
-        }
-        additions
-        deletions
-        changedFiles
-      }
-    }
-  }
-}
-"#;
-
-/// Query to get details of a specific pull request.
-pub const GET_PULL_REQUEST_DETAILS: &str = r#"
-query GetPullRequestDetails($owner: String!, $repo: String!, $number: Int!) {
-  repository(owner: $owner, name: $repo) {
-    pullRequest(number: $number) {
-      id
-      number
-      title
-      body
-      state
-      isDraft
-      mergeable
-      createdAt
-      updatedAt
-      author {
-        login
-        avatarUrl
-      }
-      headRefName
-      baseRefName
-      headRefOid
-      baseRefOid
-      additions
-      deletions
-      changedFiles
-      commits(first: 100) {
-        nodes {
-          commit {
-            oid
-            message
-            author {
-              name
-              date
-            }
-            statusCheckRollup {
-              state
-              contexts(first: 50) {
-                nodes {
-                  ... on CheckRun {
-                    __typename
-                    name
-                    status
-                    conclusion
-                    detailsUrl
-                  }
-                  ... on StatusContext {
-                    __typename
-                    context
-                    state
-                    targetUrl
-                    description
-                  }
-                }
-              }
-            }
-          }
-        }
-      }
-      files(first: 100) {
-        nodes {
-          path
-          additions
-          deletions
-          changeType
-        }
-      }
-      reviews(first: 50) {
-        nodes {
-          author {
-            login
-          }
-          state
-          submittedAt
-        }
-      }
-      reviewRequests(first: 10) {
-        nodes {
-          requestedReviewer {
-            ... on User {
-              login
-            }
-          }
-        }
-      }
-    }
-  }
-}
-"#;
-
-/// Mutation to create a pull request.
-pub const CREATE_PULL_REQUEST: &str = r#"
-mutation CreatePullRequest($repositoryId: ID!, $baseRefName: String!, $headRefName: String!, $title: String!, $body: String, $draft: Boolean) {
-  createPullRequest(input: {
-    repositoryId: $repositoryId
-    baseRefName: $baseRefName
-    headRefName: $headRefName
-    title: $title
-    body: $body
-    draft: $draft
-  }) {
-    pullRequest {
-      id
-      number
-      url
-    }
-  }
-}
-"#;
-
-/// Mutation to merge a pull request.
-pub const MERGE_PULL_REQUEST: &str = r#"
-mutation MergePullRequest($pullRequestId: ID!, $mergeMethod: PullRequestMergeMethod) {
-  mergePullRequest(input: { 
-    pullRequestId: $pullRequestId
-    mergeMethod: $mergeMethod
-  }) {
-    pullRequest {
-      number
-      merged
-    }
-  }
-}
-"#;
-
-/// Mutation to update pull request base branch.
-pub const UPDATE_PULL_REQUEST_BRANCH: &str = r#"
-mutation UpdatePullRequestBranch($pullRequestId: ID!, $expectedHeadOid: GitObjectID) {
-  updatePullRequestBranch(input: { 
-    pullRequestId: $pullRequestId
-    expectedHeadOid: $expectedHeadOid
-  }) {
-    pullRequest {
-      number
-      headRefOid
-    }
-  }
-}
-"#;
-
-/// Mutation to update pull request (change base branch, title, body, etc.).
-pub const UPDATE_PULL_REQUEST: &str = r#"
-mutation UpdatePullRequest($pullRequestId: ID!, $baseRefName: String) {
-  updatePullRequest(input: { 
-    pullRequestId: $pullRequestId
-    baseRefName: $baseRefName
-  }) {
-    pullRequest {
-      number
-      baseRefName
-    }
-  }
-}
-"#;
-
-/// Mutation to close a pull request.
-pub const CLOSE_PULL_REQUEST: &str = r#"
-mutation ClosePullRequest($pullRequestId: ID!) {
-  closePullRequest(input: { pullRequestId: $pullRequestId }) {
-    pullRequest {
-      number
-      state
-    }
-  }
-}
-"#;
-
-/// Query to get repository ID.
-pub const GET_REPOSITORY_ID: &str = r#"
-query GetRepositoryId($owner: String!, $repo: String!) {
-  repository(owner: $owner, name: $repo) {
-    id
-    defaultBranchRef {
-      name
-    }
-  }
-}
-"#;
-
-/// Variables for listing pull requests.
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ListPullRequestsVariables {
-    pub owner: String,
-    pub repo: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub base_ref_name: Option<String>,
-    pub first: i32,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub after: Option<String>,
-}
-
-/// Variables for getting PR details.
-#[derive(Debug, Clone, Serialize)]
-pub struct GetPullRequestDetailsVariables {
-    pub owner: String,
-    pub repo: String,
-    pub number: i32,
-}
-
-/// Variables for creating a pull request.
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct CreatePullRequestVariables {
-    pub repository_id: String,
-    pub base_ref_name: String,
-    pub head_ref_name: String,
-    pub title: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub body: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub draft: Option<bool>,
-}
-
-/// Variables for merging a pull request.
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct MergePullRequestVariables {
-    pub pull_request_id: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub merge_method: Option<String>,
-}
-
-/// Variables for closing a pull request.
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ClosePullRequestVariables {
-    pub pull_request_id: String,
-}
-
-/// Variables for updating a pull request (changing base branch).
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct UpdatePullRequestVariables {
-    pub pull_request_id: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub base_ref_name: Option<String>,
-}
-
-/// Variables for getting repository ID.
-#[derive(Debug, Clone, Serialize)]
-pub struct GetRepositoryIdVariables {
-    pub owner: String,
-    pub repo: String,
-}
-
-// Response types
-
-/// Page info for pagination.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct PageInfo {
-    pub has_next_page: bool,
-    pub end_cursor: Option<String>,
-}
-
-/// Author information from GraphQL.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlAuthor {
-    pub login: String,
-    pub avatar_url: String,
-}
-
-/// Label from GraphQL.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GqlLabel {
-    pub name: String,
-    pub color: String,
-}
-
-/// Labels wrapper.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GqlLabels {
-    pub nodes: Option<Vec<GqlLabel>>,
-}
-
-/// Commits wrapper.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlCommits {
-    pub total_count: i32,
-}
-
-/// Pull request node from list query.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlPullRequestNode {
-    pub id: String,
-    pub number: i64,
-    pub title: String,
-    pub body: Option<String>,
-    pub state: String,
-    pub is_draft: bool,
-    pub created_at: String,
-    pub updated_at: String,
-    pub author: Option<GqlAuthor>,
-    pub labels: GqlLabels,
-    pub review_decision: Option<String>,
-    pub head_ref_name: String,
-    pub base_ref_name: String,
-    pub mergeable: String,
-    pub commits: GqlCommits,
-    pub additions: i32,
-    pub deletions: i32,
-    pub changed_files: i32,
-}
-
-/// Pull requests connection.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlPullRequestsConnection {
-    pub page_info: PageInfo,
-    pub nodes: Option<Vec<GqlPullRequestNode>>,
-}
-
-/// Repository from list PRs query.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlRepositoryPullRequests {
-    pub pull_requests: GqlPullRequestsConnection,
-}
-
-/// Response for list pull requests query.
-#[derive(Debug, Clone, Deserialize)]
-pub struct ListPullRequestsResponse {
-    pub repository: Option<GqlRepositoryPullRequests>,
-}
-
-/// Commit author for details query.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GqlCommitAuthor {
-    pub name: Option<String>,
-    pub date: Option<String>,
-}
-
-/// Check run context from status rollup.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlCheckRun {
-    #[serde(rename = "__typename")]
-    pub typename: Option<String>,
-    pub name: Option<String>,
-    pub status: Option<String>,
-    pub conclusion: Option<String>,
-    pub details_url: Option<String>,
-}
-
-/// Status context from status rollup (legacy commit status).
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlStatusContext {
-    #[serde(rename = "__typename")]
-    pub typename: Option<String>,
-    pub context: Option<String>,
-    pub state: Option<String>,
-    pub target_url: Option<String>,
-    pub description: Option<String>,
-}
-
-/// Union type for check run or status context.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(untagged)]
-pub enum GqlCheckContext {
-    CheckRun(GqlCheckRun),
-    StatusContext(GqlStatusContext),
-}
-
-/// Contexts connection in status rollup.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GqlContextsConnection {
-    pub nodes: Option<Vec<GqlCheckContext>>,
-}
-
-/// Status check rollup for a commit.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GqlStatusCheckRollup {
-    pub state: Option<String>,
-    pub contexts: Option<GqlContextsConnection>,
-}
-
-/// Commit details.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlCommitDetails {
-    pub oid: String,
-    pub message: String,
-    pub author: Option<GqlCommitAuthor>,
-    pub status_check_rollup: Option<GqlStatusCheckRollup>,
-}
-
-/// Commit node wrapper.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GqlCommitNode {
-    pub commit: GqlCommitDetails,
-}
-
-/// Commits connection.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GqlCommitsConnection {
-    pub nodes: Option<Vec<GqlCommitNode>>,
-}
-
-/// File change details.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlFileChange {
-    pub path: String,
-    pub additions: i32,
-    pub deletions: i32,
-    pub change_type: String,
-}
+use graphql_client::GraphQLQuery;
+use serde::Deserialize;
 
-/// Files connection.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GqlFilesConnection {
-    pub nodes: Option<Vec<GqlFileChange>>,
-}
+// Custom scalars, deserialized as their JSON string representation.
 
-/// Review node.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlReviewNode {
-    pub author: Option<GqlAuthor>,
-    pub state: String,
-    pub submitted_at: Option<String>,
-}
+/// ISO-8601 timestamp.
+pub type DateTime = String;
 
-/// Reviews connection.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GqlReviewsConnection {
-    pub nodes: Option<Vec<GqlReviewNode>>,
-}
+/// Git object ID (SHA).
+pub type GitObjectID = String;
 
-/// Review requester.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlRequestedReviewer {
-    pub login: Option<String>,
-}
-
-/// Review request node.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlReviewRequestNode {
-    pub requested_reviewer: Option<GqlRequestedReviewer>,
-}
+/// ISO-8601 timestamp as reported by Git.
+pub type GitTimestamp = String;
 
-/// Review requests connection.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GqlReviewRequestsConnection {
-    pub nodes: Option<Vec<GqlReviewRequestNode>>,
-}
-
-/// Detailed pull request.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlPullRequestDetails {
-    pub id: String,
-    pub number: i64,
-    pub title: String,
-    pub body: Option<String>,
-    pub state: String,
-    pub is_draft: bool,
-    pub mergeable: String,
-    pub created_at: String,
-    pub updated_at: String,
-    pub author: Option<GqlAuthor>,
-    pub head_ref_name: String,
-    pub base_ref_name: String,
-    pub head_ref_oid: String,
-    pub base_ref_oid: String,
-    pub additions: i32,
-    pub deletions: i32,
-    pub changed_files: i32,
-    pub commits: GqlCommitsConnection,
-    pub files: GqlFilesConnection,
-    pub reviews: GqlReviewsConnection,
-    pub review_requests: GqlReviewRequestsConnection,
-}
+/// RFC 3986 URI.
+pub type URI = String;
 
-/// Repository from get PR details query.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlRepositoryPullRequest {
-    pub pull_request: Option<GqlPullRequestDetails>,
-}
-
-/// Response for get PR details query.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GetPullRequestDetailsResponse {
-    pub repository: Option<GqlRepositoryPullRequest>,
-}
-
-/// Created pull request result.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GqlCreatedPullRequest {
-    pub id: String,
-    pub number: i64,
-    pub url: String,
-}
-
-/// Create PR mutation result.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlCreatePullRequestResult {
-    pub pull_request: GqlCreatedPullRequest,
-}
-
-/// Response for create PR mutation.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct CreatePullRequestResponse {
-    pub create_pull_request: GqlCreatePullRequestResult,
-}
+/// Query to list pull requests for a repository.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/list_pull_requests.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct ListPullRequests;
+
+/// Query to get details of a specific pull request, along with the first
+/// page of each of its connections.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/get_pull_request_details.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct GetPullRequestDetails;
+
+/// Query to get a page of a pull request's commits.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/get_pull_request_commits.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct GetPullRequestCommits;
+
+/// Query to get a page of a pull request's changed files.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/get_pull_request_files.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct GetPullRequestFiles;
+
+/// Query to get a page of a pull request's reviews.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/get_pull_request_reviews.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct GetPullRequestReviews;
+
+/// Query to get a page of a pull request's review requests.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/get_pull_request_review_requests.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct GetPullRequestReviewRequests;
+
+/// Query to get a page of a commit's check contexts.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/get_pull_request_checks.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct GetPullRequestChecks;
 
-/// Merged pull request result.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GqlMergedPullRequest {
-    pub number: i64,
-    pub merged: bool,
-}
+/// Mutation to create a pull request.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/create_pull_request.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct CreatePullRequest;
 
-/// Merge PR mutation result.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GqlMergePullRequestResult {
-    pub pull_request: GqlMergedPullRequest,
-}
+/// Mutation to merge a pull request.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/merge_pull_request.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct MergePullRequest;
+
+/// Mutation to merge a pull request automatically once its requirements are met.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/enable_pull_request_auto_merge.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct EnablePullRequestAutoMerge;
+
+/// Mutation to cancel auto-merge of a pull request.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/disable_pull_request_auto_merge.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct DisablePullRequestAutoMerge;
+
+/// Mutation to add a pull request to the merge queue.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/enqueue_pull_request.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct EnqueuePullRequest;
+
+/// Mutation to remove a pull request from the merge queue.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/dequeue_pull_request.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct DequeuePullRequest;
+
+/// Mutation to bring a pull request branch up to date with its base.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/update_pull_request_branch.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct UpdatePullRequestBranch;
 
-/// Response for merge PR mutation.
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct MergePullRequestResponse {
-    pub merge_pull_request: GqlMergePullRequestResult,
-}
+/// Mutation to update pull request (change base branch, title, body, etc.).
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/update_pull_request.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct UpdatePullRequest;
+
+/// Mutation to change a pull request's title and/or body.
+///
+/// Unset variables are left out of the request rather than sent as `null`,
+/// so only the given fields change.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/edit_pull_request.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone",
+    skip_serializing_none
+)]
+pub struct EditPullRequest;
+
+/// Mutation to replace a pull request's assignees.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/set_pull_request_assignees.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct SetPullRequestAssignees;
+
+/// Mutation to set or clear (`null`) a pull request's milestone.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/set_pull_request_milestone.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct SetPullRequestMilestone;
+
+/// Mutation to convert a pull request to a draft.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/convert_pull_request_to_draft.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct ConvertPullRequestToDraft;
+
+/// Mutation to mark a draft pull request ready for review.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/mark_pull_request_ready_for_review.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct MarkPullRequestReadyForReview;
+
+/// Mutation to reopen a closed pull request.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/reopen_pull_request.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct ReopenPullRequest;
+
+/// Mutation to add labels to a pull request.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/add_labels_to_labelable.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct AddLabelsToLabelable;
+
+/// Mutation to remove labels from a pull request.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/remove_labels_from_labelable.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct RemoveLabelsFromLabelable;
+
+/// Mutation to request reviews from users and teams, keeping existing requests.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/request_reviews.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct RequestReviews;
+
+/// Query to list a repository's labels.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/list_labels.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct ListLabels;
+
+/// Query to list a repository's open milestones.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/list_milestones.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct ListMilestones;
+
+/// Query to look up a user's node ID by login; meant to be batched.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/get_user_id.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct GetUserId;
+
+/// Query to look up a team's node ID by organization and slug; meant to be
+/// batched.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/get_team_id.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct GetTeamId;
 
-/// Default branch ref.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GqlDefaultBranchRef {
-    pub name: String,
-}
+/// Mutation to close a pull request.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/close_pull_request.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct ClosePullRequest;
 
-/// Repository ID result.
+/// Query to get repository ID.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/get_repository_id.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct GetRepositoryId;
+
+/// Query to look up a pull request's node ID and branches by number.
+///
+/// Cheap alternative to `GetPullRequestDetails` for operations that only need
+/// to address a pull request; meant to be batched.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/get_pull_request_ref.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct GetPullRequestRef;
+
+/// Query to get a page of a pull request's review threads and their comments.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/get_pull_request_review_threads.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct GetPullRequestReviewThreads;
+
+/// Query to find the viewer's pending review on a pull request.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/get_pending_review.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct GetPendingReview;
+
+/// Mutation to start a pending review, or to review in one step when an
+/// event is given.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/add_pull_request_review.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct AddPullRequestReview;
+
+/// Mutation to add a line or multi-line comment thread to a pending review.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/add_pull_request_review_thread.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct AddPullRequestReviewThread;
+
+/// Mutation to submit a pending review.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/submit_pull_request_review.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct SubmitPullRequestReview;
+
+/// Mutation to discard a pending review and its comments.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/delete_pull_request_review.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct DeletePullRequestReview;
+
+/// Mutation to mark a review thread as resolved.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/resolve_review_thread.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct ResolveReviewThread;
+
+/// Mutation to mark a review thread as unresolved.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/unresolve_review_thread.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct UnresolveReviewThread;
+
+/// Query to get a page of a pull request's conversation timeline.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/get_pull_request_timeline.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct GetPullRequestTimeline;
+
+/// Mutation to post a comment on a pull request's conversation.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/add_comment.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct AddComment;
+
+/// Mutation to reply to a review thread.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/add_pull_request_review_thread_reply.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct AddPullRequestReviewThreadReply;
+
+/// Mutation to edit a conversation comment.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/update_issue_comment.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct UpdateIssueComment;
+
+/// Mutation to edit a review comment.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/update_pull_request_review_comment.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct UpdatePullRequestReviewComment;
+
+/// Mutation to delete a conversation comment.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/delete_issue_comment.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct DeleteIssueComment;
+
+/// Mutation to delete a review comment.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/delete_pull_request_review_comment.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct DeletePullRequestReviewComment;
+
+/// Mutation to react to a comment or review.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/add_reaction.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct AddReaction;
+
+/// Mutation to remove the viewer's reaction from a comment or review.
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.graphql",
+    query_path = "graphql/remove_reaction.graphql",
+    response_derives = "Debug, Clone",
+    variables_derives = "Debug, Clone"
+)]
+pub struct RemoveReaction;
+
+/// Rate limit budget reported by the GraphQL `rateLimit` field.
+///
+/// Read by the client from the raw response of any query that selects
+/// `rateLimit { cost limit remaining resetAt }`, independent of the
+/// operation's generated response type.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct GqlRepositoryId {
-    pub id: String,
-    pub default_branch_ref: Option<GqlDefaultBranchRef>,
-}
-
-/// Response for get repository ID query.
-#[derive(Debug, Clone, Deserialize)]
-pub struct GetRepositoryIdResponse {
-    pub repository: Option<GqlRepositoryId>,
+pub struct GqlRateLimit {
+    pub cost: u32,
+    pub limit: u32,
+    pub remaining: u32,
+    pub reset_at: String,
 }
 
 #[cfg(test)]
@@ -617,7 +537,7 @@ mod tests {
 
     #[test]
     fn test_list_pr_variables_serialization() {
-        let vars = ListPullRequestsVariables {
+        let vars = list_pull_requests::Variables {
             owner: "owner".to_string(),
             repo: "repo".to_string(),
             base_ref_name: Some("main".to_string()),
@@ -631,7 +551,7 @@ mod tests {
 
     #[test]
     fn test_list_pr_variables_without_base() {
-        let vars = ListPullRequestsVariables {
+        let vars = list_pull_requests::Variables {
             owner: "owner".to_string(),
             repo: "repo".to_string(),
             base_ref_name: None,
@@ -639,6 +559,52 @@ mod tests {
             after: None,
         };
         let json = serde_json::to_string(&vars).unwrap();
-        assert!(!json.contains("baseRefName"));
+        // A null filter argument is equivalent to omitting it.
+        assert!(json.contains("\"baseRefName\":null"));
+    }
+
+    #[test]
+    fn test_build_query_uses_operation_document() {
+        let body = GetRepositoryId::build_query(get_repository_id::Variables {
+            owner: "owner".to_string(),
+            repo: "repo".to_string(),
+        });
+        assert_eq!(body.operation_name, "GetRepositoryId");
+        assert!(body.query.contains("defaultBranchRef"));
+    }
+
+    #[test]
+    fn test_check_contexts_deserialize_by_typename() {
+        use get_pull_request_checks::*;
+        type Context = GetPullRequestChecksRepositoryObjectOnCommitStatusCheckRollupContextsNodes;
+
+        let json = serde_json::json!([
+            {
+                "__typename": "CheckRun",
+                "name": "build",
+                "status": "COMPLETED",
+                "conclusion": "SUCCESS",
+                "detailsUrl": null
+            },
+            {
+                "__typename": "StatusContext",
+                "context": "ci/lint",
+                "state": "FAILURE",
+                "targetUrl": null,
+                "description": "2 errors"
+            }
+        ]);
+        let contexts: Vec<Context> = serde_json::from_value(json).unwrap();
+
+        assert!(matches!(
+            &contexts[0],
+            Context::CheckRun(run)
+                if run.name == "build" && matches!(run.conclusion, Some(CheckConclusionState::SUCCESS))
+        ));
+        assert!(matches!(
+            &contexts[1],
+            Context::StatusContext(status)
+                if status.context == "ci/lint" && matches!(status.state, StatusState::FAILURE)
+        ));
     }
 }
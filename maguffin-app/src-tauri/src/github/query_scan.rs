@@ -0,0 +1,142 @@
+//! Lexical scanning of GraphQL documents.
+//!
+//! Batching rewrites aliases and variables and cost estimation reads page
+//! sizes from the same documents; both go through [`scan`] so they always
+//! agree on what is a comment, a string or a name.
+
+/// Lexical token reported by [`scan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Token<'a> {
+    /// A name at the given offset
+    Name(usize, &'a str),
+
+    /// A variable at the given offset of its `$`, without the `$`
+    Variable(usize, &'a str),
+
+    /// A numeric literal at the given offset
+    Number(usize, &'a str),
+
+    /// A punctuation character at the given offset
+    Punct(usize, char),
+}
+
+/// Walk the tokens of a GraphQL document, skipping whitespace, commas,
+/// comments and strings.
+pub(crate) fn scan<'a>(text: &'a str, mut visit: impl FnMut(Token<'a>)) {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'$' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len() && is_name_char(bytes[i]) {
+                    i += 1;
+                }
+                visit(Token::Variable(start, &text[start + 1..i]));
+            }
+            b if is_name_start(b) => {
+                let start = i;
+                while i < bytes.len() && is_name_char(bytes[i]) {
+                    i += 1;
+                }
+                visit(Token::Name(start, &text[start..i]));
+            }
+            b if b.is_ascii_digit() || b == b'-' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len() && (is_name_char(bytes[i]) || b".+-".contains(&bytes[i])) {
+                    i += 1;
+                }
+                visit(Token::Number(start, &text[start..i]));
+            }
+            b if b.is_ascii_whitespace() || b == b',' => i += 1,
+            b if b.is_ascii() => {
+                visit(Token::Punct(i, b as char));
+                i += 1;
+            }
+            // Non-ASCII text only appears in strings and comments
+            _ => i += 1,
+        }
+    }
+}
+
+/// Skip leading whitespace and comments.
+pub(crate) fn skip_ignored(text: &str) -> &str {
+    let mut rest = text.trim_start();
+    while let Some(comment) = rest.strip_prefix('#') {
+        rest = comment
+            .find('\n')
+            .map(|i| &comment[i..])
+            .unwrap_or("")
+            .trim_start();
+    }
+    rest
+}
+
+fn is_name_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_'
+}
+
+/// Whether `b` can appear in a name after its first character.
+pub(crate) fn is_name_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_skips_comments_and_strings() {
+        let mut tokens = Vec::new();
+        scan(
+            "# first: 5\nquery($n: Int) { a(first: 10, q: \"b: { \\\" }\") }",
+            |token| tokens.push(token),
+        );
+
+        assert_eq!(
+            tokens,
+            [
+                Token::Name(11, "query"),
+                Token::Punct(16, '('),
+                Token::Variable(17, "n"),
+                Token::Punct(19, ':'),
+                Token::Name(21, "Int"),
+                Token::Punct(24, ')'),
+                Token::Punct(26, '{'),
+                Token::Name(28, "a"),
+                Token::Punct(29, '('),
+                Token::Name(30, "first"),
+                Token::Punct(35, ':'),
+                Token::Number(37, "10"),
+                Token::Name(41, "q"),
+                Token::Punct(42, ':'),
+                Token::Punct(55, ')'),
+                Token::Punct(57, '}'),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skip_ignored() {
+        assert_eq!(skip_ignored("  # a\n# b\n query"), "query");
+        assert_eq!(skip_ignored("# only a comment"), "");
+    }
+}
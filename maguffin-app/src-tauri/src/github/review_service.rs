@@ -0,0 +1,537 @@
+//! Pull Request Review Service.
+//!
+//! This module provides inline review of pull requests: per-file patches,
+//! review threads with their resolved state, and the pending-review
+//! workflow (start, comment on lines or ranges, submit or discard).
+
+use crate::domain::pr::{Author, ChangeType};
+use crate::domain::review::{
+    DiffSide, DraftReviewComment, FilePatch, ReviewComment, ReviewEvent, ReviewThread,
+    ReviewThreads,
+};
+use crate::error::{GitHubError, Result};
+use crate::github::queries::{
+    add_pull_request_review as add_review, add_pull_request_review_thread as add_thread,
+    delete_pull_request_review as delete_review, get_pending_review as pending,
+    get_pull_request_review_threads as threads, resolve_review_thread, submit_pull_request_review,
+    unresolve_review_thread, AddPullRequestReview, AddPullRequestReviewThread,
+    DeletePullRequestReview, GetPendingReview, GetPullRequestReviewThreads, ResolveReviewThread,
+    SubmitPullRequestReview, UnresolveReviewThread,
+};
+use crate::github::{GitHubClient, PrService};
+use serde::Deserialize;
+use std::sync::Arc;
+
+type GqlThread = threads::GetPullRequestReviewThreadsRepositoryPullRequestReviewThreadsNodes;
+type GqlAddedThread = add_thread::AddPullRequestReviewThreadAddPullRequestReviewThreadThread;
+
+/// Files per page of the REST pull request files endpoint.
+const FILES_PER_PAGE: usize = 100;
+
+/// The REST files endpoint returns at most 3000 files.
+const MAX_FILE_PAGES: usize = 30;
+
+/// A changed file as returned by `GET /repos/{owner}/{repo}/pulls/{number}/files`.
+#[derive(Debug, Deserialize)]
+struct RestPullRequestFile {
+    filename: String,
+    status: String,
+    additions: i32,
+    deletions: i32,
+    patch: Option<String>,
+    previous_filename: Option<String>,
+}
+
+/// The viewer's pending review and what's needed to start one.
+struct PendingReview {
+    pr_id: String,
+    head_oid: String,
+    review_id: Option<String>,
+}
+
+/// Service for reviewing pull requests.
+pub struct ReviewService {
+    client: Arc<GitHubClient>,
+    owner: String,
+    repo: String,
+}
+
+impl ReviewService {
+    /// Create a new review service.
+    pub fn new(client: Arc<GitHubClient>, owner: String, repo: String) -> Self {
+        Self {
+            client,
+            owner,
+            repo,
+        }
+    }
+
+    /// Get the patch of every file changed by a PR.
+    ///
+    /// GraphQL doesn't expose diff content, so this uses the REST API.
+    pub async fn get_patches(&self, number: i64) -> Result<Vec<FilePatch>> {
+        let mut patches = Vec::new();
+
+        for page in 1..=MAX_FILE_PAGES {
+            let path = format!(
+                "repos/{}/{}/pulls/{}/files?per_page={}&page={}",
+                self.owner, self.repo, number, FILES_PER_PAGE, page
+            );
+            let files: Vec<RestPullRequestFile> = self.client.rest_get(&path).await?;
+            let last_page = files.len() < FILES_PER_PAGE;
+
+            patches.extend(files.into_iter().map(Self::convert_file));
+
+            if last_page {
+                break;
+            }
+        }
+
+        Ok(patches)
+    }
+
+    /// Get all review threads of a PR and the viewer's pending review.
+    pub async fn get_threads(&self, number: i64) -> Result<ReviewThreads> {
+        let mut all_threads = Vec::new();
+        let mut after = None;
+
+        loop {
+            let variables = threads::Variables {
+                owner: self.owner.clone(),
+                repo: self.repo.clone(),
+                number,
+                after,
+            };
+
+            let response = self
+                .client
+                .execute::<GetPullRequestReviewThreads>(variables)
+                .await?;
+            let connection = response
+                .repository
+                .ok_or_else(PrService::repository_not_found)?
+                .pull_request
+                .ok_or_else(|| PrService::pr_not_found(number))?
+                .review_threads;
+
+            all_threads.extend(
+                connection
+                    .nodes
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flatten()
+                    .map(Self::convert_thread),
+            );
+
+            if !connection.page_info.has_next_page {
+                break;
+            }
+            after = connection.page_info.end_cursor;
+        }
+
+        let pending_review_id = self.pending_review(number).await?.review_id;
+
+        Ok(ReviewThreads {
+            pending_review_id,
+            threads: all_threads,
+        })
+    }
+
+    /// Start a pending review, or return the one already in progress.
+    ///
+    /// The review is anchored to the PR's current head commit.
+    pub async fn start_review(&self, number: i64) -> Result<String> {
+        let pending = self.pending_review(number).await?;
+        if let Some(review_id) = pending.review_id {
+            return Ok(review_id);
+        }
+
+        let variables = add_review::Variables {
+            pull_request_id: pending.pr_id,
+            commit_oid: Some(pending.head_oid),
+            event: None,
+            body: None,
+        };
+
+        let response = self
+            .client
+            .execute::<AddPullRequestReview>(variables)
+            .await?;
+
+        response
+            .add_pull_request_review
+            .and_then(|payload| payload.pull_request_review)
+            .map(|review| review.id)
+            .ok_or_else(|| GitHubError::GraphQL("Failed to start review".to_string()).into())
+    }
+
+    /// Add a line or multi-line comment to the viewer's pending review,
+    /// starting one if needed.
+    pub async fn add_comment(
+        &self,
+        number: i64,
+        comment: DraftReviewComment,
+    ) -> Result<ReviewThread> {
+        comment.validate().map_err(GitHubError::InvalidRequest)?;
+        let review_id = self.start_review(number).await?;
+
+        let side = |side: DiffSide| match side {
+            DiffSide::Left => add_thread::DiffSide::LEFT,
+            DiffSide::Right => add_thread::DiffSide::RIGHT,
+        };
+
+        let variables = add_thread::Variables {
+            pull_request_review_id: review_id,
+            path: comment.path,
+            body: comment.body,
+            line: comment.line as i64,
+            side: side(comment.side),
+            start_line: comment.start_line.map(i64::from),
+            start_side: comment
+                .start_line
+                .map(|_| side(comment.start_side.unwrap_or(comment.side))),
+        };
+
+        let response = self
+            .client
+            .execute::<AddPullRequestReviewThread>(variables)
+            .await?;
+
+        response
+            .add_pull_request_review_thread
+            .and_then(|payload| payload.thread)
+            .map(Self::convert_added_thread)
+            .ok_or_else(|| GitHubError::GraphQL("Failed to add review comment".to_string()).into())
+    }
+
+    /// Submit the viewer's review.
+    ///
+    /// Submits the pending review if there is one; otherwise the review is
+    /// created and submitted in one step, e.g. to approve without comments.
+    pub async fn submit_review(
+        &self,
+        number: i64,
+        event: ReviewEvent,
+        body: Option<String>,
+    ) -> Result<()> {
+        let pending = self.pending_review(number).await?;
+        let body = body.filter(|b| !b.trim().is_empty());
+
+        if event == ReviewEvent::RequestChanges && body.is_none() && pending.review_id.is_none() {
+            return Err(GitHubError::InvalidRequest(
+                "Requesting changes needs a comment".to_string(),
+            )
+            .into());
+        }
+
+        let Some(review_id) = pending.review_id else {
+            let event = match event {
+                ReviewEvent::Approve => add_review::PullRequestReviewEvent::APPROVE,
+                ReviewEvent::Comment => add_review::PullRequestReviewEvent::COMMENT,
+                ReviewEvent::RequestChanges => add_review::PullRequestReviewEvent::REQUEST_CHANGES,
+            };
+            let variables = add_review::Variables {
+                pull_request_id: pending.pr_id,
+                commit_oid: Some(pending.head_oid),
+                event: Some(event),
+                body,
+            };
+            self.client
+                .execute::<AddPullRequestReview>(variables)
+                .await?
+                .add_pull_request_review
+                .and_then(|payload| payload.pull_request_review)
+                .ok_or_else(|| GitHubError::GraphQL("Failed to submit review".to_string()))?;
+            return Ok(());
+        };
+
+        let event = match event {
+            ReviewEvent::Approve => submit_pull_request_review::PullRequestReviewEvent::APPROVE,
+            ReviewEvent::Comment => submit_pull_request_review::PullRequestReviewEvent::COMMENT,
+            ReviewEvent::RequestChanges => {
+                submit_pull_request_review::PullRequestReviewEvent::REQUEST_CHANGES
+            }
+        };
+        let variables = submit_pull_request_review::Variables {
+            pull_request_review_id: review_id,
+            event,
+            body,
+        };
+
+        self.client
+            .execute::<SubmitPullRequestReview>(variables)
+            .await?
+            .submit_pull_request_review
+            .and_then(|payload| payload.pull_request_review)
+            .ok_or_else(|| GitHubError::GraphQL("Failed to submit review".to_string()))?;
+
+        Ok(())
+    }
+
+    /// Discard the viewer's pending review and its comments.
+    ///
+    /// Returns false if there was no pending review.
+    pub async fn discard_review(&self, number: i64) -> Result<bool> {
+        let Some(review_id) = self.pending_review(number).await?.review_id else {
+            return Ok(false);
+        };
+
+        let variables = delete_review::Variables {
+            pull_request_review_id: review_id,
+        };
+
+        let response = self
+            .client
+            .execute::<DeletePullRequestReview>(variables)
+            .await?;
+
+        Ok(response
+            .delete_pull_request_review
+            .and_then(|payload| payload.pull_request_review)
+            .is_some())
+    }
+
+    /// Resolve or unresolve a review thread.
+    ///
+    /// Returns the thread's resolved state afterwards.
+    pub async fn set_thread_resolved(&self, thread_id: String, resolved: bool) -> Result<bool> {
+        let is_resolved = if resolved {
+            let variables = resolve_review_thread::Variables { thread_id };
+            self.client
+                .execute::<ResolveReviewThread>(variables)
+                .await?
+                .resolve_review_thread
+                .and_then(|payload| payload.thread)
+                .map(|thread| thread.is_resolved)
+        } else {
+            let variables = unresolve_review_thread::Variables { thread_id };
+            self.client
+                .execute::<UnresolveReviewThread>(variables)
+                .await?
+                .unresolve_review_thread
+                .and_then(|payload| payload.thread)
+                .map(|thread| thread.is_resolved)
+        };
+
+        is_resolved.ok_or_else(|| {
+            GitHubError::GraphQL("Failed to update review thread".to_string()).into()
+        })
+    }
+
+    /// Look up the viewer's pending review on a PR.
+    async fn pending_review(&self, number: i64) -> Result<PendingReview> {
+        let variables = pending::Variables {
+            owner: self.owner.clone(),
+            repo: self.repo.clone(),
+            number,
+        };
+
+        let response = self.client.execute::<GetPendingReview>(variables).await?;
+        let pr = response
+            .repository
+            .ok_or_else(PrService::repository_not_found)?
+            .pull_request
+            .ok_or_else(|| PrService::pr_not_found(number))?;
+
+        let review_id = pr
+            .reviews
+            .and_then(|reviews| reviews.nodes)
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .find(|review| review.viewer_did_author)
+            .map(|review| review.id);
+
+        Ok(PendingReview {
+            pr_id: pr.id,
+            head_oid: pr.head_ref_oid,
+            review_id,
+        })
+    }
+
+    /// Convert a REST changed file to a patch.
+    fn convert_file(file: RestPullRequestFile) -> FilePatch {
+        let change_type = match file.status.as_str() {
+            "added" => ChangeType::Added,
+            "removed" => ChangeType::Deleted,
+            "modified" => ChangeType::Modified,
+            "renamed" => ChangeType::Renamed,
+            "copied" => ChangeType::Copied,
+            _ => ChangeType::Changed,
+        };
+        let hunks = file
+            .patch
+            .as_deref()
+            .map(FilePatch::parse_hunks)
+            .unwrap_or_default();
+
+        FilePatch {
+            path: file.filename,
+            previous_path: file.previous_filename,
+            change_type,
+            additions: file.additions,
+            deletions: file.deletions,
+            patch: file.patch,
+            hunks,
+        }
+    }
+
+    /// Convert a GraphQL review thread to domain type.
+    fn convert_thread(thread: GqlThread) -> ReviewThread {
+        let side = |side: threads::DiffSide| match side {
+            threads::DiffSide::LEFT => DiffSide::Left,
+            threads::DiffSide::RIGHT | threads::DiffSide::Other(_) => DiffSide::Right,
+        };
+
+        let comments = thread
+            .comments
+            .nodes
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .map(|c| ReviewComment {
+                id: c.id,
+                author: c
+                    .author
+                    .map(|a| Author {
+                        login: a.login,
+                        avatar_url: a.avatar_url,
+                    })
+                    .unwrap_or_else(PrService::ghost),
+                body: c.body,
+                created_at: PrService::parse_datetime(&c.created_at),
+                is_pending: matches!(c.state, threads::PullRequestReviewCommentState::PENDING),
+            })
+            .collect();
+
+        ReviewThread {
+            id: thread.id,
+            path: thread.path,
+            line: thread.line.map(|l| l as i32),
+            start_line: thread.start_line.map(|l| l as i32),
+            side: side(thread.diff_side),
+            start_side: thread.start_diff_side.map(side),
+            is_resolved: thread.is_resolved,
+            is_outdated: thread.is_outdated,
+            can_resolve: thread.viewer_can_resolve || thread.viewer_can_unresolve,
+            comments,
+        }
+    }
+
+    /// Convert the thread returned by `addPullRequestReviewThread`.
+    fn convert_added_thread(thread: GqlAddedThread) -> ReviewThread {
+        let side = |side: add_thread::DiffSide| match side {
+            add_thread::DiffSide::LEFT => DiffSide::Left,
+            add_thread::DiffSide::RIGHT | add_thread::DiffSide::Other(_) => DiffSide::Right,
+        };
+
+        let comments = thread
+            .comments
+            .nodes
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .map(|c| ReviewComment {
+                id: c.id,
+                author: c
+                    .author
+                    .map(|a| Author {
+                        login: a.login,
+                        avatar_url: a.avatar_url,
+                    })
+                    .unwrap_or_else(PrService::ghost),
+                body: c.body,
+                created_at: PrService::parse_datetime(&c.created_at),
+                is_pending: matches!(c.state, add_thread::PullRequestReviewCommentState::PENDING),
+            })
+            .collect();
+
+        ReviewThread {
+            id: thread.id,
+            path: thread.path,
+            line: thread.line.map(|l| l as i32),
+            start_line: thread.start_line.map(|l| l as i32),
+            side: side(thread.diff_side),
+            start_side: thread.start_diff_side.map(side),
+            is_resolved: thread.is_resolved,
+            is_outdated: thread.is_outdated,
+            can_resolve: thread.viewer_can_resolve || thread.viewer_can_unresolve,
+            comments,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_rest_file() {
+        let file: RestPullRequestFile = serde_json::from_value(serde_json::json!({
+            "sha": "abc123",
+            "filename": "src/new.rs",
+            "previous_filename": "src/old.rs",
+            "status": "renamed",
+            "additions": 1,
+            "deletions": 1,
+            "changes": 2,
+            "patch": "@@ -3,2 +3,2 @@ fn main() {\n-    old();\n+    new();\n }"
+        }))
+        .unwrap();
+
+        let patch = ReviewService::convert_file(file);
+        assert_eq!(patch.change_type, ChangeType::Renamed);
+        assert_eq!(patch.previous_path.as_deref(), Some("src/old.rs"));
+        assert_eq!(patch.hunks.len(), 1);
+        assert!(patch.contains_line(DiffSide::Right, 4));
+    }
+
+    #[test]
+    fn test_convert_binary_file_has_no_hunks() {
+        let file: RestPullRequestFile = serde_json::from_value(serde_json::json!({
+            "filename": "logo.png",
+            "status": "added",
+            "additions": 0,
+            "deletions": 0
+        }))
+        .unwrap();
+
+        let patch = ReviewService::convert_file(file);
+        assert!(patch.patch.is_none());
+        assert!(patch.hunks.is_empty());
+    }
+
+    #[test]
+    fn test_thread_deserializes_pending_comments() {
+        let thread: GqlThread = serde_json::from_value(serde_json::json!({
+            "id": "PRRT_1",
+            "path": "src/lib.rs",
+            "line": 12,
+            "startLine": 10,
+            "diffSide": "RIGHT",
+            "startDiffSide": "RIGHT",
+            "isResolved": false,
+            "isOutdated": false,
+            "viewerCanResolve": true,
+            "viewerCanUnresolve": false,
+            "comments": {
+                "nodes": [{
+                    "id": "PRRC_1",
+                    "author": { "__typename": "User", "login": "octocat", "avatarUrl": "" },
+                    "body": "Nit",
+                    "createdAt": "2025-01-01T00:00:00Z",
+                    "state": "PENDING"
+                }]
+            }
+        }))
+        .unwrap();
+
+        let thread = ReviewService::convert_thread(thread);
+        assert_eq!(thread.line, Some(12));
+        assert_eq!(thread.start_line, Some(10));
+        assert_eq!(thread.side, DiffSide::Right);
+        assert!(thread.can_resolve);
+        assert!(thread.comments[0].is_pending);
+        assert_eq!(thread.comments[0].author.login, "octocat");
+    }
+}
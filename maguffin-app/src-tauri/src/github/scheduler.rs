@@ -0,0 +1,391 @@
+//! Client-side request scheduling for the GitHub API.
+//!
+//! Limits the number of requests in flight, hands free slots to
+//! user-initiated requests before background sync requests, pauses all
+//! requests while GitHub's secondary rate limit is in effect, and cancels
+//! pending requests when the repository context changes.
+//!
+//! Requests are user-initiated unless they run inside [`background`], so
+//! command handlers need no changes; the sync loop opts into the lower
+//! priority.
+
+use crate::error::{GitHubError, Result};
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{oneshot, watch};
+use tokio::time::{sleep, Instant};
+
+/// Priority of a GitHub API request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Background work such as periodic sync
+    Background,
+    /// Requests made on behalf of the user (merge, create PR, ...)
+    Interactive,
+}
+
+tokio::task_local! {
+    static PRIORITY: Priority;
+}
+
+/// Run `future` with its GitHub API requests scheduled as background work.
+pub async fn background<F: Future>(future: F) -> F::Output {
+    PRIORITY.scope(Priority::Background, future).await
+}
+
+/// Priority of requests made by the current task.
+pub fn current_priority() -> Priority {
+    PRIORITY.try_with(|p| *p).unwrap_or(Priority::Interactive)
+}
+
+/// Add random jitter to a backoff duration.
+///
+/// Returns a duration between half and all of `duration`, so clients that
+/// were throttled together don't retry in lockstep.
+pub fn jittered(duration: Duration) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    let fraction = 0.5 + (random % 1000) as f64 / 2000.0;
+    duration.mul_f64(fraction)
+}
+
+/// Waiters for a free request slot, by priority.
+#[derive(Default)]
+struct Queue {
+    /// Number of requests currently holding a slot
+    in_flight: usize,
+    interactive: VecDeque<oneshot::Sender<RequestPermit>>,
+    background: VecDeque<oneshot::Sender<RequestPermit>>,
+    /// Requests wait until then before starting (secondary rate limit)
+    paused_until: Option<Instant>,
+}
+
+struct Inner {
+    max_concurrent: usize,
+    queue: Mutex<Queue>,
+}
+
+impl Inner {
+    /// Slots background requests may use; one is kept free for user requests.
+    fn background_limit(&self) -> usize {
+        self.max_concurrent.saturating_sub(1).max(1)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Hand a released slot to the next waiter, or free it.
+    fn release(self: &Arc<Self>) {
+        let waiter = {
+            let mut queue = self.lock();
+            // The releasing request still counts as in flight here
+            let others = queue.in_flight - 1;
+            let next = queue.interactive.pop_front().or_else(|| {
+                if others < self.background_limit() {
+                    queue.background.pop_front()
+                } else {
+                    None
+                }
+            });
+            if next.is_none() {
+                queue.in_flight -= 1;
+            }
+            next
+        };
+
+        // Transfer the slot outside the lock: if the waiter was cancelled the
+        // permit comes back and dropping it releases the slot again.
+        if let Some(waiter) = waiter {
+            let _ = waiter.send(RequestPermit {
+                inner: self.clone(),
+            });
+        }
+    }
+}
+
+/// A slot for one in-flight request; released on drop.
+pub struct RequestPermit {
+    inner: Arc<Inner>,
+}
+
+impl Drop for RequestPermit {
+    fn drop(&mut self) {
+        self.inner.release();
+    }
+}
+
+/// Concurrency-limited, priority-aware request scheduler.
+pub struct RequestScheduler {
+    inner: Arc<Inner>,
+    generation: watch::Sender<u64>,
+}
+
+impl RequestScheduler {
+    /// Create a scheduler allowing `max_concurrent` requests in flight.
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                max_concurrent: max_concurrent.max(1),
+                queue: Mutex::new(Queue::default()),
+            }),
+            generation: watch::Sender::new(0),
+        }
+    }
+
+    /// Maximum number of requests in flight.
+    pub fn max_concurrent(&self) -> usize {
+        self.inner.max_concurrent
+    }
+
+    /// Current generation; bumped by [`RequestScheduler::cancel_all`].
+    pub fn generation(&self) -> u64 {
+        *self.generation.borrow()
+    }
+
+    /// Cancel every request started in an earlier generation that is still
+    /// waiting for a slot or backing off, and in-flight background requests.
+    pub fn cancel_all(&self) {
+        self.generation.send_modify(|g| *g += 1);
+    }
+
+    /// Pause all requests for `duration`, e.g. as told by a `retry-after`
+    /// header.
+    pub fn pause_for(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut queue = self.inner.lock();
+        queue.paused_until = Some(queue.paused_until.map_or(until, |p| p.max(until)));
+    }
+
+    /// Time left until requests may start again, if paused.
+    pub fn pause_remaining(&self) -> Option<Duration> {
+        let paused_until = self.inner.lock().paused_until?;
+        let now = Instant::now();
+        (paused_until > now).then(|| paused_until - now)
+    }
+
+    /// Run `future` unless the scheduler moves past `generation` first.
+    pub async fn cancellable<F: Future>(&self, generation: u64, future: F) -> Result<F::Output> {
+        let mut changes = self.generation.subscribe();
+        tokio::select! {
+            output = future => Ok(output),
+            _ = changes.wait_for(|g| *g != generation) => Err(GitHubError::Cancelled.into()),
+        }
+    }
+
+    /// Wait for a request slot.
+    ///
+    /// Interactive requests are served before queued background requests, and
+    /// background requests never take the last free slot. Fails with
+    /// [`GitHubError::Cancelled`] if the generation changes while waiting.
+    pub async fn acquire(&self, priority: Priority, generation: u64) -> Result<RequestPermit> {
+        loop {
+            if let Some(wait) = self.pause_remaining() {
+                self.cancellable(generation, sleep(wait)).await?;
+                continue;
+            }
+
+            let waiter = {
+                let mut queue = self.inner.lock();
+                // Drop waiters that were cancelled while queued
+                queue.interactive.retain(|tx| !tx.is_closed());
+                queue.background.retain(|tx| !tx.is_closed());
+
+                let can_start = match priority {
+                    Priority::Interactive => {
+                        queue.in_flight < self.inner.max_concurrent && queue.interactive.is_empty()
+                    }
+                    Priority::Background => {
+                        queue.in_flight < self.inner.background_limit()
+                            && queue.interactive.is_empty()
+                            && queue.background.is_empty()
+                    }
+                };
+
+                if can_start {
+                    queue.in_flight += 1;
+                    return Ok(RequestPermit {
+                        inner: self.inner.clone(),
+                    });
+                }
+
+                let (tx, rx) = oneshot::channel();
+                match priority {
+                    Priority::Interactive => queue.interactive.push_back(tx),
+                    Priority::Background => queue.background.push_back(tx),
+                }
+                rx
+            };
+
+            if let Ok(permit) = self.cancellable(generation, waiter).await? {
+                return Ok(permit);
+            }
+        }
+    }
+}
+
+impl Default for RequestScheduler {
+    fn default() -> Self {
+        Self::new(crate::config::GitHubConfig::default().max_concurrent_requests)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_limits_concurrency() {
+        let scheduler = RequestScheduler::new(2);
+        let generation = scheduler.generation();
+
+        let first = scheduler
+            .acquire(Priority::Interactive, generation)
+            .await
+            .unwrap();
+        let _second = scheduler
+            .acquire(Priority::Interactive, generation)
+            .await
+            .unwrap();
+
+        let third = scheduler.acquire(Priority::Interactive, generation);
+        tokio::pin!(third);
+        assert!(tokio::time::timeout(Duration::from_millis(20), &mut third)
+            .await
+            .is_err());
+
+        drop(first);
+        assert!(third.await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_background_keeps_a_slot_free() {
+        let scheduler = RequestScheduler::new(2);
+        let generation = scheduler.generation();
+
+        let _background = scheduler
+            .acquire(Priority::Background, generation)
+            .await
+            .unwrap();
+        let blocked = tokio::time::timeout(
+            Duration::from_millis(20),
+            scheduler.acquire(Priority::Background, generation),
+        )
+        .await;
+        assert!(blocked.is_err());
+
+        // The reserved slot is still available to the user
+        let interactive = tokio::time::timeout(
+            Duration::from_millis(20),
+            scheduler.acquire(Priority::Interactive, generation),
+        )
+        .await;
+        assert!(interactive.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_interactive_preempts_queued_background() {
+        let scheduler = Arc::new(RequestScheduler::new(1));
+        let generation = scheduler.generation();
+        let held = scheduler
+            .acquire(Priority::Interactive, generation)
+            .await
+            .unwrap();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let spawn = |priority: Priority| {
+            let scheduler = scheduler.clone();
+            let order = order.clone();
+            tokio::spawn(async move {
+                let _permit = scheduler.acquire(priority, generation).await.unwrap();
+                order.lock().unwrap().push(priority);
+            })
+        };
+
+        let background = spawn(Priority::Background);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let interactive = spawn(Priority::Interactive);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        drop(held);
+        interactive.await.unwrap();
+        background.await.unwrap();
+
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec![Priority::Interactive, Priority::Background]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_all_aborts_waiters() {
+        let scheduler = Arc::new(RequestScheduler::new(1));
+        let generation = scheduler.generation();
+        let held = scheduler
+            .acquire(Priority::Interactive, generation)
+            .await
+            .unwrap();
+
+        let waiter = {
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move {
+                scheduler
+                    .acquire(Priority::Background, generation)
+                    .await
+                    .map(|_| ())
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        scheduler.cancel_all();
+        let result = waiter.await.unwrap();
+        assert!(matches!(
+            result,
+            Err(crate::error::AppError::GitHub(GitHubError::Cancelled))
+        ));
+
+        // The cancelled waiter doesn't leak the slot it was queued for
+        drop(held);
+        let next = tokio::time::timeout(
+            Duration::from_millis(20),
+            scheduler.acquire(Priority::Interactive, scheduler.generation()),
+        )
+        .await;
+        assert!(next.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_pause_delays_requests() {
+        let scheduler = RequestScheduler::new(1);
+        scheduler.pause_for(Duration::from_millis(50));
+        assert!(scheduler.pause_remaining().is_some());
+
+        let started = Instant::now();
+        let _permit = scheduler
+            .acquire(Priority::Interactive, scheduler.generation())
+            .await
+            .unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_jittered_stays_within_bounds() {
+        let base = Duration::from_secs(60);
+        for _ in 0..100 {
+            let d = jittered(base);
+            assert!(d >= Duration::from_secs(30) && d <= base);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_priority_is_task_local() {
+        assert_eq!(current_priority(), Priority::Interactive);
+        background(async {
+            assert_eq!(current_priority(), Priority::Background);
+        })
+        .await;
+    }
+}
@@ -3,69 +3,409 @@
 //! This module provides the service layer for managing stacked branches,
 //! including creation, restacking, and reconciliation.
 
+use crate::domain::pr::PullRequest;
+use crate::domain::stack::edit::{
+    BranchCommit, BranchMove, BranchSnapshot, EditPlan, StackEdit, StackEditPreview,
+    StackEditResult,
+};
 use crate::domain::stack::{
-    BranchStatus, ReconcileReport, RestackBranchPreview, RestackConflict, RestackPreview,
-    RestackResult, RestackStatus, Stack, StackBranch, StackMetadata, Warning,
+    self, BranchStatus, DiscoveredStack, ReconcileReport, RestackBranchPreview, RestackConflict,
+    RestackPreview, RestackResult, RestackStatus, Stack, StackBranch, StackImport, StackMetadata,
+    Warning,
 };
-use crate::error::{GitError, Result};
+use crate::error::{AppError, GitError, GitHubError, Result, StorageError};
+use crate::git::stack_interop::StackTool;
+use crate::git::stack_store::{StackStorage, StackStore};
 use crate::git::{Git2Backend, GitOperations};
 use crate::github::pr_service::PrService;
 use chrono::Utc;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::RwLock;
 
+/// How many times a save merges in a concurrent write and retries.
+const SAVE_ATTEMPTS: usize = 3;
+
+/// How often [`StackService::watch`] checks for external changes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many stack edits can be undone.
+const UNDO_LIMIT: usize = 20;
+
+/// What it takes to undo a stack edit.
+#[derive(Debug, Clone)]
+struct EditUndo {
+    /// Entries of the branches the edit touched, from before it
+    branches: Vec<BranchSnapshot>,
+
+    /// Each rewritten branch with its head before and after the edit
+    heads: Vec<(String, String, String)>,
+
+    /// Branch the edit created
+    created: Option<String>,
+
+    /// Branch the edit deleted, with its head
+    deleted: Option<(String, String)>,
+
+    /// Parent changes the edit made
+    moves: Vec<BranchMove>,
+}
+
+impl EditUndo {
+    /// What it takes to undo reverting this edit, given the metadata
+    /// before it is reverted.
+    fn inverse(&self, metadata: &StackMetadata) -> EditUndo {
+        EditUndo {
+            branches: metadata.snapshot_branches(self.branches.iter().map(|b| b.name.as_str())),
+            heads: self
+                .heads
+                .iter()
+                .filter(|(name, _, _)| Some(name) != self.created.as_ref())
+                .map(|(name, before, after)| (name.clone(), after.clone(), before.clone()))
+                .collect(),
+            created: self.deleted.as_ref().map(|(name, _)| name.clone()),
+            deleted: self.created.as_ref().and_then(|created| {
+                self.heads
+                    .iter()
+                    .find(|(name, _, _)| name == created)
+                    .map(|(name, _, after)| (name.clone(), after.clone()))
+            }),
+            moves: self
+                .moves
+                .iter()
+                .filter_map(|m| {
+                    Some(BranchMove {
+                        branch: m.branch.clone(),
+                        from: Some(m.to.clone()),
+                        to: m.from.clone()?,
+                        pr_number: m.pr_number,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
 /// Service for managing stacked branches and PRs.
 pub struct StackService {
-    /// Path to the repository
-    repo_path: PathBuf,
-
     /// Git backend for local operations (uses std::sync::Mutex as git2::Repository is not Send)
     git: Arc<Mutex<Git2Backend>>,
 
     /// PR service for GitHub operations
     pr_service: Option<Arc<PrService>>,
 
+    /// Where the metadata is persisted
+    store: Mutex<StackStore>,
+
+    /// Metadata as last loaded or saved, the base for merging in changes
+    /// saved by another process
+    base: Mutex<StackMetadata>,
+
     /// Stack metadata
     metadata: Arc<RwLock<StackMetadata>>,
+
+    /// Stack edits that can be undone, most recent last
+    undo: Mutex<Vec<EditUndo>>,
 }
 
 impl StackService {
     /// Create a new stack service.
+    ///
+    /// Unreadable metadata is rebuilt from local branch ancestry; use
+    /// [`StackService::open`] to take PR bases into account too.
     pub fn new(repo_path: PathBuf, git: Git2Backend) -> Result<Self> {
-        let metadata_path = repo_path.join(".git").join("stack-metadata.json");
-        let metadata = if metadata_path.exists() {
-            let content = std::fs::read_to_string(&metadata_path)
-                .map_err(|e| GitError::RepositoryNotFound(e.to_string()))?;
-            serde_json::from_str(&content).unwrap_or_else(|_| StackMetadata::default())
-        } else {
-            StackMetadata::default()
+        Self::open(repo_path, git, &[])
+    }
+
+    /// Create a new stack service, rebuilding unreadable metadata from the
+    /// repository's branches and `prs`, its open PRs.
+    ///
+    /// Fails if the metadata was written by a newer version, rather than
+    /// overwrite what it doesn't understand.
+    pub fn open(repo_path: PathBuf, git: Git2Backend, prs: &[PullRequest]) -> Result<Self> {
+        let store = StackStore::open(&repo_path)?;
+        let metadata = match store.load() {
+            Ok(metadata) => metadata.unwrap_or_default(),
+            Err(AppError::Storage(StorageError::Serialization(e))) => {
+                tracing::warn!("Rebuilding unreadable stack metadata: {}", e);
+                store.set_aside()?;
+                let mut metadata = Self::rebuild(&git, prs)?;
+                store.save(&mut metadata)?;
+                metadata
+            }
+            Err(e) => return Err(e),
         };
 
         Ok(Self {
-            repo_path,
             git: Arc::new(Mutex::new(git)),
             pr_service: None,
+            store: Mutex::new(store),
+            base: Mutex::new(metadata.clone()),
             metadata: Arc::new(RwLock::new(metadata)),
+            undo: Mutex::new(Vec::new()),
         })
     }
 
+    /// Stacks found among the repository's branches and `prs`, for metadata
+    /// that was lost.
+    fn rebuild(git: &Git2Backend, prs: &[PullRequest]) -> Result<StackMetadata> {
+        let trunk = git.default_branch().unwrap_or_else(|_| "main".to_string());
+        let mut metadata = StackMetadata::default();
+        let discovered = Self::discover(git, prs, &trunk, &metadata)?;
+
+        for discovered in discovered {
+            let mut stack = discovered.into_stack();
+            for branch in &mut stack.branches {
+                branch.head_sha = git.get_head_sha(&branch.name).ok();
+            }
+            metadata.add_stack(stack);
+        }
+        tracing::info!("Rebuilt {} stacks", metadata.stacks.len());
+
+        Ok(metadata)
+    }
+
+    /// Stacks found among the repository's branches and `prs` that aren't
+    /// in `known` stacks.
+    fn discover(
+        git: &Git2Backend,
+        prs: &[PullRequest],
+        trunk: &str,
+        known: &StackMetadata,
+    ) -> Result<Vec<DiscoveredStack>> {
+        let head = |branch: &str| format!("refs/heads/{}", branch);
+        Ok(stack::discover_stacks(
+            prs,
+            &git.local_branches()?,
+            trunk,
+            known,
+            |branch| git.merge_base(&head(branch), &head(trunk)).ok(),
+            |ancestor, descendant| git.is_ancestor(ancestor, descendant).unwrap_or(false),
+        ))
+    }
+
     /// Set the PR service for GitHub operations.
     pub fn with_pr_service(mut self, pr_service: Arc<PrService>) -> Self {
         self.pr_service = Some(pr_service);
         self
     }
 
-    /// Save metadata to disk.
+    /// Save metadata to the configured storage.
+    ///
+    /// If another process saved the metadata since it was loaded, its
+    /// changes are merged in first.
     async fn save_metadata(&self) -> Result<()> {
-        let metadata = self.metadata.read().await;
-        let content = serde_json::to_string_pretty(&*metadata)
-            .map_err(|e| GitError::RepositoryNotFound(e.to_string()))?;
+        let mut metadata = self.metadata.write().await;
+        let mut saved = metadata.clone();
+        let mut base = self.base.lock().expect("base lock poisoned").clone();
+
+        let (saved, base) = self
+            .with_store(move |store| {
+                let mut attempts = 0;
+                loop {
+                    match store.save(&mut saved) {
+                        Ok(()) => return Ok((saved.clone(), saved)),
+                        Err(AppError::Storage(StorageError::Conflict { .. }))
+                            if attempts < SAVE_ATTEMPTS =>
+                        {
+                            attempts += 1;
+                            let theirs = store.load()?.unwrap_or_default();
+                            let mut merged = StackMetadata::merge(Some(&base), &saved, &theirs);
+                            merged.revision = theirs.revision;
+                            saved = merged;
+                            base = theirs;
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+            })
+            .await?;
+        *metadata = saved;
+        *self.base.lock().expect("base lock poisoned") = base;
+        Ok(())
+    }
+
+    /// Run `f` on a copy of the store on the blocking thread pool, as store
+    /// I/O can wait on another process's lock.
+    async fn with_store<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut StackStore) -> Result<T> + Send + 'static,
+    {
+        let mut store = self.store.lock().expect("store lock poisoned").clone();
+        tokio::task::spawn_blocking(move || f(&mut store))
+            .await
+            .map_err(|e| AppError::Internal(format!("Task failed: {:?}", e)))?
+    }
+
+    /// Pick up metadata another process saved since it was last loaded.
+    ///
+    /// Returns whether anything was reloaded.
+    pub async fn reload(&self) -> Result<bool> {
+        let loaded = self.with_store(|store| store.load()).await?;
+        let Some(theirs) = loaded else {
+            return Ok(false);
+        };
+
+        let mut metadata = self.metadata.write().await;
+        if theirs.revision <= metadata.revision {
+            return Ok(false);
+        }
+
+        // Merge rather than replace, to keep edits not saved yet
+        let mut base = self.base.lock().expect("base lock poisoned");
+        let mut merged = StackMetadata::merge(Some(&base), &metadata, &theirs);
+        merged.revision = theirs.revision;
+        *metadata = merged;
+        *base = theirs;
+        Ok(true)
+    }
+
+    /// Reload the metadata whenever another process, such as a CLI or a
+    /// second window, saves it.
+    ///
+    /// Polls the store's fingerprint, which doesn't read the metadata. The
+    /// task ends once the service is dropped.
+    pub fn watch(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let service = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(WATCH_INTERVAL);
+            let mut last = None;
+            loop {
+                interval.tick().await;
+                let Some(service) = service.upgrade() else {
+                    break;
+                };
+
+                let fingerprint = service.with_store(|store| store.fingerprint()).await;
+                match fingerprint {
+                    Ok(fingerprint) if fingerprint != last => {
+                        last = fingerprint;
+                        match service.reload().await {
+                            Ok(true) => tracing::debug!("Reloaded stack metadata"),
+                            Ok(false) => {}
+                            Err(e) => tracing::warn!("Failed to reload stack metadata: {}", e),
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Failed to check stack metadata: {}", e),
+                }
+            }
+        })
+    }
 
-        let metadata_path = self.repo_path.join(".git").join("stack-metadata.json");
-        std::fs::write(&metadata_path, content)
-            .map_err(|e| GitError::RepositoryNotFound(e.to_string()))?;
+    /// Revision of the metadata, which changes whenever it is saved or
+    /// reloaded.
+    pub async fn revision(&self) -> u64 {
+        self.metadata.read().await.revision
+    }
+
+    /// Where the stack metadata is kept.
+    pub fn storage(&self) -> StackStorage {
+        self.store.lock().expect("store lock poisoned").storage()
+    }
+
+    /// Move the stack metadata to another storage.
+    ///
+    /// Moving to ref storage also brings in the stacks already shared on
+    /// the default remote.
+    pub async fn set_storage(&self, storage: StackStorage) -> Result<()> {
+        {
+            let mut metadata = self.metadata.write().await;
+            let moved = metadata.clone();
+            let (store, moved) = self
+                .with_store(move |store| {
+                    let mut moved = moved;
+                    store.set_storage(storage, &mut moved)?;
+                    Ok((store.clone(), moved))
+                })
+                .await?;
+            *self.store.lock().expect("store lock poisoned") = store;
+            *self.base.lock().expect("base lock poisoned") = moved.clone();
+            *metadata = moved;
+        }
+
+        self.share_metadata().await;
+        Ok(())
+    }
+
+    /// Remote the stack metadata is shared through.
+    pub fn default_remote(&self) -> String {
+        self.git.lock().expect("git lock poisoned").default_remote()
+    }
+
+    /// Merge the stack metadata with the copy on `remote` and push the result.
+    ///
+    /// The metadata is only locked while merging, not during the fetch and
+    /// push, and if `remote` moves before the push, it is fetched and merged
+    /// again. Only available with ref storage.
+    pub async fn sync_metadata(&self, remote: &str) -> Result<()> {
+        let mut attempts = 0;
+        loop {
+            let fetched = remote.to_string();
+            self.with_store(move |store| store.fetch(&fetched)).await?;
+
+            let unpushed = {
+                let mut metadata = self.metadata.write().await;
+                let fetched = remote.to_string();
+                let (merged, unpushed) = self
+                    .with_store(move |store| store.merge_remote(&fetched))
+                    .await?;
+                *self.base.lock().expect("base lock poisoned") = merged.clone();
+                *metadata = merged;
+                unpushed
+            };
+            if !unpushed {
+                return Ok(());
+            }
+
+            let pushed = remote.to_string();
+            match self.with_store(move |store| store.push(&pushed)).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempts < SAVE_ATTEMPTS => {
+                    attempts += 1;
+                    tracing::debug!("Stack metadata push failed, merging again: {}", e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Share the stack metadata through the default remote, with ref
+    /// storage, after stack branches were pushed there.
+    ///
+    /// Failures are logged, as the branches themselves were pushed.
+    async fn share_metadata(&self) {
+        if self.storage() != StackStorage::Ref {
+            return;
+        }
+        if let Err(e) = self.sync_metadata(&self.default_remote()).await {
+            tracing::warn!("Failed to sync stack metadata: {}", e);
+        }
+    }
+
+    /// Record that `branch` was pushed with a new head outside of the
+    /// service, such as a PR branch rebased on update, and share the
+    /// metadata along with it.
+    ///
+    /// Does nothing for branches that aren't in a stack.
+    pub async fn branch_pushed(&self, branch: &str) -> Result<()> {
+        let head = self
+            .git
+            .lock()
+            .expect("git lock poisoned")
+            .get_head_sha(branch)?;
+        {
+            let mut metadata = self.metadata.write().await;
+            let Some(tracked) = metadata.find_branch_mut(branch) else {
+                return Ok(());
+            };
+            tracked.head_sha = Some(head);
+        }
 
+        self.save_metadata().await?;
+        self.share_metadata().await;
         Ok(())
     }
 
@@ -136,6 +476,101 @@ impl StackService {
         metadata.find_stack_containing(branch_name).cloned()
     }
 
+    /// Find stacks among existing branches and open PRs that aren't tracked yet.
+    ///
+    /// `prs` are the repository's open PRs; `trunk` is its default branch.
+    pub async fn discover_stacks(
+        &self,
+        prs: Vec<PullRequest>,
+        trunk: String,
+    ) -> Result<Vec<DiscoveredStack>> {
+        let metadata = self.metadata.read().await.clone();
+        let path = self
+            .git
+            .lock()
+            .expect("git lock poisoned")
+            .path()
+            .to_path_buf();
+
+        // The ancestry walks use a backend of their own, so other git
+        // operations don't wait for them
+        tokio::task::spawn_blocking(move || {
+            let git = Git2Backend::open(&path)?;
+            Self::discover(&git, &prs, &trunk, &metadata)
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Task failed: {:?}", e)))?
+    }
+
+    /// Start tracking a discovered stack, with the parents chosen for its branches.
+    pub async fn adopt_stack(&self, discovered: DiscoveredStack) -> Result<Stack> {
+        let mut stack = discovered.into_stack();
+
+        {
+            let git = self.git.lock().expect("git lock poisoned");
+            for branch in &mut stack.branches {
+                branch.head_sha = git.get_head_sha(&branch.name).ok();
+            }
+        }
+
+        let mut metadata = self.metadata.write().await;
+        if let Some(tracked) = stack
+            .branches
+            .iter()
+            .find(|b| metadata.find_stack_containing(&b.name).is_some())
+        {
+            return Err(GitError::Branch(format!("{} is already in a stack", tracked.name)).into());
+        }
+        metadata.add_stack(stack.clone());
+        drop(metadata);
+
+        self.save_metadata().await?;
+
+        Ok(stack)
+    }
+
+    /// Import the stacks recorded by another stacking tool.
+    ///
+    /// Branches that are already tracked are skipped rather than reparented.
+    pub async fn import_stacks(&self, tool: StackTool) -> Result<StackImport> {
+        let branches = {
+            let git = self.git.lock().expect("git lock poisoned");
+            git.read_stack_parents(tool)?
+        };
+
+        let mut metadata = self.metadata.write().await;
+        let import = metadata.import(branches);
+        if import.imported.is_empty() {
+            return Ok(import);
+        }
+
+        // Record head SHAs so the imported branches' first restack can be detected
+        {
+            let git = self.git.lock().expect("git lock poisoned");
+            for branch in metadata
+                .stacks
+                .iter_mut()
+                .flat_map(|s| &mut s.branches)
+                .filter(|b| import.imported.contains(&b.name))
+            {
+                branch.head_sha = git.get_head_sha(&branch.name).ok();
+            }
+        }
+        drop(metadata);
+
+        self.save_metadata().await?;
+
+        Ok(import)
+    }
+
+    /// Write every tracked branch's parent to git-town's config, so git-town
+    /// sees the same stacks. Returns the number of branches written.
+    pub async fn export_git_town(&self, trunk: &str) -> Result<usize> {
+        let metadata = self.metadata.read().await;
+        let git = self.git.lock().expect("git lock poisoned");
+        git.write_git_town_parents(&metadata, trunk)
+    }
+
     /// Restack all branches after a parent has been merged.
     pub async fn restack(&self, stack_id: uuid::Uuid) -> Result<RestackResult> {
         let stack = self
@@ -153,6 +588,7 @@ impl StackService {
 
         // Get branches in topological order
         let branches = stack.topological_order();
+        let mut pushed = false;
 
         for branch in branches {
             // Check if branch needs restacking
@@ -176,14 +612,17 @@ impl StackService {
             match rebase_result {
                 Ok(_) => {
                     // Force push after successful rebase
-                    let push_result = {
+                    let (remote, push_result) = {
                         let git = self.git.lock().expect("git lock poisoned");
-                        git.force_push(&branch.name, "origin")
+                        let remote = git.push_remote(&branch.name);
+                        let push_result = git.force_push(&branch.name, &remote);
+                        (remote, push_result)
                     };
 
                     match push_result {
                         Ok(_) => {
-                            tracing::info!("Force pushed branch {} to origin", branch.name);
+                            tracing::info!("Force pushed branch {} to {}", branch.name, remote);
+                            pushed = true;
                         }
                         Err(e) => {
                             tracing::warn!("Failed to force push {}: {}", branch.name, e);
@@ -253,6 +692,11 @@ impl StackService {
 
         self.save_metadata().await?;
 
+        // Share the stacks along with the force-pushed branches
+        if pushed {
+            self.share_metadata().await;
+        }
+
         Ok(result)
     }
 
@@ -362,6 +806,673 @@ impl StackService {
         }
     }
 
+    /// Preview what a stack edit will do without making any changes.
+    pub async fn preview_edit(&self, edit: &StackEdit) -> Result<StackEditPreview> {
+        let before = self.metadata.read().await.clone();
+        let mut after = before.clone();
+        let plan = after.apply_edit(edit)?;
+
+        let mut preview = StackEditPreview {
+            moves: plan.moves.clone(),
+            will_rebase: Vec::new(),
+            total_commits: 0,
+        };
+
+        let git = self.git.lock().expect("git lock poisoned");
+        for name in &plan.rebase {
+            let Some(upstream) = Self::edit_base(&git, &before, &after, name)? else {
+                continue;
+            };
+            let branch = Self::tracked(&after, name)?;
+            let commits = git.count_commits(&upstream, &format!("refs/heads/{}", name))?;
+
+            preview.will_rebase.push(RestackBranchPreview {
+                branch: name.clone(),
+                onto: branch.parent.clone(),
+                commits_to_replay: commits,
+                has_pr: branch.pr_number.is_some(),
+            });
+            preview.total_commits += commits;
+        }
+
+        Ok(preview)
+    }
+
+    /// Move, insert or reorder branches in their stacks.
+    ///
+    /// Every branch whose base moves is rebased onto its new parent and
+    /// force-pushed, and the PRs of moved branches are retargeted. If any
+    /// step fails, the steps already taken are reverted and nothing changes.
+    /// The edit can be reverted with [`StackService::undo_edit`].
+    pub async fn edit_stack(&self, edit: &StackEdit) -> Result<StackEditResult> {
+        let (plan, undo) = {
+            let mut metadata = self.metadata.write().await;
+            let mut edited = metadata.clone();
+            let plan = edited.apply_edit(edit)?;
+            if plan.moves.is_empty() {
+                return Ok(StackEditResult::default());
+            }
+
+            let undo = {
+                let git = self.git.lock().expect("git lock poisoned");
+                Self::rewrite(&git, &metadata, &mut edited, &plan)?
+            };
+            *metadata = edited;
+            (plan, undo)
+        };
+
+        let result = StackEditResult {
+            moves: plan.moves,
+            rebased: Self::rewritten(&undo),
+            closed_pr: None,
+        };
+        self.publish_edit(&result, &result.rebased, &undo).await?;
+        self.remember(undo);
+        Ok(result)
+    }
+
+    /// Undo the most recent stack edit.
+    ///
+    /// Branches are reset to where they were before the edit, so this is
+    /// refused once any of them has moved since. Only the parents and heads
+    /// the edit changed are put back; anything recorded about the branches
+    /// since, such as a new PR, is kept. A branch created by a split is
+    /// deleted on the remote as well, which closes any PR opened for it.
+    pub async fn undo_edit(&self) -> Result<StackEditResult> {
+        let undo = self
+            .undo
+            .lock()
+            .expect("undo lock poisoned")
+            .last()
+            .cloned()
+            .ok_or_else(|| GitError::Branch("Nothing to undo".to_string()))?;
+
+        let (redo, created) = {
+            let mut metadata = self.metadata.write().await;
+            let git = self.git.lock().expect("git lock poisoned");
+            if git.is_rebase_in_progress() {
+                return Err(GitError::Branch("A rebase is in progress".to_string()).into());
+            }
+            for (name, _, after) in &undo.heads {
+                if git.get_head_sha(name).ok().as_ref() != Some(after) {
+                    return Err(
+                        GitError::Branch(format!("{} has changed since the edit", name)).into(),
+                    );
+                }
+            }
+            if let Some((name, _)) = &undo.deleted {
+                if git.branch_exists(name)? {
+                    return Err(
+                        GitError::Branch(format!("{} has been recreated since", name)).into(),
+                    );
+                }
+            }
+            if undo.created.is_some() && git.current_branch().ok() == undo.created {
+                return Err(GitError::Branch(format!(
+                    "{} is checked out",
+                    undo.created.as_deref().unwrap_or_default()
+                ))
+                .into());
+            }
+
+            // Resolved before the branch and its config are deleted
+            let created = undo
+                .created
+                .as_ref()
+                .map(|name| (name.clone(), git.push_remote(name)));
+            let redo = undo.inverse(&metadata);
+            Self::revert(&git, &mut metadata, &undo)?;
+            (redo, created)
+        };
+
+        let result = StackEditResult {
+            moves: redo.moves.clone(),
+            rebased: Self::rewritten(&undo),
+            closed_pr: None,
+        };
+        self.publish_edit(&result, &result.rebased, &redo).await?;
+        self.undo.lock().expect("undo lock poisoned").pop();
+
+        if let Some((branch, remote)) = created {
+            let git = self.git.lock().expect("git lock poisoned");
+            if let Err(e) = git.delete_remote_branch(&branch, &remote) {
+                tracing::warn!("Failed to delete {} on {}: {}", branch, remote, e);
+            }
+        }
+        Ok(result)
+    }
+
+    /// List the commits of a stack branch since it forked from its parent,
+    /// oldest first.
+    pub async fn branch_commits(&self, branch: &str) -> Result<Vec<BranchCommit>> {
+        let parent = self
+            .metadata
+            .read()
+            .await
+            .find_branch(branch)
+            .map(|b| b.parent.clone())
+            .ok_or_else(|| GitError::Branch(format!("{} is not in a stack", branch)))?;
+
+        let git = self.git.lock().expect("git lock poisoned");
+        let head = format!("refs/heads/{}", branch);
+        let base = git.merge_base(&head, &format!("refs/heads/{}", parent))?;
+        git.list_commits(&base, &head)
+    }
+
+    /// Split `branch` into two stacked branches after commit `at`.
+    ///
+    /// `branch` keeps its commits up to and including `at`, and
+    /// `new_branch`, stacked on it, takes the rest. The children of `branch`
+    /// move to `new_branch`, which ends where `branch` did, so they need no
+    /// rebase; their PRs are retargeted to it. The split can be reverted
+    /// with [`StackService::undo_edit`].
+    pub async fn split_branch(
+        &self,
+        branch: &str,
+        at: &str,
+        new_branch: &str,
+    ) -> Result<StackEditResult> {
+        let mut metadata = self.metadata.write().await;
+        let mut edited = metadata.clone();
+        let stack_id = metadata
+            .find_stack_containing(branch)
+            .ok_or_else(|| GitError::Branch(format!("{} is not in a stack", branch)))?
+            .id;
+        let plan = edited.apply_edit(&StackEdit::Insert {
+            stack_id,
+            branch: new_branch.to_string(),
+            parent: branch.to_string(),
+        })?;
+
+        let undo = {
+            let git = self.git.lock().expect("git lock poisoned");
+            if git.is_rebase_in_progress() {
+                return Err(GitError::Branch("A rebase is in progress".to_string()).into());
+            }
+            if git.branch_exists(new_branch)? {
+                return Err(GitError::Branch(format!("{} already exists", new_branch)).into());
+            }
+
+            let parent = &Self::tracked(&metadata, branch)?.parent;
+            let head_ref = format!("refs/heads/{}", branch);
+            let base = git.merge_base(&head_ref, &format!("refs/heads/{}", parent))?;
+            let commits = git.list_commits(&base, &head_ref)?;
+            let sha = git.resolve_commit(at)?.id().to_string();
+            match commits.iter().position(|c| c.sha == sha) {
+                Some(i) if i + 1 < commits.len() => {}
+                Some(_) => {
+                    return Err(GitError::Branch(format!(
+                        "{} is the last commit of {}; there is nothing to split off",
+                        at, branch
+                    ))
+                    .into())
+                }
+                None => {
+                    return Err(
+                        GitError::Branch(format!("{} is not a commit of {}", at, branch)).into(),
+                    )
+                }
+            }
+            let at = sha.as_str();
+
+            let head = git.get_head_sha(branch)?;
+            git.create_branch(new_branch, branch)?;
+            if let Err(e) = git.reset_branches(&[(branch.to_string(), at.to_string())]) {
+                if let Err(e) = git.delete_branch(new_branch) {
+                    tracing::error!("Failed to delete {}: {}", new_branch, e);
+                }
+                return Err(e);
+            }
+
+            for (name, sha) in [(branch, at), (new_branch, head.as_str())] {
+                if let Some(b) = edited.find_branch_mut(name) {
+                    b.head_sha = Some(sha.to_string());
+                }
+            }
+
+            EditUndo {
+                branches: metadata.snapshot_branches(
+                    [branch, new_branch]
+                        .into_iter()
+                        .chain(plan.moves.iter().map(|m| m.branch.as_str())),
+                ),
+                heads: vec![
+                    (branch.to_string(), head.clone(), at.to_string()),
+                    (new_branch.to_string(), head.clone(), head),
+                ],
+                created: Some(new_branch.to_string()),
+                deleted: None,
+                moves: plan.moves.clone(),
+            }
+        };
+        *metadata = edited;
+        drop(metadata);
+
+        let result = StackEditResult {
+            moves: plan.moves,
+            rebased: Self::rewritten(&undo),
+            closed_pr: None,
+        };
+        // Children's PRs are retargeted to the new branch, so it has to be
+        // on the remote first
+        let mut push = vec![new_branch.to_string()];
+        push.extend(result.rebased.iter().cloned());
+        self.publish_edit(&result, &push, &undo).await?;
+        self.remember(undo);
+        Ok(result)
+    }
+
+    /// Fold `branch` into its parent branch.
+    ///
+    /// The parent takes on the commits of `branch`, which is rebased onto
+    /// the parent first if it is behind, and the children of `branch` move
+    /// to the parent. `branch` is then deleted, its PR closed, as its commits
+    /// are now part of the parent's, and the children's PRs retargeted to
+    /// the parent. The fold can be reverted with [`StackService::undo_edit`],
+    /// though the closed PR stays closed.
+    pub async fn fold_branch(&self, branch: &str) -> Result<StackEditResult> {
+        let mut metadata = self.metadata.write().await;
+        let index = metadata
+            .stacks
+            .iter()
+            .position(|s| s.find_branch(branch).is_some())
+            .ok_or_else(|| GitError::Branch(format!("{} is not in a stack", branch)))?;
+        let folded = Self::tracked(&metadata, branch)?.clone();
+        let parent = folded.parent.clone();
+        if metadata.stacks[index].find_branch(&parent).is_none() {
+            return Err(GitError::Branch(format!(
+                "{} is at the bottom of its stack; merge its PR instead",
+                branch
+            ))
+            .into());
+        }
+
+        let stack_id = metadata.stacks[index].id;
+        let before = metadata.stacks[index].clone();
+        let mut edited = metadata.clone();
+        let undo = {
+            let git = self.git.lock().expect("git lock poisoned");
+            if git.is_rebase_in_progress() {
+                return Err(GitError::Branch("A rebase is in progress".to_string()).into());
+            }
+            let old_head = git.get_head_sha(branch)?;
+            let old_parent = git.get_head_sha(&parent)?;
+
+            // A branch behind its parent is brought up to date first, along
+            // with everything stacked on it
+            let mut heads = Vec::new();
+            if !git.is_ancestor(&parent, branch)? {
+                let mut rebase = vec![branch.to_string()];
+                rebase.extend(before.descendants_of(branch));
+                let plan = EditPlan {
+                    stacks: vec![stack_id],
+                    moves: Vec::new(),
+                    rebase,
+                };
+                heads = Self::rewrite(&git, &metadata, &mut edited, &plan)?.heads;
+                heads.retain(|(name, _, _)| name != branch);
+            }
+
+            let original = git.current_branch().ok();
+            let head = git.get_head_sha(branch)?;
+            let folded_in = git
+                .reset_branches(&[(parent.clone(), head.clone())])
+                .and_then(|_| {
+                    // Leave the branch before deleting it
+                    let back = original
+                        .clone()
+                        .filter(|name| name != branch)
+                        .unwrap_or_else(|| parent.clone());
+                    git.checkout_branch(&back)
+                })
+                .and_then(|_| git.delete_branch(branch));
+            if let Err(e) = folded_in {
+                let restore: Vec<(String, String)> = heads
+                    .iter()
+                    .map(|(name, before, _)| (name.clone(), before.clone()))
+                    .chain([(parent.clone(), old_parent), (branch.to_string(), old_head)])
+                    .collect();
+                if let Err(e) = git.reset_branches(&restore) {
+                    tracing::error!("Failed to reset branches: {}", e);
+                }
+                if let Some(original) = &original {
+                    if let Err(e) = git.checkout_branch(original) {
+                        tracing::error!("Failed to check out {}: {}", original, e);
+                    }
+                }
+                return Err(e);
+            }
+            heads.push((parent.clone(), old_parent, head));
+
+            let mut names = vec![branch.to_string(), parent.clone()];
+            names.extend(before.descendants_of(branch));
+            EditUndo {
+                branches: metadata.snapshot_branches(names.iter().map(String::as_str)),
+                heads,
+                created: None,
+                deleted: Some((branch.to_string(), old_head)),
+                moves: Vec::new(),
+            }
+        };
+
+        let stack = &mut edited.stacks[index];
+        stack.branches.retain(|b| b.name != branch);
+        let mut moves = Vec::new();
+        for child in stack.branches.iter_mut().filter(|b| b.parent == branch) {
+            child.parent = parent.clone();
+            moves.push(BranchMove {
+                branch: child.name.clone(),
+                from: Some(branch.to_string()),
+                to: parent.clone(),
+                pr_number: child.pr_number,
+            });
+        }
+        if let Some((_, _, head)) = undo.heads.last() {
+            if let Some(b) = stack.find_branch_mut(&parent) {
+                b.head_sha = Some(head.clone());
+                b.status = BranchStatus::UpToDate;
+            }
+        }
+        stack.updated_at = Utc::now();
+        *metadata = edited;
+        drop(metadata);
+
+        let mut result = StackEditResult {
+            moves: moves.clone(),
+            rebased: Self::rewritten(&undo),
+            closed_pr: None,
+        };
+        let undo = EditUndo { moves, ..undo };
+        self.publish_edit(&result, &result.rebased, &undo).await?;
+        self.remember(undo);
+
+        if let (Some(pr_service), Some(number)) = (&self.pr_service, folded.pr_number) {
+            let closed = match pr_service.get_pr_ref(number).await {
+                Ok(pr) => pr_service.close_pr(pr.id).await,
+                Err(e) => Err(e),
+            };
+            match closed {
+                Ok(true) => {
+                    tracing::info!("Closed PR #{} of folded branch {}", number, branch);
+                    result.closed_pr = Some(number);
+                }
+                Ok(false) => tracing::warn!("Failed to close PR #{}", number),
+                Err(e) => tracing::error!("Error closing PR #{}: {}", number, e),
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Where the commits of `branch` start: its fork point from its parent
+    /// before the edit, or from its new parent if it is joining a stack.
+    /// `None` if the branch doesn't exist yet.
+    fn edit_base(
+        git: &Git2Backend,
+        before: &StackMetadata,
+        after: &StackMetadata,
+        branch: &str,
+    ) -> Result<Option<String>> {
+        if !git.branch_exists(branch)? {
+            return Ok(None);
+        }
+        let parent = &match before.find_branch(branch) {
+            Some(b) => b,
+            None => Self::tracked(after, branch)?,
+        }
+        .parent;
+        git.merge_base(
+            &format!("refs/heads/{}", branch),
+            &format!("refs/heads/{}", parent),
+        )
+        .map(Some)
+    }
+
+    /// Rebase the branches of `plan` onto their new parents in `after`,
+    /// recording their new heads there.
+    ///
+    /// Bases are all found before anything is rewritten. If a rebase fails,
+    /// it is aborted and every branch is reset to where it was.
+    fn rewrite(
+        git: &Git2Backend,
+        before: &StackMetadata,
+        after: &mut StackMetadata,
+        plan: &EditPlan,
+    ) -> Result<EditUndo> {
+        if git.is_rebase_in_progress() {
+            return Err(GitError::Branch("A rebase is in progress".to_string()).into());
+        }
+        let original = git.current_branch().ok();
+
+        let mut steps = Vec::new();
+        for name in &plan.rebase {
+            if let Some(upstream) = Self::edit_base(git, before, after, name)? {
+                steps.push((name.clone(), upstream, git.get_head_sha(name)?));
+            }
+        }
+
+        // A branch inserted by name starts out at its parent
+        let mut created = None;
+        for m in plan.moves.iter().filter(|m| m.from.is_none()) {
+            if !git.branch_exists(&m.branch)? {
+                git.create_branch(&m.branch, &m.to)?;
+                created = Some(m.branch.clone());
+            }
+        }
+
+        let rebased = steps.iter().try_for_each(|(name, upstream, _)| {
+            let parent = &Self::tracked(after, name)?.parent;
+            git.rebase_onto(name, parent, upstream)
+        });
+
+        if let Err(e) = rebased {
+            let files = git.get_conflict_files();
+            let heads: Vec<(String, String)> = steps
+                .iter()
+                .map(|(name, _, head)| (name.clone(), head.clone()))
+                .collect();
+
+            // The rebase error is the one worth reporting, so failures to
+            // clean up are only logged
+            let cleanup = [
+                if git.is_rebase_in_progress() {
+                    git.abort_rebase()
+                } else {
+                    Ok(())
+                },
+                git.reset_branches(&heads),
+                original.as_ref().map_or(Ok(()), |b| git.checkout_branch(b)),
+                created.as_ref().map_or(Ok(()), |b| git.delete_branch(b)),
+            ];
+            for failed in cleanup.into_iter().filter_map(|r| r.err()) {
+                tracing::error!(
+                    "Failed to put branches back after a failed edit: {}",
+                    failed
+                );
+            }
+            return Err(match e {
+                AppError::Git(GitError::Conflict { .. }) => GitError::Conflict { files }.into(),
+                e => e,
+            });
+        }
+
+        if let Some(original) = &original {
+            git.checkout_branch(original)?;
+        }
+
+        let mut heads = Vec::new();
+        for (name, _, old) in steps {
+            let new = git.get_head_sha(&name)?;
+            if let Some(branch) = after.find_branch_mut(&name) {
+                branch.head_sha = Some(new.clone());
+                branch.status = BranchStatus::UpToDate;
+            }
+            heads.push((name, old, new));
+        }
+        if let Some(created) = &created {
+            let head = git.get_head_sha(created)?;
+            if let Some(branch) = after.find_branch_mut(created) {
+                branch.head_sha = Some(head.clone());
+            }
+            heads.push((created.clone(), head.clone(), head));
+        }
+
+        let names = plan
+            .moves
+            .iter()
+            .map(|m| m.branch.as_str())
+            .chain(plan.rebase.iter().map(String::as_str));
+        Ok(EditUndo {
+            branches: before.snapshot_branches(names),
+            heads,
+            created,
+            deleted: None,
+            moves: plan.moves.clone(),
+        })
+    }
+
+    /// Keep `undo` for [`StackService::undo_edit`].
+    fn remember(&self, undo: EditUndo) {
+        let mut history = self.undo.lock().expect("undo lock poisoned");
+        history.push(undo);
+        if history.len() > UNDO_LIMIT {
+            history.remove(0);
+        }
+    }
+
+    /// Branches whose head an edit changed.
+    fn rewritten(undo: &EditUndo) -> Vec<String> {
+        undo.heads
+            .iter()
+            .filter(|(_, before, after)| before != after)
+            .map(|(name, _, _)| name.clone())
+            .collect()
+    }
+
+    /// A branch an edit plans to touch, which has to be tracked.
+    fn tracked<'a>(metadata: &'a StackMetadata, branch: &str) -> Result<&'a StackBranch> {
+        metadata
+            .find_branch(branch)
+            .ok_or_else(|| GitError::Branch(format!("{} is not in a stack", branch)).into())
+    }
+
+    /// Put the branches and metadata an edit changed back as they were.
+    fn revert(git: &Git2Backend, metadata: &mut StackMetadata, undo: &EditUndo) -> Result<()> {
+        let heads: Vec<(String, String)> = undo
+            .heads
+            .iter()
+            .map(|(name, before, _)| (name.clone(), before.clone()))
+            .chain(undo.deleted.clone())
+            .collect();
+        git.reset_branches(&heads)?;
+        if let Some(created) = &undo.created {
+            git.delete_branch(created)?;
+        }
+        metadata.restore_branches(&undo.branches);
+        Ok(())
+    }
+
+    /// Save an edit made locally, push `push` and retarget the PRs of moved
+    /// branches.
+    ///
+    /// If any step fails, the edit is reverted with `undo`: retargeted PRs
+    /// are pointed back at their old bases, and branches are reset locally
+    /// and on their remotes, so the edit is left nowhere.
+    async fn publish_edit(
+        &self,
+        result: &StackEditResult,
+        push: &[String],
+        undo: &EditUndo,
+    ) -> Result<()> {
+        let mut pushed = Vec::new();
+        let mut retargeted = Vec::new();
+        let published = self
+            .try_publish(result, push, &mut pushed, &mut retargeted)
+            .await;
+        if let Err(e) = published {
+            tracing::warn!("Reverting stack edit: {}", e);
+            self.roll_back(undo, &pushed, &retargeted).await;
+            return Err(e);
+        }
+
+        if !pushed.is_empty() {
+            self.share_metadata().await;
+        }
+
+        Ok(())
+    }
+
+    /// The steps of [`StackService::publish_edit`], recording the branches
+    /// pushed, with their remote, and the PRs retargeted, with their old base.
+    async fn try_publish(
+        &self,
+        result: &StackEditResult,
+        push: &[String],
+        pushed: &mut Vec<(String, String)>,
+        retargeted: &mut Vec<(String, String)>,
+    ) -> Result<()> {
+        self.save_metadata().await?;
+
+        for branch in push {
+            let git = self.git.lock().expect("git lock poisoned");
+            let remote = git.push_remote(branch);
+            git.force_push(branch, &remote)?;
+            pushed.push((branch.clone(), remote));
+        }
+
+        if self.pr_service.is_some() {
+            let updates: Vec<(String, String)> = result
+                .moves
+                .iter()
+                .filter(|m| m.pr_number.is_some())
+                .map(|m| (m.branch.clone(), m.to.clone()))
+                .collect();
+            self.retarget_prs(&updates, retargeted).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Revert an edit whose publishing failed part way.
+    ///
+    /// Best effort: failures are logged, as the error that caused the
+    /// rollback is the one reported.
+    async fn roll_back(
+        &self,
+        undo: &EditUndo,
+        pushed: &[(String, String)],
+        retargeted: &[(String, String)],
+    ) {
+        if !retargeted.is_empty() {
+            if let Err(e) = self.retarget_prs(retargeted, &mut Vec::new()).await {
+                tracing::error!("Failed to retarget PRs back: {}", e);
+            }
+        }
+
+        {
+            let mut metadata = self.metadata.write().await;
+            let git = self.git.lock().expect("git lock poisoned");
+            if let Err(e) = Self::revert(&git, &mut metadata, undo) {
+                tracing::error!("Failed to reset branches: {}", e);
+            }
+        }
+        if let Err(e) = self.save_metadata().await {
+            tracing::error!("Failed to save stack metadata: {}", e);
+        }
+
+        let git = self.git.lock().expect("git lock poisoned");
+        for (branch, remote) in pushed {
+            let restored = match git.branch_exists(branch) {
+                Ok(true) => git.force_push(branch, remote),
+                Ok(false) => git.delete_remote_branch(branch, remote),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = restored {
+                tracing::error!("Failed to restore {} on {}: {}", branch, remote, e);
+            }
+        }
+    }
+
     /// Check if a rebase is currently in progress.
     pub fn is_rebase_in_progress(&self) -> bool {
         let git = self.git.lock().expect("git lock poisoned");
@@ -485,53 +1596,67 @@ impl StackService {
 
     /// Update PR base branch after parent is merged.
     pub async fn update_pr_base(&self, branch_name: &str, new_base: &str) -> Result<()> {
+        self.update_pr_bases(&[(branch_name, new_base)]).await
+    }
+
+    /// Retarget the PRs of several branches.
+    ///
+    /// The PR node IDs are looked up in a single batched request, and PRs
+    /// that already target the requested base are left alone.
+    pub async fn update_pr_bases(&self, updates: &[(&str, &str)]) -> Result<()> {
         let pr_service = self
             .pr_service
             .as_ref()
             .ok_or_else(|| GitError::Branch("PR service not configured".to_string()))?;
 
-        // Find the branch and its PR ID
+        // Find the PR number of each branch
         let metadata = self.metadata.read().await;
-        let branch_info = metadata
-            .stacks
+        let targets: Vec<(i64, &str, &str)> = updates
             .iter()
-            .flat_map(|s| s.branches.iter())
-            .find(|b| b.name == branch_name)
-            .cloned();
+            .filter_map(|&(branch_name, new_base)| {
+                metadata
+                    .stacks
+                    .iter()
+                    .flat_map(|s| s.branches.iter())
+                    .find(|b| b.name == branch_name)
+                    .and_then(|b| b.pr_number)
+                    .map(|pr_number| (pr_number, branch_name, new_base))
+            })
+            .collect();
         drop(metadata);
 
-        if let Some(branch) = branch_info {
-            if let Some(pr_number) = branch.pr_number {
-                // Need to get the PR ID from the PR number
-                match pr_service.get_pr_details(pr_number).await {
-                    Ok(details) => {
-                        match pr_service
-                            .update_pr_base(details.pr.id, new_base.to_string())
-                            .await
-                        {
-                            Ok(true) => {
-                                tracing::info!(
-                                    "Updated PR #{} for {} to target {}",
-                                    pr_number,
-                                    branch_name,
-                                    new_base
-                                );
-                            }
-                            Ok(false) => {
-                                tracing::warn!(
-                                    "Failed to update PR #{} base to {}",
-                                    pr_number,
-                                    new_base
-                                );
-                            }
-                            Err(e) => {
-                                tracing::error!("Error updating PR #{} base: {}", pr_number, e);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to get PR details for #{}: {}", pr_number, e);
-                    }
+        if targets.is_empty() {
+            return Ok(());
+        }
+
+        let numbers: Vec<i64> = targets.iter().map(|(number, _, _)| *number).collect();
+        let refs = match pr_service.get_pr_refs(&numbers).await {
+            Ok(refs) => refs,
+            Err(e) => {
+                tracing::error!("Failed to look up PRs {:?}: {}", numbers, e);
+                return Ok(());
+            }
+        };
+
+        for ((pr_number, branch_name, new_base), pr) in targets.into_iter().zip(refs) {
+            if pr.base_ref == new_base {
+                continue;
+            }
+
+            match pr_service.update_pr_base(pr.id, new_base.to_string()).await {
+                Ok(true) => {
+                    tracing::info!(
+                        "Updated PR #{} for {} to target {}",
+                        pr_number,
+                        branch_name,
+                        new_base
+                    );
+                }
+                Ok(false) => {
+                    tracing::warn!("Failed to update PR #{} base to {}", pr_number, new_base);
+                }
+                Err(e) => {
+                    tracing::error!("Error updating PR #{} base: {}", pr_number, e);
                 }
             }
         }
@@ -539,6 +1664,65 @@ impl StackService {
         Ok(())
     }
 
+    /// Retarget the PRs of `updates`, given as branch and new base, failing
+    /// on the first PR that can't be retargeted.
+    ///
+    /// Each PR retargeted is added to `done` with its old base.
+    async fn retarget_prs(
+        &self,
+        updates: &[(String, String)],
+        done: &mut Vec<(String, String)>,
+    ) -> Result<()> {
+        let pr_service = self
+            .pr_service
+            .as_ref()
+            .ok_or_else(|| GitError::Branch("PR service not configured".to_string()))?;
+
+        let targets: Vec<(i64, &str, &str)> = {
+            let metadata = self.metadata.read().await;
+            updates
+                .iter()
+                .filter_map(|(branch_name, new_base)| {
+                    metadata
+                        .find_branch(branch_name)
+                        .and_then(|b| b.pr_number)
+                        .map(|number| (number, branch_name.as_str(), new_base.as_str()))
+                })
+                .collect()
+        };
+        if targets.is_empty() {
+            return Ok(());
+        }
+
+        let numbers: Vec<i64> = targets.iter().map(|(number, _, _)| *number).collect();
+        let refs = pr_service.get_pr_refs(&numbers).await?;
+
+        for ((pr_number, branch_name, new_base), pr) in targets.into_iter().zip(refs) {
+            if pr.base_ref == new_base {
+                continue;
+            }
+            if !pr_service
+                .update_pr_base(pr.id, new_base.to_string())
+                .await?
+            {
+                return Err(GitHubError::GraphQL(format!(
+                    "Failed to retarget PR #{} to {}",
+                    pr_number, new_base
+                ))
+                .into());
+            }
+            tracing::info!(
+                "Updated PR #{} for {} to target {}",
+                pr_number,
+                branch_name,
+                new_base
+            );
+            done.push((branch_name.to_string(), pr.base_ref));
+        }
+
+        Ok(())
+    }
+
     /// Associate a PR number with a branch.
     pub async fn set_branch_pr(&self, branch_name: &str, pr_number: i64) -> Result<()> {
         let mut metadata = self.metadata.write().await;
@@ -585,11 +1769,562 @@ impl StackService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::tempdir;
+
+    fn init_test_repo() -> (tempfile::TempDir, git2::Repository) {
+        let dir = tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+
+        // Create initial commit - use a block to drop the tree before we return
+        {
+            let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+            let tree_id = repo.index().unwrap().write_tree().unwrap();
+            let tree = repo.find_tree(tree_id).unwrap();
+            repo.commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
+                .unwrap();
+        }
+
+        (dir, repo)
+    }
+
+    /// Create `name` off `parent` with one commit of its own.
+    fn commit_branch(repo: &git2::Repository, name: &str, parent: &str) {
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo
+            .find_branch(parent, git2::BranchType::Local)
+            .unwrap()
+            .get()
+            .peel_to_commit()
+            .unwrap();
+        let tree = parent.tree().unwrap();
+        let oid = repo
+            .commit(None, &sig, &sig, name, &tree, &[&parent])
+            .unwrap();
+        repo.branch(name, &repo.find_commit(oid).unwrap(), false)
+            .unwrap();
+    }
+
+    fn open_service(path: &std::path::Path) -> StackService {
+        let git = Git2Backend::open(path).unwrap();
+        StackService::new(path.to_path_buf(), git).unwrap()
+    }
+
+    fn roots(stacks: &[Stack]) -> Vec<String> {
+        let mut roots: Vec<String> = stacks.iter().map(|s| s.root.clone()).collect();
+        roots.sort();
+        roots
+    }
+
+    fn git(dir: &std::path::Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// Check out `name` as a new branch off `parent` and commit `content` to
+    /// `file` on it.
+    fn commit_change(dir: &std::path::Path, name: &str, parent: &str, file: &str, content: &str) {
+        git(dir, &["checkout", "-q", "-b", name, parent]);
+        std::fs::write(dir.join(file), content).unwrap();
+        git(dir, &["add", file]);
+        git(dir, &["commit", "-qm", name]);
+    }
+
+    /// A repository with a commit on each branch, back on the trunk, whose
+    /// name is returned.
+    fn init_stacked_repo(branches: &[(&str, &str)]) -> (tempfile::TempDir, String) {
+        let (dir, repo) = init_test_repo();
+        let trunk = repo.head().unwrap().shorthand().unwrap().to_string();
+        let work = dir.path();
+        git(work, &["config", "user.name", "Test"]);
+        git(work, &["config", "user.email", "test@example.com"]);
+        for (name, parent) in branches {
+            let parent: &str = if *parent == "trunk" { &trunk } else { parent };
+            commit_change(work, name, parent, &format!("{}.txt", name), name);
+        }
+        git(work, &["checkout", "-q", &trunk]);
+
+        // Edits push what they rewrite, so the branches live on a remote
+        // too, kept inside the repo's git dir
+        let origin = work.join(".git").join("origin.git");
+        git(work, &["init", "-q", "--bare", origin.to_str().unwrap()]);
+        git(work, &["remote", "add", "origin", origin.to_str().unwrap()]);
+        let mut push = vec!["push", "-q", "origin"];
+        push.extend(branches.iter().map(|(name, _)| *name));
+        git(work, &push);
+        (dir, trunk)
+    }
+
+    /// Head of `branch` on the test remote.
+    fn remote_head(dir: &std::path::Path, branch: &str) -> String {
+        let origin = dir.join(".git").join("origin.git");
+        git(&origin, &["rev-parse", &format!("refs/heads/{}", branch)])
+    }
+
+    /// Track `branches` as a stack on `root`.
+    async fn track(service: &StackService, root: &str, branches: &[(&str, &str)]) -> uuid::Uuid {
+        let mut stack = Stack::new(root.to_string());
+        for (name, parent) in branches {
+            let parent: &str = if *parent == "trunk" { root } else { parent };
+            stack.add_branch(StackBranch::new(name.to_string(), parent.to_string()));
+        }
+        let id = stack.id;
+        service.metadata.write().await.add_stack(stack);
+        service.save_metadata().await.unwrap();
+        id
+    }
+
+    async fn parent_of(service: &StackService, branch: &str) -> String {
+        service
+            .find_stack_for_branch(branch)
+            .await
+            .unwrap()
+            .find_branch(branch)
+            .unwrap()
+            .parent
+            .clone()
+    }
 
     #[test]
     fn test_stack_metadata_default() {
         let metadata = StackMetadata::default();
         assert!(metadata.stacks.is_empty());
-        assert_eq!(metadata.version, 1);
+        assert_eq!(metadata.version, stack::migrations::latest_version());
+    }
+
+    #[tokio::test]
+    async fn test_rebuilds_corrupt_metadata() {
+        let (dir, repo) = init_test_repo();
+        let trunk = repo.head().unwrap().shorthand().unwrap().to_string();
+        commit_branch(&repo, "feature-a", &trunk);
+        commit_branch(&repo, "feature-b", "feature-a");
+
+        let path = dir.path().join(".git").join("stack-metadata.json");
+        std::fs::write(&path, "{\"version\": 1, \"stacks\": [").unwrap();
+
+        let service = open_service(dir.path());
+        let stacks = service.list_stacks().await;
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].root, trunk);
+        assert_eq!(
+            stacks[0].find_branch("feature-b").unwrap().parent,
+            "feature-a"
+        );
+
+        // The unreadable copy is kept, and the rebuilt one saved
+        assert!(path.with_extension("json.corrupt").exists());
+        let stored = StackStore::open(dir.path())
+            .unwrap()
+            .load()
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.stacks.len(), 1);
+    }
+
+    #[test]
+    fn test_refuses_newer_metadata() {
+        let (dir, _repo) = init_test_repo();
+        let path = dir.path().join(".git").join("stack-metadata.json");
+        let content = r#"{"version": 99, "stacks": [], "last_sync": null, "owners": {}}"#;
+        std::fs::write(&path, content).unwrap();
+
+        let git = Git2Backend::open(dir.path()).unwrap();
+        let err = StackService::new(dir.path().to_path_buf(), git)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            AppError::Storage(StorageError::UnsupportedVersion { found: 99, .. })
+        ));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_services_merge_on_save() {
+        let (dir, _repo) = init_test_repo();
+        let app = open_service(dir.path());
+        let cli = open_service(dir.path());
+
+        app.create_stack("main".to_string()).await.unwrap();
+        assert_eq!(app.revision().await, 1);
+
+        // The second writer loaded revision 0, so its save merges in the first
+        cli.create_stack("develop".to_string()).await.unwrap();
+        assert_eq!(cli.revision().await, 2);
+        assert_eq!(roots(&cli.list_stacks().await), ["develop", "main"]);
+
+        let stored = StackStore::open(dir.path())
+            .unwrap()
+            .load()
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.revision, 2);
+        assert_eq!(roots(&stored.stacks), ["develop", "main"]);
+
+        // The first picks the change up on reload
+        assert!(app.reload().await.unwrap());
+        assert_eq!(roots(&app.list_stacks().await), ["develop", "main"]);
+        assert!(!app.reload().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_watch_reloads_external_changes() {
+        let (dir, _repo) = init_test_repo();
+        let app = Arc::new(open_service(dir.path()));
+        let watcher = app.watch();
+
+        open_service(dir.path())
+            .create_stack("main".to_string())
+            .await
+            .unwrap();
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while app.revision().await == 0 {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "change not picked up"
+            );
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(roots(&app.list_stacks().await), ["main"]);
+
+        // The watcher stops with the service
+        drop(app);
+        tokio::time::timeout(WATCH_INTERVAL * 3, watcher)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_share_stacks_through_remote() {
+        let (dir, _repo) = init_test_repo();
+        let origin = dir.path().join(".git").join("origin.git");
+        git(
+            dir.path(),
+            &["init", "-q", "--bare", origin.to_str().unwrap()],
+        );
+        let clone = tempdir().unwrap();
+        git(
+            clone.path(),
+            &["clone", "-q", origin.to_str().unwrap(), "."],
+        );
+        git(
+            dir.path(),
+            &["remote", "add", "origin", origin.to_str().unwrap()],
+        );
+
+        let laptop = open_service(dir.path());
+        laptop.set_storage(StackStorage::Ref).await.unwrap();
+        laptop.create_stack("main".to_string()).await.unwrap();
+        laptop.sync_metadata("origin").await.unwrap();
+
+        // Switching to ref storage brings in what was shared
+        let desktop = open_service(clone.path());
+        desktop.create_stack("develop".to_string()).await.unwrap();
+        desktop.set_storage(StackStorage::Ref).await.unwrap();
+        assert_eq!(roots(&desktop.list_stacks().await), ["develop", "main"]);
+
+        laptop.sync_metadata("origin").await.unwrap();
+        assert_eq!(roots(&laptop.list_stacks().await), ["develop", "main"]);
+    }
+
+    #[tokio::test]
+    async fn test_reparent_across_stacks_and_undo() {
+        let chain = [("a", "trunk"), ("b", "a"), ("c", "b")];
+        let (dir, trunk) =
+            init_stacked_repo(&[("a", "trunk"), ("b", "a"), ("c", "b"), ("d", "trunk")]);
+        let service = open_service(dir.path());
+        track(&service, &trunk, &chain).await;
+        let other = track(&service, &trunk, &[("d", "trunk")]).await;
+        let git = Git2Backend::open(dir.path()).unwrap();
+        let heads: Vec<String> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|b| git.get_head_sha(b).unwrap())
+            .collect();
+
+        let edit = StackEdit::Reparent {
+            branch: "b".to_string(),
+            parent: "d".to_string(),
+            stack_id: None,
+        };
+        let preview = service.preview_edit(&edit).await.unwrap();
+        assert_eq!(preview.total_commits, 2);
+        assert_eq!(preview.will_rebase[0].onto, "d");
+        assert_eq!(git.get_head_sha("b").unwrap(), heads[1]);
+
+        let result = service.edit_stack(&edit).await.unwrap();
+        assert_eq!(result.rebased, ["b", "c"]);
+        assert!(git.is_ancestor("d", "b").unwrap());
+        assert!(!git.is_ancestor("a", "b").unwrap());
+        assert!(git.is_ancestor("b", "c").unwrap());
+        assert_eq!(parent_of(&service, "b").await, "d");
+        assert_eq!(service.find_stack_for_branch("c").await.unwrap().id, other);
+        assert_eq!(git.current_branch().unwrap(), trunk);
+        assert_eq!(remote_head(dir.path(), "b"), git.get_head_sha("b").unwrap());
+        service.set_branch_pr("c", 7).await.unwrap();
+
+        // Undoing puts the branches and their parents back, keeping what
+        // was recorded since
+        let undone = service.undo_edit().await.unwrap();
+        assert_eq!(undone.moves[0].to, "a");
+        let restored: Vec<String> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|b| git.get_head_sha(b).unwrap())
+            .collect();
+        assert_eq!(restored, heads);
+        assert_eq!(parent_of(&service, "b").await, "a");
+        assert_ne!(service.find_stack_for_branch("c").await.unwrap().id, other);
+        assert_eq!(remote_head(dir.path(), "b"), heads[1]);
+        assert_eq!(
+            service
+                .metadata
+                .read()
+                .await
+                .find_branch("c")
+                .unwrap()
+                .pr_number,
+            Some(7)
+        );
+        assert!(service.undo_edit().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_swap_and_insert() {
+        let chain = [("a", "trunk"), ("b", "a"), ("c", "b")];
+        let (dir, trunk) = init_stacked_repo(&chain);
+        let service = open_service(dir.path());
+        let id = track(&service, &trunk, &chain).await;
+        let git = Git2Backend::open(dir.path()).unwrap();
+
+        service
+            .edit_stack(&StackEdit::Swap {
+                branch: "b".to_string(),
+            })
+            .await
+            .unwrap();
+        assert!(git.is_ancestor("b", "a").unwrap());
+        assert!(!git.is_ancestor("a", "b").unwrap());
+        assert!(git.is_ancestor("a", "c").unwrap());
+        assert_eq!(parent_of(&service, "b").await, trunk);
+        assert_eq!(parent_of(&service, "c").await, "a");
+
+        let result = service
+            .edit_stack(&StackEdit::Insert {
+                stack_id: id,
+                branch: "n".to_string(),
+                parent: "b".to_string(),
+            })
+            .await
+            .unwrap();
+        assert!(result.rebased.is_empty());
+        assert_eq!(
+            git.get_head_sha("n").unwrap(),
+            git.get_head_sha("b").unwrap()
+        );
+        assert_eq!(parent_of(&service, "a").await, "n");
+
+        // The inserted branch goes away again
+        service.undo_edit().await.unwrap();
+        assert!(!git.branch_exists("n").unwrap());
+        assert_eq!(parent_of(&service, "a").await, "b");
+
+        // An edit can't be undone once its branches have moved on
+        let head = git.get_head_sha("b").unwrap();
+        git.reset_branches(&[("b".to_string(), git.get_head_sha("c").unwrap())])
+            .unwrap();
+        assert!(service.undo_edit().await.is_err());
+        git.reset_branches(&[("b".to_string(), head)]).unwrap();
+        service.undo_edit().await.unwrap();
+        assert!(git.is_ancestor("a", "b").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_failed_edit_changes_nothing() {
+        let (dir, trunk) = init_stacked_repo(&[("a", "trunk")]);
+        let work = dir.path();
+        // b edits the file a added, so it can't go without a
+        commit_change(work, "b", "a", "a.txt", "changed");
+        git(work, &["checkout", "-q", &trunk]);
+
+        let service = open_service(work);
+        track(&service, &trunk, &[("a", "trunk"), ("b", "a")]).await;
+        let git = Git2Backend::open(work).unwrap();
+        let head = git.get_head_sha("b").unwrap();
+
+        let err = service
+            .edit_stack(&StackEdit::Reparent {
+                branch: "b".to_string(),
+                parent: trunk.clone(),
+                stack_id: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, AppError::Git(GitError::Conflict { files }) if files == &["a.txt"]),
+            "{}",
+            err
+        );
+        assert_eq!(git.get_head_sha("b").unwrap(), head);
+        assert!(!git.is_rebase_in_progress());
+        assert_eq!(git.current_branch().unwrap(), trunk);
+        assert_eq!(parent_of(&service, "b").await, "a");
+        assert!(service.undo_edit().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_push_reverts_edit() {
+        let chain = [("a", "trunk"), ("b", "a"), ("c", "b")];
+        let (dir, trunk) =
+            init_stacked_repo(&[("a", "trunk"), ("b", "a"), ("c", "b"), ("d", "trunk")]);
+        let work = dir.path();
+        let service = open_service(work);
+        track(&service, &trunk, &chain).await;
+        track(&service, &trunk, &[("d", "trunk")]).await;
+        let backend = Git2Backend::open(work).unwrap();
+        let heads: Vec<String> = ["b", "c"]
+            .iter()
+            .map(|b| backend.get_head_sha(b).unwrap())
+            .collect();
+
+        // b is pushed, then c can't be
+        git(work, &["remote", "add", "gone", "/nonexistent/repo.git"]);
+        git(work, &["config", "branch.c.pushRemote", "gone"]);
+
+        assert!(service
+            .edit_stack(&StackEdit::Reparent {
+                branch: "b".to_string(),
+                parent: "d".to_string(),
+                stack_id: None,
+            })
+            .await
+            .is_err());
+        assert_eq!(backend.get_head_sha("b").unwrap(), heads[0]);
+        assert_eq!(backend.get_head_sha("c").unwrap(), heads[1]);
+        assert_eq!(remote_head(work, "b"), heads[0]);
+        assert_eq!(parent_of(&service, "b").await, "a");
+        assert!(service.undo_edit().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_split_branch_and_undo() {
+        let (dir, trunk) = init_stacked_repo(&[("a", "trunk")]);
+        let work = dir.path();
+        git(work, &["checkout", "-q", "a"]);
+        for file in ["a2.txt", "a3.txt"] {
+            std::fs::write(work.join(file), file).unwrap();
+            git(work, &["add", file]);
+            git(work, &["commit", "-qm", file]);
+        }
+        commit_change(work, "c", "a", "c.txt", "c");
+        git(work, &["checkout", "-q", &trunk]);
+
+        let service = open_service(work);
+        track(&service, &trunk, &[("a", "trunk"), ("c", "a")]).await;
+        let backend = Git2Backend::open(work).unwrap();
+        let head = backend.get_head_sha("a").unwrap();
+
+        let commits = service.branch_commits("a").await.unwrap();
+        let summaries: Vec<&str> = commits.iter().map(|c| c.summary.as_str()).collect();
+        assert_eq!(summaries, ["a", "a2.txt", "a3.txt"]);
+
+        // Both halves must keep at least one commit
+        for at in [commits[2].sha.as_str(), "0123456"] {
+            assert!(service.split_branch("a", at, "a-top").await.is_err());
+        }
+        assert!(service
+            .split_branch("a", &commits[0].sha, "c")
+            .await
+            .is_err());
+
+        // The new branch is pushed first, and taken off the remote again
+        // when a can't be pushed
+        git(work, &["remote", "add", "gone", "/nonexistent/repo.git"]);
+        git(work, &["config", "branch.a.pushRemote", "gone"]);
+        assert!(service
+            .split_branch("a", &commits[0].sha, "a-top")
+            .await
+            .is_err());
+        assert_eq!(backend.get_head_sha("a").unwrap(), head);
+        assert!(!backend.branch_exists("a-top").unwrap());
+        let origin = work.join(".git").join("origin.git");
+        assert!(git(&origin, &["branch", "--list", "a-top"]).is_empty());
+        assert_eq!(parent_of(&service, "c").await, "a");
+        git(work, &["config", "--unset", "branch.a.pushRemote"]);
+
+        // Abbreviated SHAs work too
+        let result = service
+            .split_branch("a", &commits[0].sha[..8], "a-top")
+            .await
+            .unwrap();
+        assert_eq!(result.rebased, ["a"]);
+        assert_eq!(remote_head(work, "a"), commits[0].sha);
+        assert_eq!(remote_head(work, "a-top"), head);
+        assert_eq!(backend.get_head_sha("a").unwrap(), commits[0].sha);
+        assert_eq!(backend.get_head_sha("a-top").unwrap(), head);
+        assert_eq!(parent_of(&service, "a-top").await, "a");
+        assert_eq!(parent_of(&service, "c").await, "a-top");
+        assert_eq!(service.branch_commits("a-top").await.unwrap().len(), 2);
+        assert!(backend.is_ancestor("a-top", "c").unwrap());
+
+        service.undo_edit().await.unwrap();
+        assert_eq!(backend.get_head_sha("a").unwrap(), head);
+        assert!(!backend.branch_exists("a-top").unwrap());
+        assert_eq!(parent_of(&service, "c").await, "a");
+        assert_eq!(remote_head(work, "a"), head);
+        assert!(git(&origin, &["branch", "--list", "a-top"]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fold_branch_and_undo() {
+        let chain = [("a", "trunk"), ("b", "a"), ("c", "b")];
+        let (dir, trunk) = init_stacked_repo(&chain);
+        let work = dir.path();
+        // a moves on after b was stacked on it
+        git(work, &["checkout", "-q", "a"]);
+        std::fs::write(work.join("a2.txt"), "a2").unwrap();
+        git(work, &["add", "a2.txt"]);
+        git(work, &["commit", "-qm", "a2"]);
+        git(work, &["checkout", "-q", "b"]);
+
+        let service = open_service(work);
+        track(&service, &trunk, &chain).await;
+        let git = Git2Backend::open(work).unwrap();
+        let heads: Vec<String> = ["a", "b", "c"]
+            .iter()
+            .map(|b| git.get_head_sha(b).unwrap())
+            .collect();
+
+        assert!(service.fold_branch("a").await.is_err());
+
+        let result = service.fold_branch("b").await.unwrap();
+        assert_eq!(result.rebased, ["c", "a"]);
+        assert!(result.closed_pr.is_none());
+        assert!(!git.branch_exists("b").unwrap());
+        let summaries: Vec<String> = git
+            .list_commits(&trunk, "a")
+            .unwrap()
+            .into_iter()
+            .map(|c| c.summary)
+            .collect();
+        assert_eq!(summaries, ["a", "a2", "b"]);
+        assert!(git.is_ancestor("a", "c").unwrap());
+        assert_eq!(parent_of(&service, "c").await, "a");
+        assert!(service.find_stack_for_branch("b").await.is_none());
+        // The folded branch was checked out, so its parent is now
+        assert_eq!(git.current_branch().unwrap(), "a");
+
+        service.undo_edit().await.unwrap();
+        let restored: Vec<String> = ["a", "b", "c"]
+            .iter()
+            .map(|b| git.get_head_sha(b).unwrap())
+            .collect();
+        assert_eq!(restored, heads);
+        assert_eq!(parent_of(&service, "b").await, "a");
+        assert_eq!(parent_of(&service, "c").await, "b");
     }
 }
@@ -1,21 +1,26 @@
 //! Background Sync Service.
 //!
 //! This module provides the background synchronization service that keeps
-//! local data in sync with GitHub. It polls for changes at configurable
-//! intervals and handles rate limiting gracefully.
+//! local data in sync with GitHub. It polls for changes at an adaptive
+//! interval that reacts to the rate limit budget, window focus and recent
+//! user activity.
 
+use crate::cache::Cache;
 use crate::config::SyncConfig;
 use crate::domain::sync::{RateLimitInfo, SyncChange, SyncStats, SyncStatus};
 use crate::domain::PullRequest;
-use crate::error::Result;
-use crate::github::{GitHubClient, PrService};
-use chrono::Utc;
+use crate::error::{AppError, GitHubError, Result};
+use crate::github::scheduler;
+use crate::github::webhook::{self, WebhookDelivery};
+use crate::github::{ChangeProbe, GitHubClient, PrService};
+use chrono::{DateTime, Utc};
 use std::sync::Arc;
 use tokio::sync::{broadcast, mpsc, RwLock};
-use tokio::time::{interval, Duration};
+use tokio::time::{sleep_until, Duration, Instant};
 
-/// GitHub's default authenticated API rate limit (requests per hour)
-const GITHUB_RATE_LIMIT_DEFAULT: u32 = 5000;
+/// Hourly GraphQL point budget of a user token, for when GitHub hasn't
+/// reported one yet.
+const DEFAULT_RATE_LIMIT: u32 = 5000;
 
 /// Message types for controlling the sync service.
 #[derive(Debug, Clone)]
@@ -28,6 +33,10 @@ pub enum SyncCommand {
     SyncNow,
     /// Update sync configuration
     UpdateConfig(SyncConfig),
+    /// App window gained or lost focus
+    SetFocused(bool),
+    /// User performed an action (create, merge, ...) that warrants faster syncing
+    UserAction,
 }
 
 /// Event types emitted by the sync service.
@@ -43,6 +52,80 @@ pub enum SyncEvent {
     Error(String),
 }
 
+/// Adaptive scheduling state for the background sync loop.
+///
+/// Computes the delay until the next sync from the configured baseline,
+/// the current rate limit budget, window focus and recent user activity.
+#[derive(Debug, Clone)]
+pub struct SyncScheduler {
+    /// Whether the app window has focus
+    focused: bool,
+
+    /// End of the boosted (faster) period after a user action
+    boost_until: Option<DateTime<Utc>>,
+}
+
+impl Default for SyncScheduler {
+    fn default() -> Self {
+        Self {
+            focused: true,
+            boost_until: None,
+        }
+    }
+}
+
+impl SyncScheduler {
+    /// Record whether the app window has focus.
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Start a boosted period after a user action.
+    pub fn boost(&mut self, config: &SyncConfig, now: DateTime<Utc>) {
+        self.boost_until = Some(now + chrono::Duration::seconds(config.boost_duration_secs as i64));
+    }
+
+    /// Check if the boosted period is still active.
+    pub fn is_boosted(&self, now: DateTime<Utc>) -> bool {
+        self.boost_until.is_some_and(|until| until > now)
+    }
+
+    /// Compute the delay until the next sync.
+    pub fn next_delay(
+        &self,
+        config: &SyncConfig,
+        rate_limit: Option<&RateLimitInfo>,
+        now: DateTime<Utc>,
+    ) -> Duration {
+        // Exhausted budget: nothing useful can happen before the reset
+        if let Some(info) = rate_limit.filter(|i| i.is_limited()) {
+            let until_reset = (info.resets_at - now).num_seconds().max(0) as u64 + 1;
+            return Duration::from_secs(until_reset.max(config.interval_secs));
+        }
+
+        let mut secs = config.interval_secs;
+
+        if !self.focused {
+            secs = secs.max(config.unfocused_interval_secs);
+        }
+
+        let max_secs = config.max_interval_secs.max(config.interval_secs);
+        let secs = match rate_limit.filter(|i| i.should_slow_down()) {
+            // Low budget wins over any boost: at least double the interval,
+            // and never faster than the remaining budget allows, even beyond
+            // the cap, or the budget runs out before the reset.
+            Some(info) => secs
+                .saturating_mul(2)
+                .min(max_secs)
+                .max(info.budget_interval(now).as_secs()),
+            None if self.is_boosted(now) => secs.min(config.boost_interval_secs).min(max_secs),
+            None => secs.min(max_secs),
+        };
+
+        Duration::from_secs(secs.max(1))
+    }
+}
+
 /// Background sync service for keeping data in sync with GitHub.
 pub struct SyncService {
     /// GitHub client for API calls
@@ -63,6 +146,9 @@ pub struct SyncService {
     /// Cached PR data for change detection
     cached_prs: Arc<RwLock<Vec<PullRequest>>>,
 
+    /// Persistent cache for ETag change probes
+    cache: Option<Arc<Cache>>,
+
     /// Repository context (owner, repo)
     repo_context: Arc<RwLock<Option<(String, String)>>>,
 
@@ -92,6 +178,7 @@ impl SyncService {
             rate_limit: Arc::new(RwLock::new(None)),
             stats: Arc::new(RwLock::new(SyncStats::default())),
             cached_prs: Arc::new(RwLock::new(Vec::new())),
+            cache: None,
             repo_context: Arc::new(RwLock::new(None)),
             event_tx,
             command_rx: Arc::new(RwLock::new(Some(command_rx))),
@@ -100,6 +187,15 @@ impl SyncService {
         }
     }
 
+    /// Use a persistent cache for ETag change probes.
+    ///
+    /// With a cache, each sync first sends a conditional REST request and only
+    /// runs the GraphQL list query if the pull requests changed.
+    pub fn with_cache(mut self, cache: Arc<Cache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
     /// Set the repository context.
     pub async fn set_repository(&self, owner: String, repo: String) {
         *self.repo_context.write().await = Some((owner, repo));
@@ -123,6 +219,11 @@ impl SyncService {
         self.command_tx.clone()
     }
 
+    /// Get the open PRs as of the last sync.
+    pub async fn pull_requests(&self) -> Vec<PullRequest> {
+        self.cached_prs.read().await.clone()
+    }
+
     /// Get the current sync status.
     pub async fn status(&self) -> SyncStatus {
         self.status.read().await.clone()
@@ -183,45 +284,47 @@ impl SyncService {
         let rate_limit = self.rate_limit.clone();
         let stats = self.stats.clone();
         let cached_prs = self.cached_prs.clone();
+        let cache = self.cache.clone();
         let repo_context = self.repo_context.clone();
         let github_client = self.github_client.clone();
         let event_tx = self.event_tx.clone();
         let running = self.running.clone();
 
         tokio::spawn(async move {
-            let initial_interval_secs = config.read().await.interval_secs;
-            let mut sync_interval = interval(Duration::from_secs(initial_interval_secs));
-            let mut current_interval_secs = initial_interval_secs;
+            let mut scheduler = SyncScheduler::default();
+            let mut last_sync = Instant::now();
+            // First sync runs immediately
+            let mut next_sync = last_sync;
 
             loop {
                 tokio::select! {
-                    // Timer tick
-                    _ = sync_interval.tick() => {
-                        // Check if interval config changed
-                        let new_interval_secs = config.read().await.interval_secs;
-                        if new_interval_secs != current_interval_secs {
-                            sync_interval = interval(Duration::from_secs(new_interval_secs));
-                            current_interval_secs = new_interval_secs;
-                        }
-
+                    // Next scheduled sync
+                    _ = sleep_until(next_sync) => {
                         if *running.read().await {
-                            let should_sync = {
+                            let (should_sync, max_age_secs) = {
                                 let cfg = config.read().await;
-                                cfg.enabled
+                                (cfg.enabled, cfg.full_sync_interval_secs)
                             };
 
                             if should_sync {
-                                Self::perform_sync(
+                                // Timed syncs yield to user-initiated requests
+                                scheduler::background(Self::perform_sync(
                                     &github_client,
+                                    cache.as_ref(),
+                                    max_age_secs,
                                     &repo_context,
                                     &cached_prs,
                                     &status,
                                     &stats,
                                     &rate_limit,
                                     &event_tx,
-                                ).await;
+                                )).await;
                             }
                         }
+
+                        last_sync = Instant::now();
+                        next_sync = last_sync
+                            + Self::next_delay(&scheduler, &config, &rate_limit).await;
                     }
 
                     // Command received
@@ -239,8 +342,11 @@ impl SyncService {
                                     }
                                     SyncCommand::SyncNow => {
                                         tracing::info!("Manual sync triggered");
+                                        let max_age_secs = config.read().await.full_sync_interval_secs;
                                         Self::perform_sync(
                                             &github_client,
+                                            cache.as_ref(),
+                                            max_age_secs,
                                             &repo_context,
                                             &cached_prs,
                                             &status,
@@ -248,12 +354,25 @@ impl SyncService {
                                             &rate_limit,
                                             &event_tx,
                                         ).await;
+                                        last_sync = Instant::now();
                                     }
                                     SyncCommand::UpdateConfig(new_config) => {
                                         *config.write().await = new_config;
                                         tracing::info!("Sync config updated");
                                     }
+                                    SyncCommand::SetFocused(focused) => {
+                                        scheduler.set_focused(focused);
+                                    }
+                                    SyncCommand::UserAction => {
+                                        let cfg = config.read().await;
+                                        scheduler.boost(&cfg, Utc::now());
+                                    }
                                 }
+
+                                // Re-plan the next sync relative to the last one, so
+                                // e.g. regaining focus can bring it forward.
+                                let delay = Self::next_delay(&scheduler, &config, &rate_limit).await;
+                                next_sync = (last_sync + delay).max(Instant::now());
                             }
                             None => {
                                 // Channel closed, exit the loop
@@ -268,9 +387,23 @@ impl SyncService {
         });
     }
 
+    /// Compute the delay until the next sync from the shared state.
+    async fn next_delay(
+        scheduler: &SyncScheduler,
+        config: &Arc<RwLock<SyncConfig>>,
+        rate_limit: &Arc<RwLock<Option<RateLimitInfo>>>,
+    ) -> Duration {
+        let config = config.read().await;
+        let rate_limit = rate_limit.read().await;
+        scheduler.next_delay(&config, rate_limit.as_ref(), Utc::now())
+    }
+
     /// Perform a single sync operation.
+    #[allow(clippy::too_many_arguments)]
     async fn perform_sync(
         github_client: &Arc<GitHubClient>,
+        cache: Option<&Arc<Cache>>,
+        max_age_secs: u64,
         repo_context: &Arc<RwLock<Option<(String, String)>>>,
         cached_prs: &Arc<RwLock<Vec<PullRequest>>>,
         status: &Arc<RwLock<SyncStatus>>,
@@ -285,6 +418,7 @@ impl SyncService {
         };
 
         // Update status to in progress
+        let previous_status = status.read().await.clone();
         let started_at = Utc::now();
         *status.write().await = SyncStatus::InProgress {
             started_at,
@@ -295,8 +429,13 @@ impl SyncService {
             current_task: Some("Fetching pull requests".to_string()),
         }));
 
-        // Create PR service
-        let pr_service = PrService::new(github_client.clone(), owner, repo);
+        // Create PR service, gated on the ETag change probe when a cache is available
+        let mut pr_service = PrService::new(github_client.clone(), owner, repo);
+        if let Some(cache) = cache {
+            let probe =
+                ChangeProbe::new(github_client.clone(), cache.clone()).with_max_age(max_age_secs);
+            pr_service = pr_service.with_change_probe(Arc::new(probe));
+        }
 
         // Fetch PRs
         match pr_service.list_prs(None).await {
@@ -310,6 +449,12 @@ impl SyncService {
                 // Update cache
                 *cached_prs.write().await = new_prs;
 
+                // Record the budget reported by the GraphQL `rateLimit` field
+                if let Some(info) = github_client.rate_limit_info().await {
+                    *rate_limit.write().await = Some(info.clone());
+                    let _ = event_tx.send(SyncEvent::RateLimitUpdated(info));
+                }
+
                 // Emit changes if any
                 if !changes.is_empty() {
                     let _ = event_tx.send(SyncEvent::ChangesDetected(changes));
@@ -336,34 +481,43 @@ impl SyncService {
                         / s.successful_syncs;
                 }
             }
+            Err(AppError::GitHub(GitHubError::Cancelled)) => {
+                // The repository changed under us; the next sync picks it up
+                tracing::debug!("Sync cancelled");
+                *status.write().await = previous_status.clone();
+                let _ = event_tx.send(SyncEvent::StatusChanged(previous_status));
+            }
             Err(e) => {
                 let error_msg = e.to_string();
                 tracing::error!("Sync failed: {}", error_msg);
 
-                // Check if it's a rate limit error by checking common rate limit indicators
-                // GitHub API returns errors containing "rate limit" or HTTP 403/429 status
-                let error_lower = error_msg.to_lowercase();
-                let is_rate_limited = error_lower.contains("rate limit")
-                    || error_lower.contains("rate_limit")
-                    || error_lower.contains("403")
-                    || error_lower.contains("429")
-                    || error_lower.contains("too many requests");
-
-                if is_rate_limited {
-                    // Default rate limit reset time (GitHub typically resets hourly)
-                    let resets_at = Utc::now() + chrono::Duration::minutes(15);
-
-                    *status.write().await = SyncStatus::RateLimited { resets_at };
-                    *rate_limit.write().await = Some(RateLimitInfo {
-                        remaining: 0,
-                        limit: GITHUB_RATE_LIMIT_DEFAULT,
-                        resets_at,
-                    });
-                    let _ = event_tx.send(SyncEvent::RateLimitUpdated(RateLimitInfo {
-                        remaining: 0,
-                        limit: GITHUB_RATE_LIMIT_DEFAULT,
-                        resets_at,
+                if let AppError::GitHub(GitHubError::RateLimited { reset_at }) = &e {
+                    // Prefer the budget tracked by the client (from the GraphQL
+                    // `rateLimit` field or response headers) over the error text.
+                    let info = match github_client.rate_limit_info().await {
+                        Some(info) => info,
+                        None => RateLimitInfo {
+                            remaining: 0,
+                            limit: rate_limit
+                                .read()
+                                .await
+                                .as_ref()
+                                .map_or(DEFAULT_RATE_LIMIT, |i| i.limit),
+                            resets_at: DateTime::parse_from_rfc3339(reset_at)
+                                .map(|dt| dt.with_timezone(&Utc))
+                                .unwrap_or_else(|_| Utc::now()),
+                            cost: None,
+                        },
+                    };
+
+                    *status.write().await = SyncStatus::RateLimited {
+                        resets_at: info.resets_at,
+                    };
+                    let _ = event_tx.send(SyncEvent::StatusChanged(SyncStatus::RateLimited {
+                        resets_at: info.resets_at,
                     }));
+                    *rate_limit.write().await = Some(info.clone());
+                    let _ = event_tx.send(SyncEvent::RateLimitUpdated(info));
                 } else {
                     // Update failure status
                     let failed_at = Utc::now();
@@ -434,6 +588,38 @@ impl SyncService {
                             new_status,
                         });
                     }
+                    // Check state changes don't bump `updated_at`
+                    if old_pr.check_state != new_pr.check_state {
+                        changes.push(SyncChange::PrChecksChanged {
+                            number: new_pr.number,
+                            new_state: new_pr.check_state,
+                        });
+                    }
+                    if old_pr.auto_merge.is_some() != new_pr.auto_merge.is_some() {
+                        changes.push(SyncChange::PrAutoMergeChanged {
+                            number: new_pr.number,
+                            enabled: new_pr.auto_merge.is_some(),
+                        });
+                    }
+                    // Still open, so leaving the queue means it was kicked out
+                    // or removed by hand
+                    match (&old_pr.merge_queue, &new_pr.merge_queue) {
+                        (Some(_), None) => changes.push(SyncChange::PrDequeued {
+                            number: new_pr.number,
+                            reason: None,
+                        }),
+                        (old_entry, Some(entry))
+                            if old_entry.as_ref().map(|e| (e.position, e.state))
+                                != Some((entry.position, entry.state)) =>
+                        {
+                            changes.push(SyncChange::PrMergeQueueChanged {
+                                number: new_pr.number,
+                                position: entry.position,
+                                state: entry.state,
+                            });
+                        }
+                        _ => {}
+                    }
                 }
                 None => {
                     // New PR
@@ -458,6 +644,148 @@ impl SyncService {
         changes
     }
 
+    /// Ingest a webhook delivery for the current repository.
+    ///
+    /// Updates the cached PR list in place, invalidates the affected ETag
+    /// cache entries so the next gated query refetches, and broadcasts the
+    /// resulting changes. Deliveries for other repositories are ignored;
+    /// deliveries without a repository are refused.
+    pub async fn ingest_webhook(&self, delivery: &WebhookDelivery) -> Result<Vec<SyncChange>> {
+        let Some(full_name) = delivery.repository() else {
+            return Err(GitHubError::Webhook(format!(
+                "Webhook delivery {} has no repository",
+                delivery.delivery_id.as_deref().unwrap_or("unknown")
+            ))
+            .into());
+        };
+
+        let Some((owner, repo)) = self.repo_context.read().await.clone() else {
+            return Ok(Vec::new());
+        };
+
+        if !full_name.eq_ignore_ascii_case(&format!("{}/{}", owner, repo)) {
+            tracing::debug!("Ignoring webhook delivery for {}", full_name);
+            return Ok(Vec::new());
+        }
+
+        let event = delivery.parse()?;
+        let changes = {
+            let mut prs = self.cached_prs.write().await;
+            webhook::apply_event(&event, &mut prs)
+        };
+
+        if changes.is_empty() {
+            return Ok(changes);
+        }
+
+        if let Some(cache) = &self.cache {
+            let mut keys = vec![ChangeProbe::pulls_key(&owner, &repo)];
+            keys.extend(changes.iter().filter_map(|change| match change {
+                SyncChange::PrCreated { number, .. }
+                | SyncChange::PrUpdated { number, .. }
+                | SyncChange::PrClosed { number, .. }
+                | SyncChange::PrReviewChanged { number, .. }
+                | SyncChange::PrChecksChanged { number, .. }
+                | SyncChange::PrAutoMergeChanged { number, .. }
+                | SyncChange::PrMergeQueueChanged { number, .. }
+                | SyncChange::PrDequeued { number, .. } => {
+                    Some(ChangeProbe::pull_key(&owner, &repo, *number))
+                }
+                SyncChange::StackParentMerged { .. } => None,
+            }));
+            for key in keys {
+                if let Err(e) = cache.remove_cached_response(&key).await {
+                    tracing::warn!("Failed to invalidate cached response for {}: {}", key, e);
+                }
+            }
+        }
+
+        let _ = self
+            .event_tx
+            .send(SyncEvent::ChangesDetected(changes.clone()));
+        Ok(changes)
+    }
+
+    /// Apply `edit` to a cached PR ahead of the mutation that makes it, so
+    /// the change shows up before the next sync.
+    ///
+    /// Both the synced list and the cached list query payload served to the
+    /// UI are updated. Returns the PR as it was, to pass to
+    /// [`SyncService::restore_pull_request`] if the mutation fails, or `None`
+    /// if the PR isn't cached.
+    pub async fn update_pull_request(
+        &self,
+        number: i64,
+        edit: impl Fn(&mut PullRequest),
+    ) -> Option<PullRequest> {
+        let previous = {
+            let mut prs = self.cached_prs.write().await;
+            prs.iter_mut().find(|pr| pr.number == number).map(|pr| {
+                let previous = pr.clone();
+                edit(pr);
+                previous
+            })
+        };
+
+        self.update_cached_list(|prs| {
+            if let Some(pr) = prs.iter_mut().find(|pr| pr.number == number) {
+                edit(pr);
+            }
+        })
+        .await;
+
+        previous
+    }
+
+    /// Roll back an optimistic [`SyncService::update_pull_request`].
+    ///
+    /// A copy that was replaced by a sync in the meantime (its `updated_at`
+    /// changed) is newer than `previous` and is left alone.
+    pub async fn restore_pull_request(&self, previous: PullRequest) {
+        let restore = |prs: &mut Vec<PullRequest>| {
+            if let Some(pr) = prs
+                .iter_mut()
+                .find(|pr| pr.number == previous.number && pr.updated_at == previous.updated_at)
+            {
+                *pr = previous.clone();
+            }
+        };
+
+        restore(&mut *self.cached_prs.write().await);
+        self.update_cached_list(restore).await;
+    }
+
+    /// Rewrite the cached payload of the PR list query in place, keeping its
+    /// ETag so the next probe still revalidates against GitHub, and its fetch
+    /// time so the patched list doesn't pass for freshly fetched.
+    async fn update_cached_list(&self, update: impl FnOnce(&mut Vec<PullRequest>)) {
+        let (Some(cache), Some((owner, repo))) =
+            (&self.cache, self.repo_context.read().await.clone())
+        else {
+            return;
+        };
+
+        let key = ChangeProbe::pulls_key(&owner, &repo);
+        let Ok(Some(cached)) = cache.get_cached_response(&key).await else {
+            return;
+        };
+        let Some(mut prs) = cached
+            .payload
+            .and_then(|payload| serde_json::from_str::<Vec<PullRequest>>(&payload).ok())
+        else {
+            return;
+        };
+
+        update(&mut prs);
+        let result = match serde_json::to_string(&prs) {
+            Ok(payload) => cache.update_cached_payload(&key, &payload).await,
+            Err(e) => Err(AppError::from(e)),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to update cached pull requests: {}", e);
+        }
+    }
+
     /// Trigger an immediate sync.
     pub async fn sync_now(&self) -> Result<()> {
         self.command_tx
@@ -466,14 +794,36 @@ impl SyncService {
             .map_err(|e| crate::error::AppError::from(e.to_string()))
     }
 
+    /// Current sync configuration.
+    pub async fn config(&self) -> SyncConfig {
+        self.config.read().await.clone()
+    }
+
     /// Update the sync configuration.
     pub async fn update_config(&self, config: SyncConfig) -> Result<()> {
+        // Applied right away too, so it reads back before the loop gets to it
+        *self.config.write().await = config.clone();
         self.command_tx
             .send(SyncCommand::UpdateConfig(config))
             .await
             .map_err(|e| crate::error::AppError::from(e.to_string()))
     }
 
+    /// Report whether the app window has focus.
+    ///
+    /// Syncing slows down while the window is in the background.
+    pub fn set_focused(&self, focused: bool) {
+        // Scheduling hints are best-effort; drop them if the loop is not running
+        let _ = self.command_tx.try_send(SyncCommand::SetFocused(focused));
+    }
+
+    /// Report a user action that is likely to change PR state.
+    ///
+    /// Syncing speeds up for `SyncConfig::boost_duration_secs` afterwards.
+    pub fn notify_user_action(&self) {
+        let _ = self.command_tx.try_send(SyncCommand::UserAction);
+    }
+
     /// Stop the sync service.
     pub async fn stop(&self) -> Result<()> {
         *self.running.write().await = false;
@@ -487,7 +837,10 @@ impl SyncService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::pr::{Author, Mergeable, PrState};
+    use crate::domain::pr::{
+        Author, AutoMerge, CheckState, MergeMethod, MergeQueueEntry, MergeQueueState, Mergeable,
+        PrState,
+    };
     use chrono::DateTime;
 
     fn make_pr(number: i64, title: &str, updated_at: DateTime<Utc>) -> PullRequest {
@@ -513,6 +866,12 @@ mod tests {
             additions: 0,
             deletions: 0,
             changed_files: 0,
+            check_state: None,
+            auto_merge: None,
+            merge_queue: None,
+            assignees: Vec::new(),
+            milestone: None,
+            requested_reviewers: Vec::new(),
         }
     }
 
@@ -553,6 +912,269 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_detect_check_state_change() {
+        let now = Utc::now();
+        let old_prs = vec![PullRequest {
+            check_state: Some(CheckState::Pending),
+            ..make_pr(1, "PR", now)
+        }];
+        let new_prs = vec![PullRequest {
+            check_state: Some(CheckState::Failure),
+            ..make_pr(1, "PR", now)
+        }];
+
+        let changes = SyncService::detect_changes(&old_prs, &new_prs);
+        assert!(matches!(
+            changes[..],
+            [SyncChange::PrChecksChanged {
+                number: 1,
+                new_state: Some(CheckState::Failure)
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_detect_merge_queue_changes() {
+        let now = Utc::now();
+        let entry = |position| MergeQueueEntry {
+            position,
+            state: MergeQueueState::AwaitingChecks,
+            enqueued_at: now,
+            estimated_time_to_merge: None,
+        };
+        let queued = |number, position| PullRequest {
+            merge_queue: Some(entry(position)),
+            ..make_pr(number, "PR", now)
+        };
+
+        let old_prs = vec![queued(1, 1), queued(2, 2), make_pr(3, "PR", now)];
+        let new_prs = vec![
+            make_pr(1, "PR", now),
+            queued(2, 1),
+            PullRequest {
+                auto_merge: Some(AutoMerge {
+                    merge_method: MergeMethod::Squash,
+                    enabled_at: Some(now),
+                    enabled_by: Some("octocat".to_string()),
+                }),
+                ..make_pr(3, "PR", now)
+            },
+        ];
+
+        let changes = SyncService::detect_changes(&old_prs, &new_prs);
+        assert!(matches!(
+            changes[..],
+            [
+                SyncChange::PrDequeued {
+                    number: 1,
+                    reason: None
+                },
+                SyncChange::PrMergeQueueChanged {
+                    number: 2,
+                    position: 1,
+                    state: MergeQueueState::AwaitingChecks
+                },
+                SyncChange::PrAutoMergeChanged {
+                    number: 3,
+                    enabled: true
+                },
+            ]
+        ));
+    }
+
+    fn rate_limit(remaining: u32, resets_in_secs: i64, now: DateTime<Utc>) -> RateLimitInfo {
+        RateLimitInfo {
+            remaining,
+            limit: 5000,
+            resets_at: now + chrono::Duration::seconds(resets_in_secs),
+            cost: Some(1),
+        }
+    }
+
+    #[test]
+    fn test_scheduler_uses_base_interval() {
+        let config = SyncConfig::default();
+        let scheduler = SyncScheduler::default();
+        let now = Utc::now();
+
+        let delay = scheduler.next_delay(&config, Some(&rate_limit(4000, 3600, now)), now);
+        assert_eq!(delay, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_scheduler_backs_off_when_unfocused() {
+        let config = SyncConfig::default();
+        let mut scheduler = SyncScheduler::default();
+        scheduler.set_focused(false);
+
+        let delay = scheduler.next_delay(&config, None, Utc::now());
+        assert_eq!(delay, Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_scheduler_boosts_after_user_action() {
+        let config = SyncConfig::default();
+        let mut scheduler = SyncScheduler::default();
+        let now = Utc::now();
+        scheduler.boost(&config, now);
+
+        assert_eq!(
+            scheduler.next_delay(&config, None, now),
+            Duration::from_secs(15)
+        );
+
+        // Boost expires after boost_duration_secs
+        let later = now + chrono::Duration::seconds(121);
+        assert_eq!(
+            scheduler.next_delay(&config, None, later),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn test_scheduler_backs_off_on_low_budget() {
+        let config = SyncConfig::default();
+        let mut scheduler = SyncScheduler::default();
+        let now = Utc::now();
+        scheduler.boost(&config, now);
+
+        // 10 queries left over 1000 seconds -> one every 100 seconds; doubling wins, boost ignored
+        let delay = scheduler.next_delay(&config, Some(&rate_limit(10, 1000, now)), now);
+        assert_eq!(delay, Duration::from_secs(120));
+
+        // The budget interval isn't capped by max_interval_secs
+        let delay = scheduler.next_delay(&config, Some(&rate_limit(2, 3600, now)), now);
+        assert_eq!(delay, Duration::from_secs(1800));
+    }
+
+    #[test]
+    fn test_scheduler_waits_for_reset_when_limited() {
+        let config = SyncConfig::default();
+        let scheduler = SyncScheduler::default();
+        let now = Utc::now();
+
+        let delay = scheduler.next_delay(&config, Some(&rate_limit(0, 1800, now)), now);
+        assert_eq!(delay, Duration::from_secs(1801));
+    }
+
+    #[tokio::test]
+    async fn test_ingest_webhook() {
+        let cache = Arc::new(Cache::in_memory().unwrap());
+        let service = SyncService::new(Arc::new(GitHubClient::default()), SyncConfig::default())
+            .with_cache(cache.clone());
+        let delivery: WebhookDelivery = serde_json::from_str(include_str!(
+            "../../fixtures/webhooks/pull_request_opened.json"
+        ))
+        .unwrap();
+
+        // No repository opened yet
+        assert!(service.ingest_webhook(&delivery).await.unwrap().is_empty());
+
+        // Deliveries that don't name a repository are refused
+        let mut anonymous = delivery.clone();
+        anonymous
+            .payload
+            .as_object_mut()
+            .unwrap()
+            .remove("repository");
+        assert!(service.ingest_webhook(&anonymous).await.is_err());
+
+        // Deliveries for other repositories are ignored
+        service
+            .set_repository("octo-org".to_string(), "other".to_string())
+            .await;
+        assert!(service.ingest_webhook(&delivery).await.unwrap().is_empty());
+
+        service
+            .set_repository("octo-org".to_string(), "maguffin".to_string())
+            .await;
+        let pull_key = ChangeProbe::pull_key("octo-org", "maguffin", 42);
+        cache
+            .save_cached_response(&pull_key, "\"v1\"", Some("{}"))
+            .await
+            .unwrap();
+        let mut events = service.subscribe();
+
+        let changes = service.ingest_webhook(&delivery).await.unwrap();
+        assert!(matches!(
+            changes.as_slice(),
+            [SyncChange::PrCreated { number: 42, .. }]
+        ));
+        assert_eq!(service.cached_prs.read().await.len(), 1);
+        assert!(cache
+            .get_cached_response(&pull_key)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            SyncEvent::ChangesDetected(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_optimistic_update_and_restore() {
+        let cache = Arc::new(Cache::in_memory().unwrap());
+        let service = SyncService::new(Arc::new(GitHubClient::default()), SyncConfig::default())
+            .with_cache(cache.clone());
+        service
+            .set_repository("octo".to_string(), "repo".to_string())
+            .await;
+
+        let pr = make_pr(1, "Old title", Utc::now());
+        *service.cached_prs.write().await = vec![pr.clone()];
+        let key = ChangeProbe::pulls_key("octo", "repo");
+        let payload = serde_json::to_string(&vec![pr]).unwrap();
+        cache
+            .save_cached_response(&key, "\"v1\"", Some(&payload))
+            .await
+            .unwrap();
+
+        let fetched_at = cache
+            .get_cached_response(&key)
+            .await
+            .unwrap()
+            .unwrap()
+            .updated_at;
+
+        let cached_title = || async {
+            let payload = cache
+                .get_cached_response(&key)
+                .await
+                .unwrap()
+                .unwrap()
+                .payload;
+            let prs: Vec<PullRequest> = serde_json::from_str(&payload.unwrap()).unwrap();
+            prs[0].title.clone()
+        };
+
+        assert!(service.update_pull_request(2, |_| {}).await.is_none());
+
+        let previous = service
+            .update_pull_request(1, |pr| pr.title = "New title".to_string())
+            .await
+            .unwrap();
+        assert_eq!(previous.title, "Old title");
+        assert_eq!(service.pull_requests().await[0].title, "New title");
+        assert_eq!(cached_title().await, "New title");
+        let cached = cache.get_cached_response(&key).await.unwrap().unwrap();
+        assert_eq!(cached.etag, "\"v1\"");
+        assert_eq!(cached.updated_at, fetched_at);
+
+        service.restore_pull_request(previous.clone()).await;
+        assert_eq!(service.pull_requests().await[0].title, "Old title");
+        assert_eq!(cached_title().await, "Old title");
+
+        // A PR refreshed by a sync in the meantime is not rolled back
+        service
+            .update_pull_request(1, |pr| pr.title = "New title".to_string())
+            .await;
+        service.cached_prs.write().await[0].updated_at = Utc::now() + chrono::Duration::seconds(1);
+        service.restore_pull_request(previous).await;
+        assert_eq!(service.pull_requests().await[0].title, "New title");
+    }
+
     #[test]
     fn test_no_changes() {
         let now = Utc::now();
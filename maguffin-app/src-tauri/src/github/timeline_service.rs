@@ -0,0 +1,640 @@
+//! Pull Request Conversation Service.
+//!
+//! This module fetches a pull request's conversation timeline and posts,
+//! edits and deletes comments and reactions on it.
+
+use crate::domain::pr::{Author, Label, ReviewState};
+use crate::domain::timeline::{
+    self, Comment, CommentKind, ReactionContent, ReactionGroup, TimelineCommit, TimelineItem,
+};
+use crate::error::{GitHubError, Result};
+use crate::github::queries::{
+    add_comment, add_pull_request_review_thread_reply as add_reply, add_reaction,
+    delete_issue_comment, delete_pull_request_review_comment, get_pull_request_timeline as gql,
+    remove_reaction, update_issue_comment, update_pull_request_review_comment, AddComment,
+    AddPullRequestReviewThreadReply, AddReaction, DeleteIssueComment,
+    DeletePullRequestReviewComment, GetPullRequestTimeline, RemoveReaction, UpdateIssueComment,
+    UpdatePullRequestReviewComment,
+};
+use crate::github::{GitHubClient, PrService};
+use std::sync::Arc;
+
+type GqlTimelineItem = gql::GetPullRequestTimelineRepositoryPullRequestTimelineItemsNodes;
+type GqlReview =
+    gql::GetPullRequestTimelineRepositoryPullRequestTimelineItemsNodesOnPullRequestReview;
+type GqlIssueComment =
+    gql::GetPullRequestTimelineRepositoryPullRequestTimelineItemsNodesOnIssueComment;
+
+/// Service for a pull request's conversation.
+pub struct TimelineService {
+    client: Arc<GitHubClient>,
+    owner: String,
+    repo: String,
+}
+
+impl TimelineService {
+    /// Create a new timeline service.
+    pub fn new(client: Arc<GitHubClient>, owner: String, repo: String) -> Self {
+        Self {
+            client,
+            owner,
+            repo,
+        }
+    }
+
+    /// Get the whole conversation timeline of a PR, oldest first.
+    pub async fn get_timeline(&self, number: i64) -> Result<Vec<TimelineItem>> {
+        let mut items = Vec::new();
+        let mut after = None;
+
+        loop {
+            let variables = gql::Variables {
+                owner: self.owner.clone(),
+                repo: self.repo.clone(),
+                number,
+                after,
+            };
+
+            let response = self
+                .client
+                .execute::<GetPullRequestTimeline>(variables)
+                .await?;
+            let connection = response
+                .repository
+                .ok_or_else(PrService::repository_not_found)?
+                .pull_request
+                .ok_or_else(|| PrService::pr_not_found(number))?
+                .timeline_items;
+
+            items.extend(
+                connection
+                    .nodes
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flatten()
+                    .map(Self::convert_item),
+            );
+
+            if !connection.page_info.has_next_page {
+                break;
+            }
+            after = connection.page_info.end_cursor;
+        }
+
+        Ok(items)
+    }
+
+    /// Post a comment on a PR's conversation.
+    pub async fn post_comment(&self, number: i64, body: String) -> Result<Comment> {
+        Self::validate_body(&body)?;
+        let pr = PrService::new(self.client.clone(), self.owner.clone(), self.repo.clone())
+            .get_pr_ref(number)
+            .await?;
+
+        let variables = add_comment::Variables {
+            subject_id: pr.id,
+            body,
+        };
+
+        let response = self.client.execute::<AddComment>(variables).await?;
+        let comment = response
+            .add_comment
+            .and_then(|payload| payload.comment_edge)
+            .and_then(|edge| edge.node)
+            .ok_or_else(|| GitHubError::GraphQL("Failed to post comment".to_string()))?;
+
+        Ok(Comment {
+            id: comment.id,
+            kind: CommentKind::Issue,
+            author: comment
+                .author
+                .map(|a| Author {
+                    login: a.login,
+                    avatar_url: a.avatar_url,
+                })
+                .unwrap_or_else(PrService::ghost),
+            body: comment.body,
+            created_at: PrService::parse_datetime(&comment.created_at),
+            last_edited_at: None,
+            path: None,
+            reply_to: None,
+            reactions: Vec::new(),
+            can_edit: comment.viewer_can_update,
+            can_delete: comment.viewer_can_delete,
+            can_react: comment.viewer_can_react,
+        })
+    }
+
+    /// Reply to a review thread.
+    ///
+    /// The reply is published right away, outside any pending review.
+    pub async fn reply_to_thread(&self, thread_id: String, body: String) -> Result<Comment> {
+        Self::validate_body(&body)?;
+
+        let variables = add_reply::Variables {
+            pull_request_review_thread_id: thread_id,
+            body,
+        };
+
+        let response = self
+            .client
+            .execute::<AddPullRequestReviewThreadReply>(variables)
+            .await?;
+        let comment = response
+            .add_pull_request_review_thread_reply
+            .and_then(|payload| payload.comment)
+            .ok_or_else(|| GitHubError::GraphQL("Failed to post reply".to_string()))?;
+
+        Ok(Comment {
+            id: comment.id,
+            kind: CommentKind::Review,
+            author: comment
+                .author
+                .map(|a| Author {
+                    login: a.login,
+                    avatar_url: a.avatar_url,
+                })
+                .unwrap_or_else(PrService::ghost),
+            body: comment.body,
+            created_at: PrService::parse_datetime(&comment.created_at),
+            last_edited_at: None,
+            path: Some(comment.path),
+            reply_to: comment.reply_to.map(|parent| parent.id),
+            reactions: Vec::new(),
+            can_edit: comment.viewer_can_update,
+            can_delete: comment.viewer_can_delete,
+            can_react: comment.viewer_can_react,
+        })
+    }
+
+    /// Replace the body of a comment.
+    pub async fn edit_comment(&self, kind: CommentKind, id: String, body: String) -> Result<()> {
+        Self::validate_body(&body)?;
+
+        let edited = match kind {
+            CommentKind::Issue => {
+                let variables = update_issue_comment::Variables { id, body };
+                self.client
+                    .execute::<UpdateIssueComment>(variables)
+                    .await?
+                    .update_issue_comment
+                    .and_then(|payload| payload.issue_comment)
+                    .is_some()
+            }
+            CommentKind::Review => {
+                let variables = update_pull_request_review_comment::Variables {
+                    pull_request_review_comment_id: id,
+                    body,
+                };
+                self.client
+                    .execute::<UpdatePullRequestReviewComment>(variables)
+                    .await?
+                    .update_pull_request_review_comment
+                    .and_then(|payload| payload.pull_request_review_comment)
+                    .is_some()
+            }
+        };
+
+        if !edited {
+            return Err(GitHubError::GraphQL("Failed to edit comment".to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Delete a comment.
+    pub async fn delete_comment(&self, kind: CommentKind, id: String) -> Result<()> {
+        match kind {
+            CommentKind::Issue => {
+                let variables = delete_issue_comment::Variables { id };
+                self.client.execute::<DeleteIssueComment>(variables).await?;
+            }
+            CommentKind::Review => {
+                let variables = delete_pull_request_review_comment::Variables { id };
+                self.client
+                    .execute::<DeletePullRequestReviewComment>(variables)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add or remove the viewer's reaction on a comment or review.
+    ///
+    /// Returns the subject's reactions afterwards.
+    pub async fn set_reaction(
+        &self,
+        subject_id: String,
+        content: ReactionContent,
+        reacted: bool,
+    ) -> Result<Vec<ReactionGroup>> {
+        let groups = if reacted {
+            let content = match content {
+                ReactionContent::ThumbsUp => add_reaction::ReactionContent::THUMBS_UP,
+                ReactionContent::ThumbsDown => add_reaction::ReactionContent::THUMBS_DOWN,
+                ReactionContent::Laugh => add_reaction::ReactionContent::LAUGH,
+                ReactionContent::Hooray => add_reaction::ReactionContent::HOORAY,
+                ReactionContent::Confused => add_reaction::ReactionContent::CONFUSED,
+                ReactionContent::Heart => add_reaction::ReactionContent::HEART,
+                ReactionContent::Rocket => add_reaction::ReactionContent::ROCKET,
+                ReactionContent::Eyes => add_reaction::ReactionContent::EYES,
+            };
+            let variables = add_reaction::Variables {
+                subject_id,
+                content,
+            };
+            self.client
+                .execute::<AddReaction>(variables)
+                .await?
+                .add_reaction
+                .and_then(|payload| payload.subject)
+                .map(|subject| {
+                    subject
+                        .reaction_groups
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|group| {
+                            let content = match group.content {
+                                add_reaction::ReactionContent::THUMBS_UP => {
+                                    ReactionContent::ThumbsUp
+                                }
+                                add_reaction::ReactionContent::THUMBS_DOWN => {
+                                    ReactionContent::ThumbsDown
+                                }
+                                add_reaction::ReactionContent::LAUGH => ReactionContent::Laugh,
+                                add_reaction::ReactionContent::HOORAY => ReactionContent::Hooray,
+                                add_reaction::ReactionContent::CONFUSED => {
+                                    ReactionContent::Confused
+                                }
+                                add_reaction::ReactionContent::HEART => ReactionContent::Heart,
+                                add_reaction::ReactionContent::ROCKET => ReactionContent::Rocket,
+                                add_reaction::ReactionContent::EYES => ReactionContent::Eyes,
+                                add_reaction::ReactionContent::Other(_) => return None,
+                            };
+                            Some(ReactionGroup {
+                                content,
+                                count: group.reactors.total_count,
+                                viewer_has_reacted: group.viewer_has_reacted,
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                })
+        } else {
+            let content = match content {
+                ReactionContent::ThumbsUp => remove_reaction::ReactionContent::THUMBS_UP,
+                ReactionContent::ThumbsDown => remove_reaction::ReactionContent::THUMBS_DOWN,
+                ReactionContent::Laugh => remove_reaction::ReactionContent::LAUGH,
+                ReactionContent::Hooray => remove_reaction::ReactionContent::HOORAY,
+                ReactionContent::Confused => remove_reaction::ReactionContent::CONFUSED,
+                ReactionContent::Heart => remove_reaction::ReactionContent::HEART,
+                ReactionContent::Rocket => remove_reaction::ReactionContent::ROCKET,
+                ReactionContent::Eyes => remove_reaction::ReactionContent::EYES,
+            };
+            let variables = remove_reaction::Variables {
+                subject_id,
+                content,
+            };
+            self.client
+                .execute::<RemoveReaction>(variables)
+                .await?
+                .remove_reaction
+                .and_then(|payload| payload.subject)
+                .map(|subject| {
+                    subject
+                        .reaction_groups
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|group| {
+                            let content = match group.content {
+                                remove_reaction::ReactionContent::THUMBS_UP => {
+                                    ReactionContent::ThumbsUp
+                                }
+                                remove_reaction::ReactionContent::THUMBS_DOWN => {
+                                    ReactionContent::ThumbsDown
+                                }
+                                remove_reaction::ReactionContent::LAUGH => ReactionContent::Laugh,
+                                remove_reaction::ReactionContent::HOORAY => ReactionContent::Hooray,
+                                remove_reaction::ReactionContent::CONFUSED => {
+                                    ReactionContent::Confused
+                                }
+                                remove_reaction::ReactionContent::HEART => ReactionContent::Heart,
+                                remove_reaction::ReactionContent::ROCKET => ReactionContent::Rocket,
+                                remove_reaction::ReactionContent::EYES => ReactionContent::Eyes,
+                                remove_reaction::ReactionContent::Other(_) => return None,
+                            };
+                            Some(ReactionGroup {
+                                content,
+                                count: group.reactors.total_count,
+                                viewer_has_reacted: group.viewer_has_reacted,
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                })
+        };
+
+        groups
+            .map(ReactionGroup::non_empty)
+            .ok_or_else(|| GitHubError::GraphQL("Failed to update reaction".to_string()).into())
+    }
+
+    /// Reject comments GitHub would refuse anyway.
+    fn validate_body(body: &str) -> Result<()> {
+        if body.trim().is_empty() {
+            return Err(GitHubError::InvalidRequest("Comment body is empty".to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// Convert a GraphQL timeline item to domain type.
+    fn convert_item(item: GqlTimelineItem) -> TimelineItem {
+        match item {
+            GqlTimelineItem::IssueComment(comment) => {
+                TimelineItem::Comment(Self::convert_issue_comment(comment))
+            }
+            GqlTimelineItem::PullRequestReview(review) => Self::convert_review(review),
+            GqlTimelineItem::PullRequestCommit(item) => {
+                let author = item.commit.author.and_then(|a| match a.user {
+                    Some(user) => Some(user.login),
+                    None => a.name,
+                });
+                TimelineItem::Commit(TimelineCommit {
+                    oid: item.commit.oid,
+                    headline: item.commit.message_headline,
+                    author,
+                    committed_at: PrService::parse_datetime(&item.commit.committed_date),
+                })
+            }
+            GqlTimelineItem::HeadRefForcePushedEvent(event) => TimelineItem::ForcePush {
+                actor: event.actor.map(|a| Author {
+                    login: a.login,
+                    avatar_url: a.avatar_url,
+                }),
+                before_oid: event.before_commit.map(|c| c.oid),
+                after_oid: event.after_commit.map(|c| c.oid),
+                created_at: PrService::parse_datetime(&event.created_at),
+            },
+            GqlTimelineItem::LabeledEvent(event) => TimelineItem::Labeled {
+                actor: event.actor.map(|a| Author {
+                    login: a.login,
+                    avatar_url: a.avatar_url,
+                }),
+                label: Label {
+                    name: event.label.name,
+                    color: event.label.color,
+                },
+                created_at: PrService::parse_datetime(&event.created_at),
+            },
+            GqlTimelineItem::UnlabeledEvent(event) => TimelineItem::Unlabeled {
+                actor: event.actor.map(|a| Author {
+                    login: a.login,
+                    avatar_url: a.avatar_url,
+                }),
+                label: Label {
+                    name: event.label.name,
+                    color: event.label.color,
+                },
+                created_at: PrService::parse_datetime(&event.created_at),
+            },
+            GqlTimelineItem::BaseRefChangedEvent(event) => TimelineItem::BaseChanged {
+                actor: event.actor.map(|a| Author {
+                    login: a.login,
+                    avatar_url: a.avatar_url,
+                }),
+                from: event.previous_ref_name,
+                to: event.current_ref_name,
+                created_at: PrService::parse_datetime(&event.created_at),
+            },
+            GqlTimelineItem::MergedEvent(event) => TimelineItem::Merged {
+                actor: event.actor.map(|a| Author {
+                    login: a.login,
+                    avatar_url: a.avatar_url,
+                }),
+                commit_oid: event.commit.map(|c| c.oid),
+                base_ref: event.merge_ref_name,
+                created_at: PrService::parse_datetime(&event.created_at),
+            },
+        }
+    }
+
+    /// Convert a conversation comment.
+    fn convert_issue_comment(comment: GqlIssueComment) -> Comment {
+        Comment {
+            id: comment.id,
+            kind: CommentKind::Issue,
+            author: comment
+                .author
+                .map(|a| Author {
+                    login: a.login,
+                    avatar_url: a.avatar_url,
+                })
+                .unwrap_or_else(PrService::ghost),
+            body: comment.body,
+            created_at: PrService::parse_datetime(&comment.created_at),
+            last_edited_at: comment
+                .last_edited_at
+                .as_deref()
+                .map(PrService::parse_datetime),
+            path: None,
+            reply_to: None,
+            reactions: Self::convert_reactions(comment.reaction_groups),
+            can_edit: comment.viewer_can_update,
+            can_delete: comment.viewer_can_delete,
+            can_react: comment.viewer_can_react,
+        }
+    }
+
+    /// Convert a review and its comments.
+    fn convert_review(review: GqlReview) -> TimelineItem {
+        let state = match review.state {
+            gql::PullRequestReviewState::PENDING => ReviewState::Pending,
+            gql::PullRequestReviewState::COMMENTED => ReviewState::Commented,
+            gql::PullRequestReviewState::APPROVED => ReviewState::Approved,
+            gql::PullRequestReviewState::CHANGES_REQUESTED => ReviewState::ChangesRequested,
+            gql::PullRequestReviewState::DISMISSED => ReviewState::Dismissed,
+            gql::PullRequestReviewState::Other(_) => ReviewState::Commented,
+        };
+
+        let comments = review
+            .comments
+            .nodes
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .map(|c| Comment {
+                id: c.id,
+                kind: CommentKind::Review,
+                author: c
+                    .author
+                    .map(|a| Author {
+                        login: a.login,
+                        avatar_url: a.avatar_url,
+                    })
+                    .unwrap_or_else(PrService::ghost),
+                body: c.body,
+                created_at: PrService::parse_datetime(&c.created_at),
+                last_edited_at: c.last_edited_at.as_deref().map(PrService::parse_datetime),
+                path: Some(c.path),
+                reply_to: c.reply_to.map(|parent| parent.id),
+                reactions: Self::convert_reactions(c.reaction_groups),
+                can_edit: c.viewer_can_update,
+                can_delete: c.viewer_can_delete,
+                can_react: c.viewer_can_react,
+            })
+            .collect();
+
+        TimelineItem::Review {
+            id: review.id,
+            author: review
+                .author
+                .map(|a| Author {
+                    login: a.login,
+                    avatar_url: a.avatar_url,
+                })
+                .unwrap_or_else(PrService::ghost),
+            state,
+            body: review.body,
+            created_at: PrService::parse_datetime(&review.created_at),
+            submitted_at: review
+                .submitted_at
+                .as_deref()
+                .map(PrService::parse_datetime),
+            reactions: Self::convert_reactions(review.reaction_groups),
+            can_react: review.viewer_can_react,
+            comments: timeline::thread_comments(comments),
+        }
+    }
+
+    /// Convert reaction groups, dropping empty ones.
+    fn convert_reactions(groups: Option<Vec<gql::ReactionGroupFields>>) -> Vec<ReactionGroup> {
+        let groups = groups.unwrap_or_default().into_iter().filter_map(|group| {
+            let content = match group.content {
+                gql::ReactionContent::THUMBS_UP => ReactionContent::ThumbsUp,
+                gql::ReactionContent::THUMBS_DOWN => ReactionContent::ThumbsDown,
+                gql::ReactionContent::LAUGH => ReactionContent::Laugh,
+                gql::ReactionContent::HOORAY => ReactionContent::Hooray,
+                gql::ReactionContent::CONFUSED => ReactionContent::Confused,
+                gql::ReactionContent::HEART => ReactionContent::Heart,
+                gql::ReactionContent::ROCKET => ReactionContent::Rocket,
+                gql::ReactionContent::EYES => ReactionContent::Eyes,
+                gql::ReactionContent::Other(_) => return None,
+            };
+            Some(ReactionGroup {
+                content,
+                count: group.reactors.total_count,
+                viewer_has_reacted: group.viewer_has_reacted,
+            })
+        });
+
+        ReactionGroup::non_empty(groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_timeline_items() {
+        let nodes: Vec<GqlTimelineItem> = serde_json::from_value(serde_json::json!([
+            {
+                "__typename": "PullRequestCommit",
+                "commit": {
+                    "oid": "abc123",
+                    "messageHeadline": "Add feature",
+                    "committedDate": "2025-01-01T00:00:00Z",
+                    "author": { "name": "Mona", "user": null }
+                }
+            },
+            {
+                "__typename": "IssueComment",
+                "id": "IC_1",
+                "author": { "__typename": "User", "login": "hubot", "avatarUrl": "" },
+                "body": "LGTM",
+                "createdAt": "2025-01-02T00:00:00Z",
+                "lastEditedAt": "2025-01-02T01:00:00Z",
+                "reactionGroups": [
+                    { "content": "THUMBS_UP", "viewerHasReacted": true, "reactors": { "totalCount": 2 } },
+                    { "content": "EYES", "viewerHasReacted": false, "reactors": { "totalCount": 0 } }
+                ],
+                "viewerCanUpdate": false,
+                "viewerCanDelete": false,
+                "viewerCanReact": true
+            },
+            {
+                "__typename": "PullRequestReview",
+                "id": "PRR_1",
+                "author": { "__typename": "User", "login": "octocat", "avatarUrl": "" },
+                "body": "",
+                "state": "CHANGES_REQUESTED",
+                "createdAt": "2025-01-03T00:00:00Z",
+                "submittedAt": "2025-01-03T00:05:00Z",
+                "reactionGroups": [],
+                "viewerCanReact": true,
+                "comments": {
+                    "nodes": [
+                        {
+                            "id": "PRRC_1",
+                            "author": null,
+                            "body": "Rename this",
+                            "path": "src/lib.rs",
+                            "createdAt": "2025-01-03T00:00:00Z",
+                            "lastEditedAt": null,
+                            "replyTo": null,
+                            "reactionGroups": null,
+                            "viewerCanUpdate": true,
+                            "viewerCanDelete": true,
+                            "viewerCanReact": true
+                        }
+                    ]
+                }
+            },
+            {
+                "__typename": "BaseRefChangedEvent",
+                "actor": { "__typename": "User", "login": "octocat", "avatarUrl": "" },
+                "createdAt": "2025-01-04T00:00:00Z",
+                "previousRefName": "main",
+                "currentRefName": "release"
+            }
+        ]))
+        .unwrap();
+
+        let items: Vec<TimelineItem> = nodes
+            .into_iter()
+            .map(TimelineService::convert_item)
+            .collect();
+
+        assert!(matches!(
+            &items[0],
+            TimelineItem::Commit(commit) if commit.author.as_deref() == Some("Mona")
+        ));
+
+        let TimelineItem::Comment(comment) = &items[1] else {
+            panic!("expected a comment");
+        };
+        assert_eq!(comment.kind, CommentKind::Issue);
+        assert!(comment.last_edited_at.is_some());
+        assert_eq!(
+            comment.reactions,
+            vec![ReactionGroup {
+                content: ReactionContent::ThumbsUp,
+                count: 2,
+                viewer_has_reacted: true,
+            }]
+        );
+
+        let TimelineItem::Review {
+            state, comments, ..
+        } = &items[2]
+        else {
+            panic!("expected a review");
+        };
+        assert_eq!(*state, ReviewState::ChangesRequested);
+        assert_eq!(comments[0].author.login, "ghost");
+        assert_eq!(comments[0].path.as_deref(), Some("src/lib.rs"));
+
+        assert!(matches!(
+            &items[3],
+            TimelineItem::BaseChanged { from, to, .. } if from == "main" && to == "release"
+        ));
+        assert!(items[2].created_at() < items[3].created_at());
+    }
+}
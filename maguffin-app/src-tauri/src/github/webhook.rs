@@ -0,0 +1,1206 @@
+//! GitHub webhook receiver.
+//!
+//! An optional alternative to polling for near-real-time updates. A local
+//! HTTP listener accepts GitHub webhook deliveries, either directly or
+//! forwarded by a user-provided tunnel or relay (ngrok, smee.io, ...), and
+//! validates their `X-Hub-Signature-256` HMAC. Accepted deliveries are handed
+//! to the sync service, which turns them into `SyncChange` events and cache
+//! updates via [`apply_event`].
+//!
+//! Deliveries can be recorded to disk and replayed later through the same
+//! ingestion path, which is also how the mapping is tested.
+
+use crate::domain::pr::{
+    Author, AutoMerge, Label, MergeMethod, Mergeable, Milestone, PrState, PullRequest,
+    ReviewDecision,
+};
+use crate::domain::sync::SyncChange;
+use crate::error::{GitHubError, Result, StorageError};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashSet, VecDeque};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+
+/// Header carrying the event name.
+pub const EVENT_HEADER: &str = "x-github-event";
+
+/// Header carrying the delivery GUID.
+pub const DELIVERY_HEADER: &str = "x-github-delivery";
+
+/// Header carrying the HMAC-SHA256 signature of the body.
+pub const SIGNATURE_HEADER: &str = "x-hub-signature-256";
+
+/// Maximum accepted payload size (GitHub caps deliveries at 25 MB).
+const MAX_PAYLOAD_BYTES: usize = 25 * 1024 * 1024;
+
+/// Number of recent payload digests remembered to drop replayed deliveries.
+const SEEN_DELIVERIES: usize = 10_000;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Verify an `X-Hub-Signature-256` header (`sha256=<hex>`) against a body.
+///
+/// The comparison is constant-time.
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(hex_digest) = signature.strip_prefix("sha256=") else {
+        return false;
+    };
+    let Ok(expected) = hex::decode(hex_digest) else {
+        return false;
+    };
+    let Ok(mut mac) = HmacSha256::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Whether a header value only has characters in `[A-Za-z0-9_-]`.
+///
+/// The event and delivery headers aren't covered by the signature but end up
+/// in recording file names, so nothing else is accepted.
+fn is_safe_header(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Compute the `X-Hub-Signature-256` header value for a body.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// A single webhook delivery, as received or recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    /// Event name from `X-GitHub-Event`
+    pub event: String,
+
+    /// Delivery GUID from `X-GitHub-Delivery`
+    pub delivery_id: Option<String>,
+
+    /// JSON payload
+    pub payload: serde_json::Value,
+}
+
+impl WebhookDelivery {
+    /// Full name (`owner/repo`) of the repository the delivery is about.
+    pub fn repository(&self) -> Option<&str> {
+        self.payload
+            .pointer("/repository/full_name")
+            .and_then(|v| v.as_str())
+    }
+
+    /// Parse the payload into a typed event.
+    pub fn parse(&self) -> Result<WebhookEvent> {
+        let payload = self.payload.clone();
+        let event = match self.event.as_str() {
+            "pull_request" => {
+                let p: PullRequestPayload = serde_json::from_value(payload)?;
+                WebhookEvent::PullRequest {
+                    action: p.action,
+                    pull_request: Box::new(p.pull_request),
+                    reason: p.reason,
+                }
+            }
+            "pull_request_review" => {
+                let p: PullRequestReviewPayload = serde_json::from_value(payload)?;
+                WebhookEvent::PullRequestReview {
+                    action: p.action,
+                    number: p.pull_request.number,
+                    title: p.pull_request.title,
+                    state: p.review.state,
+                }
+            }
+            "check_suite" => {
+                let p: CheckSuitePayload = serde_json::from_value(payload)?;
+                WebhookEvent::CheckSuite {
+                    action: p.action,
+                    head_branch: p.check_suite.head_branch,
+                    pr_numbers: p
+                        .check_suite
+                        .pull_requests
+                        .into_iter()
+                        .map(|pr| pr.number)
+                        .collect(),
+                }
+            }
+            "status" => {
+                let p: StatusPayload = serde_json::from_value(payload)?;
+                WebhookEvent::Status {
+                    state: p.state,
+                    branches: p.branches.into_iter().map(|b| b.name).collect(),
+                }
+            }
+            "ping" => WebhookEvent::Ping,
+            other => WebhookEvent::Unsupported(other.to_string()),
+        };
+        Ok(event)
+    }
+}
+
+/// Typed webhook event, limited to the events the sync service understands.
+#[derive(Debug, Clone)]
+pub enum WebhookEvent {
+    /// `pull_request`: opened, closed, edited, synchronize, ...
+    PullRequest {
+        action: String,
+        pull_request: Box<RestPullRequest>,
+        /// Why the PR left the merge queue (`dequeued` only)
+        reason: Option<String>,
+    },
+
+    /// `pull_request_review`: submitted, edited, dismissed
+    PullRequestReview {
+        action: String,
+        number: i64,
+        title: String,
+        state: String,
+    },
+
+    /// `check_suite`: requested, rerequested, completed
+    CheckSuite {
+        action: String,
+        head_branch: Option<String>,
+        pr_numbers: Vec<i64>,
+    },
+
+    /// `status`: a commit status changed
+    Status {
+        state: String,
+        branches: Vec<String>,
+    },
+
+    /// `ping`: sent when the webhook is created
+    Ping,
+
+    /// Any other event
+    Unsupported(String),
+}
+
+/// Pull request as represented in REST webhook payloads.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RestPullRequest {
+    pub node_id: String,
+    pub number: i64,
+    pub title: String,
+    pub body: Option<String>,
+    pub state: String,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub merged: bool,
+    pub mergeable: Option<bool>,
+    pub user: RestUser,
+    pub head: RestRef,
+    pub base: RestRef,
+    #[serde(default)]
+    pub labels: Vec<RestLabel>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub commits: i32,
+    #[serde(default)]
+    pub additions: i32,
+    #[serde(default)]
+    pub deletions: i32,
+    #[serde(default)]
+    pub changed_files: i32,
+    #[serde(default)]
+    pub auto_merge: Option<RestAutoMerge>,
+    #[serde(default)]
+    pub assignees: Vec<RestUser>,
+    #[serde(default)]
+    pub milestone: Option<RestMilestone>,
+    #[serde(default)]
+    pub requested_reviewers: Vec<RestUser>,
+    #[serde(default)]
+    pub requested_teams: Vec<RestTeam>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RestMilestone {
+    pub node_id: String,
+    pub number: i64,
+    pub title: String,
+    pub due_on: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RestTeam {
+    pub slug: String,
+    pub html_url: Option<String>,
+}
+
+impl RestTeam {
+    /// `org/slug`, matching GraphQL's `Team.combinedSlug`.
+    ///
+    /// The organization is only given by the team's URL
+    /// (`https://github.com/orgs/<org>/teams/<slug>`).
+    fn combined_slug(&self) -> String {
+        let org = self.html_url.as_deref().and_then(|url| {
+            let rest = &url[url.find("/orgs/")? + "/orgs/".len()..];
+            rest.split('/').next()
+        });
+        match org {
+            Some(org) => format!("{}/{}", org, self.slug),
+            None => self.slug.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RestAutoMerge {
+    pub merge_method: String,
+    pub enabled_by: Option<RestUser>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RestUser {
+    pub login: String,
+    #[serde(default)]
+    pub avatar_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RestRef {
+    #[serde(rename = "ref")]
+    pub ref_name: String,
+    pub sha: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RestLabel {
+    pub name: String,
+    #[serde(default)]
+    pub color: String,
+}
+
+impl RestPullRequest {
+    /// Convert to the domain type.
+    ///
+    /// Webhook payloads carry no review decision, so it has to be supplied
+    /// from previously cached data. Check state and merge queue entry are
+    /// missing too and left empty.
+    pub fn into_domain(self, review_decision: Option<ReviewDecision>) -> PullRequest {
+        let state = match (self.state.as_str(), self.merged) {
+            (_, true) => PrState::Merged,
+            ("closed", false) => PrState::Closed,
+            _ => PrState::Open,
+        };
+
+        let mergeable = match self.mergeable {
+            Some(true) => Mergeable::Mergeable,
+            Some(false) => Mergeable::Conflicting,
+            None => Mergeable::Unknown,
+        };
+
+        let auto_merge = self.auto_merge.map(|auto_merge| AutoMerge {
+            merge_method: match auto_merge.merge_method.as_str() {
+                "squash" => MergeMethod::Squash,
+                "rebase" => MergeMethod::Rebase,
+                _ => MergeMethod::Merge,
+            },
+            enabled_at: None,
+            enabled_by: auto_merge.enabled_by.map(|user| user.login),
+        });
+
+        PullRequest {
+            id: self.node_id,
+            number: self.number,
+            title: self.title,
+            body: self.body,
+            state,
+            is_draft: self.draft,
+            author: Author {
+                login: self.user.login,
+                avatar_url: self.user.avatar_url,
+            },
+            head_ref: self.head.ref_name,
+            base_ref: self.base.ref_name,
+            labels: self
+                .labels
+                .into_iter()
+                .map(|l| Label {
+                    name: l.name,
+                    color: l.color,
+                })
+                .collect(),
+            review_decision,
+            mergeable,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            commit_count: self.commits,
+            additions: self.additions,
+            deletions: self.deletions,
+            changed_files: self.changed_files,
+            check_state: None,
+            auto_merge,
+            merge_queue: None,
+            assignees: self.assignees.into_iter().map(|user| user.login).collect(),
+            milestone: self.milestone.map(|m| Milestone {
+                id: m.node_id,
+                number: m.number,
+                title: m.title,
+                due_on: m.due_on,
+            }),
+            requested_reviewers: self
+                .requested_reviewers
+                .into_iter()
+                .map(|user| user.login)
+                .chain(self.requested_teams.iter().map(RestTeam::combined_slug))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PullRequestPayload {
+    action: String,
+    pull_request: RestPullRequest,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PullRequestReviewPayload {
+    action: String,
+    review: ReviewPayload,
+    pull_request: PullRequestRef,
+}
+
+#[derive(Deserialize)]
+struct ReviewPayload {
+    state: String,
+}
+
+#[derive(Deserialize)]
+struct PullRequestRef {
+    number: i64,
+    #[serde(default)]
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct CheckSuitePayload {
+    action: String,
+    check_suite: CheckSuiteBody,
+}
+
+#[derive(Deserialize)]
+struct CheckSuiteBody {
+    head_branch: Option<String>,
+    #[serde(default)]
+    pull_requests: Vec<PullRequestRef>,
+}
+
+#[derive(Deserialize)]
+struct StatusPayload {
+    state: String,
+    #[serde(default)]
+    branches: Vec<StatusBranch>,
+}
+
+#[derive(Deserialize)]
+struct StatusBranch {
+    name: String,
+}
+
+/// Apply a webhook event to the cached list of open pull requests.
+///
+/// Updates `prs` in place and returns the resulting changes. Events about
+/// pull requests that aren't cached (and can't be reconstructed from the
+/// payload) produce no changes; the next sync picks them up.
+pub fn apply_event(event: &WebhookEvent, prs: &mut Vec<PullRequest>) -> Vec<SyncChange> {
+    match event {
+        WebhookEvent::PullRequest {
+            action,
+            pull_request,
+            reason,
+        } => {
+            let number = pull_request.number;
+            let existing = prs.iter().position(|pr| pr.number == number);
+
+            if action == "closed" || pull_request.state == "closed" {
+                // The cache only tracks open pull requests
+                return match existing {
+                    Some(index) => {
+                        prs.remove(index);
+                        vec![SyncChange::PrClosed {
+                            number,
+                            merged: pull_request.merged,
+                        }]
+                    }
+                    None if action == "closed" => vec![SyncChange::PrClosed {
+                        number,
+                        merged: pull_request.merged,
+                    }],
+                    None => Vec::new(),
+                };
+            }
+
+            let review_decision = existing.and_then(|i| prs[i].review_decision);
+            let mut pr = pull_request.as_ref().clone().into_domain(review_decision);
+            // Keep the cached check state and queue entry until the next sync
+            if let Some(index) = existing {
+                pr.check_state = prs[index].check_state;
+                if action != "dequeued" {
+                    pr.merge_queue = prs[index].merge_queue.clone();
+                }
+            }
+            let title = pr.title.clone();
+
+            match existing {
+                Some(index) => prs[index] = pr,
+                None => prs.push(pr),
+            }
+
+            match (action.as_str(), existing) {
+                ("opened" | "reopened", _) | (_, None) => {
+                    vec![SyncChange::PrCreated { number, title }]
+                }
+                ("dequeued", _) => vec![SyncChange::PrDequeued {
+                    number,
+                    reason: reason.clone(),
+                }],
+                ("auto_merge_enabled" | "auto_merge_disabled", _) => {
+                    vec![SyncChange::PrAutoMergeChanged {
+                        number,
+                        enabled: action == "auto_merge_enabled",
+                    }]
+                }
+                _ => vec![SyncChange::PrUpdated { number, title }],
+            }
+        }
+
+        WebhookEvent::PullRequestReview {
+            action,
+            number,
+            title,
+            state,
+        } => {
+            let decision = match (action.as_str(), state.as_str()) {
+                ("submitted", "approved") => Some(ReviewDecision::Approved),
+                ("submitted", "changes_requested") => Some(ReviewDecision::ChangesRequested),
+                _ => None,
+            };
+
+            match decision {
+                Some(decision) => {
+                    if let Some(pr) = prs.iter_mut().find(|pr| pr.number == *number) {
+                        pr.review_decision = Some(decision);
+                    }
+                    vec![SyncChange::PrReviewChanged {
+                        number: *number,
+                        new_status: format!("{:?}", decision),
+                    }]
+                }
+                // Comments and dismissals don't map to a known decision;
+                // report an update and let the next sync reconcile.
+                None => vec![SyncChange::PrUpdated {
+                    number: *number,
+                    title: title.clone(),
+                }],
+            }
+        }
+
+        WebhookEvent::CheckSuite {
+            action,
+            head_branch,
+            pr_numbers,
+        } => {
+            if action != "completed" {
+                return Vec::new();
+            }
+
+            // Fork PRs aren't listed in the payload; fall back to the branch
+            prs.iter()
+                .filter(|pr| {
+                    pr_numbers.contains(&pr.number)
+                        || (pr_numbers.is_empty() && head_branch.as_deref() == Some(&pr.head_ref))
+                })
+                .map(|pr| SyncChange::PrUpdated {
+                    number: pr.number,
+                    title: pr.title.clone(),
+                })
+                .collect()
+        }
+
+        WebhookEvent::Status { branches, .. } => prs
+            .iter()
+            .filter(|pr| branches.contains(&pr.head_ref))
+            .map(|pr| SyncChange::PrUpdated {
+                number: pr.number,
+                title: pr.title.clone(),
+            })
+            .collect(),
+
+        WebhookEvent::Ping | WebhookEvent::Unsupported(_) => Vec::new(),
+    }
+}
+
+/// Why a delivery was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// Missing `X-GitHub-Event` header
+    MissingEvent,
+
+    /// Missing `X-GitHub-Delivery` header
+    MissingDelivery,
+
+    /// `X-GitHub-Event` or `X-GitHub-Delivery` has characters other than
+    /// `[A-Za-z0-9_-]`
+    InvalidHeader,
+
+    /// Missing or invalid `X-Hub-Signature-256` header
+    InvalidSignature,
+
+    /// Body isn't valid JSON
+    InvalidPayload,
+
+    /// A delivery with the same payload was already received
+    Replayed,
+}
+
+impl Rejection {
+    fn status(self) -> StatusCode {
+        match self {
+            Rejection::MissingEvent
+            | Rejection::MissingDelivery
+            | Rejection::InvalidHeader
+            | Rejection::InvalidPayload => StatusCode::BAD_REQUEST,
+            Rejection::InvalidSignature => StatusCode::UNAUTHORIZED,
+            Rejection::Replayed => StatusCode::CONFLICT,
+        }
+    }
+}
+
+/// Validate a raw delivery and turn it into a [`WebhookDelivery`].
+pub fn accept(
+    secret: &str,
+    event: Option<&str>,
+    delivery_id: Option<&str>,
+    signature: Option<&str>,
+    body: &[u8],
+) -> std::result::Result<WebhookDelivery, Rejection> {
+    let event = event.ok_or(Rejection::MissingEvent)?;
+    let delivery_id = delivery_id.ok_or(Rejection::MissingDelivery)?;
+    if !is_safe_header(event) || !is_safe_header(delivery_id) {
+        return Err(Rejection::InvalidHeader);
+    }
+
+    if !signature.is_some_and(|sig| verify_signature(secret, body, sig)) {
+        return Err(Rejection::InvalidSignature);
+    }
+
+    let payload = serde_json::from_slice(body).map_err(|_| Rejection::InvalidPayload)?;
+
+    Ok(WebhookDelivery {
+        event: event.to_string(),
+        delivery_id: Some(delivery_id.to_string()),
+        payload,
+    })
+}
+
+/// Write a delivery to `dir` so it can be replayed later.
+pub fn record_delivery(dir: &Path, delivery: &WebhookDelivery) -> Result<PathBuf> {
+    let delivery_id = delivery.delivery_id.as_deref().unwrap_or("unknown");
+    if !is_safe_header(&delivery.event) || !is_safe_header(delivery_id) {
+        return Err(GitHubError::Webhook(format!(
+            "Refusing to record delivery {:?} of event {:?}",
+            delivery_id, delivery.event
+        ))
+        .into());
+    }
+
+    std::fs::create_dir_all(dir).map_err(StorageError::Io)?;
+
+    let file_name = format!(
+        "{}-{}-{}.json",
+        Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+        delivery.event,
+        delivery_id
+    );
+    let path = dir.join(file_name);
+
+    let content = serde_json::to_string_pretty(delivery)?;
+    std::fs::write(&path, content).map_err(StorageError::Io)?;
+
+    Ok(path)
+}
+
+/// Load recorded deliveries from a file or a directory of `.json` files.
+///
+/// Directory entries are returned in file name order, which for recordings
+/// made by [`record_delivery`] is the order they were received in.
+pub fn load_recorded(path: &Path) -> Result<Vec<WebhookDelivery>> {
+    let mut files = if path.is_dir() {
+        std::fs::read_dir(path)
+            .map_err(StorageError::Io)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .collect::<Vec<_>>()
+    } else {
+        vec![path.to_path_buf()]
+    };
+    files.sort();
+
+    files
+        .iter()
+        .map(|file| {
+            let content = std::fs::read_to_string(file).map_err(StorageError::Io)?;
+            Ok(serde_json::from_str(&content)?)
+        })
+        .collect()
+}
+
+/// Status of a running webhook receiver.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookStatus {
+    /// Address the receiver is listening on
+    pub address: String,
+
+    /// Deliveries accepted
+    pub received: u64,
+
+    /// Deliveries rejected (bad signature, malformed, ...)
+    pub rejected: u64,
+}
+
+/// SHA-256 digests of recently accepted payloads, oldest first.
+#[derive(Debug, Default)]
+struct SeenDeliveries {
+    order: VecDeque<[u8; 32]>,
+    digests: HashSet<[u8; 32]>,
+}
+
+impl SeenDeliveries {
+    /// Remember the payload `body`, returning `false` if it was already seen.
+    ///
+    /// Only the last [`SEEN_DELIVERIES`] digests are kept.
+    fn insert(&mut self, body: &[u8]) -> bool {
+        let digest: [u8; 32] = Sha256::digest(body).into();
+        if !self.digests.insert(digest) {
+            return false;
+        }
+        self.order.push_back(digest);
+        if self.order.len() > SEEN_DELIVERIES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.digests.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Shared state for request handling.
+struct ReceiverContext {
+    secret: String,
+    record_dir: Option<PathBuf>,
+    sink: mpsc::Sender<WebhookDelivery>,
+    seen: Mutex<SeenDeliveries>,
+    received: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl ReceiverContext {
+    async fn handle(&self, req: Request<Incoming>) -> Response<Full<Bytes>> {
+        if req.method() != Method::POST {
+            return respond(StatusCode::METHOD_NOT_ALLOWED, "POST only");
+        }
+
+        let header = |name: &str| {
+            req.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+        let event = header(EVENT_HEADER);
+        let delivery_id = header(DELIVERY_HEADER);
+        let signature = header(SIGNATURE_HEADER);
+
+        let body = match Limited::new(req.into_body(), MAX_PAYLOAD_BYTES)
+            .collect()
+            .await
+        {
+            Ok(collected) => collected.to_bytes(),
+            Err(_) => {
+                self.rejected.fetch_add(1, Ordering::Relaxed);
+                return respond(StatusCode::PAYLOAD_TOO_LARGE, "payload too large");
+            }
+        };
+
+        let accepted = accept(
+            &self.secret,
+            event.as_deref(),
+            delivery_id.as_deref(),
+            signature.as_deref(),
+            &body,
+        )
+        .and_then(|delivery| {
+            // Signed payloads can be replayed as is, and the delivery ID isn't
+            // signed, so each payload is only accepted once
+            let mut seen = self.seen.lock().expect("seen deliveries lock poisoned");
+            if seen.insert(&body) {
+                Ok(delivery)
+            } else {
+                Err(Rejection::Replayed)
+            }
+        });
+        let delivery = match accepted {
+            Ok(delivery) => delivery,
+            Err(rejection) => {
+                self.rejected.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!(
+                    "Rejected webhook delivery {:?}: {:?}",
+                    delivery_id,
+                    rejection
+                );
+                return respond(rejection.status(), "rejected");
+            }
+        };
+
+        if let Some(dir) = &self.record_dir {
+            if let Err(e) = record_delivery(dir, &delivery) {
+                tracing::warn!("Failed to record webhook delivery: {}", e);
+            }
+        }
+
+        if self.sink.send(delivery).await.is_err() {
+            return respond(StatusCode::SERVICE_UNAVAILABLE, "not accepting deliveries");
+        }
+
+        self.received.fetch_add(1, Ordering::Relaxed);
+        respond(StatusCode::ACCEPTED, "accepted")
+    }
+}
+
+fn respond(status: StatusCode, message: &'static str) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from_static(message.as_bytes())));
+    *response.status_mut() = status;
+    response
+}
+
+/// Local HTTP listener for GitHub webhook deliveries.
+///
+/// The listener stops when the receiver is dropped.
+pub struct WebhookReceiver {
+    local_addr: SocketAddr,
+    context: Arc<ReceiverContext>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl WebhookReceiver {
+    /// Start listening on `addr`, forwarding validated deliveries to `sink`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the secret is empty or the address can't be bound.
+    pub async fn start(
+        addr: SocketAddr,
+        secret: String,
+        record_dir: Option<PathBuf>,
+        sink: mpsc::Sender<WebhookDelivery>,
+    ) -> Result<Self> {
+        if secret.is_empty() {
+            return Err(GitHubError::Webhook("A webhook secret is required".to_string()).into());
+        }
+
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| GitHubError::Webhook(format!("Failed to bind {}: {}", addr, e)))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| GitHubError::Webhook(e.to_string()))?;
+
+        let context = Arc::new(ReceiverContext {
+            secret,
+            record_dir,
+            sink,
+            seen: Mutex::new(SeenDeliveries::default()),
+            received: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        });
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+
+        tracing::info!("Webhook receiver listening on {}", local_addr);
+
+        let ctx = context.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown_rx => break,
+                    accepted = listener.accept() => {
+                        let stream = match accepted {
+                            Ok((stream, _)) => stream,
+                            Err(e) => {
+                                tracing::warn!("Failed to accept webhook connection: {}", e);
+                                continue;
+                            }
+                        };
+
+                        let ctx = ctx.clone();
+                        tokio::spawn(async move {
+                            let service = service_fn(move |req| {
+                                let ctx = ctx.clone();
+                                async move { Ok::<_, Infallible>(ctx.handle(req).await) }
+                            });
+                            if let Err(e) = http1::Builder::new()
+                                .serve_connection(TokioIo::new(stream), service)
+                                .await
+                            {
+                                tracing::debug!("Webhook connection error: {}", e);
+                            }
+                        });
+                    }
+                }
+            }
+            tracing::info!("Webhook receiver on {} stopped", local_addr);
+        });
+
+        Ok(Self {
+            local_addr,
+            context,
+            shutdown: Some(shutdown_tx),
+        })
+    }
+
+    /// Address the receiver is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Get delivery counters.
+    pub fn status(&self) -> WebhookStatus {
+        WebhookStatus {
+            address: self.local_addr.to_string(),
+            received: self.context.received.load(Ordering::Relaxed),
+            rejected: self.context.rejected.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for WebhookReceiver {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "It's a Secret to Everybody";
+
+    fn fixture(json: &str) -> WebhookDelivery {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn opened() -> WebhookDelivery {
+        fixture(include_str!(
+            "../../fixtures/webhooks/pull_request_opened.json"
+        ))
+    }
+
+    fn apply(delivery: &WebhookDelivery, prs: &mut Vec<PullRequest>) -> Vec<SyncChange> {
+        apply_event(&delivery.parse().unwrap(), prs)
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        // Example from GitHub's "Validating webhook deliveries" documentation
+        let body = b"Hello, World!";
+        let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+
+        assert!(verify_signature(SECRET, body, signature));
+        assert_eq!(sign(SECRET, body), signature);
+        assert!(!verify_signature("wrong secret", body, signature));
+        assert!(!verify_signature(SECRET, b"Hello, World?", signature));
+        assert!(!verify_signature(SECRET, body, "sha1=757107ea"));
+        assert!(!verify_signature(SECRET, body, "sha256=not-hex"));
+    }
+
+    #[test]
+    fn test_accept_rejects_bad_deliveries() {
+        let body = br#"{"zen": "Keep it logically awesome."}"#;
+        let signature = sign(SECRET, body);
+
+        assert_eq!(
+            accept(SECRET, None, Some("abc"), Some(&signature), body).unwrap_err(),
+            Rejection::MissingEvent
+        );
+        assert_eq!(
+            accept(SECRET, Some("ping"), None, Some(&signature), body).unwrap_err(),
+            Rejection::MissingDelivery
+        );
+        // The headers end up in file names but aren't signed
+        assert_eq!(
+            accept(SECRET, Some("../ping"), Some("abc"), Some(&signature), body).unwrap_err(),
+            Rejection::InvalidHeader
+        );
+        assert_eq!(
+            accept(SECRET, Some("ping"), Some("a/b"), Some(&signature), body).unwrap_err(),
+            Rejection::InvalidHeader
+        );
+        assert_eq!(
+            accept(SECRET, Some("ping"), Some("abc"), None, body).unwrap_err(),
+            Rejection::InvalidSignature
+        );
+        assert_eq!(
+            accept(SECRET, Some("ping"), Some("abc"), Some("sha256=00"), body).unwrap_err(),
+            Rejection::InvalidSignature
+        );
+
+        let not_json = b"not json";
+        assert_eq!(
+            accept(
+                SECRET,
+                Some("ping"),
+                Some("abc"),
+                Some(&sign(SECRET, not_json)),
+                not_json
+            )
+            .unwrap_err(),
+            Rejection::InvalidPayload
+        );
+
+        let delivery = accept(SECRET, Some("ping"), Some("abc"), Some(&signature), body).unwrap();
+        assert_eq!(delivery.event, "ping");
+        assert_eq!(delivery.delivery_id.as_deref(), Some("abc"));
+        assert!(matches!(delivery.parse().unwrap(), WebhookEvent::Ping));
+    }
+
+    #[test]
+    fn test_replay_pull_request_lifecycle() {
+        let mut prs = Vec::new();
+
+        let delivery = opened();
+        assert_eq!(delivery.repository(), Some("octo-org/maguffin"));
+
+        let changes = apply(&delivery, &mut prs);
+        assert!(matches!(
+            changes.as_slice(),
+            [SyncChange::PrCreated { number: 42, .. }]
+        ));
+        assert_eq!(prs.len(), 1);
+        assert_eq!(prs[0].head_ref, "feature/restack-preview");
+        assert_eq!(prs[0].labels[0].name, "enhancement");
+        assert_eq!(prs[0].mergeable, Mergeable::Unknown);
+        assert_eq!(prs[0].assignees, ["octocat"]);
+        assert_eq!(prs[0].milestone.as_ref().map(|m| m.number), Some(3));
+        assert_eq!(prs[0].requested_reviewers, ["hubot", "octo-org/core"]);
+
+        let review = fixture(include_str!(
+            "../../fixtures/webhooks/pull_request_review_approved.json"
+        ));
+        let changes = apply(&review, &mut prs);
+        assert!(matches!(
+            changes.as_slice(),
+            [SyncChange::PrReviewChanged { number: 42, new_status }] if new_status == "Approved"
+        ));
+        assert_eq!(prs[0].review_decision, Some(ReviewDecision::Approved));
+
+        // Re-delivering the opened event keeps the cached review decision
+        apply(&delivery, &mut prs);
+        assert_eq!(prs[0].review_decision, Some(ReviewDecision::Approved));
+
+        let closed = fixture(include_str!(
+            "../../fixtures/webhooks/pull_request_closed_merged.json"
+        ));
+        let changes = apply(&closed, &mut prs);
+        assert!(matches!(
+            changes.as_slice(),
+            [SyncChange::PrClosed {
+                number: 42,
+                merged: true
+            }]
+        ));
+        assert!(prs.is_empty());
+    }
+
+    #[test]
+    fn test_replay_ci_events() {
+        let mut prs = Vec::new();
+        apply(&opened(), &mut prs);
+
+        let check_suite = fixture(include_str!(
+            "../../fixtures/webhooks/check_suite_completed.json"
+        ));
+        let changes = apply(&check_suite, &mut prs);
+        assert!(matches!(
+            changes.as_slice(),
+            [SyncChange::PrUpdated { number: 42, .. }]
+        ));
+
+        let status = fixture(include_str!("../../fixtures/webhooks/status_failure.json"));
+        let changes = apply(&status, &mut prs);
+        assert!(matches!(
+            changes.as_slice(),
+            [SyncChange::PrUpdated { number: 42, .. }]
+        ));
+
+        // CI events for unknown PRs are ignored
+        let changes = apply(&status, &mut Vec::new());
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_replay_merge_queue_events() {
+        use crate::domain::pr::{MergeQueueEntry, MergeQueueState};
+
+        let mut prs = Vec::new();
+        apply(&opened(), &mut prs);
+        assert!(prs[0].auto_merge.is_none());
+
+        let auto_merge = fixture(include_str!(
+            "../../fixtures/webhooks/pull_request_auto_merge_enabled.json"
+        ));
+        let changes = apply(&auto_merge, &mut prs);
+        assert!(matches!(
+            changes.as_slice(),
+            [SyncChange::PrAutoMergeChanged {
+                number: 42,
+                enabled: true
+            }]
+        ));
+        let enabled = prs[0].auto_merge.as_ref().unwrap();
+        assert_eq!(enabled.merge_method, MergeMethod::Squash);
+        assert_eq!(enabled.enabled_by.as_deref(), Some("octocat"));
+
+        prs[0].merge_queue = Some(MergeQueueEntry {
+            position: 1,
+            state: MergeQueueState::AwaitingChecks,
+            enqueued_at: Utc::now(),
+            estimated_time_to_merge: None,
+        });
+
+        // Other events keep the synced queue entry
+        apply(&auto_merge, &mut prs);
+        assert!(prs[0].merge_queue.is_some());
+
+        let dequeued = fixture(include_str!(
+            "../../fixtures/webhooks/pull_request_dequeued.json"
+        ));
+        let changes = apply(&dequeued, &mut prs);
+        assert!(matches!(
+            changes.as_slice(),
+            [SyncChange::PrDequeued { number: 42, reason: Some(reason) }] if reason == "CI_FAILURE"
+        ));
+        assert!(prs[0].merge_queue.is_none());
+    }
+
+    #[test]
+    fn test_record_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let first = record_delivery(dir.path(), &opened()).unwrap();
+        let second = record_delivery(
+            dir.path(),
+            &fixture(include_str!(
+                "../../fixtures/webhooks/pull_request_closed_merged.json"
+            )),
+        )
+        .unwrap();
+        assert!(first < second);
+
+        let loaded = load_recorded(dir.path()).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].payload["action"], "opened");
+        assert_eq!(loaded[1].payload["action"], "closed");
+
+        let single = load_recorded(&first).unwrap();
+        assert_eq!(single.len(), 1);
+
+        let mut unsafe_name = opened();
+        unsafe_name.delivery_id = Some("../../escape".to_string());
+        assert!(record_delivery(dir.path(), &unsafe_name).is_err());
+        assert_eq!(load_recorded(dir.path()).unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_receiver_end_to_end() {
+        let (tx, mut rx) = mpsc::channel(4);
+        let receiver =
+            WebhookReceiver::start("127.0.0.1:0".parse().unwrap(), SECRET.to_string(), None, tx)
+                .await
+                .unwrap();
+        let url = format!("http://{}/webhook", receiver.local_addr());
+        let body = serde_json::to_vec(&opened().payload).unwrap();
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(&url)
+            .header(EVENT_HEADER, "pull_request")
+            .header(DELIVERY_HEADER, "delivery-1")
+            .header(SIGNATURE_HEADER, sign(SECRET, &body))
+            .body(body.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 202);
+
+        let delivery = rx.recv().await.unwrap();
+        assert_eq!(delivery.event, "pull_request");
+        assert_eq!(delivery.delivery_id.as_deref(), Some("delivery-1"));
+
+        // Replaying a delivery is refused, even under a fresh delivery ID
+        for id in ["delivery-1", "delivery-3"] {
+            let response = client
+                .post(&url)
+                .header(EVENT_HEADER, "pull_request")
+                .header(DELIVERY_HEADER, id)
+                .header(SIGNATURE_HEADER, sign(SECRET, &body))
+                .body(body.clone())
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status().as_u16(), 409);
+        }
+        assert!(rx.try_recv().is_err());
+
+        let response = client
+            .post(&url)
+            .header(EVENT_HEADER, "pull_request")
+            .header(DELIVERY_HEADER, "delivery-2")
+            .header(SIGNATURE_HEADER, sign("wrong", &body))
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 401);
+
+        let status = receiver.status();
+        assert_eq!(status.received, 1);
+        assert_eq!(status.rejected, 3);
+    }
+
+    #[tokio::test]
+    async fn test_receiver_requires_secret() {
+        let (tx, _rx) = mpsc::channel(1);
+        let result =
+            WebhookReceiver::start("127.0.0.1:0".parse().unwrap(), String::new(), None, tx).await;
+        assert!(result.is_err());
+    }
+}
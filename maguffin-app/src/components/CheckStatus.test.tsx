@@ -16,8 +16,9 @@ describe("CheckStatusDisplay", () => {
     it("renders success state correctly", () => {
       const checkStatus: CheckStatus = {
         state: "SUCCESS",
+        commit_oid: "abc123",
         checks: [
-          { name: "build", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
+          { id: null, name: "build", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
         ],
       };
       render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -28,8 +29,9 @@ describe("CheckStatusDisplay", () => {
     it("renders pending state correctly", () => {
       const checkStatus: CheckStatus = {
         state: "PENDING",
+        commit_oid: "abc123",
         checks: [
-          { name: "build", status: "IN_PROGRESS", conclusion: null, details_url: null },
+          { id: null, name: "build", status: "IN_PROGRESS", conclusion: null, details_url: null },
         ],
       };
       render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -39,8 +41,9 @@ describe("CheckStatusDisplay", () => {
     it("renders failure state correctly", () => {
       const checkStatus: CheckStatus = {
         state: "FAILURE",
+        commit_oid: "abc123",
         checks: [
-          { name: "build", status: "COMPLETED", conclusion: "FAILURE", details_url: null },
+          { id: null, name: "build", status: "COMPLETED", conclusion: "FAILURE", details_url: null },
         ],
       };
       render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -50,6 +53,7 @@ describe("CheckStatusDisplay", () => {
     it("renders unknown state correctly", () => {
       const checkStatus: CheckStatus = {
         state: "UNKNOWN",
+        commit_oid: "abc123",
         checks: [],
       };
       render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -61,8 +65,9 @@ describe("CheckStatusDisplay", () => {
     it("shows success icon for successful checks", () => {
       const checkStatus: CheckStatus = {
         state: "SUCCESS",
+        commit_oid: "abc123",
         checks: [
-          { name: "build", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
+          { id: null, name: "build", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
         ],
       };
       const { container } = render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -72,8 +77,9 @@ describe("CheckStatusDisplay", () => {
     it("shows failure icon for failed checks", () => {
       const checkStatus: CheckStatus = {
         state: "FAILURE",
+        commit_oid: "abc123",
         checks: [
-          { name: "build", status: "COMPLETED", conclusion: "FAILURE", details_url: null },
+          { id: null, name: "build", status: "COMPLETED", conclusion: "FAILURE", details_url: null },
         ],
       };
       const { container } = render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -83,8 +89,9 @@ describe("CheckStatusDisplay", () => {
     it("shows pending icon for in-progress checks", () => {
       const checkStatus: CheckStatus = {
         state: "PENDING",
+        commit_oid: "abc123",
         checks: [
-          { name: "build", status: "IN_PROGRESS", conclusion: null, details_url: null },
+          { id: null, name: "build", status: "IN_PROGRESS", conclusion: null, details_url: null },
         ],
       };
       const { container } = render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -94,8 +101,9 @@ describe("CheckStatusDisplay", () => {
     it("shows pending icon for queued checks", () => {
       const checkStatus: CheckStatus = {
         state: "PENDING",
+        commit_oid: "abc123",
         checks: [
-          { name: "build", status: "QUEUED", conclusion: null, details_url: null },
+          { id: null, name: "build", status: "QUEUED", conclusion: null, details_url: null },
         ],
       };
       const { container } = render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -105,8 +113,9 @@ describe("CheckStatusDisplay", () => {
     it("shows neutral icon for skipped checks", () => {
       const checkStatus: CheckStatus = {
         state: "SUCCESS",
+        commit_oid: "abc123",
         checks: [
-          { name: "optional", status: "COMPLETED", conclusion: "SKIPPED", details_url: null },
+          { id: null, name: "optional", status: "COMPLETED", conclusion: "SKIPPED", details_url: null },
         ],
       };
       const { container } = render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -116,8 +125,9 @@ describe("CheckStatusDisplay", () => {
     it("shows warning icon for timed out checks", () => {
       const checkStatus: CheckStatus = {
         state: "FAILURE",
+        commit_oid: "abc123",
         checks: [
-          { name: "build", status: "COMPLETED", conclusion: "TIMED_OUT", details_url: null },
+          { id: null, name: "build", status: "COMPLETED", conclusion: "TIMED_OUT", details_url: null },
         ],
       };
       const { container } = render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -127,8 +137,9 @@ describe("CheckStatusDisplay", () => {
     it("shows warning icon for cancelled checks", () => {
       const checkStatus: CheckStatus = {
         state: "FAILURE",
+        commit_oid: "abc123",
         checks: [
-          { name: "build", status: "COMPLETED", conclusion: "CANCELLED", details_url: null },
+          { id: null, name: "build", status: "COMPLETED", conclusion: "CANCELLED", details_url: null },
         ],
       };
       const { container } = render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -138,8 +149,9 @@ describe("CheckStatusDisplay", () => {
     it("shows warning icon for action required checks", () => {
       const checkStatus: CheckStatus = {
         state: "PENDING",
+        commit_oid: "abc123",
         checks: [
-          { name: "review", status: "COMPLETED", conclusion: "ACTION_REQUIRED", details_url: null },
+          { id: null, name: "review", status: "COMPLETED", conclusion: "ACTION_REQUIRED", details_url: null },
         ],
       };
       const { container } = render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -151,10 +163,11 @@ describe("CheckStatusDisplay", () => {
     it("renders all checks in the list", () => {
       const checkStatus: CheckStatus = {
         state: "SUCCESS",
+        commit_oid: "abc123",
         checks: [
-          { name: "build", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
-          { name: "test", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
-          { name: "lint", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
+          { id: null, name: "build", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
+          { id: null, name: "test", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
+          { id: null, name: "lint", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
         ],
       };
       render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -166,6 +179,7 @@ describe("CheckStatusDisplay", () => {
     it("renders details link when available", () => {
       const checkStatus: CheckStatus = {
         state: "SUCCESS",
+        commit_oid: "abc123",
         checks: [
           {
             name: "build",
@@ -186,8 +200,9 @@ describe("CheckStatusDisplay", () => {
     it("does not render details link when not available", () => {
       const checkStatus: CheckStatus = {
         state: "SUCCESS",
+        commit_oid: "abc123",
         checks: [
-          { name: "build", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
+          { id: null, name: "build", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
         ],
       };
       render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -199,10 +214,11 @@ describe("CheckStatusDisplay", () => {
     it("correctly counts passed checks", () => {
       const checkStatus: CheckStatus = {
         state: "FAILURE",
+        commit_oid: "abc123",
         checks: [
-          { name: "build", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
-          { name: "test", status: "COMPLETED", conclusion: "FAILURE", details_url: null },
-          { name: "lint", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
+          { id: null, name: "build", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
+          { id: null, name: "test", status: "COMPLETED", conclusion: "FAILURE", details_url: null },
+          { id: null, name: "lint", status: "COMPLETED", conclusion: "SUCCESS", details_url: null },
         ],
       };
       render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -212,8 +228,9 @@ describe("CheckStatusDisplay", () => {
     it("shows 0 passed when all fail", () => {
       const checkStatus: CheckStatus = {
         state: "FAILURE",
+        commit_oid: "abc123",
         checks: [
-          { name: "build", status: "COMPLETED", conclusion: "FAILURE", details_url: null },
+          { id: null, name: "build", status: "COMPLETED", conclusion: "FAILURE", details_url: null },
         ],
       };
       render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -227,9 +244,10 @@ describe("CheckStatusDisplay", () => {
     it("handles unknown conclusion gracefully", () => {
       const checkStatus: CheckStatus = {
         state: "PENDING",
+        commit_oid: "abc123",
         checks: [
           // eslint-disable-next-line @typescript-eslint/no-explicit-any
-          { name: "build", status: "COMPLETED", conclusion: "FUTURE_NEW_VALUE" as any, details_url: null },
+          { id: null, name: "build", status: "COMPLETED", conclusion: "FUTURE_NEW_VALUE" as any, details_url: null },
         ],
       };
       const { container } = render(<CheckStatusDisplay checkStatus={checkStatus} />);
@@ -13,7 +13,7 @@ interface PRDetailPanelProps {
 
 export function PRDetailPanel({ pr, repository, onClose, onActionComplete }: PRDetailPanelProps) {
   const { loading, error, mergePR, closePR, checkoutPR, clearError } = usePullRequestActions();
-  const { details, loading: detailsLoading } = usePullRequestDetails(pr.number);
+  const { details, loading: detailsLoading, loadMore } = usePullRequestDetails(pr.number);
   const [showMergeOptions, setShowMergeOptions] = useState(false);
 
   // Use check status from details if available
@@ -115,6 +115,12 @@ export function PRDetailPanel({ pr, repository, onClose, onActionComplete }: PRD
           ) : (
             <CheckStatusDisplay checkStatus={checkStatus} />
           )}
+          {details?.pagination.checks.has_next_page && (
+            <button className="load-more-btn" onClick={() => loadMore("checks")}>
+              Show more checks ({checkStatus?.checks.length ?? 0} of{" "}
+              {details.pagination.checks.total_count})
+            </button>
+          )}
         </div>
       </div>
 
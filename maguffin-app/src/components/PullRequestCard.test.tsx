@@ -27,6 +27,12 @@ function createTestPR(overrides: Partial<PullRequest> = {}): PullRequest {
     additions: 100,
     deletions: 50,
     changed_files: 5,
+    check_state: null,
+    auto_merge: null,
+    merge_queue: null,
+    assignees: [],
+    milestone: null,
+    requested_reviewers: [],
     ...overrides,
   };
 }
@@ -1,6 +1,12 @@
 export { useAuth } from "./useAuth";
 export { usePullRequests } from "./usePullRequests";
+export { useSavedViews } from "./useSavedViews";
+export { useSearch } from "./useSearch";
 export { usePullRequestDetails } from "./usePullRequestDetails";
+export { usePullRequestReview } from "./usePullRequestReview";
+export { usePullRequestTimeline } from "./usePullRequestTimeline";
+export { useDiff, type DiffSource } from "./useDiff";
+export { useCheckRun } from "./useCheckRun";
 export { usePullRequestActions, type CreatePRParams } from "./usePullRequestActions";
 export { useStacks } from "./useStacks";
 export { useRepository } from "./useRepository";
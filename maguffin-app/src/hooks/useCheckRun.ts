@@ -0,0 +1,118 @@
+import { useState, useEffect, useCallback, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { CheckRunDetails, JobLogChunk } from "../types";
+
+// How often to poll for more of a log while it downloads
+const LOG_POLL_INTERVAL_MS = 500;
+
+export function useCheckRun(checkRunId: number | null) {
+  const [details, setDetails] = useState<CheckRunDetails | null>(null);
+  const [log, setLog] = useState<string | null>(null);
+  const [logComplete, setLogComplete] = useState(false);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  // Bumped to stop an in-flight log poll when the check run changes
+  const logSession = useRef(0);
+
+  const fetchDetails = useCallback(async () => {
+    logSession.current += 1;
+    setLog(null);
+    setLogComplete(false);
+
+    if (checkRunId === null) {
+      setDetails(null);
+      return;
+    }
+
+    try {
+      setLoading(true);
+      setDetails(await invoke<CheckRunDetails>("get_check_run", { checkRunId }));
+      setError(null);
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setLoading(false);
+    }
+  }, [checkRunId]);
+
+  // Read the job log chunk by chunk, polling while it downloads
+  const loadLog = useCallback(async () => {
+    if (checkRunId === null) {
+      return;
+    }
+
+    const session = ++logSession.current;
+    let offset = 0;
+    setLog("");
+    setLogComplete(false);
+
+    try {
+      for (;;) {
+        const chunk = await invoke<JobLogChunk>("get_job_log", { jobId: checkRunId, offset });
+        if (session !== logSession.current) {
+          return;
+        }
+
+        setLog((current) => (current ?? "") + chunk.content);
+        offset = chunk.next_offset;
+        if (chunk.complete) {
+          setLogComplete(true);
+          return;
+        }
+        if (chunk.content === "") {
+          await new Promise((resolve) => setTimeout(resolve, LOG_POLL_INTERVAL_MS));
+        }
+      }
+    } catch (e) {
+      if (session === logSession.current) {
+        setError(e instanceof Error ? e.message : String(e));
+      }
+    }
+  }, [checkRunId]);
+
+  const rerunFailedJobs = useCallback(async (): Promise<boolean> => {
+    if (checkRunId === null) {
+      return false;
+    }
+
+    try {
+      await invoke("rerun_failed_jobs", { checkRunId });
+      return true;
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return false;
+    }
+  }, [checkRunId]);
+
+  const rerequestCheckSuite = useCallback(async (): Promise<boolean> => {
+    if (details === null) {
+      return false;
+    }
+
+    try {
+      await invoke("rerequest_check_suite", { checkSuiteId: details.check_suite_id });
+      return true;
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return false;
+    }
+  }, [details]);
+
+  useEffect(() => {
+    fetchDetails();
+  }, [fetchDetails]);
+
+  return {
+    details,
+    isActionsJob: details?.app === "github-actions",
+    log,
+    logComplete,
+    loading,
+    error,
+    refresh: fetchDetails,
+    loadLog,
+    rerunFailedJobs,
+    rerequestCheckSuite,
+  };
+}
@@ -0,0 +1,95 @@
+import { useState, useEffect, useCallback, useMemo } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { DiffOptions, DiffPage, FileDiff } from "../types";
+
+// What to diff: a pull request by number, or any two revisions
+export type DiffSource =
+  | { type: "pull_request"; number: number }
+  | { type: "revisions"; from: string; to: string };
+
+const PAGE_SIZE = 50;
+
+export function useDiff(source: DiffSource | null, options?: DiffOptions) {
+  const [files, setFiles] = useState<FileDiff[]>([]);
+  const [page, setPage] = useState<DiffPage | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  // Callers usually pass fresh object literals; key on their contents so a
+  // re-render doesn't refetch the diff.
+  const sourceKey = source ? JSON.stringify(source) : null;
+  const optionsKey = JSON.stringify(options ?? {});
+  const stableSource = useMemo<DiffSource | null>(
+    () => (sourceKey ? JSON.parse(sourceKey) : null),
+    [sourceKey]
+  );
+  const stableOptions = useMemo<DiffOptions>(() => JSON.parse(optionsKey), [optionsKey]);
+
+  const fetchPage = useCallback(
+    async (offset: number): Promise<DiffPage | null> => {
+      if (stableSource === null) {
+        return null;
+      }
+      const args = { options: stableOptions, offset, limit: PAGE_SIZE };
+      return stableSource.type === "pull_request"
+        ? invoke<DiffPage>("get_pull_request_diff", { number: stableSource.number, ...args })
+        : invoke<DiffPage>("get_diff", { from: stableSource.from, to: stableSource.to, ...args });
+    },
+    [stableSource, stableOptions]
+  );
+
+  const fetchDiff = useCallback(async () => {
+    if (stableSource === null) {
+      setFiles([]);
+      setPage(null);
+      return;
+    }
+
+    try {
+      setLoading(true);
+      const first = await fetchPage(0);
+      setFiles(first?.files ?? []);
+      setPage(first);
+      setError(null);
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setLoading(false);
+    }
+  }, [stableSource, fetchPage]);
+
+  const loadMore = useCallback(async () => {
+    if (!page?.has_more || loading) {
+      return;
+    }
+
+    try {
+      setLoading(true);
+      const next = await fetchPage(page.offset + page.files.length);
+      if (next) {
+        setFiles((current) => [...current, ...next.files]);
+        setPage(next);
+      }
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setLoading(false);
+    }
+  }, [page, loading, fetchPage]);
+
+  useEffect(() => {
+    fetchDiff();
+  }, [fetchDiff]);
+
+  return {
+    files,
+    totalFiles: page?.total_files ?? 0,
+    additions: page?.additions ?? 0,
+    deletions: page?.deletions ?? 0,
+    hasMore: page?.has_more ?? false,
+    loading,
+    error,
+    refresh: fetchDiff,
+    loadMore,
+  };
+}
@@ -1,6 +1,16 @@
 import { useState, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import type { MergeMethod } from "../types";
+import type {
+  AutoMerge,
+  BranchUpdate,
+  BranchUpdateMethod,
+  MergeMethod,
+  MergeQueueEntry,
+  Milestone,
+  PrEdit,
+  PullRequest,
+  RepoLabel,
+} from "../types";
 
 export interface CreatePRParams {
   title: string;
@@ -53,6 +63,142 @@ export function usePullRequestActions() {
     }
   }, []);
 
+  const enableAutoMerge = useCallback(
+    async (prId: string, method: MergeMethod = "SQUASH"): Promise<AutoMerge | null> => {
+      try {
+        setLoading(true);
+        setError(null);
+        return await invoke<AutoMerge>("enable_auto_merge", {
+          pr_id: prId,
+          merge_method: method,
+        });
+      } catch (e) {
+        const message = e instanceof Error ? e.message : String(e);
+        setError(message);
+        return null;
+      } finally {
+        setLoading(false);
+      }
+    },
+    []
+  );
+
+  const disableAutoMerge = useCallback(async (prId: string): Promise<boolean> => {
+    try {
+      setLoading(true);
+      setError(null);
+      return await invoke<boolean>("disable_auto_merge", { pr_id: prId });
+    } catch (e) {
+      const message = e instanceof Error ? e.message : String(e);
+      setError(message);
+      return false;
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  const enqueuePR = useCallback(
+    async (prId: string, jump = false): Promise<MergeQueueEntry | null> => {
+      try {
+        setLoading(true);
+        setError(null);
+        return await invoke<MergeQueueEntry>("enqueue_pull_request", {
+          pr_id: prId,
+          jump,
+        });
+      } catch (e) {
+        const message = e instanceof Error ? e.message : String(e);
+        setError(message);
+        return null;
+      } finally {
+        setLoading(false);
+      }
+    },
+    []
+  );
+
+  const dequeuePR = useCallback(async (prId: string): Promise<boolean> => {
+    try {
+      setLoading(true);
+      setError(null);
+      return await invoke<boolean>("dequeue_pull_request", { pr_id: prId });
+    } catch (e) {
+      const message = e instanceof Error ? e.message : String(e);
+      setError(message);
+      return false;
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  const updateBranch = useCallback(
+    async (
+      number: number,
+      method: BranchUpdateMethod = "MERGE",
+      expectedHeadOid?: string
+    ): Promise<BranchUpdate | null> => {
+      try {
+        setLoading(true);
+        setError(null);
+        return await invoke<BranchUpdate>("update_pull_request_branch", {
+          number,
+          method,
+          expected_head_oid: expectedHeadOid ?? null,
+        });
+      } catch (e) {
+        const message = e instanceof Error ? e.message : String(e);
+        setError(message);
+        return null;
+      } finally {
+        setLoading(false);
+      }
+    },
+    []
+  );
+
+  // Returns the edited PR, or null if it isn't synced or the edit failed
+  const editPR = useCallback(
+    async (number: number, edit: PrEdit): Promise<PullRequest | null> => {
+      try {
+        setLoading(true);
+        setError(null);
+        return await invoke<PullRequest | null>("edit_pull_request", {
+          number,
+          edit,
+        });
+      } catch (e) {
+        const message = e instanceof Error ? e.message : String(e);
+        setError(message);
+        return null;
+      } finally {
+        setLoading(false);
+      }
+    },
+    []
+  );
+
+  const listLabels = useCallback(async (): Promise<RepoLabel[]> => {
+    try {
+      setError(null);
+      return await invoke<RepoLabel[]>("list_labels");
+    } catch (e) {
+      const message = e instanceof Error ? e.message : String(e);
+      setError(message);
+      return [];
+    }
+  }, []);
+
+  const listMilestones = useCallback(async (): Promise<Milestone[]> => {
+    try {
+      setError(null);
+      return await invoke<Milestone[]>("list_milestones");
+    } catch (e) {
+      const message = e instanceof Error ? e.message : String(e);
+      setError(message);
+      return [];
+    }
+  }, []);
+
   const closePR = useCallback(async (prId: string): Promise<boolean> => {
     try {
       setLoading(true);
@@ -94,6 +240,14 @@ export function usePullRequestActions() {
     error,
     createPR,
     mergePR,
+    enableAutoMerge,
+    disableAutoMerge,
+    enqueuePR,
+    dequeuePR,
+    updateBranch,
+    editPR,
+    listLabels,
+    listMilestones,
     closePR,
     checkoutPR,
     clearError,
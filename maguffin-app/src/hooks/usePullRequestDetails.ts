@@ -1,6 +1,33 @@
 import { useState, useEffect, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import type { PullRequestDetails } from "../types";
+import type { DetailsConnection, DetailsPage, PullRequestDetails } from "../types";
+
+function appendPage(details: PullRequestDetails, page: DetailsPage): PullRequestDetails {
+  const pagination = { ...details.pagination, [page.connection]: page.page_info };
+  switch (page.connection) {
+    case "commits":
+      return { ...details, pagination, commits: [...details.commits, ...page.items] };
+    case "files":
+      return { ...details, pagination, files: [...details.files, ...page.items] };
+    case "reviews":
+      return { ...details, pagination, reviews: [...details.reviews, ...page.items] };
+    case "review_requests":
+      return {
+        ...details,
+        pagination,
+        review_requests: [...details.review_requests, ...page.items],
+      };
+    case "checks":
+      return {
+        ...details,
+        pagination,
+        check_status: details.check_status && {
+          ...details.check_status,
+          checks: [...details.check_status.checks, ...page.items],
+        },
+      };
+  }
+}
 
 export function usePullRequestDetails(number: number | null) {
   const [details, setDetails] = useState<PullRequestDetails | null>(null);
@@ -27,14 +54,42 @@ export function usePullRequestDetails(number: number | null) {
     }
   }, [number]);
 
+  const loadMore = useCallback(
+    async (connection: DetailsConnection) => {
+      const cursor = details?.pagination[connection].end_cursor;
+      if (number === null || !details?.pagination[connection].has_next_page || !cursor) {
+        return;
+      }
+
+      try {
+        const page = await invoke<DetailsPage>("get_pull_request_details_page", {
+          number,
+          connection,
+          cursor,
+          commit_oid: details.check_status?.commit_oid ?? null,
+        });
+        setDetails((current) => current && appendPage(current, page));
+      } catch (e) {
+        setError(e instanceof Error ? e.message : String(e));
+      }
+    },
+    [number, details]
+  );
+
   useEffect(() => {
     fetchDetails();
   }, [fetchDetails]);
 
+  const truncated = details
+    ? Object.values(details.pagination).some((page) => page.has_next_page)
+    : false;
+
   return {
     details,
     loading,
     error,
+    truncated,
     refresh: fetchDetails,
+    loadMore,
   };
 }
@@ -0,0 +1,127 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type {
+  DraftReviewComment,
+  FilePatch,
+  ReviewEvent,
+  ReviewThread,
+  ReviewThreads,
+} from "../types";
+
+export function usePullRequestReview(number: number | null) {
+  const [patches, setPatches] = useState<FilePatch[]>([]);
+  const [threads, setThreads] = useState<ReviewThread[]>([]);
+  const [pendingReviewId, setPendingReviewId] = useState<string | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const fetchReview = useCallback(async () => {
+    if (number === null) {
+      setPatches([]);
+      setThreads([]);
+      setPendingReviewId(null);
+      return;
+    }
+
+    try {
+      setLoading(true);
+      const [filePatches, reviewThreads] = await Promise.all([
+        invoke<FilePatch[]>("get_pull_request_patches", { number }),
+        invoke<ReviewThreads>("get_review_threads", { number }),
+      ]);
+      setPatches(filePatches);
+      setThreads(reviewThreads.threads);
+      setPendingReviewId(reviewThreads.pending_review_id);
+      setError(null);
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setLoading(false);
+    }
+  }, [number]);
+
+  const addComment = useCallback(
+    async (comment: DraftReviewComment): Promise<boolean> => {
+      if (number === null) {
+        return false;
+      }
+
+      try {
+        const thread = await invoke<ReviewThread>("add_review_comment", { number, comment });
+        setThreads((current) => [...current, thread]);
+        if (pendingReviewId === null) {
+          setPendingReviewId(await invoke<string>("start_review", { number }));
+        }
+        return true;
+      } catch (e) {
+        setError(e instanceof Error ? e.message : String(e));
+        return false;
+      }
+    },
+    [number, pendingReviewId]
+  );
+
+  const submitReview = useCallback(
+    async (event: ReviewEvent, body?: string): Promise<boolean> => {
+      if (number === null) {
+        return false;
+      }
+
+      try {
+        await invoke("submit_review", { number, event, body: body ?? null });
+        await fetchReview();
+        return true;
+      } catch (e) {
+        setError(e instanceof Error ? e.message : String(e));
+        return false;
+      }
+    },
+    [number, fetchReview]
+  );
+
+  const discardReview = useCallback(async (): Promise<boolean> => {
+    if (number === null) {
+      return false;
+    }
+
+    try {
+      await invoke<boolean>("discard_review", { number });
+      await fetchReview();
+      return true;
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return false;
+    }
+  }, [number, fetchReview]);
+
+  const setThreadResolved = useCallback(async (threadId: string, resolved: boolean) => {
+    try {
+      const isResolved = await invoke<boolean>("set_review_thread_resolved", {
+        threadId,
+        resolved,
+      });
+      setThreads((current) =>
+        current.map((t) => (t.id === threadId ? { ...t, is_resolved: isResolved } : t))
+      );
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  }, []);
+
+  useEffect(() => {
+    fetchReview();
+  }, [fetchReview]);
+
+  return {
+    patches,
+    threads,
+    pendingReviewId,
+    loading,
+    error,
+    refresh: fetchReview,
+    addComment,
+    submitReview,
+    discardReview,
+    setThreadResolved,
+  };
+}
@@ -0,0 +1,156 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type {
+  Comment,
+  CommentKind,
+  ReactionContent,
+  ReactionGroup,
+  TimelineItem,
+} from "../types";
+
+// Apply `update` to the comment with `id`, wherever it is in the timeline
+function updateComment(
+  items: TimelineItem[],
+  id: string,
+  update: (comment: Comment) => Comment | null
+): TimelineItem[] {
+  return items.flatMap((item): TimelineItem[] => {
+    if (item.type === "comment" && item.id === id) {
+      const updated = update(item);
+      return updated ? [{ type: "comment", ...updated }] : [];
+    }
+    if (item.type === "review") {
+      const comments = item.comments.flatMap((c) => {
+        if (c.id !== id) return [c];
+        const updated = update(c);
+        return updated ? [updated] : [];
+      });
+      return [{ ...item, comments }];
+    }
+    return [item];
+  });
+}
+
+export function usePullRequestTimeline(number: number | null) {
+  const [items, setItems] = useState<TimelineItem[]>([]);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const fetchTimeline = useCallback(async () => {
+    if (number === null) {
+      setItems([]);
+      return;
+    }
+
+    try {
+      setLoading(true);
+      setItems(await invoke<TimelineItem[]>("get_pull_request_timeline", { number }));
+      setError(null);
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    } finally {
+      setLoading(false);
+    }
+  }, [number]);
+
+  const postComment = useCallback(
+    async (body: string): Promise<boolean> => {
+      if (number === null) {
+        return false;
+      }
+
+      try {
+        const comment = await invoke<Comment>("post_pull_request_comment", { number, body });
+        setItems((current) => [...current, { type: "comment", ...comment }]);
+        return true;
+      } catch (e) {
+        setError(e instanceof Error ? e.message : String(e));
+        return false;
+      }
+    },
+    [number]
+  );
+
+  const replyToThread = useCallback(
+    async (threadId: string, body: string): Promise<Comment | null> => {
+      try {
+        return await invoke<Comment>("reply_to_review_thread", { threadId, body });
+      } catch (e) {
+        setError(e instanceof Error ? e.message : String(e));
+        return null;
+      }
+    },
+    []
+  );
+
+  const editComment = useCallback(
+    async (kind: CommentKind, commentId: string, body: string): Promise<boolean> => {
+      try {
+        await invoke("edit_comment", { kind, commentId, body });
+        const editedAt = new Date().toISOString();
+        setItems((current) =>
+          updateComment(current, commentId, (c) => ({ ...c, body, last_edited_at: editedAt }))
+        );
+        return true;
+      } catch (e) {
+        setError(e instanceof Error ? e.message : String(e));
+        return false;
+      }
+    },
+    []
+  );
+
+  const deleteComment = useCallback(
+    async (kind: CommentKind, commentId: string): Promise<boolean> => {
+      try {
+        await invoke("delete_comment", { kind, commentId });
+        setItems((current) => updateComment(current, commentId, () => null));
+        return true;
+      } catch (e) {
+        setError(e instanceof Error ? e.message : String(e));
+        return false;
+      }
+    },
+    []
+  );
+
+  const setReaction = useCallback(
+    async (subjectId: string, content: ReactionContent, reacted: boolean) => {
+      try {
+        const reactions = await invoke<ReactionGroup[]>("set_reaction", {
+          subjectId,
+          content,
+          reacted,
+        });
+        setItems((current) =>
+          updateComment(
+            current.map((item) =>
+              item.type === "review" && item.id === subjectId ? { ...item, reactions } : item
+            ),
+            subjectId,
+            (c) => ({ ...c, reactions })
+          )
+        );
+      } catch (e) {
+        setError(e instanceof Error ? e.message : String(e));
+      }
+    },
+    []
+  );
+
+  useEffect(() => {
+    fetchTimeline();
+  }, [fetchTimeline]);
+
+  return {
+    items,
+    loading,
+    error,
+    refresh: fetchTimeline,
+    postComment,
+    replyToThread,
+    editComment,
+    deleteComment,
+    setReaction,
+  };
+}
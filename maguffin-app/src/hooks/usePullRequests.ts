@@ -1,17 +1,21 @@
 import { useState, useEffect, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import type { PullRequest } from "../types";
+import type { PrQuery, PullRequest } from "../types";
 
-export function usePullRequests(baseBranch?: string) {
+export function usePullRequests(baseBranch?: string, query?: PrQuery) {
   const [pullRequests, setPullRequests] = useState<PullRequest[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
 
+  // Compare queries by value so callers can pass object literals
+  const queryKey = query ? JSON.stringify(query) : null;
+
   const fetchPullRequests = useCallback(async () => {
     try {
       setLoading(true);
       const prs = await invoke<PullRequest[]>("list_pull_requests", {
         base_branch: baseBranch,
+        query: queryKey ? (JSON.parse(queryKey) as PrQuery) : null,
       });
       setPullRequests(prs);
       setError(null);
@@ -20,7 +24,7 @@ export function usePullRequests(baseBranch?: string) {
     } finally {
       setLoading(false);
     }
-  }, [baseBranch]);
+  }, [baseBranch, queryKey]);
 
   useEffect(() => {
     fetchPullRequests();
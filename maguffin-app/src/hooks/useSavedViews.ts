@@ -0,0 +1,54 @@
+import { useState, useEffect, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { SavedView } from "../types";
+
+export function useSavedViews() {
+  const [views, setViews] = useState<SavedView[]>([]);
+  const [error, setError] = useState<string | null>(null);
+
+  const fetchViews = useCallback(async () => {
+    try {
+      setViews(await invoke<SavedView[]>("get_saved_views"));
+      setError(null);
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+    }
+  }, []);
+
+  const saveView = useCallback(
+    async (view: SavedView): Promise<boolean> => {
+      try {
+        await invoke("save_view", { view });
+        await fetchViews();
+        return true;
+      } catch (e) {
+        setError(e instanceof Error ? e.message : String(e));
+        return false;
+      }
+    },
+    [fetchViews]
+  );
+
+  const deleteView = useCallback(async (name: string): Promise<boolean> => {
+    try {
+      const deleted = await invoke<boolean>("delete_view", { name });
+      setViews((current) => current.filter((v) => v.name !== name));
+      return deleted;
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return false;
+    }
+  }, []);
+
+  useEffect(() => {
+    fetchViews();
+  }, [fetchViews]);
+
+  return {
+    views,
+    error,
+    refresh: fetchViews,
+    saveView,
+    deleteView,
+  };
+}
@@ -0,0 +1,38 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { SearchHit } from "../types";
+
+// Searches the local index, so results arrive quickly enough to update on
+// every keystroke and are available offline.
+export function useSearch(query: string, limit?: number) {
+  const [results, setResults] = useState<SearchHit[]>([]);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!query.trim()) {
+      setResults([]);
+      return;
+    }
+
+    // Drop responses to queries that were superseded while in flight
+    let cancelled = false;
+    invoke<SearchHit[]>("search_pull_requests", { query, limit })
+      .then((hits) => {
+        if (!cancelled) {
+          setResults(hits);
+          setError(null);
+        }
+      })
+      .catch((e) => {
+        if (!cancelled) {
+          setError(e instanceof Error ? e.message : String(e));
+        }
+      });
+
+    return () => {
+      cancelled = true;
+    };
+  }, [query, limit]);
+
+  return { results, error };
+}
@@ -1,12 +1,13 @@
-import { useState, useEffect, useCallback } from "react";
+import { useState, useEffect, useCallback, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import type { Stack, Repository, RestackResult, ReconcileReport, RestackPreview, RebaseState } from "../types";
+import type { Stack, Repository, RestackResult, ReconcileReport, RestackPreview, RebaseState, StackStorage, DiscoveredStack, StackTool, StackImport, StackEdit, StackEditPreview, StackEditResult, BranchCommit } from "../types";
 
 export function useStacks(repository: Repository | null) {
   const [stacks, setStacks] = useState<Stack[]>([]);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [isRebaseInProgress, setIsRebaseInProgress] = useState(false);
+  const revision = useRef<number | null>(null);
 
   const fetchStacks = useCallback(async () => {
     if (!repository) {
@@ -120,6 +121,74 @@ export function useStacks(repository: Repository | null) {
     }
   }, [fetchStacks]);
 
+  const previewStackEdit = useCallback(async (edit: StackEdit): Promise<StackEditPreview | null> => {
+    try {
+      setError(null);
+      return await invoke<StackEditPreview>("preview_stack_edit", { edit });
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return null;
+    }
+  }, []);
+
+  const editStack = useCallback(async (edit: StackEdit): Promise<StackEditResult | null> => {
+    try {
+      setError(null);
+      const result = await invoke<StackEditResult>("edit_stack", { edit });
+      await fetchStacks(); // Refresh to get updated stack
+      return result;
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return null;
+    }
+  }, [fetchStacks]);
+
+  const undoStackEdit = useCallback(async (): Promise<StackEditResult | null> => {
+    try {
+      setError(null);
+      const result = await invoke<StackEditResult>("undo_stack_edit");
+      await fetchStacks(); // Refresh to get updated stack
+      return result;
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return null;
+    }
+  }, [fetchStacks]);
+
+  const listBranchCommits = useCallback(async (branch: string): Promise<BranchCommit[] | null> => {
+    try {
+      setError(null);
+      return await invoke<BranchCommit[]>("list_branch_commits", { branch });
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return null;
+    }
+  }, []);
+
+  const splitBranch = useCallback(async (branch: string, at: string, newBranch: string): Promise<StackEditResult | null> => {
+    try {
+      setError(null);
+      const result = await invoke<StackEditResult>("split_branch", { branch, at, newBranch });
+      await fetchStacks(); // Refresh to get updated stack
+      return result;
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return null;
+    }
+  }, [fetchStacks]);
+
+  const foldBranch = useCallback(async (branch: string): Promise<StackEditResult | null> => {
+    try {
+      setError(null);
+      const result = await invoke<StackEditResult>("fold_branch", { branch });
+      await fetchStacks(); // Refresh to get updated stack
+      return result;
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return null;
+    }
+  }, [fetchStacks]);
+
   const getRebaseState = useCallback(async (): Promise<RebaseState | null> => {
     try {
       const state = await invoke<RebaseState | null>("get_rebase_state");
@@ -142,8 +211,85 @@ export function useStacks(repository: Repository | null) {
     }
   }, [fetchStacks]);
 
+  const discoverStacks = useCallback(async (): Promise<DiscoveredStack[] | null> => {
+    try {
+      setError(null);
+      return await invoke<DiscoveredStack[]>("discover_stacks");
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return null;
+    }
+  }, []);
+
+  const adoptStack = useCallback(async (stack: DiscoveredStack): Promise<Stack | null> => {
+    try {
+      setError(null);
+      const adopted = await invoke<Stack>("adopt_stack", { stack });
+      await fetchStacks(); // Refresh to include the adopted stack with its CI rollup
+      return adopted;
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return null;
+    }
+  }, [fetchStacks]);
+
+  const importStacks = useCallback(async (tool: StackTool): Promise<StackImport | null> => {
+    try {
+      setError(null);
+      const result = await invoke<StackImport>("import_stacks", { tool });
+      await fetchStacks(); // Refresh to include the imported stacks
+      return result;
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return null;
+    }
+  }, [fetchStacks]);
+
+  const exportStacksToGitTown = useCallback(async (): Promise<number | null> => {
+    try {
+      setError(null);
+      return await invoke<number>("export_stacks_to_git_town");
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return null;
+    }
+  }, []);
+
+  const getStackStorage = useCallback(async (): Promise<StackStorage | null> => {
+    try {
+      return await invoke<StackStorage>("get_stack_storage");
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return null;
+    }
+  }, []);
+
+  const setStackStorage = useCallback(async (storage: StackStorage): Promise<boolean> => {
+    try {
+      setError(null);
+      await invoke("set_stack_storage", { storage });
+      return true;
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return false;
+    }
+  }, []);
+
+  const syncStacks = useCallback(async (): Promise<boolean> => {
+    try {
+      setError(null);
+      await invoke("sync_stacks");
+      await fetchStacks(); // Refresh to show stacks merged from the remote
+      return true;
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e));
+      return false;
+    }
+  }, [fetchStacks]);
+
   // Fetch stacks when repository changes
   useEffect(() => {
+    revision.current = null;
     if (repository) {
       fetchStacks();
     } else {
@@ -154,6 +300,25 @@ export function useStacks(repository: Repository | null) {
     }
   }, [repository, fetchStacks]);
 
+  // Refetch when stacks are saved elsewhere, e.g. by the CLI
+  useEffect(() => {
+    if (!repository) return;
+    const checkRevision = async () => {
+      try {
+        const current = await invoke<number>("get_stack_revision");
+        if (revision.current !== null && revision.current !== current) {
+          await fetchStacks();
+        }
+        revision.current = current;
+      } catch {
+        // Errors surface through the next fetch
+      }
+    };
+    checkRevision();
+    const interval = setInterval(checkRevision, 2000); // Poll every 2 seconds
+    return () => clearInterval(interval);
+  }, [repository, fetchStacks]);
+
   return {
     stacks,
     loading,
@@ -166,7 +331,20 @@ export function useStacks(repository: Repository | null) {
     restackStack,
     previewRestack,
     continueRestack,
+    previewStackEdit,
+    editStack,
+    undoStackEdit,
+    listBranchCommits,
+    splitBranch,
+    foldBranch,
     getRebaseState,
     reconcileStacks,
+    discoverStacks,
+    adoptStack,
+    importStacks,
+    exportStacksToGitTown,
+    getStackStorage,
+    setStackStorage,
+    syncStacks,
   };
 }
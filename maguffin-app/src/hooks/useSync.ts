@@ -73,16 +73,21 @@ export function useSync() {
     }
   }, [fetchStatus]);
 
-  // Update sync configuration
+  // Update sync configuration; adaptive settings left out keep their values
   const updateConfig = useCallback(
-    async (intervalSecs: number, enabled: boolean) => {
+    async (
+      intervalSecs: number,
+      enabled: boolean,
+      adaptive: Omit<Partial<SyncConfig>, "interval_secs" | "enabled" | "sync_on_startup"> = {}
+    ) => {
       setLoading(true);
       try {
         await invoke("update_sync_config", {
           interval_secs: intervalSecs,
           enabled,
+          ...adaptive,
         });
-        setConfig(prev => ({ ...prev, interval_secs: intervalSecs, enabled }));
+        setConfig(prev => ({ ...prev, ...adaptive, interval_secs: intervalSecs, enabled }));
         setError(null);
       } catch (e) {
         setError(e instanceof Error ? e.message : String(e));
@@ -98,6 +103,25 @@ export function useSync() {
     setError(null);
   }, []);
 
+  // Report window focus so the backend can slow down syncing in the background
+  useEffect(() => {
+    const report = (focused: boolean) => {
+      invoke("set_window_focused", { focused }).catch(() => {
+        // Scheduling hint only; ignore failures
+      });
+    };
+    const onFocus = () => report(true);
+    const onBlur = () => report(false);
+
+    report(document.hasFocus());
+    window.addEventListener("focus", onFocus);
+    window.addEventListener("blur", onBlur);
+    return () => {
+      window.removeEventListener("focus", onFocus);
+      window.removeEventListener("blur", onBlur);
+    };
+  }, []);
+
   // Fetch status on mount and periodically
   useEffect(() => {
     fetchStatus();
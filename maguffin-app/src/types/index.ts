@@ -35,6 +35,114 @@ export interface PullRequest {
   additions: number;
   deletions: number;
   changed_files: number;
+  // Combined check state of the head commit; null if it has no checks
+  check_state: CheckState | null;
+  // null unless auto-merge is enabled
+  auto_merge: AutoMerge | null;
+  // null unless the PR is in the merge queue
+  merge_queue: MergeQueueEntry | null;
+  // Logins of assigned users
+  assignees: string[];
+  milestone: Milestone | null;
+  // Pending review requests: user logins and "org/team" slugs
+  requested_reviewers: string[];
+}
+
+export type PrSortOrder = "created_at" | "updated_at" | "review_status";
+
+// Filters for the PR list; empty lists and omitted fields match everything.
+// Values within a list are alternatives, except labels, which are all required.
+export interface PrQuery {
+  authors?: string[];
+  labels?: string[];
+  review_decisions?: ReviewDecision[];
+  draft?: boolean | null;
+  check_states?: CheckState[];
+  mergeable?: Mergeable[];
+  // Glob patterns, e.g. "release/*"
+  base?: string | null;
+  head?: string | null;
+  // Exact base branch name
+  base_branch?: string | null;
+  // Words to find in title, body, head branch or author; "#123" matches PR 123
+  text?: string | null;
+  sort?: PrSortOrder;
+}
+
+export interface SavedView {
+  name: string;
+  query: PrQuery;
+}
+
+// Local full-text search
+export interface TextSpan {
+  text: string;
+  matched: boolean;
+}
+
+export interface SearchHit {
+  owner: string;
+  repo: string;
+  pull_request: PullRequest;
+  // Higher is more relevant
+  score: number;
+  title: TextSpan[];
+  // Excerpt of the best matching field
+  snippet: TextSpan[];
+}
+
+// A label defined in the repository
+export interface RepoLabel {
+  id: string;
+  name: string;
+  color: string;
+  description: string | null;
+}
+
+export interface Milestone {
+  id: string;
+  number: number;
+  title: string;
+  due_on: string | null;
+}
+
+// A single edit to a pull request
+export type PrEdit =
+  | { type: "title"; title: string }
+  | { type: "body"; body: string }
+  | { type: "draft"; is_draft: boolean }
+  | { type: "add_labels"; labels: string[] }
+  | { type: "remove_labels"; labels: string[] }
+  | { type: "request_reviewers"; users: string[]; teams: string[] }
+  | { type: "remove_reviewers"; users: string[]; teams: string[] }
+  | { type: "set_assignees"; assignees: string[] }
+  // Milestone number; null clears it
+  | { type: "set_milestone"; milestone: number | null }
+  | { type: "reopen" };
+
+export type BranchUpdateMethod = "MERGE" | "REBASE";
+
+export interface BranchUpdate {
+  head_oid: string;
+  method: BranchUpdateMethod;
+  // Whether the branch was rebased locally and force-pushed because GitHub couldn't update it
+  local: boolean;
+}
+
+export interface AutoMerge {
+  merge_method: MergeMethod;
+  enabled_at: string | null;
+  enabled_by: string | null;
+}
+
+export type MergeQueueState = "QUEUED" | "AWAITING_CHECKS" | "MERGEABLE" | "UNMERGEABLE" | "LOCKED";
+
+export interface MergeQueueEntry {
+  position: number;
+  state: MergeQueueState;
+  enqueued_at: string;
+  // Estimated seconds until the PR is merged
+  estimated_time_to_merge: number | null;
 }
 
 export interface Commit {
@@ -68,6 +176,214 @@ export interface PullRequestDetails {
   reviews: Review[];
   review_requests: string[];
   check_status: CheckStatus | null;
+  pagination: DetailsPagination;
+}
+
+export type DetailsConnection = "commits" | "files" | "reviews" | "review_requests" | "checks";
+
+export interface PageInfo {
+  total_count: number;
+  has_next_page: boolean;
+  end_cursor: string | null;
+}
+
+export type DetailsPagination = Record<DetailsConnection, PageInfo>;
+
+export type DetailsPage = { page_info: PageInfo } & (
+  | { connection: "commits"; items: Commit[] }
+  | { connection: "files"; items: ChangedFile[] }
+  | { connection: "reviews"; items: Review[] }
+  | { connection: "review_requests"; items: string[] }
+  | { connection: "checks"; items: CheckRun[] }
+);
+
+// Review types
+export type DiffSide = "LEFT" | "RIGHT";
+export type ReviewEvent = "APPROVE" | "COMMENT" | "REQUEST_CHANGES";
+
+export interface HunkRange {
+  old_start: number;
+  old_lines: number;
+  new_start: number;
+  new_lines: number;
+}
+
+export interface FilePatch {
+  path: string;
+  previous_path: string | null;
+  change_type: ChangeType;
+  additions: number;
+  deletions: number;
+  // null for binary files and diffs too large for GitHub to return
+  patch: string | null;
+  hunks: HunkRange[];
+}
+
+export interface ReviewComment {
+  id: string;
+  author: Author;
+  body: string;
+  created_at: string;
+  is_pending: boolean;
+}
+
+export interface ReviewThread {
+  id: string;
+  path: string;
+  line: number | null;
+  start_line: number | null;
+  side: DiffSide;
+  start_side: DiffSide | null;
+  is_resolved: boolean;
+  is_outdated: boolean;
+  can_resolve: boolean;
+  comments: ReviewComment[];
+}
+
+export interface ReviewThreads {
+  pending_review_id: string | null;
+  threads: ReviewThread[];
+}
+
+export interface DraftReviewComment {
+  path: string;
+  line: number;
+  side: DiffSide;
+  start_line?: number;
+  start_side?: DiffSide;
+  body: string;
+}
+
+// Conversation timeline types
+export type ReactionContent =
+  | "THUMBS_UP"
+  | "THUMBS_DOWN"
+  | "LAUGH"
+  | "HOORAY"
+  | "CONFUSED"
+  | "HEART"
+  | "ROCKET"
+  | "EYES";
+
+export interface ReactionGroup {
+  content: ReactionContent;
+  count: number;
+  viewer_has_reacted: boolean;
+}
+
+// "issue" for conversation comments, "review" for review thread comments
+export type CommentKind = "issue" | "review";
+
+export interface Comment {
+  id: string;
+  kind: CommentKind;
+  author: Author;
+  body: string;
+  created_at: string;
+  last_edited_at: string | null;
+  // Set on review comments
+  path: string | null;
+  reply_to: string | null;
+  reactions: ReactionGroup[];
+  can_edit: boolean;
+  can_delete: boolean;
+  can_react: boolean;
+}
+
+export interface TimelineCommit {
+  oid: string;
+  headline: string;
+  author: string | null;
+  committed_at: string;
+}
+
+export type TimelineItem =
+  | ({ type: "comment" } & Comment)
+  | {
+      type: "review";
+      id: string;
+      author: Author;
+      state: ReviewState;
+      body: string;
+      created_at: string;
+      submitted_at: string | null;
+      reactions: ReactionGroup[];
+      can_react: boolean;
+      // Replies follow the comment they reply to
+      comments: Comment[];
+    }
+  | ({ type: "commit" } & TimelineCommit)
+  | {
+      type: "force_push";
+      actor: Author | null;
+      before_oid: string | null;
+      after_oid: string | null;
+      created_at: string;
+    }
+  | { type: "labeled" | "unlabeled"; actor: Author | null; label: Label; created_at: string }
+  | { type: "base_changed"; actor: Author | null; from: string; to: string; created_at: string }
+  | {
+      type: "merged";
+      actor: Author | null;
+      commit_oid: string | null;
+      base_ref: string;
+      created_at: string;
+    };
+
+// Local diff types
+export interface DiffOptions {
+  merge_base?: boolean;
+  context_lines?: number;
+  detect_renames?: boolean;
+  word_diff?: boolean;
+  ignore_whitespace?: boolean;
+}
+
+export type LineKind = "context" | "addition" | "deletion";
+
+// Range of a changed word within a line, in UTF-16 code units
+export interface WordSpan {
+  start: number;
+  end: number;
+}
+
+export interface DiffLine {
+  kind: LineKind;
+  old_lineno: number | null;
+  new_lineno: number | null;
+  content: string;
+  changes: WordSpan[];
+}
+
+export interface DiffHunk {
+  header: string;
+  old_start: number;
+  old_lines: number;
+  new_start: number;
+  new_lines: number;
+  lines: DiffLine[];
+}
+
+export interface FileDiff {
+  path: string;
+  old_path: string | null;
+  change_type: ChangeType;
+  is_binary: boolean;
+  additions: number;
+  deletions: number;
+  hunks: DiffHunk[];
+  truncated: boolean;
+}
+
+export interface DiffPage {
+  from_oid: string;
+  to_oid: string;
+  files: FileDiff[];
+  offset: number;
+  total_files: number;
+  has_more: boolean;
+  additions: number;
+  deletions: number;
 }
 
 // CI/Check status types
@@ -76,6 +392,8 @@ export type CheckRunStatus = "QUEUED" | "IN_PROGRESS" | "COMPLETED";
 export type CheckConclusion = "SUCCESS" | "FAILURE" | "NEUTRAL" | "CANCELLED" | "SKIPPED" | "TIMED_OUT" | "ACTION_REQUIRED";
 
 export interface CheckRun {
+  // Check run database ID; null for commit status contexts
+  id: number | null;
   name: string;
   status: CheckRunStatus;
   conclusion: CheckConclusion | null;
@@ -85,6 +403,49 @@ export interface CheckRun {
 export interface CheckStatus {
   state: CheckState;
   checks: CheckRun[];
+  commit_oid: string;
+}
+
+export type AnnotationLevel = "notice" | "warning" | "failure";
+
+export interface CheckAnnotation {
+  path: string;
+  start_line: number;
+  end_line: number;
+  start_column: number | null;
+  end_column: number | null;
+  level: AnnotationLevel;
+  title: string | null;
+  message: string;
+  raw_details: string | null;
+}
+
+export interface CheckRunDetails {
+  id: number;
+  name: string;
+  status: CheckRunStatus;
+  conclusion: CheckConclusion | null;
+  head_sha: string;
+  check_suite_id: number;
+  // Slug of the app that created the run ("github-actions" for Actions jobs)
+  app: string | null;
+  html_url: string | null;
+  details_url: string | null;
+  started_at: string | null;
+  completed_at: string | null;
+  title: string | null;
+  summary: string | null;
+  text: string | null;
+  annotations: CheckAnnotation[];
+}
+
+export interface JobLogChunk {
+  job_id: number;
+  offset: number;
+  next_offset: number;
+  content: string;
+  // Whether the chunk reaches the end of the fully downloaded log
+  complete: boolean;
 }
 
 // Stack types
@@ -99,12 +460,63 @@ export interface StackBranch {
   head_sha: string | null;
 }
 
+export interface BranchCi {
+  branch: string;
+  pr_number: number | null;
+  state: CheckState | null;
+}
+
+export interface StackCiRollup {
+  state: CheckState;
+  // Branch closest to the root whose checks are failing
+  first_failing: string | null;
+  branches: BranchCi[];
+}
+
 export interface Stack {
   id: string;
   root: string;
   branches: StackBranch[];
   created_at: string;
   updated_at: string;
+  // Only present on stacks returned by list_stacks
+  ci?: StackCiRollup;
+}
+
+export type ParentSource = "pr_base" | "ancestry";
+
+export interface ParentCandidate {
+  parent: string;
+  // Share of the evidence pointing at this parent, from 0 to 1
+  confidence: number;
+  sources: ParentSource[];
+}
+
+export interface DiscoveredBranch {
+  name: string;
+  // Parent to adopt the branch under; initially the likeliest candidate
+  parent: string;
+  pr_number: number | null;
+  // Likeliest first; more than one means the parent is ambiguous
+  candidates: ParentCandidate[];
+}
+
+export interface DiscoveredStack {
+  root: string;
+  branches: DiscoveredBranch[];
+}
+
+// Where stack metadata is kept: the local .git/stack-metadata.json file, or
+// the refs/maguffin/stacks ref shared through the remote
+export type StackStorage = "file" | "ref";
+
+// Stacking tools whose branch parents can be imported
+export type StackTool = "graphite" | "git_town";
+
+export interface StackImport {
+  imported: string[];
+  // Branches already in a stack, or whose parents form a cycle
+  skipped: string[];
 }
 
 // Auth types
@@ -168,6 +580,39 @@ export interface SyncConfig {
   interval_secs: number;
   enabled: boolean;
   sync_on_startup: boolean;
+  unfocused_interval_secs?: number;
+  boost_interval_secs?: number;
+  boost_duration_secs?: number;
+  max_interval_secs?: number;
+  full_sync_interval_secs?: number;
+}
+
+// GraphQL point cost per operation
+export interface OperationCost {
+  operation: string;
+  calls: number;
+  total_cost: number;
+  last_cost: number;
+  last_estimated_cost: number;
+  max_cost: number;
+}
+
+// Matches Rust's serde(tag = "type") serialization of SyncChange
+export type SyncChange =
+  | { type: "pr_created"; number: number; title: string }
+  | { type: "pr_updated"; number: number; title: string }
+  | { type: "pr_closed"; number: number; merged: boolean }
+  | { type: "pr_review_changed"; number: number; new_status: string }
+  | { type: "pr_checks_changed"; number: number; new_state: CheckState | null }
+  | { type: "pr_auto_merge_changed"; number: number; enabled: boolean }
+  | { type: "pr_merge_queue_changed"; number: number; position: number; state: MergeQueueState }
+  | { type: "pr_dequeued"; number: number; reason: string | null }
+  | { type: "stack_parent_merged"; stack_id: string; branch: string };
+
+export interface WebhookStatus {
+  address: string;
+  received: number;
+  rejected: number;
 }
 
 // Restack types
@@ -200,6 +645,36 @@ export interface RestackPreview {
   total_commits: number;
 }
 
+// Stack edit types (moving, inserting and reordering branches)
+export type StackEdit =
+  | { kind: "reparent"; branch: string; parent: string; stack_id?: string | null }
+  | { kind: "insert"; stack_id: string; branch: string; parent: string }
+  | { kind: "swap"; branch: string };
+
+export interface BranchMove {
+  branch: string;
+  from: string | null;
+  to: string;
+  pr_number: number | null;
+}
+
+export interface StackEditPreview {
+  moves: BranchMove[];
+  will_rebase: RestackBranchPreview[];
+  total_commits: number;
+}
+
+export interface StackEditResult {
+  moves: BranchMove[];
+  rebased: string[];
+  closed_pr: number | null;
+}
+
+export interface BranchCommit {
+  sha: string;
+  summary: string;
+}
+
 // Rebase state
 export interface RebaseState {
   branch: string | null;